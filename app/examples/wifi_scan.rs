@@ -27,7 +27,7 @@ fn app_main() {
             println!("Found {} access points:", aps.len());
 
             for ap in aps {
-              println!("  - {} '{}'", ap.bssid(), ap.ssid())
+              println!("  - {} '{}' (channel {}, {} dBm, {:?})", ap.bssid(), ap.ssid(), ap.channel(), ap.rssi(), ap.auth_mode())
             }
           }
         }
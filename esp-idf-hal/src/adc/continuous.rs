@@ -0,0 +1,257 @@
+use core::mem::{self, size_of, transmute, MaybeUninit};
+use core::ptr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering::SeqCst};
+use std::sync::mpsc::{self, Receiver, RecvError, RecvTimeoutError, TryRecvError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use esp_idf_bindgen::{
+  adc1_config_channel_atten,
+  adc_digi_config_t,
+  adc_digi_controller_config,
+  adc_digi_convert_mode_t,
+  adc_digi_output_format_t,
+  adc_digi_pattern_table_t,
+  adc_unit_t,
+  esp_err_t,
+  i2s_adc_disable,
+  i2s_adc_enable,
+  i2s_bits_per_sample_t,
+  i2s_channel_fmt_t,
+  i2s_comm_format_t,
+  i2s_config_t,
+  i2s_driver_install,
+  i2s_driver_uninstall,
+  i2s_mode_t,
+  i2s_port_t,
+  i2s_read,
+  i2s_set_adc_mode,
+  ESP_ERR_INVALID_ARG,
+  ESP_FAIL,
+};
+
+use crate::EspError;
+
+use super::{adc_channel, Adc1Pin, AdcUnit, Attenuation};
+
+/// Continuous sampling uses the I2S0 peripheral, which can only be connected to ADC1 on the ESP32.
+const I2S_PORT: i2s_port_t = i2s_port_t::I2S_NUM_0;
+
+const DMA_BUF_COUNT: usize = 4;
+
+/// The maximum number of channels in a scan pattern.
+const MAX_PATTERN_LEN: usize = 16;
+
+/// The number of I2S ticks to wait for data before checking whether sampling was stopped.
+const READ_TIMEOUT_TICKS: u32 = 10;
+
+/// A single conversion result of a [`ContinuousAdc`](struct.ContinuousAdc.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+  channel: u8,
+  value: u16,
+}
+
+impl Sample {
+  /// The ADC1 channel this sample was taken from.
+  pub fn channel(&self) -> u8 {
+    self.channel
+  }
+
+  /// The raw 12-bit conversion result.
+  pub fn value(&self) -> u16 {
+    self.value
+  }
+}
+
+/// Options for continuous sampling using [`ContinuousAdc::start`](struct.ContinuousAdc.html#method.start).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContinuousConfig {
+  sample_rate: u32,
+  frame_len: usize,
+  pattern: Vec<(u8, Attenuation)>,
+}
+
+impl ContinuousConfig {
+  /// Create a configuration sampling with `sample_rate` Hz in frames of 1024 samples.
+  pub fn new(sample_rate: u32) -> Self {
+    Self { sample_rate, frame_len: 1024, pattern: Vec::new() }
+  }
+
+  /// Add the ADC1 pin `pin` to the scan pattern. Pins are sampled in the order they were added.
+  pub fn with_pin<P: Adc1Pin>(mut self, pin: &P, attenuation: Attenuation) -> Self {
+    self.pattern.push((pin.number(), attenuation));
+    self
+  }
+
+  /// Set the number of samples in each frame.
+  pub fn with_frame_len(mut self, frame_len: usize) -> Self {
+    self.frame_len = frame_len;
+    self
+  }
+
+  pub fn sample_rate(&self) -> u32 {
+    self.sample_rate
+  }
+
+  pub fn frame_len(&self) -> usize {
+    self.frame_len
+  }
+}
+
+/// Continuous ADC sampling using DMA.
+///
+/// Samples are read on a separate thread and delivered in frames. Sampling stops when this is dropped.
+#[derive(Debug)]
+pub struct ContinuousAdc {
+  receiver: Receiver<Vec<Sample>>,
+  stopped: Arc<AtomicBool>,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl ContinuousAdc {
+  /// Start sampling the pins of the `config` scan pattern.
+  ///
+  /// Returns `ESP_ERR_INVALID_ARG` if the pattern is empty or too long.
+  pub fn start(config: &ContinuousConfig) -> Result<Self, EspError> {
+    const INVALID_ARG: EspError = EspError { code: ESP_ERR_INVALID_ARG as esp_err_t };
+
+    if config.pattern.is_empty() || config.pattern.len() > MAX_PATTERN_LEN || config.frame_len == 0 {
+      return Err(INVALID_ARG)
+    }
+
+    let mut pattern = Vec::with_capacity(config.pattern.len());
+    for &(gpio, attenuation) in &config.pattern {
+      let channel = match adc_channel(gpio) {
+        Some((AdcUnit::Adc1, channel)) => channel,
+        _ => return Err(INVALID_ARG),
+      };
+
+      esp_ok!(adc1_config_channel_atten(transmute(channel as u32), attenuation.into()))?;
+
+      // The pattern table entry contains the attenuation, a 12-bit width and the channel.
+      let mut entry: adc_digi_pattern_table_t = unsafe { mem::zeroed() };
+      entry.__bindgen_anon_1.val = attenuation as u8 | 3 << 2 | channel << 4;
+      pattern.push((channel, entry));
+    }
+
+    let i2s_config = i2s_config_t {
+      mode: i2s_mode_t::I2S_MODE_MASTER,
+      sample_rate: config.sample_rate as _,
+      bits_per_sample: i2s_bits_per_sample_t::I2S_BITS_PER_SAMPLE_16BIT,
+      channel_format: i2s_channel_fmt_t::I2S_CHANNEL_FMT_ONLY_LEFT,
+      communication_format: i2s_comm_format_t::I2S_COMM_FORMAT_STAND_MSB,
+      intr_alloc_flags: 0,
+      dma_buf_count: DMA_BUF_COUNT as _,
+      dma_buf_len: config.frame_len.min(1024) as _,
+      use_apll: false,
+      tx_desc_auto_clear: false,
+      fixed_mclk: 0,
+    };
+
+    // The mode is a combination of flags, which is not a variant of `i2s_mode_t`, so write it as a
+    // raw integer into a `MaybeUninit` which is only passed on as a pointer.
+    let mode = i2s_mode_t::I2S_MODE_MASTER as u32 | i2s_mode_t::I2S_MODE_RX as u32 | i2s_mode_t::I2S_MODE_ADC_BUILT_IN as u32;
+    let mut i2s_config = MaybeUninit::new(i2s_config);
+    unsafe { ptr::write(&mut (*i2s_config.as_mut_ptr()).mode as *mut _ as *mut u32, mode) };
+
+    esp_ok!(i2s_driver_install(I2S_PORT, i2s_config.as_ptr(), 0, ptr::null_mut()))?;
+
+    let mut table: Vec<adc_digi_pattern_table_t> = pattern.iter().map(|&(_, entry)| entry).collect();
+    let digi_config = adc_digi_config_t {
+      conv_limit_en: true,
+      conv_limit_num: 255,
+      adc1_pattern_len: table.len() as _,
+      adc2_pattern_len: 0,
+      adc1_pattern: table.as_mut_ptr(),
+      adc2_pattern: ptr::null_mut(),
+      conv_mode: adc_digi_convert_mode_t::ADC_CONV_SINGLE_UNIT_1,
+      format: adc_digi_output_format_t::ADC_DIGI_FORMAT_12BIT,
+    };
+
+    let started = esp_ok!(i2s_set_adc_mode(adc_unit_t::ADC_UNIT_1, transmute(pattern[0].0 as u32)))
+      .and_then(|()| esp_ok!(adc_digi_controller_config(&digi_config)))
+      .and_then(|()| esp_ok!(i2s_adc_enable(I2S_PORT)));
+
+    if let Err(err) = started {
+      let _ = esp_ok!(i2s_driver_uninstall(I2S_PORT));
+      return Err(err)
+    }
+
+    let (sender, receiver) = mpsc::sync_channel(2);
+    let stopped = Arc::new(AtomicBool::new(false));
+    let thread_stopped = Arc::clone(&stopped);
+    let frame_len = config.frame_len;
+
+    let spawned = thread::Builder::new()
+      .name("adc_continuous".into())
+      .stack_size(3072)
+      .spawn(move || {
+        let mut buf = vec![0u16; frame_len];
+        let mut frame = Vec::with_capacity(frame_len);
+
+        while !thread_stopped.load(SeqCst) {
+          let mut bytes_read = 0;
+          let remaining = frame_len - frame.len();
+
+          if esp_ok!(i2s_read(
+            I2S_PORT, buf.as_mut_ptr() as *mut _, remaining * size_of::<u16>(), &mut bytes_read, READ_TIMEOUT_TICKS,
+          )).is_err() {
+            continue
+          }
+
+          frame.extend(buf[..(bytes_read / size_of::<u16>())].iter().map(|&word| Sample {
+            channel: (word >> 12) as u8,
+            value: word & 0x0fff,
+          }));
+
+          if frame.len() == frame_len {
+            let full = mem::replace(&mut frame, Vec::with_capacity(frame_len));
+
+            // Drop frames while the receiver is not keeping up, instead of blocking the DMA.
+            if let Err(mpsc::TrySendError::Disconnected(_)) = sender.try_send(full) {
+              break
+            }
+          }
+        }
+      });
+
+    match spawned {
+      Ok(thread) => Ok(Self { receiver, stopped, thread: Some(thread) }),
+      Err(_) => {
+        let _ = esp_ok!(i2s_adc_disable(I2S_PORT));
+        let _ = esp_ok!(i2s_driver_uninstall(I2S_PORT));
+        Err(EspError { code: ESP_FAIL as esp_err_t })
+      },
+    }
+  }
+
+  /// Block until the next frame is received.
+  pub fn recv(&self) -> Result<Vec<Sample>, RecvError> {
+    self.receiver.recv()
+  }
+
+  /// Return the next frame if one has already been received.
+  pub fn try_recv(&self) -> Result<Vec<Sample>, TryRecvError> {
+    self.receiver.try_recv()
+  }
+
+  /// Block until the next frame is received or `timeout` has elapsed.
+  pub fn recv_timeout(&self, timeout: Duration) -> Result<Vec<Sample>, RecvTimeoutError> {
+    self.receiver.recv_timeout(timeout)
+  }
+}
+
+impl Drop for ContinuousAdc {
+  fn drop(&mut self) {
+    self.stopped.store(true, SeqCst);
+
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+
+    let _ = esp_ok!(i2s_adc_disable(I2S_PORT));
+    let _ = esp_ok!(i2s_driver_uninstall(I2S_PORT));
+  }
+}
@@ -0,0 +1,286 @@
+use core::fmt;
+use core::mem::{transmute, MaybeUninit};
+
+use esp_idf_bindgen::{
+  adc1_config_channel_atten,
+  adc1_config_width,
+  adc1_get_raw,
+  adc2_config_channel_atten,
+  adc2_get_raw,
+  adc_atten_t,
+  adc_bits_width_t,
+  adc_unit_t,
+  esp_adc_cal_characteristics_t,
+  esp_adc_cal_characterize,
+  esp_adc_cal_raw_to_voltage,
+  esp_adc_cal_value_t,
+  esp_err_t,
+  ESP_ERR_INVALID_ARG,
+  ESP_ERR_INVALID_STATE,
+  ESP_ERR_TIMEOUT,
+};
+
+use crate::EspError;
+use crate::gpio::{Gpio32, Gpio33, Gpio34, Gpio35, Gpio36, Gpio37, Gpio38, Gpio39, Pin};
+
+mod continuous;
+pub use continuous::*;
+
+/// The reference voltage in millivolts used for calibration if the eFuse contains no calibration values.
+const DEFAULT_VREF: u32 = 1100;
+
+/// A GPIO pin connected to an ADC1 channel.
+///
+/// Like [`Pin`](../gpio/trait.Pin.html), this trait can only be implemented by this crate.
+pub trait Adc1Pin: Pin {}
+
+impl Adc1Pin for Gpio32 {}
+impl Adc1Pin for Gpio33 {}
+impl Adc1Pin for Gpio34 {}
+impl Adc1Pin for Gpio35 {}
+impl Adc1Pin for Gpio36 {}
+impl Adc1Pin for Gpio37 {}
+impl Adc1Pin for Gpio38 {}
+impl Adc1Pin for Gpio39 {}
+
+/// An ADC unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdcUnit {
+  /// ADC1, on GPIOs 32 to 39.
+  Adc1,
+  /// ADC2, on GPIOs 0, 2, 4, 12 to 15 and 25 to 27. ADC2 cannot be used while WiFi is started.
+  Adc2,
+}
+
+impl From<AdcUnit> for adc_unit_t {
+  fn from(unit: AdcUnit) -> Self {
+    match unit {
+      AdcUnit::Adc1 => adc_unit_t::ADC_UNIT_1,
+      AdcUnit::Adc2 => adc_unit_t::ADC_UNIT_2,
+    }
+  }
+}
+
+/// The input attenuation, which determines the measurable voltage range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attenuation {
+  /// Measure up to approximately 800 mV.
+  Db0,
+  /// Measure up to approximately 1100 mV.
+  Db2_5,
+  /// Measure up to approximately 1350 mV.
+  Db6,
+  /// Measure up to approximately 2600 mV.
+  Db11,
+}
+
+impl From<Attenuation> for adc_atten_t {
+  fn from(attenuation: Attenuation) -> Self {
+    match attenuation {
+      Attenuation::Db0 => adc_atten_t::ADC_ATTEN_DB_0,
+      Attenuation::Db2_5 => adc_atten_t::ADC_ATTEN_DB_2_5,
+      Attenuation::Db6 => adc_atten_t::ADC_ATTEN_DB_6,
+      Attenuation::Db11 => adc_atten_t::ADC_ATTEN_DB_11,
+    }
+  }
+}
+
+/// The bit width of conversion results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+  Bits9,
+  Bits10,
+  Bits11,
+  Bits12,
+}
+
+impl From<Resolution> for adc_bits_width_t {
+  fn from(resolution: Resolution) -> Self {
+    match resolution {
+      Resolution::Bits9 => adc_bits_width_t::ADC_WIDTH_BIT_9,
+      Resolution::Bits10 => adc_bits_width_t::ADC_WIDTH_BIT_10,
+      Resolution::Bits11 => adc_bits_width_t::ADC_WIDTH_BIT_11,
+      Resolution::Bits12 => adc_bits_width_t::ADC_WIDTH_BIT_12,
+    }
+  }
+}
+
+/// The source of the calibration values used to convert raw readings to millivolts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Calibration {
+  /// The two-point calibration values burned into the eFuse.
+  EfuseTwoPoint,
+  /// The reference voltage burned into the eFuse.
+  EfuseVref,
+  /// The default reference voltage of 1100 mV, since the eFuse contains no calibration values.
+  DefaultVref,
+}
+
+/// An ADC error.
+#[derive(Debug, Clone)]
+pub enum AdcError {
+  /// An internal error.
+  Internal(EspError),
+  /// ADC2 is in use by the WiFi driver.
+  Adc2InUse,
+}
+
+impl From<EspError> for AdcError {
+  fn from(esp_error: EspError) -> Self {
+    Self::Internal(esp_error)
+  }
+}
+
+impl fmt::Display for AdcError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Internal(esp_error) => esp_error.fmt(f),
+      Self::Adc2InUse => write!(f, "ADC2 is in use by WiFi"),
+    }
+  }
+}
+
+/// The ADC unit and channel of a GPIO, or `None` if it is not connected to an ADC.
+pub(crate) fn adc_channel(pin: u8) -> Option<(AdcUnit, u8)> {
+  Some(match pin {
+    36 => (AdcUnit::Adc1, 0),
+    37 => (AdcUnit::Adc1, 1),
+    38 => (AdcUnit::Adc1, 2),
+    39 => (AdcUnit::Adc1, 3),
+    32 => (AdcUnit::Adc1, 4),
+    33 => (AdcUnit::Adc1, 5),
+    34 => (AdcUnit::Adc1, 6),
+    35 => (AdcUnit::Adc1, 7),
+    4 => (AdcUnit::Adc2, 0),
+    0 => (AdcUnit::Adc2, 1),
+    2 => (AdcUnit::Adc2, 2),
+    15 => (AdcUnit::Adc2, 3),
+    13 => (AdcUnit::Adc2, 4),
+    12 => (AdcUnit::Adc2, 5),
+    14 => (AdcUnit::Adc2, 6),
+    27 => (AdcUnit::Adc2, 7),
+    25 => (AdcUnit::Adc2, 8),
+    26 => (AdcUnit::Adc2, 9),
+    _ => return None,
+  })
+}
+
+/// A GPIO configured as an analog input.
+pub struct AdcChannel<P: Pin> {
+  pin: P,
+  unit: AdcUnit,
+  channel: u8,
+  attenuation: Attenuation,
+  resolution: Resolution,
+  calibration: Calibration,
+  characteristics: esp_adc_cal_characteristics_t,
+}
+
+impl<P: Pin> fmt::Debug for AdcChannel<P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("AdcChannel")
+      .field("pin", &self.pin.number())
+      .field("unit", &self.unit)
+      .field("channel", &self.channel)
+      .field("attenuation", &self.attenuation)
+      .field("resolution", &self.resolution)
+      .field("calibration", &self.calibration)
+      .finish()
+  }
+}
+
+impl<P: Pin> AdcChannel<P> {
+  /// Configure `pin` as an analog input.
+  ///
+  /// Returns `ESP_ERR_INVALID_ARG` if the pin is not connected to an ADC.
+  pub fn new(pin: P, attenuation: Attenuation, resolution: Resolution) -> Result<Self, EspError> {
+    let (unit, channel) = adc_channel(pin.number()).ok_or(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })?;
+
+    match unit {
+      AdcUnit::Adc1 => {
+        esp_ok!(adc1_config_width(resolution.into()))?;
+        esp_ok!(adc1_config_channel_atten(transmute(channel as u32), attenuation.into()))?;
+      },
+      AdcUnit::Adc2 => {
+        esp_ok!(adc2_config_channel_atten(transmute(channel as u32), attenuation.into()))?;
+      },
+    }
+
+    let mut characteristics = MaybeUninit::<esp_adc_cal_characteristics_t>::uninit();
+    let value = unsafe {
+      esp_adc_cal_characterize(unit.into(), attenuation.into(), resolution.into(), DEFAULT_VREF, characteristics.as_mut_ptr())
+    };
+
+    let calibration = match value {
+      esp_adc_cal_value_t::ESP_ADC_CAL_VAL_EFUSE_TP => Calibration::EfuseTwoPoint,
+      esp_adc_cal_value_t::ESP_ADC_CAL_VAL_EFUSE_VREF => Calibration::EfuseVref,
+      _ => Calibration::DefaultVref,
+    };
+
+    Ok(Self {
+      pin,
+      unit,
+      channel,
+      attenuation,
+      resolution,
+      calibration,
+      characteristics: unsafe { characteristics.assume_init() },
+    })
+  }
+
+  pub fn unit(&self) -> AdcUnit {
+    self.unit
+  }
+
+  pub fn channel(&self) -> u8 {
+    self.channel
+  }
+
+  pub fn attenuation(&self) -> Attenuation {
+    self.attenuation
+  }
+
+  pub fn resolution(&self) -> Resolution {
+    self.resolution
+  }
+
+  /// The source of the calibration values used by [`read_millivolts`](#method.read_millivolts).
+  pub fn calibration(&self) -> Calibration {
+    self.calibration
+  }
+
+  /// Read the raw conversion result.
+  pub fn read_raw(&mut self) -> Result<u16, AdcError> {
+    match self.unit {
+      AdcUnit::Adc1 => {
+        // The bit width is shared by all ADC1 channels.
+        esp_ok!(adc1_config_width(self.resolution.into()))?;
+
+        match unsafe { adc1_get_raw(transmute(self.channel as u32)) } {
+          -1 => Err(EspError { code: ESP_ERR_INVALID_STATE as esp_err_t }.into()),
+          raw => Ok(raw as u16),
+        }
+      },
+      AdcUnit::Adc2 => {
+        let mut raw = 0;
+
+        match esp_ok!(adc2_get_raw(transmute(self.channel as u32), self.resolution.into(), &mut raw)) {
+          Ok(()) => Ok(raw as u16),
+          Err(err) if err.code == ESP_ERR_TIMEOUT as esp_err_t => Err(AdcError::Adc2InUse),
+          Err(err) => Err(err.into()),
+        }
+      },
+    }
+  }
+
+  /// Read the input voltage in millivolts, using the eFuse calibration values if available.
+  pub fn read_millivolts(&mut self) -> Result<u32, AdcError> {
+    let raw = self.read_raw()?;
+    Ok(unsafe { esp_adc_cal_raw_to_voltage(raw as u32, &self.characteristics) })
+  }
+
+  /// Return the pin.
+  pub fn release(self) -> P {
+    self.pin
+  }
+}
@@ -0,0 +1,64 @@
+use core::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+use std::thread;
+use std::time::Duration;
+
+/// A value shared with a C callback, which can be cleared while the callback may be running.
+///
+/// Callbacks access the value using [`with`](#method.with), which marks the value as in use,
+/// so [`clear`](#method.clear) only frees it once no callback is using it anymore.
+pub(crate) struct CallbackSlot<T> {
+  ptr: AtomicUsize,
+  active: AtomicUsize,
+  _marker: PhantomData<T>,
+}
+
+// The value is only accessed by the driver task calling the callback.
+unsafe impl<T: Send> Sync for CallbackSlot<T> {}
+
+impl<T> CallbackSlot<T> {
+  pub const fn new() -> Self {
+    Self { ptr: AtomicUsize::new(0), active: AtomicUsize::new(0), _marker: PhantomData }
+  }
+
+  /// Store `value`, or return it if the slot is already occupied.
+  pub fn set(&self, value: T) -> Result<(), T> {
+    let ptr = Box::into_raw(Box::new(value));
+
+    if self.ptr.compare_and_swap(0, ptr as usize, SeqCst) == 0 {
+      Ok(())
+    } else {
+      Err(*unsafe { Box::from_raw(ptr) })
+    }
+  }
+
+  /// Call `f` with the value, or return `None` if the slot is empty.
+  pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> Option<R> {
+    self.active.fetch_add(1, SeqCst);
+
+    let result = match self.ptr.load(SeqCst) {
+      0 => None,
+      ptr => Some(f(unsafe { &*(ptr as *const T) })),
+    };
+
+    self.active.fetch_sub(1, SeqCst);
+    result
+  }
+
+  /// Take the value out of the slot, waiting until no callback is using it anymore.
+  ///
+  /// The callback should be unregistered from the driver before, so it is not called needlessly.
+  pub fn clear(&self) -> Option<T> {
+    let ptr = self.ptr.swap(0, SeqCst);
+
+    // Sleep instead of yielding, so a lower priority task running the callback can finish.
+    while self.active.load(SeqCst) != 0 {
+      thread::sleep(Duration::from_millis(1));
+    }
+
+    match ptr {
+      0 => None,
+      ptr => Some(*unsafe { Box::from_raw(ptr as *mut T) }),
+    }
+  }
+}
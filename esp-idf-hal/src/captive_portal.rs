@@ -0,0 +1,365 @@
+use core::fmt;
+use core::marker::PhantomData;
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering::SeqCst};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::{interface::Interface, wifi::{ApConfig, ApStaConfig, Wifi}};
+
+const DNS_PORT: u16 = 53;
+const HTTP_PORT: u16 = 80;
+const DNS_TTL: u32 = 60;
+const REQUEST_MAX_LEN: usize = 2048;
+const ERRORS_CAPACITY: usize = 8;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Paths requested by operating systems to detect a captive portal.
+const PROBE_PATHS: &[&str] = &[
+  "/hotspot-detect.html",
+  "/library/test/success.html",
+  "/generate_204",
+  "/gen_204",
+  "/connecttest.txt",
+  "/ncsi.txt",
+  "/redirect",
+  "/canonical.html",
+  "/success.txt",
+];
+
+/// An HTTP request received by a [`CaptivePortal`](struct.CaptivePortal.html).
+#[derive(Debug, Clone)]
+pub struct Request {
+  method: String,
+  path: String,
+  headers: Vec<(String, Vec<u8>)>,
+  body: Vec<u8>,
+  addr: SocketAddr,
+}
+
+impl Request {
+  pub fn method(&self) -> &str {
+    &self.method
+  }
+
+  pub fn path(&self) -> &str {
+    &self.path
+  }
+
+  /// Get the value of the first header with the given name, ignoring case.
+  pub fn header(&self, name: &str) -> Option<&[u8]> {
+    self.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_slice())
+  }
+
+  pub fn headers(&self) -> impl Iterator<Item = (&str, &[u8])> {
+    self.headers.iter().map(|(n, v)| (n.as_str(), v.as_slice()))
+  }
+
+  pub fn body(&self) -> &[u8] {
+    &self.body
+  }
+
+  /// The address of the client.
+  pub fn addr(&self) -> &SocketAddr {
+    &self.addr
+  }
+}
+
+type Handler = dyn Fn(&Request, &mut TcpStream) -> io::Result<()> + Send + Sync;
+
+/// A captive portal running on the access point interface.
+///
+/// All DNS queries are answered with the address of the access point and requests for
+/// another host or for one of the connectivity check URLs used by common operating systems
+/// are redirected to `/`, which causes phones and laptops to open their sign-in page.
+/// All remaining requests are passed to the handler.
+///
+/// The portal is stopped and its sockets are closed when dropped.
+#[must_use = "captive portal will be stopped immediately"]
+pub struct CaptivePortal<'w> {
+  ip: Ipv4Addr,
+  stopped: Arc<AtomicBool>,
+  errors: Receiver<io::Error>,
+  dns_thread: Option<JoinHandle<()>>,
+  http_thread: Option<JoinHandle<()>>,
+  _wifi: PhantomData<&'w ()>,
+}
+
+impl fmt::Debug for CaptivePortal<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("CaptivePortal")
+      .field("ip", &self.ip)
+      .field("stopped", &self.stopped)
+      .finish()
+  }
+}
+
+impl CaptivePortal<'_> {
+  fn start<H>(handler: H) -> io::Result<Self>
+  where
+    H: Fn(&Request, &mut TcpStream) -> io::Result<()> + Send + Sync + 'static,
+  {
+    let ip = *Interface::Ap.ip_info().ip();
+
+    let dns_socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, DNS_PORT))?;
+    dns_socket.set_read_timeout(Some(Duration::from_secs(1)))?;
+    dns_socket.set_write_timeout(Some(TIMEOUT))?;
+
+    let http_listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, HTTP_PORT))?;
+
+    let stopped = Arc::new(AtomicBool::new(false));
+    let (error_sender, errors) = mpsc::sync_channel(ERRORS_CAPACITY);
+
+    let mut portal = Self { ip, stopped, errors, dns_thread: None, http_thread: None, _wifi: PhantomData };
+
+    let dns_stopped = Arc::clone(&portal.stopped);
+    let dns_errors = error_sender.clone();
+    portal.dns_thread = Some(thread::Builder::new()
+      .name("captive_dns".into())
+      .stack_size(4096)
+      .spawn(move || dns_server(dns_socket, ip, dns_stopped, dns_errors))?);
+
+    let http_stopped = Arc::clone(&portal.stopped);
+    let handler: Arc<Handler> = Arc::new(handler);
+    portal.http_thread = Some(thread::Builder::new()
+      .name("captive_http".into())
+      .stack_size(8192)
+      .spawn(move || http_server(http_listener, ip, handler, http_stopped, error_sender))?);
+
+    Ok(portal)
+  }
+
+  /// Return the errors which occurred while serving clients since the last call.
+  ///
+  /// Only the oldest errors are kept until they are taken.
+  pub fn errors(&self) -> impl Iterator<Item = io::Error> + '_ {
+    self.errors.try_iter()
+  }
+}
+
+impl Drop for CaptivePortal<'_> {
+  fn drop(&mut self) {
+    self.stopped.store(true, SeqCst);
+
+    // Wake the servers blocked on their sockets so they notice they were stopped.
+    if self.http_thread.is_some() {
+      let _ = TcpStream::connect_timeout(&SocketAddr::from((self.ip, HTTP_PORT)), TIMEOUT);
+    }
+    if self.dns_thread.is_some() {
+      let _ = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).and_then(|socket| socket.send_to(&[], (self.ip, DNS_PORT)));
+    }
+
+    // The sockets are closed when the servers return.
+    for thread in self.http_thread.take().into_iter().chain(self.dns_thread.take()) {
+      let _ = thread.join();
+    }
+  }
+}
+
+impl Wifi<ApConfig> {
+  /// Start a [`CaptivePortal`](../captive_portal/struct.CaptivePortal.html) on this access point,
+  /// passing all requests which are not redirected to `handler`.
+  pub fn captive_portal<H>(&self, handler: H) -> io::Result<CaptivePortal<'_>>
+  where
+    H: Fn(&Request, &mut TcpStream) -> io::Result<()> + Send + Sync + 'static,
+  {
+    CaptivePortal::start(handler)
+  }
+}
+
+impl Wifi<ApStaConfig> {
+  /// Start a [`CaptivePortal`](../captive_portal/struct.CaptivePortal.html) on the access point,
+  /// passing all requests which are not redirected to `handler`.
+  pub fn captive_portal<H>(&self, handler: H) -> io::Result<CaptivePortal<'_>>
+  where
+    H: Fn(&Request, &mut TcpStream) -> io::Result<()> + Send + Sync + 'static,
+  {
+    CaptivePortal::start(handler)
+  }
+}
+
+fn dns_server(socket: UdpSocket, ip: Ipv4Addr, stopped: Arc<AtomicBool>, errors: SyncSender<io::Error>) {
+  let mut buf = [0; 512];
+
+  while !stopped.load(SeqCst) {
+    let (len, src) = match socket.recv_from(&mut buf) {
+      Ok(ok) => ok,
+      Err(err) if err.kind() == io::ErrorKind::WouldBlock || err.kind() == io::ErrorKind::TimedOut => continue,
+      Err(err) => {
+        let _ = errors.try_send(err);
+        continue
+      },
+    };
+
+    if let Some(len) = dns_response(&mut buf, len, ip) {
+      if let Err(err) = socket.send_to(&buf[..len], src) {
+        let _ = errors.try_send(err);
+      }
+    }
+  }
+}
+
+/// Turn the DNS query in `buf[..len]` into a response answering `A` queries with `ip`
+/// in place and return the response length.
+fn dns_response(buf: &mut [u8; 512], len: usize, ip: Ipv4Addr) -> Option<usize> {
+  const HEADER_LEN: usize = 12;
+
+  if len < HEADER_LEN {
+    return None
+  }
+
+  let flags = u16::from_be_bytes([buf[2], buf[3]]);
+  let is_query = flags & 0x8000 == 0;
+  let opcode = (flags >> 11) & 0xf;
+  let question_count = u16::from_be_bytes([buf[4], buf[5]]);
+
+  if !is_query || opcode != 0 || question_count != 1 {
+    return None
+  }
+
+  let mut i = HEADER_LEN;
+  loop {
+    let label_len = *buf[..len].get(i)? as usize;
+    i += 1;
+
+    if label_len == 0 {
+      break
+    }
+
+    i += label_len;
+  }
+
+  let question_end = i + 4;
+  if question_end > len {
+    return None
+  }
+
+  let kind = u16::from_be_bytes([buf[i], buf[i + 1]]);
+  let class = u16::from_be_bytes([buf[i + 2], buf[i + 3]]);
+  let answer = kind == 1 && class == 1;
+
+  // Response, authoritative answer, keep "recursion desired".
+  let flags = 0x8400 | (flags & 0x0100);
+  buf[2..4].copy_from_slice(&flags.to_be_bytes());
+  buf[6..8].copy_from_slice(&(answer as u16).to_be_bytes());
+  buf[8..12].copy_from_slice(&[0; 4]);
+
+  if !answer {
+    return Some(question_end)
+  }
+
+  let mut record = [0; 16];
+  record[0..2].copy_from_slice(&[0xc0, HEADER_LEN as u8]);
+  record[2..4].copy_from_slice(&kind.to_be_bytes());
+  record[4..6].copy_from_slice(&class.to_be_bytes());
+  record[6..10].copy_from_slice(&DNS_TTL.to_be_bytes());
+  record[10..12].copy_from_slice(&4u16.to_be_bytes());
+  record[12..16].copy_from_slice(&ip.octets());
+
+  let response_len = question_end + record.len();
+  buf.get_mut(question_end..response_len)?.copy_from_slice(&record);
+  Some(response_len)
+}
+
+fn http_server(listener: TcpListener, ip: Ipv4Addr, handler: Arc<Handler>, stopped: Arc<AtomicBool>, errors: SyncSender<io::Error>) {
+  loop {
+    let accepted = listener.accept();
+
+    if stopped.load(SeqCst) {
+      break
+    }
+
+    let (mut client, addr) = match accepted {
+      Ok(ok) => ok,
+      Err(err) => {
+        let _ = errors.try_send(err);
+        continue
+      },
+    };
+
+    if let Err(err) = handle_client(&mut client, addr, ip, &*handler) {
+      let err = io::Error::new(err.kind(), format!("error handling request from {}: {}", addr, err));
+      let _ = errors.try_send(err);
+    }
+  }
+}
+
+fn handle_client(client: &mut TcpStream, addr: SocketAddr, ip: Ipv4Addr, handler: &Handler) -> io::Result<()> {
+  client.set_read_timeout(Some(TIMEOUT))?;
+  client.set_write_timeout(Some(TIMEOUT))?;
+
+  let request = match read_request(client, addr)? {
+    Some(request) => request,
+    None => {
+      write!(client, "HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")?;
+      return Ok(())
+    },
+  };
+
+  let host = ip.to_string();
+  let foreign_host = request.header("Host")
+    .and_then(|h| h.split(|&b| b == b':').next())
+    .map_or(false, |h| h != host.as_bytes());
+
+  if foreign_host || PROBE_PATHS.contains(&request.path()) {
+    write!(
+      client,
+      "HTTP/1.1 302 Found\r\nLocation: http://{}/\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+      host,
+    )?;
+    return Ok(())
+  }
+
+  handler(&request, client)
+}
+
+fn read_request(client: &mut TcpStream, addr: SocketAddr) -> io::Result<Option<Request>> {
+  let mut buf = vec![0; REQUEST_MAX_LEN];
+  let mut len = 0;
+
+  loop {
+    if len == buf.len() {
+      return Ok(None)
+    }
+
+    let n = client.read(&mut buf[len..])?;
+    if n == 0 {
+      return Ok(None)
+    }
+    len += n;
+
+    let mut headers = [httparse::EMPTY_HEADER; 16];
+    let mut req = httparse::Request::new(&mut headers);
+
+    let header_len = match req.parse(&buf[..len]) {
+      Ok(httparse::Status::Complete(header_len)) => header_len,
+      Ok(httparse::Status::Partial) => continue,
+      Err(_) => return Ok(None),
+    };
+
+    let content_length = req.headers.iter()
+      .find(|h| h.name.eq_ignore_ascii_case("Content-Length"))
+      .and_then(|h| std::str::from_utf8(h.value).ok())
+      .and_then(|v| v.trim().parse::<usize>().ok())
+      .unwrap_or(0);
+
+    if header_len + content_length > buf.len() {
+      return Ok(None)
+    }
+
+    if len < header_len + content_length {
+      continue
+    }
+
+    return Ok(Some(Request {
+      method: req.method.unwrap_or_default().to_owned(),
+      path: req.path.unwrap_or_default().to_owned(),
+      headers: req.headers.iter().map(|h| (h.name.to_owned(), h.value.to_owned())).collect(),
+      body: buf[header_len..(header_len + content_length)].to_vec(),
+      addr,
+    }))
+  }
+}
@@ -0,0 +1,109 @@
+use esp_idf_bindgen::{
+  dac_cw_config_t,
+  dac_cw_generator_config,
+  dac_cw_generator_disable,
+  dac_cw_generator_enable,
+  dac_cw_phase_t,
+  dac_cw_scale_t,
+  dac_output_enable,
+};
+
+use crate::EspError;
+
+use super::{dac_channel, Dac, DacPin};
+
+/// The amplitude of the cosine wave relative to the full DAC range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CosineScale {
+  Full,
+  Half,
+  Quarter,
+  Eighth,
+}
+
+/// The phase of the cosine wave. Use opposite phases on both channels for a differential signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CosinePhase {
+  Deg0,
+  Deg180,
+}
+
+/// Options for the cosine wave generator, see [`Dac::start_cosine`](struct.Dac.html#method.start_cosine).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CosineConfig {
+  frequency: u32,
+  scale: CosineScale,
+  phase: CosinePhase,
+  offset: i8,
+}
+
+impl CosineConfig {
+  /// Create a configuration for a full-scale cosine wave with `frequency` Hz, between 130 Hz and 55 kHz.
+  pub fn new(frequency: u32) -> Self {
+    Self { frequency, scale: CosineScale::Full, phase: CosinePhase::Deg0, offset: 0 }
+  }
+
+  pub fn with_scale(mut self, scale: CosineScale) -> Self {
+    self.scale = scale;
+    self
+  }
+
+  pub fn with_phase(mut self, phase: CosinePhase) -> Self {
+    self.phase = phase;
+    self
+  }
+
+  /// Set the DC offset in DAC steps.
+  pub fn with_offset(mut self, offset: i8) -> Self {
+    self.offset = offset;
+    self
+  }
+
+  pub fn frequency(&self) -> u32 {
+    self.frequency
+  }
+
+  pub fn scale(&self) -> CosineScale {
+    self.scale
+  }
+
+  pub fn phase(&self) -> CosinePhase {
+    self.phase
+  }
+
+  pub fn offset(&self) -> i8 {
+    self.offset
+  }
+}
+
+impl<P: DacPin> Dac<P> {
+  /// Output a cosine wave generated in hardware.
+  ///
+  /// The frequency is shared by both channels, so it is changed for the other channel as well.
+  pub fn start_cosine(&mut self, config: &CosineConfig) -> Result<(), EspError> {
+    let mut cw_config = dac_cw_config_t {
+      en_ch: dac_channel(self.pin.number()),
+      scale: match config.scale {
+        CosineScale::Full => dac_cw_scale_t::DAC_CW_SCALE_1,
+        CosineScale::Half => dac_cw_scale_t::DAC_CW_SCALE_2,
+        CosineScale::Quarter => dac_cw_scale_t::DAC_CW_SCALE_4,
+        CosineScale::Eighth => dac_cw_scale_t::DAC_CW_SCALE_8,
+      },
+      phase: match config.phase {
+        CosinePhase::Deg0 => dac_cw_phase_t::DAC_CW_PHASE_0,
+        CosinePhase::Deg180 => dac_cw_phase_t::DAC_CW_PHASE_180,
+      },
+      freq: config.frequency,
+      offset: config.offset,
+    };
+
+    esp_ok!(dac_cw_generator_config(&mut cw_config))?;
+    esp_ok!(dac_output_enable(cw_config.en_ch))?;
+    esp_ok!(dac_cw_generator_enable())
+  }
+
+  /// Stop the cosine wave generator on both channels and return to the value set using [`write`](#method.write).
+  pub fn stop_cosine(&mut self) -> Result<(), EspError> {
+    esp_ok!(dac_cw_generator_disable())
+  }
+}
@@ -0,0 +1,66 @@
+use core::fmt;
+
+use esp_idf_bindgen::{dac_channel_t, dac_output_disable, dac_output_enable, dac_output_voltage};
+
+use crate::EspError;
+use crate::gpio::{Gpio25, Gpio26, Pin};
+
+mod cosine;
+pub use cosine::*;
+
+/// A GPIO pin connected to a DAC channel.
+///
+/// Like [`Pin`](../gpio/trait.Pin.html), this trait can only be implemented by this crate.
+pub trait DacPin: Pin {}
+
+impl DacPin for Gpio25 {}
+impl DacPin for Gpio26 {}
+
+pub(crate) fn dac_channel(pin: u8) -> dac_channel_t {
+  match pin {
+    25 => dac_channel_t::DAC_CHANNEL_1,
+    _ => dac_channel_t::DAC_CHANNEL_2,
+  }
+}
+
+/// An 8-bit DAC channel, on GPIO 25 for channel 1 and GPIO 26 for channel 2.
+///
+/// The output voltage ranges from 0 V for `0` to VDD for `255`.
+pub struct Dac<P: DacPin> {
+  pin: P,
+}
+
+impl<P: DacPin> fmt::Debug for Dac<P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Dac").field("pin", &self.pin.number()).finish()
+  }
+}
+
+impl<P: DacPin> Dac<P> {
+  /// Enable the DAC channel connected to `pin`.
+  pub fn new(pin: P) -> Result<Self, EspError> {
+    esp_ok!(dac_output_enable(dac_channel(pin.number())))?;
+    Ok(Self { pin })
+  }
+
+  /// Set the output voltage.
+  pub fn write(&mut self, value: u8) -> Result<(), EspError> {
+    esp_ok!(dac_output_voltage(dac_channel(self.pin.number()), value))
+  }
+
+  /// Power down the output buffer to save power. The pin is left floating.
+  pub fn disable(&mut self) -> Result<(), EspError> {
+    esp_ok!(dac_output_disable(dac_channel(self.pin.number())))
+  }
+
+  /// Power up the output buffer again after [`disable`](#method.disable).
+  pub fn enable(&mut self) -> Result<(), EspError> {
+    esp_ok!(dac_output_enable(dac_channel(self.pin.number())))
+  }
+
+  /// Disable the DAC channel and return the pin.
+  pub fn release(self) -> P {
+    let _ = esp_ok!(dac_output_disable(dac_channel(self.pin.number())));
+    self.pin
+  }
+}
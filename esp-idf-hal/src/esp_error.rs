@@ -13,6 +13,8 @@ impl From<!> for EspError {
   }
 }
 
+impl std::error::Error for EspError {}
+
 impl core::fmt::Display for EspError {
   fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
     unsafe {
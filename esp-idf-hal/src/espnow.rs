@@ -0,0 +1,284 @@
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr;
+use core::slice;
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, RecvError, RecvTimeoutError, SyncSender, TryRecvError};
+use std::time::Duration;
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  esp_interface_t,
+  esp_now_add_peer,
+  esp_now_deinit,
+  esp_now_del_peer,
+  esp_now_init,
+  esp_now_is_peer_exist,
+  esp_now_mod_peer,
+  esp_now_peer_info_t,
+  esp_now_register_recv_cb,
+  esp_now_register_send_cb,
+  esp_now_send,
+  esp_now_send_status_t,
+  esp_now_set_pmk,
+  esp_now_unregister_recv_cb,
+  esp_now_unregister_send_cb,
+  esp_wifi_start,
+  ESP_ERR_INVALID_ARG,
+  ESP_NOW_KEY_LEN,
+  ESP_NOW_MAX_DATA_LEN,
+};
+use macaddr::MacAddr6;
+
+use crate::{EspError, callback_slot::CallbackSlot, interface::Interface, wifi::{enter_sta_mode, leave_sta_mode, Wifi}};
+
+/// The length of an ESP-NOW encryption key.
+pub const KEY_LEN: usize = ESP_NOW_KEY_LEN as usize;
+/// The maximum length of an ESP-NOW message.
+pub const MAX_DATA_LEN: usize = ESP_NOW_MAX_DATA_LEN as usize;
+
+/// The delivery status of a sent message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendStatus {
+  Success,
+  Failed,
+}
+
+/// An ESP-NOW peer.
+#[derive(Clone)]
+pub struct Peer {
+  mac: MacAddr6,
+  lmk: Option<[u8; KEY_LEN]>,
+  channel: u8,
+  interface: Interface,
+}
+
+impl fmt::Debug for Peer {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Peer")
+      .field("mac", &self.mac)
+      .field("lmk", &self.lmk.map(|_| "********"))
+      .field("channel", &self.channel)
+      .field("interface", &self.interface)
+      .finish()
+  }
+}
+
+impl Peer {
+  /// Create an unencrypted peer on the current channel using the station interface.
+  pub fn new(mac: MacAddr6) -> Self {
+    Self { mac, lmk: None, channel: 0, interface: Interface::Sta }
+  }
+
+  pub fn mac(&self) -> &MacAddr6 {
+    &self.mac
+  }
+
+  /// Encrypt messages to this peer using the given local master key.
+  pub fn with_lmk(mut self, lmk: [u8; KEY_LEN]) -> Self {
+    self.lmk = Some(lmk);
+    self
+  }
+
+  /// Send messages on the given channel. A channel of `0` uses the current channel.
+  pub fn with_channel(mut self, channel: u8) -> Self {
+    self.channel = channel;
+    self
+  }
+
+  /// Send messages using the given WiFi interface.
+  pub fn with_interface(mut self, interface: Interface) -> Self {
+    self.interface = interface;
+    self
+  }
+
+  fn to_native(&self) -> Result<esp_now_peer_info_t, EspError> {
+    let ifidx = match self.interface {
+      Interface::Sta => esp_interface_t::ESP_IF_WIFI_STA,
+      Interface::Ap => esp_interface_t::ESP_IF_WIFI_AP,
+      _ => return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t }),
+    };
+
+    let mut peer_addr = [0; 6];
+    peer_addr.copy_from_slice(self.mac.as_bytes());
+
+    Ok(esp_now_peer_info_t {
+      peer_addr,
+      lmk: self.lmk.unwrap_or([0; KEY_LEN]),
+      channel: self.channel,
+      ifidx,
+      encrypt: self.lmk.is_some(),
+      priv_: ptr::null_mut(),
+    })
+  }
+}
+
+/// A message received via ESP-NOW.
+#[derive(Debug, Clone)]
+pub struct Message {
+  src: MacAddr6,
+  data: Vec<u8>,
+}
+
+impl Message {
+  /// The MAC address of the sender.
+  pub fn src(&self) -> &MacAddr6 {
+    &self.src
+  }
+
+  pub fn data(&self) -> &[u8] {
+    &self.data
+  }
+}
+
+type SendCallback = Box<dyn FnMut(&MacAddr6, SendStatus) + Send>;
+
+struct State {
+  sender: SyncSender<Message>,
+  send_callback: Mutex<Option<SendCallback>>,
+}
+
+static STATE: CallbackSlot<State> = CallbackSlot::new();
+
+/// An instance of the ESP-NOW protocol.
+pub struct EspNow<'w> {
+  receiver: Receiver<Message>,
+  _wifi: PhantomData<&'w ()>,
+}
+
+impl fmt::Debug for EspNow<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("EspNow").finish()
+  }
+}
+
+impl<'w> EspNow<'w> {
+  /// Initialize ESP-NOW if it is not already in use.
+  ///
+  /// Received messages are buffered in a channel holding up to `capacity` messages,
+  /// further messages are dropped until the channel has room again.
+  pub fn take<T>(_wifi: &'w Wifi<T>, capacity: usize) -> Result<Option<Self>, EspError> {
+    let (sender, receiver) = mpsc::sync_channel(capacity);
+    if STATE.set(State { sender, send_callback: Mutex::new(None) }).is_err() {
+      return Ok(None)
+    }
+
+    enter_sta_mode();
+    let espnow = EspNow { receiver, _wifi: PhantomData };
+
+    esp_ok!(esp_wifi_start())?;
+    esp_ok!(esp_now_init())?;
+    esp_ok!(esp_now_register_recv_cb(Some(espnow_recv_cb)))?;
+    esp_ok!(esp_now_register_send_cb(Some(espnow_send_cb)))?;
+
+    Ok(Some(espnow))
+  }
+
+  /// Set the primary master key used to encrypt local master keys.
+  pub fn set_pmk(&mut self, pmk: &[u8; KEY_LEN]) -> Result<(), EspError> {
+    esp_ok!(esp_now_set_pmk(pmk.as_ptr()))
+  }
+
+  /// Add a peer, or modify it if it already exists.
+  pub fn add_peer(&mut self, peer: &Peer) -> Result<(), EspError> {
+    let peer_info = peer.to_native()?;
+
+    if self.has_peer(&peer.mac) {
+      esp_ok!(esp_now_mod_peer(&peer_info))
+    } else {
+      esp_ok!(esp_now_add_peer(&peer_info))
+    }
+  }
+
+  pub fn remove_peer(&mut self, mac: &MacAddr6) -> Result<(), EspError> {
+    esp_ok!(esp_now_del_peer(mac.as_bytes().as_ptr()))
+  }
+
+  pub fn has_peer(&self, mac: &MacAddr6) -> bool {
+    unsafe { esp_now_is_peer_exist(mac.as_bytes().as_ptr()) }
+  }
+
+  /// Call `callback` with the delivery status of every sent message.
+  ///
+  /// The callback runs on the WiFi task, so it should return quickly.
+  pub fn on_send(&mut self, callback: impl FnMut(&MacAddr6, SendStatus) + Send + 'static) {
+    STATE.with(|state| {
+      *state.send_callback.lock().unwrap() = Some(Box::new(callback));
+    });
+  }
+
+  /// Send `data` to the given peer, or to all peers if `peer` is `None`.
+  pub fn send(&self, peer: Option<&MacAddr6>, data: &[u8]) -> Result<(), EspError> {
+    if data.len() > MAX_DATA_LEN {
+      return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+    }
+
+    let peer_addr = peer.map_or_else(ptr::null, |mac| mac.as_bytes().as_ptr());
+    esp_ok!(esp_now_send(peer_addr, data.as_ptr(), data.len() as _))
+  }
+
+  /// Block until the next message is received.
+  pub fn recv(&self) -> Result<Message, RecvError> {
+    self.receiver.recv()
+  }
+
+  /// Return the next message if one has already been received.
+  pub fn try_recv(&self) -> Result<Message, TryRecvError> {
+    self.receiver.try_recv()
+  }
+
+  /// Block until the next message is received or `timeout` has elapsed.
+  pub fn recv_timeout(&self, timeout: Duration) -> Result<Message, RecvTimeoutError> {
+    self.receiver.recv_timeout(timeout)
+  }
+}
+
+impl Drop for EspNow<'_> {
+  fn drop(&mut self) {
+    unsafe {
+      esp_now_unregister_recv_cb();
+      esp_now_unregister_send_cb();
+      esp_now_deinit();
+    }
+
+    leave_sta_mode();
+
+    STATE.clear();
+  }
+}
+
+fn mac_from_ptr(mac_addr: *const u8) -> MacAddr6 {
+  let mut mac = MaybeUninit::<[u8; 6]>::uninit();
+  unsafe {
+    ptr::copy_nonoverlapping(mac_addr, mac.as_mut_ptr() as *mut u8, 6);
+    MacAddr6::from(mac.assume_init())
+  }
+}
+
+extern "C" fn espnow_recv_cb(mac_addr: *const u8, data: *const u8, data_len: libc::c_int) {
+  let message = Message {
+    src: mac_from_ptr(mac_addr),
+    data: unsafe { slice::from_raw_parts(data, data_len as usize) }.to_vec(),
+  };
+
+  STATE.with(|state| {
+    let _ = state.sender.try_send(message);
+  });
+}
+
+extern "C" fn espnow_send_cb(mac_addr: *const u8, status: esp_now_send_status_t) {
+  let status = match status {
+    esp_now_send_status_t::ESP_NOW_SEND_SUCCESS => SendStatus::Success,
+    _ => SendStatus::Failed,
+  };
+
+  STATE.with(|state| {
+    // Don't panic across the FFI boundary if a callback panicked before.
+    if let Ok(mut send_callback) = state.send_callback.lock() {
+      if let Some(callback) = &mut *send_callback {
+        callback(&mac_from_ptr(mac_addr), status);
+      }
+    }
+  });
+}
@@ -0,0 +1,321 @@
+use core::ptr;
+
+use esp_idf_bindgen::{
+  _g_esp_netif_inherent_eth_config,
+  _g_esp_netif_netstack_default_eth,
+  esp_err_t,
+  esp_eth_clear_default_handlers,
+  esp_eth_config_t,
+  esp_eth_del_netif_glue,
+  esp_eth_driver_install,
+  esp_eth_driver_uninstall,
+  esp_eth_handle_t,
+  esp_eth_ioctl,
+  esp_eth_mac_new_esp32,
+  esp_eth_mac_t,
+  esp_eth_new_netif_glue,
+  esp_eth_phy_new_dp83848,
+  esp_eth_phy_new_ip101,
+  esp_eth_phy_new_lan8720,
+  esp_eth_phy_new_rtl8201,
+  esp_eth_phy_t,
+  esp_eth_set_default_handlers,
+  esp_eth_start,
+  esp_eth_stop,
+  esp_netif_attach,
+  esp_netif_config_t,
+  esp_netif_destroy,
+  esp_netif_new,
+  esp_netif_t,
+  esp_eth_io_cmd_t,
+  eth_mac_config_t,
+  eth_phy_config_t,
+  ESP_ERR_NO_MEM,
+  ESP_FAIL,
+};
+
+use macaddr::MacAddr6;
+
+use crate::EspError;
+use crate::interface::{Interface, IpInfo};
+use crate::wifi::initialize_network_interface;
+
+mod spi;
+pub use spi::*;
+
+/// PHY chips supported by the internal Ethernet MAC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phy {
+  Lan8720,
+  Ip101,
+  Rtl8201,
+  Dp83848,
+}
+
+/// Configuration for an Ethernet PHY connected to the internal MAC using RMII.
+///
+/// The RMII data pins are fixed; the clock mode is configured using `menuconfig`.
+#[derive(Debug, Clone)]
+pub struct RmiiConfig {
+  phy: Phy,
+  phy_addr: u8,
+  mdc_pin: u8,
+  mdio_pin: u8,
+  reset_pin: Option<u8>,
+}
+
+impl RmiiConfig {
+  pub fn phy(&self) -> Phy {
+    self.phy
+  }
+
+  /// The address of the PHY on the SMI bus.
+  pub fn phy_addr(&self) -> u8 {
+    self.phy_addr
+  }
+
+  pub fn mdc_pin(&self) -> u8 {
+    self.mdc_pin
+  }
+
+  pub fn mdio_pin(&self) -> u8 {
+    self.mdio_pin
+  }
+
+  /// The pin connected to the reset input of the PHY.
+  pub fn reset_pin(&self) -> Option<u8> {
+    self.reset_pin
+  }
+
+  pub fn builder() -> RmiiConfigBuilder {
+    RmiiConfigBuilder::default()
+  }
+}
+
+/// Builder for [`RmiiConfig`](struct.RmiiConfig.html).
+#[derive(Debug, Clone)]
+pub struct RmiiConfigBuilder {
+  phy: Phy,
+  phy_addr: u8,
+  mdc_pin: u8,
+  mdio_pin: u8,
+  reset_pin: Option<u8>,
+}
+
+impl Default for RmiiConfigBuilder {
+  fn default() -> Self {
+    Self {
+      phy: Phy::Lan8720,
+      phy_addr: 1,
+      mdc_pin: 23,
+      mdio_pin: 18,
+      reset_pin: None,
+    }
+  }
+}
+
+impl RmiiConfigBuilder {
+  /// Set the PHY chip. Defaults to `Phy::Lan8720`.
+  pub fn phy(&mut self, phy: Phy) -> &mut Self {
+    self.phy = phy;
+    self
+  }
+
+  /// Set the address of the PHY on the SMI bus. Defaults to `1`.
+  pub fn phy_addr(&mut self, phy_addr: u8) -> &mut Self {
+    assert!(phy_addr <= 31, "PHY address must be at most 31");
+    self.phy_addr = phy_addr;
+    self
+  }
+
+  /// Set the SMI clock pin. Defaults to GPIO 23.
+  pub fn mdc_pin(&mut self, mdc_pin: u8) -> &mut Self {
+    assert!(mdc_pin <= 33, "invalid MDC pin {}", mdc_pin);
+    self.mdc_pin = mdc_pin;
+    self
+  }
+
+  /// Set the SMI data pin. Defaults to GPIO 18.
+  pub fn mdio_pin(&mut self, mdio_pin: u8) -> &mut Self {
+    assert!(mdio_pin <= 33, "invalid MDIO pin {}", mdio_pin);
+    self.mdio_pin = mdio_pin;
+    self
+  }
+
+  /// Set the pin connected to the reset input of the PHY.
+  pub fn reset_pin(&mut self, reset_pin: impl Into<Option<u8>>) -> &mut Self {
+    let reset_pin = reset_pin.into();
+    if let Some(reset_pin) = reset_pin {
+      assert!(reset_pin <= 33, "invalid reset pin {}", reset_pin);
+    }
+    self.reset_pin = reset_pin;
+    self
+  }
+
+  pub fn build(&self) -> RmiiConfig {
+    RmiiConfig {
+      phy: self.phy,
+      phy_addr: self.phy_addr,
+      mdc_pin: self.mdc_pin,
+      mdio_pin: self.mdio_pin,
+      reset_pin: self.reset_pin,
+    }
+  }
+}
+
+fn phy_config(phy_addr: u8, reset_pin: Option<u8>) -> eth_phy_config_t {
+  eth_phy_config_t {
+    phy_addr: phy_addr as _,
+    reset_timeout_ms: 100,
+    autonego_timeout_ms: 4000,
+    reset_gpio_num: reset_pin.map(|pin| pin as i32).unwrap_or(-1),
+  }
+}
+
+/// A running Ethernet driver attached to [`Interface::Eth`](../interface/enum.Interface.html#variant.Eth).
+///
+/// Link changes and IP addresses are reported as [`InterfaceEvent`](../interface/enum.InterfaceEvent.html)s.
+/// The driver is stopped and uninstalled when dropped.
+#[derive(Debug)]
+pub struct Eth {
+  mac: *mut esp_eth_mac_t,
+  phy: *mut esp_eth_phy_t,
+  handle: esp_eth_handle_t,
+  netif: *mut esp_netif_t,
+  glue: *mut libc::c_void,
+  attached: bool,
+  started: bool,
+  _spi: Option<SpiDevice>,
+}
+
+impl Eth {
+  /// Start the internal Ethernet MAC with a PHY connected using RMII.
+  pub fn new_rmii(config: &RmiiConfig) -> Result<Self, EspError> {
+    let mac_config = eth_mac_config_t {
+      sw_reset_timeout_ms: 100,
+      rx_task_stack_size: 4096,
+      rx_task_prio: 15,
+      smi_mdc_gpio_num: config.mdc_pin as i32,
+      smi_mdio_gpio_num: config.mdio_pin as i32,
+      flags: 0,
+    };
+    let mac = unsafe { esp_eth_mac_new_esp32(&mac_config) };
+
+    let phy_config = phy_config(config.phy_addr, config.reset_pin);
+    let phy = unsafe {
+      match config.phy {
+        Phy::Lan8720 => esp_eth_phy_new_lan8720(&phy_config),
+        Phy::Ip101 => esp_eth_phy_new_ip101(&phy_config),
+        Phy::Rtl8201 => esp_eth_phy_new_rtl8201(&phy_config),
+        Phy::Dp83848 => esp_eth_phy_new_dp83848(&phy_config),
+      }
+    };
+
+    Self::start(mac, phy, None, None)
+  }
+
+  /// Install the driver for `mac` and `phy`, attach it to a new netif and start it.
+  ///
+  /// Takes ownership of `mac`, `phy` and `spi`, which are deleted if starting fails.
+  /// If `mac_addr` is given, it is assigned to the MAC before starting.
+  fn start(
+    mac: *mut esp_eth_mac_t,
+    phy: *mut esp_eth_phy_t,
+    spi: Option<SpiDevice>,
+    mac_addr: Option<MacAddr6>,
+  ) -> Result<Self, EspError> {
+    let mut eth = Eth {
+      mac, phy, handle: ptr::null_mut(), netif: ptr::null_mut(), glue: ptr::null_mut(), attached: false, started: false,
+      _spi: spi,
+    };
+
+    if mac.is_null() || phy.is_null() {
+      return Err(EspError { code: ESP_ERR_NO_MEM as esp_err_t })
+    }
+
+    initialize_network_interface();
+    crate::event::event_loop_create_default();
+
+    let netif_config = esp_netif_config_t {
+      base: unsafe { &_g_esp_netif_inherent_eth_config },
+      driver: ptr::null(),
+      stack: unsafe { _g_esp_netif_netstack_default_eth },
+    };
+    eth.netif = unsafe { esp_netif_new(&netif_config) };
+    if eth.netif.is_null() {
+      return Err(EspError { code: ESP_FAIL as esp_err_t })
+    }
+
+    Interface::attach_eth(eth.netif)?;
+    eth.attached = true;
+
+    esp_ok!(esp_eth_set_default_handlers(eth.netif as *mut _))?;
+
+    let eth_config = esp_eth_config_t {
+      mac,
+      phy,
+      check_link_period_ms: 2000,
+      stack_input: None,
+      on_lowlevel_init_done: None,
+      on_lowlevel_deinit_done: None,
+    };
+    esp_ok!(esp_eth_driver_install(&eth_config, &mut eth.handle))?;
+
+    if let Some(mac_addr) = mac_addr {
+      let mut mac_addr = mac_addr.into_array();
+      esp_ok!(esp_eth_ioctl(eth.handle, esp_eth_io_cmd_t::ETH_CMD_S_MAC_ADDR, mac_addr.as_mut_ptr() as *mut _))?;
+    }
+
+    eth.glue = unsafe { esp_eth_new_netif_glue(eth.handle) };
+    esp_ok!(esp_netif_attach(eth.netif, eth.glue))?;
+
+    esp_ok!(esp_eth_start(eth.handle))?;
+    eth.started = true;
+
+    Ok(eth)
+  }
+
+  /// Whether the Ethernet link is up.
+  pub fn is_up(&self) -> bool {
+    Interface::Eth.is_up()
+  }
+
+  pub fn ip_info(&self) -> IpInfo {
+    Interface::Eth.ip_info()
+  }
+}
+
+impl Drop for Eth {
+  fn drop(&mut self) {
+    unsafe {
+      if self.started {
+        let _ = esp_ok!(esp_eth_stop(self.handle));
+      }
+
+      if !self.glue.is_null() {
+        esp_eth_del_netif_glue(self.glue);
+      }
+
+      if !self.handle.is_null() {
+        let _ = esp_ok!(esp_eth_driver_uninstall(self.handle));
+      }
+
+      if let Some(del) = self.phy.as_ref().and_then(|phy| phy.del) {
+        del(self.phy);
+      }
+
+      if let Some(del) = self.mac.as_ref().and_then(|mac| mac.del) {
+        del(self.mac);
+      }
+
+      if !self.netif.is_null() {
+        let _ = esp_ok!(esp_eth_clear_default_handlers(self.netif as *mut _));
+        esp_netif_destroy(self.netif);
+      }
+    }
+
+    if self.attached {
+      Interface::detach_eth();
+    }
+  }
+}
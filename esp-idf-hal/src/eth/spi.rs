@@ -0,0 +1,257 @@
+use core::mem::MaybeUninit;
+use core::ptr;
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  esp_eth_mac_new_dm9051,
+  esp_eth_mac_new_w5500,
+  esp_eth_phy_new_dm9051,
+  esp_eth_phy_new_w5500,
+  esp_mac_type_t,
+  esp_read_mac,
+  eth_dm9051_config_t,
+  eth_mac_config_t,
+  eth_w5500_config_t,
+  gpio_install_isr_service,
+  spi_bus_add_device,
+  spi_bus_remove_device,
+  spi_device_handle_t,
+  spi_device_interface_config_t,
+  ESP_ERR_INVALID_STATE,
+};
+use macaddr::MacAddr6;
+
+pub use crate::spi::{SpiBus, SpiDmaChannel, SpiHost};
+use crate::EspError;
+
+use super::{phy_config, Eth};
+
+/// Ethernet controllers which can be connected using SPI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpiEthChip {
+  W5500,
+  Dm9051,
+}
+
+/// Configuration for an Ethernet controller connected using SPI.
+#[derive(Debug, Clone)]
+pub struct SpiEthConfig {
+  chip: SpiEthChip,
+  cs_pin: u8,
+  int_pin: u8,
+  reset_pin: Option<u8>,
+  clock_mhz: u8,
+  mac: Option<MacAddr6>,
+}
+
+impl SpiEthConfig {
+  pub fn chip(&self) -> SpiEthChip {
+    self.chip
+  }
+
+  pub fn cs_pin(&self) -> u8 {
+    self.cs_pin
+  }
+
+  /// The pin connected to the interrupt output of the controller.
+  pub fn int_pin(&self) -> u8 {
+    self.int_pin
+  }
+
+  /// The pin connected to the reset input of the controller.
+  pub fn reset_pin(&self) -> Option<u8> {
+    self.reset_pin
+  }
+
+  /// The SPI clock frequency in MHz.
+  pub fn clock_mhz(&self) -> u8 {
+    self.clock_mhz
+  }
+
+  /// The MAC address assigned to the controller.
+  pub fn mac(&self) -> Option<&MacAddr6> {
+    self.mac.as_ref()
+  }
+
+  pub fn builder() -> SpiEthConfigBuilder {
+    SpiEthConfigBuilder::default()
+  }
+}
+
+/// Builder for [`SpiEthConfig`](struct.SpiEthConfig.html).
+#[derive(Debug, Clone)]
+pub struct SpiEthConfigBuilder {
+  chip: SpiEthChip,
+  cs_pin: u8,
+  int_pin: u8,
+  reset_pin: Option<u8>,
+  clock_mhz: u8,
+  mac: Option<MacAddr6>,
+}
+
+impl Default for SpiEthConfigBuilder {
+  fn default() -> Self {
+    Self {
+      chip: SpiEthChip::W5500,
+      cs_pin: 15,
+      int_pin: 4,
+      reset_pin: None,
+      clock_mhz: 20,
+      mac: None,
+    }
+  }
+}
+
+fn assert_pin(name: &str, pin: u8) {
+  assert!(pin <= 39, "invalid {} pin {}", name, pin);
+}
+
+fn assert_output_pin(name: &str, pin: u8) {
+  assert!(pin <= 33, "invalid {} pin {}", name, pin);
+}
+
+impl SpiEthConfigBuilder {
+  /// Set the Ethernet controller. Defaults to `SpiEthChip::W5500`.
+  pub fn chip(&mut self, chip: SpiEthChip) -> &mut Self {
+    self.chip = chip;
+    self
+  }
+
+  /// Set the SPI chip select pin. Defaults to GPIO 15.
+  pub fn cs_pin(&mut self, cs_pin: u8) -> &mut Self {
+    assert_output_pin("CS", cs_pin);
+    self.cs_pin = cs_pin;
+    self
+  }
+
+  /// Set the pin connected to the interrupt output of the controller. Defaults to GPIO 4.
+  pub fn int_pin(&mut self, int_pin: u8) -> &mut Self {
+    assert_pin("interrupt", int_pin);
+    self.int_pin = int_pin;
+    self
+  }
+
+  /// Set the pin connected to the reset input of the controller.
+  pub fn reset_pin(&mut self, reset_pin: impl Into<Option<u8>>) -> &mut Self {
+    let reset_pin = reset_pin.into();
+    if let Some(reset_pin) = reset_pin {
+      assert_output_pin("reset", reset_pin);
+    }
+    self.reset_pin = reset_pin;
+    self
+  }
+
+  /// Set the SPI clock frequency in MHz. Defaults to 20 MHz.
+  pub fn clock_mhz(&mut self, clock_mhz: u8) -> &mut Self {
+    assert!(clock_mhz >= 1 && clock_mhz <= 80, "invalid SPI clock frequency {} MHz", clock_mhz);
+    self.clock_mhz = clock_mhz;
+    self
+  }
+
+  /// Set the MAC address assigned to the controller.
+  ///
+  /// Defaults to the Ethernet MAC address derived from the base MAC address.
+  pub fn mac(&mut self, mac: impl Into<Option<MacAddr6>>) -> &mut Self {
+    self.mac = mac.into();
+    self
+  }
+
+  pub fn build(&self) -> SpiEthConfig {
+    SpiEthConfig {
+      chip: self.chip,
+      cs_pin: self.cs_pin,
+      int_pin: self.int_pin,
+      reset_pin: self.reset_pin,
+      clock_mhz: self.clock_mhz,
+      mac: self.mac,
+    }
+  }
+}
+
+/// An SPI bus with a single device, freed when dropped.
+#[derive(Debug)]
+pub(crate) struct SpiDevice {
+  handle: spi_device_handle_t,
+  bus: SpiBus,
+}
+
+impl SpiDevice {
+  fn new(bus: SpiBus, config: &SpiEthConfig) -> Result<Self, EspError> {
+    let mut device = SpiDevice { handle: ptr::null_mut(), bus };
+
+    let (command_bits, address_bits) = match config.chip {
+      SpiEthChip::W5500 => (16, 8),
+      SpiEthChip::Dm9051 => (1, 7),
+    };
+
+    let mut device_config: spi_device_interface_config_t = unsafe { MaybeUninit::zeroed().assume_init() };
+    device_config.command_bits = command_bits;
+    device_config.address_bits = address_bits;
+    device_config.mode = 0;
+    device_config.clock_speed_hz = config.clock_mhz as i32 * 1_000_000;
+    device_config.spics_io_num = config.cs_pin as i32;
+    device_config.queue_size = 20;
+    esp_ok!(spi_bus_add_device(device.bus.host, &device_config, &mut device.handle))?;
+
+    Ok(device)
+  }
+}
+
+impl Drop for SpiDevice {
+  fn drop(&mut self) {
+    if !self.handle.is_null() {
+      let _ = esp_ok!(spi_bus_remove_device(self.handle));
+    }
+  }
+}
+
+impl Eth {
+  /// Start an Ethernet controller connected to the given SPI bus.
+  ///
+  /// The SPI bus is used exclusively by the controller until the driver is dropped.
+  pub fn new_spi(bus: SpiBus, config: &SpiEthConfig) -> Result<Self, EspError> {
+    // The driver uses a GPIO interrupt, ignore if the ISR service is already installed.
+    match esp_ok!(gpio_install_isr_service(0)) {
+      Err(err) if err.code != ESP_ERR_INVALID_STATE as esp_err_t => return Err(err),
+      _ => (),
+    }
+
+    let spi = SpiDevice::new(bus, config)?;
+
+    let mac_addr = match config.mac {
+      Some(mac_addr) => mac_addr,
+      None => {
+        let mut mac_addr = [0; 6];
+        esp_ok!(esp_read_mac(mac_addr.as_mut_ptr(), esp_mac_type_t::ESP_MAC_ETH))?;
+        MacAddr6::from(mac_addr)
+      },
+    };
+
+    let mac_config = eth_mac_config_t {
+      sw_reset_timeout_ms: 100,
+      rx_task_stack_size: 4096,
+      rx_task_prio: 15,
+      smi_mdc_gpio_num: -1,
+      smi_mdio_gpio_num: -1,
+      flags: 0,
+    };
+
+    // SPI controllers have a fixed internal PHY address.
+    let phy_config = phy_config(1, config.reset_pin);
+
+    let (mac, phy) = unsafe {
+      match config.chip {
+        SpiEthChip::W5500 => {
+          let w5500_config = eth_w5500_config_t { spi_hdl: spi.handle, int_gpio_num: config.int_pin as i32 };
+          (esp_eth_mac_new_w5500(&w5500_config, &mac_config), esp_eth_phy_new_w5500(&phy_config))
+        },
+        SpiEthChip::Dm9051 => {
+          let dm9051_config = eth_dm9051_config_t { spi_hdl: spi.handle, int_gpio_num: config.int_pin as i32 };
+          (esp_eth_mac_new_dm9051(&dm9051_config, &mac_config), esp_eth_phy_new_dm9051(&phy_config))
+        },
+      }
+    };
+
+    Self::start(mac, phy, Some(spi), Some(mac_addr))
+  }
+}
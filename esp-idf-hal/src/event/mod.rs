@@ -0,0 +1,17 @@
+use esp_idf_bindgen::esp_event_loop_create_default;
+
+use crate::init_once::InitOnce;
+
+#[cfg(target_device = "esp32")]
+mod subscription;
+#[cfg(target_device = "esp32")]
+pub use subscription::*;
+
+/// Create the default event loop if it does not exist yet.
+pub(crate) fn event_loop_create_default() {
+  static EVENT_LOOP: InitOnce<()> = InitOnce::new();
+
+  EVENT_LOOP.get_or_init(|| {
+    esp_ok!(esp_event_loop_create_default()).expect("failed to initialize default event loop");
+  });
+}
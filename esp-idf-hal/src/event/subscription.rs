@@ -0,0 +1,188 @@
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::ptr;
+use core::task::{Context, Poll, Waker};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver, RecvError, RecvTimeoutError, TryRecvError};
+use std::time::Duration;
+
+use esp_idf_bindgen::{
+  esp_event_base_t,
+  esp_event_handler_instance_t,
+  esp_event_handler_instance_register,
+  esp_event_handler_instance_unregister,
+  ESP_EVENT_ANY_ID,
+};
+
+use crate::EspError;
+use super::event_loop_create_default;
+
+type Callback = Box<dyn FnMut(esp_event_base_t, i32, *mut libc::c_void) + Send>;
+
+/// A subscription to events on the default event loop.
+///
+/// The event handler is unregistered when the subscription is dropped.
+#[must_use = "the event handler is unregistered immediately if the subscription is dropped"]
+pub struct Subscription {
+  instances: Vec<(esp_event_base_t, esp_event_handler_instance_t)>,
+  callback: *mut Callback,
+}
+
+unsafe impl Send for Subscription {}
+
+impl fmt::Debug for Subscription {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Subscription")
+      .field("instances", &self.instances.len())
+      .finish()
+  }
+}
+
+impl Subscription {
+  /// Call `callback` with the raw event ID and data for every event posted for one of `bases`.
+  pub(crate) fn new<F>(bases: &[esp_event_base_t], callback: F) -> Result<Self, EspError>
+  where
+    F: FnMut(esp_event_base_t, i32, *mut libc::c_void) + Send + 'static,
+  {
+    event_loop_create_default();
+
+    let callback: *mut Callback = Box::into_raw(Box::new(Box::new(callback)));
+    let mut subscription = Self { instances: Vec::with_capacity(bases.len()), callback };
+
+    for &base in bases {
+      let mut instance = ptr::null_mut();
+      esp_ok!(esp_event_handler_instance_register(
+        base, ESP_EVENT_ANY_ID, Some(event_handler), callback as *mut _, &mut instance,
+      ))?;
+      subscription.instances.push((base, instance));
+    }
+
+    Ok(subscription)
+  }
+}
+
+impl Drop for Subscription {
+  fn drop(&mut self) {
+    for (base, instance) in self.instances.drain(..) {
+      let _ = esp_ok!(esp_event_handler_instance_unregister(base, ESP_EVENT_ANY_ID, instance));
+    }
+
+    drop(unsafe { Box::from_raw(self.callback) });
+  }
+}
+
+extern "C" fn event_handler(
+  event_handler_arg: *mut libc::c_void,
+  event_base: esp_event_base_t,
+  event_id: i32,
+  event_data: *mut libc::c_void,
+) {
+  let callback = unsafe { &mut *(event_handler_arg as *mut Callback) };
+  callback(event_base, event_id, event_data);
+}
+
+/// A channel receiving events for as long as its [`Subscription`](struct.Subscription.html) is alive.
+#[derive(Debug)]
+pub struct EventReceiver<T> {
+  _subscription: Subscription,
+  receiver: Receiver<T>,
+}
+
+impl<T: Send + 'static> EventReceiver<T> {
+  /// Forward every event which `parse` converts successfully to the returned receiver.
+  pub(crate) fn new<F>(bases: &[esp_event_base_t], mut parse: F) -> Result<Self, EspError>
+  where
+    F: FnMut(esp_event_base_t, i32, *mut libc::c_void) -> Option<T> + Send + 'static,
+  {
+    let (sender, receiver) = mpsc::channel();
+
+    let subscription = Subscription::new(bases, move |event_base, event_id, event_data| {
+      if let Some(event) = parse(event_base, event_id, event_data) {
+        let _ = sender.send(event);
+      }
+    })?;
+
+    Ok(Self { _subscription: subscription, receiver })
+  }
+}
+
+impl<T> EventReceiver<T> {
+  /// Block until the next event is received.
+  pub fn recv(&self) -> Result<T, RecvError> {
+    self.receiver.recv()
+  }
+
+  /// Return the next event if one has already been received.
+  pub fn try_recv(&self) -> Result<T, TryRecvError> {
+    self.receiver.try_recv()
+  }
+
+  /// Block until the next event is received or `timeout` has elapsed.
+  pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+    self.receiver.recv_timeout(timeout)
+  }
+}
+
+#[derive(Debug)]
+struct EventFutureState<T> {
+  output: Option<T>,
+  waker: Option<Waker>,
+}
+
+/// A future resolving with the first event which `parse` converts successfully.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct EventFuture<T> {
+  state: Arc<Mutex<EventFutureState<T>>>,
+  subscription: Option<Subscription>,
+}
+
+impl<T: Send + 'static> EventFuture<T> {
+  pub(crate) fn new<F>(bases: &[esp_event_base_t], mut parse: F) -> Result<Self, EspError>
+  where
+    F: FnMut(esp_event_base_t, i32, *mut libc::c_void) -> Option<T> + Send + 'static,
+  {
+    let state = Arc::new(Mutex::new(EventFutureState { output: None, waker: None }));
+    let handler_state = Arc::clone(&state);
+
+    let subscription = Subscription::new(bases, move |event_base, event_id, event_data| {
+      let mut state = handler_state.lock().unwrap();
+
+      if state.output.is_some() {
+        return
+      }
+
+      if let Some(output) = parse(event_base, event_id, event_data) {
+        state.output = Some(output);
+
+        if let Some(waker) = state.waker.take() {
+          waker.wake();
+        }
+      }
+    })?;
+
+    Ok(Self { state, subscription: Some(subscription) })
+  }
+}
+
+impl<T> Future for EventFuture<T> {
+  type Output = T;
+
+  fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+    let output = {
+      let mut state = self.state.lock().unwrap();
+
+      match state.output.take() {
+        Some(output) => output,
+        None => {
+          state.waker = Some(cx.waker().clone());
+          return Poll::Pending
+        },
+      }
+    };
+
+    self.subscription = None;
+    Poll::Ready(output)
+  }
+}
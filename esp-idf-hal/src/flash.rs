@@ -0,0 +1,209 @@
+use core::ptr;
+use core::slice;
+
+use esp_idf_bindgen::{
+  esp_flash_erase_chip,
+  esp_flash_erase_region,
+  esp_flash_get_chip_write_protect,
+  esp_flash_get_protectable_regions,
+  esp_flash_get_protected_region,
+  esp_flash_get_size,
+  esp_flash_init,
+  esp_flash_io_mode_t,
+  esp_flash_read,
+  esp_flash_read_id,
+  esp_flash_region_t,
+  esp_flash_set_protected_region,
+  esp_flash_speed_t,
+  esp_flash_spi_device_config_t,
+  esp_flash_t,
+  esp_flash_write,
+  spi_bus_add_flash_device,
+  spi_bus_remove_flash_device,
+};
+
+use crate::EspError;
+use crate::spi::SpiBus;
+
+/// The SPI mode used to read from an external flash chip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlashIoMode {
+  /// Single I/O, low speed read.
+  SlowRead,
+  /// Single I/O, fast read.
+  FastRead,
+  /// Dual output.
+  Dout,
+  /// Dual I/O.
+  Dio,
+  /// Quad output.
+  Qout,
+  /// Quad I/O.
+  Qio,
+}
+
+impl From<FlashIoMode> for esp_flash_io_mode_t {
+  fn from(io_mode: FlashIoMode) -> Self {
+    match io_mode {
+      FlashIoMode::SlowRead => esp_flash_io_mode_t::SPI_FLASH_SLOWRD,
+      FlashIoMode::FastRead => esp_flash_io_mode_t::SPI_FLASH_FASTRD,
+      FlashIoMode::Dout => esp_flash_io_mode_t::SPI_FLASH_DOUT,
+      FlashIoMode::Dio => esp_flash_io_mode_t::SPI_FLASH_DIO,
+      FlashIoMode::Qout => esp_flash_io_mode_t::SPI_FLASH_QOUT,
+      FlashIoMode::Qio => esp_flash_io_mode_t::SPI_FLASH_QIO,
+    }
+  }
+}
+
+/// The SPI clock frequency used for an external flash chip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlashSpeed {
+  Mhz5,
+  Mhz10,
+  Mhz20,
+  Mhz26,
+  Mhz40,
+  Mhz80,
+}
+
+impl From<FlashSpeed> for esp_flash_speed_t {
+  fn from(speed: FlashSpeed) -> Self {
+    match speed {
+      FlashSpeed::Mhz5 => esp_flash_speed_t::ESP_FLASH_5MHZ,
+      FlashSpeed::Mhz10 => esp_flash_speed_t::ESP_FLASH_10MHZ,
+      FlashSpeed::Mhz20 => esp_flash_speed_t::ESP_FLASH_20MHZ,
+      FlashSpeed::Mhz26 => esp_flash_speed_t::ESP_FLASH_26MHZ,
+      FlashSpeed::Mhz40 => esp_flash_speed_t::ESP_FLASH_40MHZ,
+      FlashSpeed::Mhz80 => esp_flash_speed_t::ESP_FLASH_80MHZ,
+    }
+  }
+}
+
+/// A region of a flash chip which can be write protected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlashRegion {
+  offset: u32,
+  size: u32,
+}
+
+impl FlashRegion {
+  pub fn offset(&self) -> u32 {
+    self.offset
+  }
+
+  pub fn size(&self) -> u32 {
+    self.size
+  }
+
+  fn to_native(&self) -> esp_flash_region_t {
+    esp_flash_region_t { offset: self.offset, size: self.size }
+  }
+}
+
+/// An external SPI NOR flash chip. The chip is removed from the bus when this is dropped.
+#[derive(Debug)]
+pub struct ExternalFlash {
+  chip: *mut esp_flash_t,
+  _bus: SpiBus,
+}
+
+unsafe impl Send for ExternalFlash {}
+
+impl ExternalFlash {
+  /// Add a flash chip with chip select `cs_pin` to `bus` and probe it.
+  ///
+  /// The SPI bus is used exclusively by the flash chip until this is dropped.
+  pub fn new(bus: SpiBus, cs_pin: u8, io_mode: FlashIoMode, speed: FlashSpeed) -> Result<Self, EspError> {
+    assert!(cs_pin <= 33, "invalid CS pin {}", cs_pin);
+
+    let config = esp_flash_spi_device_config_t {
+      host_id: bus.host,
+      cs_io_num: cs_pin as i32,
+      io_mode: io_mode.into(),
+      speed: speed.into(),
+      input_delay_ns: 0,
+      cs_id: 0,
+    };
+
+    let mut chip = ptr::null_mut();
+    esp_ok!(spi_bus_add_flash_device(&mut chip, &config))?;
+    let flash = Self { chip, _bus: bus };
+
+    esp_ok!(esp_flash_init(flash.chip))?;
+
+    Ok(flash)
+  }
+
+  /// The JEDEC manufacturer and device ID of the chip.
+  pub fn id(&self) -> Result<u32, EspError> {
+    let mut id = 0;
+    esp_ok!(esp_flash_read_id(self.chip, &mut id))?;
+    Ok(id)
+  }
+
+  /// The size of the chip in bytes.
+  pub fn size(&self) -> Result<u32, EspError> {
+    let mut size = 0;
+    esp_ok!(esp_flash_get_size(self.chip, &mut size))?;
+    Ok(size)
+  }
+
+  /// Read `buf.len()` bytes starting at `address`.
+  pub fn read(&self, address: u32, buf: &mut [u8]) -> Result<(), EspError> {
+    esp_ok!(esp_flash_read(self.chip, buf.as_mut_ptr() as *mut _, address, buf.len() as u32))
+  }
+
+  /// Write `data` starting at `address`. The range must be erased first.
+  pub fn write(&mut self, address: u32, data: &[u8]) -> Result<(), EspError> {
+    esp_ok!(esp_flash_write(self.chip, data.as_ptr() as *const _, address, data.len() as u32))
+  }
+
+  /// Erase `len` bytes starting at `address`. Both must be multiples of the sector size.
+  pub fn erase_region(&mut self, address: u32, len: u32) -> Result<(), EspError> {
+    esp_ok!(esp_flash_erase_region(self.chip, address, len))
+  }
+
+  /// Erase the whole chip.
+  pub fn erase_chip(&mut self) -> Result<(), EspError> {
+    esp_ok!(esp_flash_erase_chip(self.chip))
+  }
+
+  /// Whether the whole chip is write protected.
+  pub fn is_write_protected(&self) -> Result<bool, EspError> {
+    let mut write_protected = false;
+    esp_ok!(esp_flash_get_chip_write_protect(self.chip, &mut write_protected))?;
+    Ok(write_protected)
+  }
+
+  /// The regions of the chip which can be write protected individually.
+  pub fn protectable_regions(&self) -> Result<Vec<FlashRegion>, EspError> {
+    let mut regions: *const esp_flash_region_t = ptr::null();
+    let mut len = 0;
+    esp_ok!(esp_flash_get_protectable_regions(self.chip, &mut regions, &mut len))?;
+
+    if regions.is_null() {
+      return Ok(Vec::new())
+    }
+
+    let regions = unsafe { slice::from_raw_parts(regions, len as usize) };
+    Ok(regions.iter().map(|region| FlashRegion { offset: region.offset, size: region.size }).collect())
+  }
+
+  /// Whether `region` is write protected.
+  pub fn is_region_protected(&self, region: &FlashRegion) -> Result<bool, EspError> {
+    let mut protected = false;
+    esp_ok!(esp_flash_get_protected_region(self.chip, &region.to_native(), &mut protected))?;
+    Ok(protected)
+  }
+
+  /// Enable or disable write protection for `region`.
+  pub fn set_region_protected(&mut self, region: &FlashRegion, protected: bool) -> Result<(), EspError> {
+    esp_ok!(esp_flash_set_protected_region(self.chip, &region.to_native(), protected))
+  }
+}
+
+impl Drop for ExternalFlash {
+  fn drop(&mut self) {
+    let _ = esp_ok!(spi_bus_remove_flash_device(self.chip));
+  }
+}
@@ -0,0 +1,128 @@
+use std::ffi::{CStr, CString};
+use std::mem::MaybeUninit;
+
+use esp_idf_bindgen::{
+  esp_vfs_fat_mount_config_t,
+  esp_vfs_fat_spiflash_mount,
+  esp_vfs_fat_spiflash_unmount,
+  wl_handle_t,
+  wl_sector_size,
+  wl_size,
+};
+
+use crate::EspError;
+
+use super::c_string;
+
+/// Options for mounting a FAT filesystem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FatMountConfig {
+  max_files: usize,
+  format_if_mount_failed: bool,
+  allocation_unit_size: usize,
+}
+
+impl Default for FatMountConfig {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl FatMountConfig {
+  /// Create a mount configuration allowing 5 open files, which does not format the filesystem.
+  pub fn new() -> Self {
+    Self { max_files: 5, format_if_mount_failed: false, allocation_unit_size: 0 }
+  }
+
+  /// Set the maximum number of files which can be open at the same time.
+  pub fn with_max_files(mut self, max_files: usize) -> Self {
+    self.max_files = max_files;
+    self
+  }
+
+  /// Format the partition if it cannot be mounted, e.g. because it was never formatted.
+  ///
+  /// All files on the partition are lost when it is formatted.
+  pub fn with_format_if_mount_failed(mut self, format_if_mount_failed: bool) -> Self {
+    self.format_if_mount_failed = format_if_mount_failed;
+    self
+  }
+
+  /// Set the allocation unit size in bytes used when formatting. A larger allocation unit
+  /// improves performance but wastes more space for small files.
+  ///
+  /// Defaults to the sector size.
+  pub fn with_allocation_unit_size(mut self, allocation_unit_size: usize) -> Self {
+    self.allocation_unit_size = allocation_unit_size;
+    self
+  }
+
+  pub fn max_files(&self) -> usize {
+    self.max_files
+  }
+
+  pub fn format_if_mount_failed(&self) -> bool {
+    self.format_if_mount_failed
+  }
+
+  pub fn allocation_unit_size(&self) -> usize {
+    self.allocation_unit_size
+  }
+
+  pub(crate) fn to_native(&self) -> esp_vfs_fat_mount_config_t {
+    esp_vfs_fat_mount_config_t {
+      format_if_mount_failed: self.format_if_mount_failed,
+      max_files: self.max_files as _,
+      allocation_unit_size: self.allocation_unit_size as _,
+    }
+  }
+}
+
+/// A FAT filesystem on a wear levelling flash partition. The filesystem is unmounted when this is dropped.
+#[derive(Debug)]
+pub struct FatFs {
+  base_path: CString,
+  wl_handle: wl_handle_t,
+}
+
+/// Mount the FAT filesystem on the flash partition `partition_label` at `base_path`, e.g. `/fat`,
+/// so it can be accessed using `std::fs`.
+///
+/// The partition must have subtype `fat`. Wear levelling is used to spread writes across the partition.
+pub fn mount_wl(partition_label: &str, base_path: &str, config: &FatMountConfig) -> Result<FatFs, EspError> {
+  let partition_label = c_string(partition_label)?;
+  let base_path = c_string(base_path)?;
+
+  let mut wl_handle = MaybeUninit::<wl_handle_t>::uninit();
+  esp_ok!(esp_vfs_fat_spiflash_mount(
+    base_path.as_ptr(),
+    partition_label.as_ptr(),
+    &config.to_native(),
+    wl_handle.as_mut_ptr(),
+  ))?;
+
+  Ok(FatFs { base_path, wl_handle: unsafe { wl_handle.assume_init() } })
+}
+
+impl FatFs {
+  /// The path this filesystem is mounted at.
+  pub fn base_path(&self) -> &CStr {
+    &self.base_path
+  }
+
+  /// The size in bytes available to the filesystem, excluding the wear levelling metadata.
+  pub fn wl_size(&self) -> usize {
+    unsafe { wl_size(self.wl_handle) as usize }
+  }
+
+  /// The sector size in bytes used by the wear levelling layer.
+  pub fn wl_sector_size(&self) -> usize {
+    unsafe { wl_sector_size(self.wl_handle) as usize }
+  }
+}
+
+impl Drop for FatFs {
+  fn drop(&mut self) {
+    let _ = esp_ok!(esp_vfs_fat_spiflash_unmount(self.base_path.as_ptr(), self.wl_handle));
+  }
+}
@@ -0,0 +1,104 @@
+use std::ffi::{CStr, CString};
+
+use esp_idf_bindgen::esp_err_t;
+
+use crate::EspError;
+
+use super::c_string;
+
+// `esp_littlefs` is not part of ESP-IDF, so there are no generated bindings for it.
+#[allow(non_camel_case_types)]
+#[repr(C)]
+struct esp_vfs_littlefs_conf_t {
+  base_path: *const libc::c_char,
+  partition_label: *const libc::c_char,
+  /// Bit 0 is `format_if_mount_failed`.
+  flags: u8,
+}
+
+extern "C" {
+  fn esp_vfs_littlefs_register(conf: *const esp_vfs_littlefs_conf_t) -> esp_err_t;
+  fn esp_vfs_littlefs_unregister(partition_label: *const libc::c_char) -> esp_err_t;
+  fn esp_littlefs_format(partition_label: *const libc::c_char) -> esp_err_t;
+  fn esp_littlefs_info(partition_label: *const libc::c_char, total_bytes: *mut usize, used_bytes: *mut usize) -> esp_err_t;
+}
+
+/// Usage information of a LittleFS partition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LittleFsInfo {
+  total: usize,
+  used: usize,
+}
+
+impl LittleFsInfo {
+  /// The total size in bytes.
+  pub fn total(&self) -> usize {
+    self.total
+  }
+
+  /// The used size in bytes.
+  pub fn used(&self) -> usize {
+    self.used
+  }
+}
+
+/// A mounted LittleFS partition. The partition is unmounted when this is dropped.
+#[derive(Debug)]
+pub struct LittleFs {
+  partition_label: CString,
+  base_path: CString,
+}
+
+/// Mount the LittleFS partition `partition_label` at `base_path`, e.g. `/littlefs`,
+/// so it can be accessed using `std::fs`.
+///
+/// If `format_if_mount_failed` is `true`, the partition is formatted if it cannot be mounted,
+/// e.g. because it was never formatted.
+pub fn mount(partition_label: &str, base_path: &str, format_if_mount_failed: bool) -> Result<LittleFs, EspError> {
+  let partition_label = c_string(partition_label)?;
+  let base_path = c_string(base_path)?;
+
+  let conf = esp_vfs_littlefs_conf_t {
+    base_path: base_path.as_ptr(),
+    partition_label: partition_label.as_ptr(),
+    flags: format_if_mount_failed as u8,
+  };
+
+  esp_ok!(esp_vfs_littlefs_register(&conf))?;
+
+  Ok(LittleFs { partition_label, base_path })
+}
+
+/// Format an unmounted LittleFS partition. All files on the partition are lost.
+pub fn format(partition_label: &str) -> Result<(), EspError> {
+  let partition_label = c_string(partition_label)?;
+  esp_ok!(esp_littlefs_format(partition_label.as_ptr()))
+}
+
+impl LittleFs {
+  /// The path this partition is mounted at.
+  pub fn base_path(&self) -> &CStr {
+    &self.base_path
+  }
+
+  /// Get the total and used size of this partition.
+  pub fn info(&self) -> Result<LittleFsInfo, EspError> {
+    let mut total = 0;
+    let mut used = 0;
+    esp_ok!(esp_littlefs_info(self.partition_label.as_ptr(), &mut total, &mut used))?;
+    Ok(LittleFsInfo { total, used })
+  }
+
+  /// Format this partition. All files on the partition are lost.
+  ///
+  /// The partition stays mounted, but all open files must be closed first.
+  pub fn format(&mut self) -> Result<(), EspError> {
+    esp_ok!(esp_littlefs_format(self.partition_label.as_ptr()))
+  }
+}
+
+impl Drop for LittleFs {
+  fn drop(&mut self) {
+    let _ = esp_ok!(esp_vfs_littlefs_unregister(self.partition_label.as_ptr()));
+  }
+}
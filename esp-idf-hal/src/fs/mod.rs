@@ -0,0 +1,17 @@
+use std::ffi::CString;
+
+use esp_idf_bindgen::{esp_err_t, ESP_ERR_INVALID_ARG};
+
+use crate::EspError;
+
+pub mod spiffs;
+#[cfg(target_device = "esp32")]
+pub mod fatfs;
+#[cfg(feature = "littlefs")]
+pub mod littlefs;
+#[cfg(target_device = "esp32")]
+pub mod vfs;
+
+pub(crate) fn c_string(s: &str) -> Result<CString, EspError> {
+  CString::new(s).map_err(|_| EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+}
@@ -0,0 +1,102 @@
+use core::ptr;
+use std::ffi::{CStr, CString};
+
+use esp_idf_bindgen::{
+  esp_spiffs_format,
+  esp_spiffs_info,
+  esp_vfs_spiffs_conf_t,
+  esp_vfs_spiffs_register,
+  esp_vfs_spiffs_unregister,
+};
+
+use crate::EspError;
+
+use super::c_string;
+
+/// Usage information of a SPIFFS partition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpiffsInfo {
+  total: usize,
+  used: usize,
+}
+
+impl SpiffsInfo {
+  /// The total size in bytes.
+  pub fn total(&self) -> usize {
+    self.total
+  }
+
+  /// The used size in bytes.
+  pub fn used(&self) -> usize {
+    self.used
+  }
+}
+
+/// A mounted SPIFFS partition. The partition is unmounted when this is dropped.
+#[derive(Debug)]
+pub struct Spiffs {
+  partition_label: Option<CString>,
+  base_path: CString,
+}
+
+fn label_ptr(partition_label: &Option<CString>) -> *const libc::c_char {
+  partition_label.as_ref().map_or(ptr::null(), |label| label.as_ptr())
+}
+
+/// Mount a SPIFFS partition at `base_path`, e.g. `/spiffs`, so it can be accessed using `std::fs`.
+///
+/// If `partition_label` is `None`, the first partition with subtype `spiffs` is used.
+/// `max_files` is the maximum number of files which can be open at the same time.
+///
+/// Mounting fails if the partition is not formatted, see [`format`](fn.format.html).
+pub fn mount(partition_label: Option<&str>, base_path: &str, max_files: usize) -> Result<Spiffs, EspError> {
+  let partition_label = partition_label.map(c_string).transpose()?;
+  let base_path = c_string(base_path)?;
+
+  let conf = esp_vfs_spiffs_conf_t {
+    base_path: base_path.as_ptr(),
+    partition_label: label_ptr(&partition_label),
+    max_files: max_files as _,
+    format_if_mount_failed: false,
+  };
+
+  esp_ok!(esp_vfs_spiffs_register(&conf))?;
+
+  Ok(Spiffs { partition_label, base_path })
+}
+
+/// Format an unmounted SPIFFS partition. All files on the partition are lost.
+///
+/// If `partition_label` is `None`, the first partition with subtype `spiffs` is used.
+pub fn format(partition_label: Option<&str>) -> Result<(), EspError> {
+  let partition_label = partition_label.map(c_string).transpose()?;
+  esp_ok!(esp_spiffs_format(label_ptr(&partition_label)))
+}
+
+impl Spiffs {
+  /// The path this partition is mounted at.
+  pub fn base_path(&self) -> &CStr {
+    &self.base_path
+  }
+
+  /// Get the total and used size of this partition.
+  pub fn info(&self) -> Result<SpiffsInfo, EspError> {
+    let mut total = 0;
+    let mut used = 0;
+    esp_ok!(esp_spiffs_info(label_ptr(&self.partition_label), &mut total, &mut used))?;
+    Ok(SpiffsInfo { total: total as usize, used: used as usize })
+  }
+
+  /// Format this partition. All files on the partition are lost.
+  ///
+  /// The partition stays mounted, but all open files must be closed first.
+  pub fn format(&mut self) -> Result<(), EspError> {
+    esp_ok!(esp_spiffs_format(label_ptr(&self.partition_label)))
+  }
+}
+
+impl Drop for Spiffs {
+  fn drop(&mut self) {
+    let _ = esp_ok!(esp_vfs_spiffs_unregister(label_ptr(&self.partition_label)));
+  }
+}
@@ -0,0 +1,142 @@
+use core::mem::MaybeUninit;
+use core::slice;
+use std::ffi::{CStr, CString};
+use std::io;
+use std::str;
+
+use esp_idf_bindgen::{esp_vfs_register, esp_vfs_t, esp_vfs_unregister, ESP_VFS_FLAG_CONTEXT_PTR};
+
+use crate::EspError;
+
+use super::c_string;
+
+/// A filesystem implemented in Rust, see [`register`](fn.register.html).
+///
+/// File descriptors are local to the driver, i.e. each driver may start numbering them at `0`.
+/// Errors are passed to the caller as their raw OS error code, or `EIO` if they have none.
+pub trait VfsDriver: Send + Sync + 'static {
+  /// Open the file at `path`, relative to the base path of the filesystem. Returns a file descriptor.
+  fn open(&self, path: &str, flags: i32, mode: i32) -> io::Result<i32>;
+
+  /// Read from the file `fd` into `buf`. Returns the number of bytes read.
+  fn read(&self, fd: i32, buf: &mut [u8]) -> io::Result<usize>;
+
+  /// Write `buf` to the file `fd`. Returns the number of bytes written.
+  ///
+  /// The default implementation fails with `EROFS`.
+  fn write(&self, fd: i32, buf: &[u8]) -> io::Result<usize> {
+    let _ = (fd, buf);
+    Err(io::Error::from_raw_os_error(libc::EROFS))
+  }
+
+  /// Move the position of the file `fd`. Returns the new position.
+  ///
+  /// The default implementation fails with `ESPIPE`.
+  fn lseek(&self, fd: i32, offset: i64, whence: i32) -> io::Result<i64> {
+    let _ = (fd, offset, whence);
+    Err(io::Error::from_raw_os_error(libc::ESPIPE))
+  }
+
+  /// Close the file `fd`.
+  fn close(&self, fd: i32) -> io::Result<()>;
+
+  /// Perform the device specific request `cmd` on the file `fd`. Additional arguments are not passed on.
+  ///
+  /// The default implementation fails with `ENOSYS`.
+  fn ioctl(&self, fd: i32, cmd: i32) -> io::Result<i32> {
+    let _ = (fd, cmd);
+    Err(io::Error::from_raw_os_error(libc::ENOSYS))
+  }
+}
+
+type Driver = Box<dyn VfsDriver>;
+
+/// A registered [`VfsDriver`](trait.VfsDriver.html). The driver is unregistered when this is dropped.
+#[derive(Debug)]
+pub struct Vfs {
+  base_path: CString,
+  driver: *mut Driver,
+}
+
+unsafe impl Send for Vfs {}
+
+/// Register `driver` at `base_path`, e.g. `/assets`, so it can be accessed using `std::fs`.
+///
+/// The base path must start with `/` and must not end with `/`.
+pub fn register(base_path: &str, driver: impl VfsDriver) -> Result<Vfs, EspError> {
+  let base_path = c_string(base_path)?;
+
+  let driver: *mut Driver = Box::into_raw(Box::new(Box::new(driver)));
+
+  let mut vfs: esp_vfs_t = unsafe { MaybeUninit::zeroed().assume_init() };
+  vfs.flags = ESP_VFS_FLAG_CONTEXT_PTR as _;
+  vfs.__bindgen_anon_1.write_p = Some(vfs_write);
+  vfs.__bindgen_anon_2.lseek_p = Some(vfs_lseek);
+  vfs.__bindgen_anon_3.read_p = Some(vfs_read);
+  vfs.__bindgen_anon_6.open_p = Some(vfs_open);
+  vfs.__bindgen_anon_7.close_p = Some(vfs_close);
+  vfs.__bindgen_anon_22.ioctl_p = Some(vfs_ioctl);
+
+  if let Err(err) = esp_ok!(esp_vfs_register(base_path.as_ptr(), &vfs, driver as *mut _)) {
+    drop(unsafe { Box::from_raw(driver) });
+    return Err(err)
+  }
+
+  Ok(Vfs { base_path, driver })
+}
+
+impl Vfs {
+  /// The path this driver is registered at.
+  pub fn base_path(&self) -> &CStr {
+    &self.base_path
+  }
+}
+
+impl Drop for Vfs {
+  fn drop(&mut self) {
+    let _ = esp_ok!(esp_vfs_unregister(self.base_path.as_ptr()));
+    drop(unsafe { Box::from_raw(self.driver) });
+  }
+}
+
+fn set_errno(err: io::Error) {
+  unsafe { *libc::__errno() = err.raw_os_error().unwrap_or(libc::EIO) };
+}
+
+unsafe fn driver<'a>(ctx: *mut libc::c_void) -> &'a dyn VfsDriver {
+  &**(ctx as *const Driver)
+}
+
+unsafe extern "C" fn vfs_open(ctx: *mut libc::c_void, path: *const libc::c_char, flags: libc::c_int, mode: libc::c_int) -> libc::c_int {
+  let path = match str::from_utf8(CStr::from_ptr(path).to_bytes()) {
+    Ok(path) => path,
+    Err(_) => {
+      set_errno(io::Error::from_raw_os_error(libc::ENOENT));
+      return -1
+    },
+  };
+
+  driver(ctx).open(path, flags, mode).unwrap_or_else(|err| { set_errno(err); -1 })
+}
+
+unsafe extern "C" fn vfs_read(ctx: *mut libc::c_void, fd: libc::c_int, dst: *mut libc::c_void, size: usize) -> isize {
+  let buf = slice::from_raw_parts_mut(dst as *mut u8, size);
+  driver(ctx).read(fd, buf).map(|len| len as isize).unwrap_or_else(|err| { set_errno(err); -1 })
+}
+
+unsafe extern "C" fn vfs_write(ctx: *mut libc::c_void, fd: libc::c_int, data: *const libc::c_void, size: usize) -> isize {
+  let buf = slice::from_raw_parts(data as *const u8, size);
+  driver(ctx).write(fd, buf).map(|len| len as isize).unwrap_or_else(|err| { set_errno(err); -1 })
+}
+
+unsafe extern "C" fn vfs_lseek(ctx: *mut libc::c_void, fd: libc::c_int, offset: libc::off_t, whence: libc::c_int) -> libc::off_t {
+  driver(ctx).lseek(fd, offset as i64, whence).map(|offset| offset as libc::off_t).unwrap_or_else(|err| { set_errno(err); -1 })
+}
+
+unsafe extern "C" fn vfs_close(ctx: *mut libc::c_void, fd: libc::c_int) -> libc::c_int {
+  driver(ctx).close(fd).map(|()| 0).unwrap_or_else(|err| { set_errno(err); -1 })
+}
+
+unsafe extern "C" fn vfs_ioctl(ctx: *mut libc::c_void, fd: libc::c_int, cmd: libc::c_int, _args: esp_idf_bindgen::va_list) -> libc::c_int {
+  driver(ctx).ioctl(fd, cmd).unwrap_or_else(|err| { set_errno(err); -1 })
+}
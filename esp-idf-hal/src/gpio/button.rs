@@ -0,0 +1,307 @@
+use core::fmt;
+use std::sync::mpsc::{self, Receiver, RecvError, RecvTimeoutError, TryRecvError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  gpio_get_level,
+  ESP_FAIL,
+};
+
+use crate::EspError;
+
+use super::{gpio_num, Input, InterruptSubscription, InterruptType, Pin, Pull};
+
+/// An event of a [`Button`](struct.Button.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonEvent {
+  /// The button was pressed.
+  Pressed,
+  /// The button was released.
+  Released,
+  /// The button was released after a short press and not pressed again within the double-click time.
+  Click,
+  /// The button was pressed and released twice within the double-click time.
+  DoubleClick,
+  /// The button has been held down for the long-press time. It is not followed by a
+  /// [`Click`](#variant.Click) when it is released.
+  LongPress,
+}
+
+/// Options for a [`Button`](struct.Button.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ButtonConfig {
+  debounce: Duration,
+  long_press: Duration,
+  double_click: Duration,
+  active_low: bool,
+  pull: Pull,
+}
+
+impl Default for ButtonConfig {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl ButtonConfig {
+  /// Create a configuration for a button connecting the pin to GND, using the internal pull-up,
+  /// a debounce time of 20 ms, a long-press time of 1 s and a double-click time of 300 ms.
+  pub fn new() -> Self {
+    Self {
+      debounce: Duration::from_millis(20),
+      long_press: Duration::from_secs(1),
+      double_click: Duration::from_millis(300),
+      active_low: true,
+      pull: Pull::Up,
+    }
+  }
+
+  /// Set the time the level must be stable before a change is accepted.
+  pub fn with_debounce(mut self, debounce: Duration) -> Self {
+    self.debounce = debounce;
+    self
+  }
+
+  /// Set the time after which a held button emits a [`LongPress`](enum.ButtonEvent.html#variant.LongPress).
+  pub fn with_long_press(mut self, long_press: Duration) -> Self {
+    self.long_press = long_press;
+    self
+  }
+
+  /// Set the maximum time between two clicks of a [`DoubleClick`](enum.ButtonEvent.html#variant.DoubleClick).
+  ///
+  /// A single [`Click`](enum.ButtonEvent.html#variant.Click) is delayed by this time.
+  pub fn with_double_click(mut self, double_click: Duration) -> Self {
+    self.double_click = double_click;
+    self
+  }
+
+  /// Set whether the pin is low while the button is pressed.
+  pub fn with_active_low(mut self, active_low: bool) -> Self {
+    self.active_low = active_low;
+    self
+  }
+
+  /// Set the internal pull resistors of the pin.
+  pub fn with_pull(mut self, pull: Pull) -> Self {
+    self.pull = pull;
+    self
+  }
+
+  pub fn debounce(&self) -> Duration {
+    self.debounce
+  }
+
+  pub fn long_press(&self) -> Duration {
+    self.long_press
+  }
+
+  pub fn double_click(&self) -> Duration {
+    self.double_click
+  }
+
+  pub fn active_low(&self) -> bool {
+    self.active_low
+  }
+
+  pub fn pull(&self) -> Pull {
+    self.pull
+  }
+}
+
+/// The debounce state machine run by the button thread.
+struct Detector {
+  config: ButtonConfig,
+  pin: u8,
+  pressed: bool,
+  pressed_at: Instant,
+  long_pressed: bool,
+  clicked_at: Option<Instant>,
+}
+
+impl Detector {
+  fn is_pressed(&self) -> bool {
+    let high = unsafe { gpio_get_level(gpio_num(self.pin)) != 0 };
+    high != self.config.active_low
+  }
+
+  /// The time until the next long press or click is due.
+  fn timeout(&self, now: Instant) -> Duration {
+    let long_press = if self.pressed && !self.long_pressed { Some(self.pressed_at + self.config.long_press) } else { None };
+    let click = self.clicked_at.map(|clicked_at| clicked_at + self.config.double_click);
+
+    match (long_press, click) {
+      (Some(a), Some(b)) => a.min(b),
+      (Some(deadline), None) | (None, Some(deadline)) => deadline,
+      (None, None) => return Duration::from_secs(1),
+    }.saturating_duration_since(now)
+  }
+
+  fn edge(&mut self, emit: &mut dyn FnMut(ButtonEvent)) {
+    let pressed = self.is_pressed();
+    if pressed == self.pressed {
+      return
+    }
+
+    let now = Instant::now();
+    self.pressed = pressed;
+
+    if pressed {
+      self.pressed_at = now;
+      self.long_pressed = false;
+      emit(ButtonEvent::Pressed);
+      return
+    }
+
+    emit(ButtonEvent::Released);
+
+    if self.long_pressed {
+      return
+    }
+
+    if self.clicked_at.take().is_some() {
+      emit(ButtonEvent::DoubleClick);
+    } else {
+      self.clicked_at = Some(now);
+    }
+  }
+
+  fn tick(&mut self, emit: &mut dyn FnMut(ButtonEvent)) {
+    let now = Instant::now();
+
+    if self.pressed && !self.long_pressed && now >= self.pressed_at + self.config.long_press {
+      self.long_pressed = true;
+      self.clicked_at = None;
+      emit(ButtonEvent::LongPress);
+    }
+
+    if let Some(clicked_at) = self.clicked_at {
+      if !self.pressed && now >= clicked_at + self.config.double_click {
+        self.clicked_at = None;
+        emit(ButtonEvent::Click);
+      }
+    }
+  }
+}
+
+/// A debounced push button on an input pin.
+///
+/// Level changes are detected using GPIO interrupts and debounced on a separate thread,
+/// which delivers [`ButtonEvent`](enum.ButtonEvent.html)s to a channel or a callback.
+pub struct Button<P: Pin> {
+  subscription: Option<InterruptSubscription<'static>>,
+  thread: Option<JoinHandle<()>>,
+  receiver: Option<Receiver<ButtonEvent>>,
+  active_low: bool,
+  input: Input<P>,
+}
+
+impl<P: Pin> fmt::Debug for Button<P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Button").field("pin", &self.input.number()).finish()
+  }
+}
+
+impl<P: Pin> Button<P> {
+  /// Create a button whose events are received using [`recv`](#method.recv).
+  pub fn new(input: Input<P>, config: ButtonConfig) -> Result<Self, EspError> {
+    let (sender, receiver) = mpsc::channel();
+
+    let mut button = Self::start(input, config, move |event| {
+      let _ = sender.send(event);
+    })?;
+
+    button.receiver = Some(receiver);
+    Ok(button)
+  }
+
+  /// Create a button which calls `callback` on its thread for every event.
+  pub fn with_callback<F>(input: Input<P>, config: ButtonConfig, callback: F) -> Result<Self, EspError>
+  where
+    F: FnMut(ButtonEvent) + Send + 'static,
+  {
+    Self::start(input, config, callback)
+  }
+
+  fn start<F>(mut input: Input<P>, config: ButtonConfig, mut emit: F) -> Result<Self, EspError>
+  where
+    F: FnMut(ButtonEvent) + Send + 'static,
+  {
+    input.set_pull(config.pull)?;
+
+    let (edge_sender, edges) = mpsc::channel::<()>();
+    let subscription = InterruptSubscription::new(input.number(), InterruptType::AnyEdge, Box::new(move || {
+      let _ = edge_sender.send(());
+    }))?;
+
+    let mut detector = Detector {
+      config,
+      pin: input.number(),
+      pressed: false,
+      pressed_at: Instant::now(),
+      long_pressed: false,
+      clicked_at: None,
+    };
+    detector.pressed = detector.is_pressed();
+
+    let thread = thread::Builder::new()
+      .name("button".into())
+      .stack_size(3072)
+      .spawn(move || loop {
+        match edges.recv_timeout(detector.timeout(Instant::now())) {
+          Ok(()) => {
+            thread::sleep(detector.config.debounce);
+            while edges.try_recv().is_ok() {}
+            detector.edge(&mut emit);
+          },
+          Err(RecvTimeoutError::Timeout) => detector.tick(&mut emit),
+          // The subscription was dropped together with the button.
+          Err(RecvTimeoutError::Disconnected) => break,
+        }
+      })
+      .map_err(|_| EspError { code: ESP_FAIL as esp_err_t })?;
+
+    Ok(Self {
+      subscription: Some(subscription),
+      thread: Some(thread),
+      receiver: None,
+      active_low: config.active_low,
+      input,
+    })
+  }
+
+  /// Whether the button is currently pressed, without debouncing.
+  pub fn is_pressed(&self) -> bool {
+    self.input.is_high() != self.active_low
+  }
+
+  /// Block until the next event is received.
+  ///
+  /// Always fails for a button created using [`with_callback`](#method.with_callback).
+  pub fn recv(&self) -> Result<ButtonEvent, RecvError> {
+    self.receiver.as_ref().ok_or(RecvError)?.recv()
+  }
+
+  /// Return the next event if one has already been received.
+  pub fn try_recv(&self) -> Result<ButtonEvent, TryRecvError> {
+    self.receiver.as_ref().ok_or(TryRecvError::Disconnected)?.try_recv()
+  }
+
+  /// Block until the next event is received or `timeout` has elapsed.
+  pub fn recv_timeout(&self, timeout: Duration) -> Result<ButtonEvent, RecvTimeoutError> {
+    self.receiver.as_ref().ok_or(RecvTimeoutError::Disconnected)?.recv_timeout(timeout)
+  }
+}
+
+impl<P: Pin> Drop for Button<P> {
+  fn drop(&mut self) {
+    // Dropping the subscription disconnects the edge channel, which stops the thread.
+    self.subscription = None;
+
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+  }
+}
@@ -0,0 +1,157 @@
+use core::convert::Infallible;
+
+use crate::EspError;
+
+use super::{Input, OpenDrain, Output, OutputCapable, Pin};
+
+#[cfg(feature = "embedded-hal-02")]
+mod v02 {
+  use embedded_hal_02::digital::v2::{InputPin, OutputPin, StatefulOutputPin, ToggleableOutputPin};
+
+  use super::*;
+
+  impl<P: Pin> InputPin for Input<P> {
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+      Ok(Input::is_high(self))
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+      Ok(Input::is_low(self))
+    }
+  }
+
+  impl<P: OutputCapable> OutputPin for Output<P> {
+    type Error = EspError;
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+      Output::set_high(self)
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+      Output::set_low(self)
+    }
+  }
+
+  impl<P: OutputCapable> StatefulOutputPin for Output<P> {
+    fn is_set_high(&self) -> Result<bool, Self::Error> {
+      Ok(Output::is_set_high(self))
+    }
+
+    fn is_set_low(&self) -> Result<bool, Self::Error> {
+      Ok(Output::is_set_low(self))
+    }
+  }
+
+  impl<P: OutputCapable> ToggleableOutputPin for Output<P> {
+    type Error = EspError;
+
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+      Output::toggle(self)
+    }
+  }
+
+  impl<P: OutputCapable> InputPin for OpenDrain<P> {
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+      Ok(OpenDrain::is_high(self))
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+      Ok(OpenDrain::is_low(self))
+    }
+  }
+
+  impl<P: OutputCapable> OutputPin for OpenDrain<P> {
+    type Error = EspError;
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+      OpenDrain::set_high(self)
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+      OpenDrain::set_low(self)
+    }
+  }
+}
+
+#[cfg(feature = "embedded-hal-1")]
+mod v1 {
+  use embedded_hal_1::digital::{Error, ErrorKind, ErrorType, InputPin, OutputPin, StatefulOutputPin};
+
+  use super::*;
+
+  impl Error for EspError {
+    fn kind(&self) -> ErrorKind {
+      ErrorKind::Other
+    }
+  }
+
+  impl<P: Pin> ErrorType for Input<P> {
+    type Error = Infallible;
+  }
+
+  impl<P: Pin> InputPin for Input<P> {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+      Ok(Input::is_high(self))
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+      Ok(Input::is_low(self))
+    }
+  }
+
+  impl<P: OutputCapable> ErrorType for Output<P> {
+    type Error = EspError;
+  }
+
+  impl<P: OutputCapable> OutputPin for Output<P> {
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+      Output::set_high(self)
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+      Output::set_low(self)
+    }
+  }
+
+  impl<P: OutputCapable> StatefulOutputPin for Output<P> {
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+      Ok(Output::is_set_high(self))
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+      Ok(Output::is_set_low(self))
+    }
+
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+      Output::toggle(self)
+    }
+  }
+
+  impl<P: OutputCapable> ErrorType for OpenDrain<P> {
+    type Error = EspError;
+  }
+
+  impl<P: OutputCapable> InputPin for OpenDrain<P> {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+      Ok(OpenDrain::is_high(self))
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+      Ok(OpenDrain::is_low(self))
+    }
+  }
+
+  impl<P: OutputCapable> OutputPin for OpenDrain<P> {
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+      OpenDrain::set_high(self)
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+      OpenDrain::set_low(self)
+    }
+  }
+}
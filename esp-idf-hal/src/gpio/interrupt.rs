@@ -0,0 +1,381 @@
+use core::fmt;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::task::{Context, Poll, Waker};
+use std::sync::{mpsc, Arc, Mutex};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering::SeqCst};
+use std::thread;
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  gpio_install_isr_service,
+  gpio_int_type_t,
+  gpio_intr_disable,
+  gpio_intr_enable,
+  gpio_isr_handler_add,
+  gpio_isr_handler_remove,
+  gpio_set_intr_type,
+  ulTaskNotifyTake,
+  vTaskNotifyGiveFromISR,
+  xTaskGetCurrentTaskHandle,
+  BaseType_t,
+  TaskHandle_t,
+  TickType_t,
+  ESP_ERR_INVALID_STATE,
+  ESP_FAIL,
+};
+
+use crate::EspError;
+use crate::init_once::InitOnce;
+
+use super::{gpio_num, Input, Pin};
+
+// `portYIELD_FROM_ISR` is a macro, so the function it expands to has no generated bindings.
+extern "C" {
+  fn _frxt_setup_switch();
+}
+
+const PORT_MAX_DELAY: TickType_t = TickType_t::max_value();
+
+const PIN_COUNT: usize = 40;
+
+/// The kind of signal change which triggers an interrupt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterruptType {
+  /// Trigger when the input changes from low to high.
+  RisingEdge,
+  /// Trigger when the input changes from high to low.
+  FallingEdge,
+  /// Trigger on any input change.
+  AnyEdge,
+  /// Trigger while the input is low.
+  LowLevel,
+  /// Trigger while the input is high.
+  HighLevel,
+}
+
+impl InterruptType {
+  fn is_level(self) -> bool {
+    self == Self::LowLevel || self == Self::HighLevel
+  }
+}
+
+impl From<InterruptType> for gpio_int_type_t {
+  fn from(interrupt_type: InterruptType) -> Self {
+    match interrupt_type {
+      InterruptType::RisingEdge => gpio_int_type_t::GPIO_INTR_POSEDGE,
+      InterruptType::FallingEdge => gpio_int_type_t::GPIO_INTR_NEGEDGE,
+      InterruptType::AnyEdge => gpio_int_type_t::GPIO_INTR_ANYEDGE,
+      InterruptType::LowLevel => gpio_int_type_t::GPIO_INTR_LOW_LEVEL,
+      InterruptType::HighLevel => gpio_int_type_t::GPIO_INTR_HIGH_LEVEL,
+    }
+  }
+}
+
+pub(super) type Callback = Box<dyn FnMut() + Send>;
+
+struct Handler {
+  id: usize,
+  callback: Option<Callback>,
+}
+
+/// Callbacks are not called in the ISR itself. The ISR only marks the pin as pending and
+/// notifies the dispatcher task, which calls the callbacks in task context.
+struct Dispatcher {
+  handlers: Mutex<Vec<Option<Handler>>>,
+  next_id: AtomicUsize,
+}
+
+static DISPATCHER: InitOnce<&'static Dispatcher> = InitOnce::new();
+static DISPATCHER_TASK: AtomicUsize = AtomicUsize::new(0);
+
+static PENDING: [AtomicU32; 2] = [AtomicU32::new(0), AtomicU32::new(0)];
+static LEVEL_TRIGGERED: [AtomicU32; 2] = [AtomicU32::new(0), AtomicU32::new(0)];
+
+fn pin_bit(pin: u8) -> (usize, u32) {
+  (pin as usize / 32, 1 << (pin % 32))
+}
+
+extern "C" fn gpio_isr(arg: *mut libc::c_void) {
+  let pin = arg as usize as u8;
+  let (word, bit) = pin_bit(pin);
+
+  // A level interrupt keeps triggering until the dispatcher task has called the callback.
+  if LEVEL_TRIGGERED[word].load(SeqCst) & bit != 0 {
+    unsafe { gpio_intr_disable(gpio_num(pin)) };
+  }
+
+  PENDING[word].fetch_or(bit, SeqCst);
+
+  let mut higher_priority_task_woken: BaseType_t = 0;
+  unsafe {
+    vTaskNotifyGiveFromISR(DISPATCHER_TASK.load(SeqCst) as TaskHandle_t, &mut higher_priority_task_woken);
+
+    if higher_priority_task_woken != 0 {
+      _frxt_setup_switch();
+    }
+  }
+}
+
+impl Dispatcher {
+  /// Install the GPIO ISR service and start the dispatcher task if they do not exist yet.
+  fn get() -> Result<&'static Self, EspError> {
+    DISPATCHER.get_or_try_init(Self::start).map(|&dispatcher| dispatcher)
+  }
+
+  fn start() -> Result<&'static Self, EspError> {
+    match esp_ok!(gpio_install_isr_service(0)) {
+      Err(err) if err.code != ESP_ERR_INVALID_STATE as esp_err_t => return Err(err),
+      _ => (),
+    }
+
+    let dispatcher: &'static Self = Box::leak(Box::new(Self {
+      handlers: Mutex::new((0..PIN_COUNT).map(|_| None).collect()),
+      next_id: AtomicUsize::new(0),
+    }));
+
+    let (sender, receiver) = mpsc::sync_channel(1);
+
+    thread::Builder::new()
+      .name("gpio_interrupt".into())
+      .stack_size(4096)
+      .spawn(move || {
+        let _ = sender.send(unsafe { xTaskGetCurrentTaskHandle() } as usize);
+
+        loop {
+          unsafe { ulTaskNotifyTake(1, PORT_MAX_DELAY) };
+
+          for (word, pending) in PENDING.iter().enumerate() {
+            let mut pending = pending.swap(0, SeqCst);
+
+            while pending != 0 {
+              let bit = pending.trailing_zeros();
+              pending &= !(1 << bit);
+              dispatcher.dispatch((word * 32) as u8 + bit as u8);
+            }
+          }
+        }
+      })
+      .map_err(|_| EspError { code: ESP_FAIL as esp_err_t })?;
+
+    let task = receiver.recv().map_err(|_| EspError { code: ESP_ERR_INVALID_STATE as esp_err_t })?;
+    DISPATCHER_TASK.store(task, SeqCst);
+
+    Ok(dispatcher)
+  }
+
+  fn dispatch(&self, pin: u8) {
+    // The callback is called without holding the lock, so it may subscribe or unsubscribe.
+    let (id, mut callback) = {
+      let mut handlers = self.handlers.lock().unwrap();
+
+      match handlers[pin as usize].as_mut() {
+        Some(handler) => match handler.callback.take() {
+          Some(callback) => (handler.id, callback),
+          None => return,
+        },
+        None => return,
+      }
+    };
+
+    callback();
+
+    let mut handlers = self.handlers.lock().unwrap();
+
+    if let Some(handler) = handlers[pin as usize].as_mut() {
+      if handler.id == id {
+        handler.callback = Some(callback);
+
+        let (word, bit) = pin_bit(pin);
+        if LEVEL_TRIGGERED[word].load(SeqCst) & bit != 0 {
+          unsafe { gpio_intr_enable(gpio_num(pin)) };
+        }
+      }
+    }
+  }
+}
+
+/// A callback registered for interrupts on an [`Input`](struct.Input.html) pin.
+///
+/// The interrupt is disabled and the callback is removed when the subscription is dropped.
+#[must_use = "the interrupt is disabled immediately if the subscription is dropped"]
+pub struct InterruptSubscription<'a> {
+  pin: u8,
+  id: usize,
+  _input: PhantomData<&'a ()>,
+}
+
+impl fmt::Debug for InterruptSubscription<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("InterruptSubscription").field("pin", &self.pin).finish()
+  }
+}
+
+impl InterruptSubscription<'_> {
+  pub(super) fn new(pin: u8, interrupt_type: InterruptType, callback: Callback) -> Result<Self, EspError> {
+    let dispatcher = Dispatcher::get()?;
+    let id = dispatcher.next_id.fetch_add(1, SeqCst);
+
+    {
+      let mut handlers = dispatcher.handlers.lock().unwrap();
+      let handler = &mut handlers[pin as usize];
+
+      if handler.is_some() {
+        return Err(EspError { code: ESP_ERR_INVALID_STATE as esp_err_t })
+      }
+
+      *handler = Some(Handler { id, callback: Some(callback) });
+    }
+
+    let subscription = Self { pin, id, _input: PhantomData };
+
+    let (word, bit) = pin_bit(pin);
+    if interrupt_type.is_level() {
+      LEVEL_TRIGGERED[word].fetch_or(bit, SeqCst);
+    } else {
+      LEVEL_TRIGGERED[word].fetch_and(!bit, SeqCst);
+    }
+
+    let gpio = gpio_num(pin);
+    esp_ok!(gpio_set_intr_type(gpio, interrupt_type.into()))?;
+    esp_ok!(gpio_isr_handler_add(gpio, Some(gpio_isr), pin as usize as *mut _))?;
+    esp_ok!(gpio_intr_enable(gpio))?;
+
+    Ok(subscription)
+  }
+}
+
+impl Drop for InterruptSubscription<'_> {
+  fn drop(&mut self) {
+    let gpio = gpio_num(self.pin);
+    let _ = esp_ok!(gpio_intr_disable(gpio));
+    let _ = esp_ok!(gpio_isr_handler_remove(gpio));
+    let _ = esp_ok!(gpio_set_intr_type(gpio, gpio_int_type_t::GPIO_INTR_DISABLE));
+
+    let (word, bit) = pin_bit(self.pin);
+    PENDING[word].fetch_and(!bit, SeqCst);
+    LEVEL_TRIGGERED[word].fetch_and(!bit, SeqCst);
+
+    let dispatcher = match DISPATCHER.get() {
+      Some(&dispatcher) => dispatcher,
+      None => return,
+    };
+
+    let mut handlers = dispatcher.handlers.lock().unwrap();
+    let handler = &mut handlers[self.pin as usize];
+
+    if handler.as_ref().map(|handler| handler.id) == Some(self.id) {
+      *handler = None;
+    }
+  }
+}
+
+#[derive(Debug)]
+struct InterruptFutureState {
+  triggered: bool,
+  waker: Option<Waker>,
+}
+
+/// A future resolving with the next interrupt on an [`Input`](struct.Input.html) pin.
+#[must_use = "futures do nothing unless polled"]
+pub struct InterruptFuture<'a> {
+  pin: u8,
+  interrupt_type: InterruptType,
+  state: Arc<Mutex<InterruptFutureState>>,
+  subscription: Option<InterruptSubscription<'a>>,
+}
+
+impl fmt::Debug for InterruptFuture<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("InterruptFuture")
+      .field("pin", &self.pin)
+      .field("interrupt_type", &self.interrupt_type)
+      .finish()
+  }
+}
+
+impl Future for InterruptFuture<'_> {
+  type Output = Result<(), EspError>;
+
+  fn poll(mut self: core::pin::Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+    {
+      let mut state = self.state.lock().unwrap();
+
+      if state.triggered {
+        drop(state);
+        self.subscription = None;
+        return Poll::Ready(Ok(()))
+      }
+
+      state.waker = Some(cx.waker().clone());
+    }
+
+    if self.subscription.is_none() {
+      let state = Arc::clone(&self.state);
+
+      let subscription = InterruptSubscription::new(self.pin, self.interrupt_type, Box::new(move || {
+        let mut state = state.lock().unwrap();
+        state.triggered = true;
+
+        if let Some(waker) = state.waker.take() {
+          waker.wake();
+        }
+      }));
+
+      match subscription {
+        Ok(subscription) => self.subscription = Some(subscription),
+        Err(err) => return Poll::Ready(Err(err)),
+      }
+    }
+
+    Poll::Pending
+  }
+}
+
+impl<P: Pin> Input<P> {
+  /// Call `callback` for every interrupt of the given type on this pin.
+  ///
+  /// Only one subscription per pin can exist at a time. The callback runs on a dedicated
+  /// task rather than in the ISR, so it may block and allocate.
+  pub fn subscribe<F>(&self, interrupt_type: InterruptType, callback: F) -> Result<InterruptSubscription<'_>, EspError>
+  where
+    F: FnMut() + Send + 'static,
+  {
+    InterruptSubscription::new(self.number(), interrupt_type, Box::new(callback))
+  }
+
+  /// Wait for the next interrupt of the given type on this pin.
+  pub fn wait_for_interrupt(&mut self, interrupt_type: InterruptType) -> InterruptFuture<'_> {
+    InterruptFuture {
+      pin: self.number(),
+      interrupt_type,
+      state: Arc::new(Mutex::new(InterruptFutureState { triggered: false, waker: None })),
+      subscription: None,
+    }
+  }
+
+  /// Wait until the input changes from low to high.
+  pub fn wait_for_rising_edge(&mut self) -> InterruptFuture<'_> {
+    self.wait_for_interrupt(InterruptType::RisingEdge)
+  }
+
+  /// Wait until the input changes from high to low.
+  pub fn wait_for_falling_edge(&mut self) -> InterruptFuture<'_> {
+    self.wait_for_interrupt(InterruptType::FallingEdge)
+  }
+
+  /// Wait until the input changes.
+  pub fn wait_for_any_edge(&mut self) -> InterruptFuture<'_> {
+    self.wait_for_interrupt(InterruptType::AnyEdge)
+  }
+
+  /// Wait until the input is high. Resolves immediately if it already is.
+  pub fn wait_for_high(&mut self) -> InterruptFuture<'_> {
+    self.wait_for_interrupt(InterruptType::HighLevel)
+  }
+
+  /// Wait until the input is low. Resolves immediately if it already is.
+  pub fn wait_for_low(&mut self) -> InterruptFuture<'_> {
+    self.wait_for_interrupt(InterruptType::LowLevel)
+  }
+}
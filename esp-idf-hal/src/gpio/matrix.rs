@@ -0,0 +1,94 @@
+use esp_idf_bindgen::{
+  esp_err_t,
+  esp_rom_gpio_connect_in_signal,
+  esp_rom_gpio_connect_out_signal,
+  esp_rom_gpio_pad_select_gpio,
+  gpio_mode_t,
+  gpio_set_direction,
+  ESP_ERR_INVALID_ARG,
+};
+
+use crate::EspError;
+
+use super::{gpio_num, Pin};
+
+/// The number of peripheral signals in the GPIO matrix, see `soc/gpio_sig_map.h`.
+const SIGNAL_COUNT: u32 = 256;
+
+/// The output signal index which disconnects a pin from the GPIO matrix, so it is driven by `gpio_set_level` again.
+const SIG_GPIO_OUT_IDX: u32 = 256;
+
+/// The pseudo pins which feed a constant low or high level into an input signal.
+const GPIO_MATRIX_CONST_ZERO_INPUT: u32 = 0x30;
+const GPIO_MATRIX_CONST_ONE_INPUT: u32 = 0x38;
+
+fn check_signal(signal: u32) -> Result<(), EspError> {
+  if signal < SIGNAL_COUNT {
+    Ok(())
+  } else {
+    Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+  }
+}
+
+/// Route the peripheral output `signal`, e.g. `U1TXD_OUT_IDX`, to `pin`.
+///
+/// The pin is switched to input-output mode, so the signal can also be looped back into a peripheral
+/// input using [`connect_input`](fn.connect_input.html). GPIOs 34 to 39 are input-only and cannot be used.
+pub fn connect_output(pin: &mut impl Pin, signal: u32, invert: bool) -> Result<(), EspError> {
+  let number = pin.number();
+
+  if number >= 34 {
+    return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+  }
+
+  check_signal(signal)?;
+
+  unsafe { esp_rom_gpio_pad_select_gpio(number as u32) };
+  esp_ok!(gpio_set_direction(gpio_num(number), gpio_mode_t::GPIO_MODE_INPUT_OUTPUT))?;
+  unsafe { esp_rom_gpio_connect_out_signal(number as u32, signal, invert, false) };
+
+  Ok(())
+}
+
+/// Disconnect `pin` from the peripheral output signal routed to it.
+pub fn disconnect_output(pin: &mut impl Pin) -> Result<(), EspError> {
+  let number = pin.number();
+
+  if number >= 34 {
+    return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+  }
+
+  unsafe { esp_rom_gpio_connect_out_signal(number as u32, SIG_GPIO_OUT_IDX, false, false) };
+  Ok(())
+}
+
+/// Route `pin` to the peripheral input `signal`, e.g. `U1RXD_IN_IDX`.
+///
+/// Multiple input signals can be connected to the same pin.
+pub fn connect_input(pin: &mut impl Pin, signal: u32, invert: bool) -> Result<(), EspError> {
+  let number = pin.number();
+
+  check_signal(signal)?;
+
+  unsafe { esp_rom_gpio_pad_select_gpio(number as u32) };
+  unsafe { esp_rom_gpio_connect_in_signal(number as u32, signal, invert) };
+
+  Ok(())
+}
+
+/// Feed a constant level into the peripheral input `signal`, disconnecting it from any pin.
+pub fn connect_input_constant(signal: u32, high: bool) -> Result<(), EspError> {
+  check_signal(signal)?;
+
+  let source = if high { GPIO_MATRIX_CONST_ONE_INPUT } else { GPIO_MATRIX_CONST_ZERO_INPUT };
+  unsafe { esp_rom_gpio_connect_in_signal(source, signal, false) };
+
+  Ok(())
+}
+
+/// Route the peripheral output `output_signal` to the peripheral input `input_signal` through `pin`,
+/// e.g. to test a UART by connecting its TX to its RX.
+pub fn loopback(pin: &mut impl Pin, output_signal: u32, input_signal: u32) -> Result<(), EspError> {
+  connect_output(pin, output_signal, false)?;
+  connect_input(pin, input_signal, false)
+}
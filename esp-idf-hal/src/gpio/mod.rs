@@ -0,0 +1,451 @@
+use core::fmt;
+use core::mem::transmute;
+use std::sync::atomic::{AtomicBool, Ordering::SeqCst};
+
+use esp_idf_bindgen::{
+  gpio_config,
+  gpio_config_t,
+  gpio_get_level,
+  gpio_int_type_t,
+  gpio_mode_t,
+  gpio_num_t,
+  gpio_pull_mode_t,
+  gpio_pulldown_t,
+  gpio_pullup_t,
+  gpio_reset_pin,
+  gpio_set_level,
+  gpio_set_pull_mode,
+};
+
+use crate::EspError;
+
+mod interrupt;
+pub use interrupt::*;
+mod rtc;
+pub use rtc::*;
+pub mod matrix;
+mod button;
+pub use button::*;
+#[cfg(any(feature = "embedded-hal-02", feature = "embedded-hal-1"))]
+mod hal;
+
+mod sealed {
+  /// Prevents implementing [`Pin`](../trait.Pin.html) outside of this crate, since drivers rely
+  /// on its number being a valid GPIO.
+  pub trait Sealed {}
+}
+pub(crate) use sealed::Sealed;
+
+/// A GPIO pin.
+///
+/// This trait is sealed, so it and the traits extending it, like
+/// [`OutputCapable`](trait.OutputCapable.html), can only be implemented by this crate.
+pub trait Pin: Sealed {
+  /// The GPIO number of this pin.
+  fn number(&self) -> u8;
+}
+
+/// A GPIO pin which can be used as an output. GPIOs 34 to 39 are input-only.
+pub trait OutputCapable: Pin {}
+
+fn configure(pin: u8, mode: gpio_mode_t) -> Result<(), EspError> {
+  let config = gpio_config_t {
+    pin_bit_mask: 1 << pin,
+    mode,
+    pull_up_en: gpio_pullup_t::GPIO_PULLUP_DISABLE,
+    pull_down_en: gpio_pulldown_t::GPIO_PULLDOWN_DISABLE,
+    intr_type: gpio_int_type_t::GPIO_INTR_DISABLE,
+  };
+
+  esp_ok!(gpio_config(&config))
+}
+
+/// Whether `pin` is the number of a GPIO which exists on the ESP32.
+pub(crate) fn is_valid_pin(pin: u8) -> bool {
+  match pin {
+    0..=19 | 21..=23 | 25..=27 | 32..=39 => true,
+    _ => false,
+  }
+}
+
+/// Whether `pin` is the number of a GPIO which can be used as an output.
+pub(crate) fn is_output_pin(pin: u8) -> bool {
+  is_valid_pin(pin) && pin < 34
+}
+
+pub(crate) fn gpio_num(pin: u8) -> gpio_num_t {
+  unsafe { transmute(pin as i32) }
+}
+
+/// The internal pull resistors of a pin. GPIOs 34 to 39 have no pull resistors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pull {
+  /// Both resistors are disabled.
+  Floating,
+  /// Pull up to VDD.
+  Up,
+  /// Pull down to GND.
+  Down,
+  /// Enable both resistors.
+  UpDown,
+}
+
+impl From<Pull> for gpio_pull_mode_t {
+  fn from(pull: Pull) -> Self {
+    match pull {
+      Pull::Floating => gpio_pull_mode_t::GPIO_FLOATING,
+      Pull::Up => gpio_pull_mode_t::GPIO_PULLUP_ONLY,
+      Pull::Down => gpio_pull_mode_t::GPIO_PULLDOWN_ONLY,
+      Pull::UpDown => gpio_pull_mode_t::GPIO_PULLUP_PULLDOWN,
+    }
+  }
+}
+
+fn set_pull(pin: u8, pull: Pull) -> Result<(), EspError> {
+  esp_ok!(gpio_set_pull_mode(gpio_num(pin), pull.into()))
+}
+
+/// A pin configured as an input.
+pub struct Input<P: Pin> {
+  pin: P,
+}
+
+impl<P: Pin> Input<P> {
+  pub(crate) fn new(pin: P) -> Result<Self, EspError> {
+    configure(pin.number(), gpio_mode_t::GPIO_MODE_INPUT)?;
+    Ok(Self { pin })
+  }
+
+  /// Whether the input level is high.
+  pub fn is_high(&self) -> bool {
+    unsafe { gpio_get_level(gpio_num(self.pin.number())) != 0 }
+  }
+
+  /// Whether the input level is low.
+  pub fn is_low(&self) -> bool {
+    !self.is_high()
+  }
+
+  /// Enable or disable the internal pull resistors.
+  pub fn set_pull(&mut self, pull: Pull) -> Result<(), EspError> {
+    set_pull(self.pin.number(), pull)
+  }
+
+  /// Reconfigure this pin as an output.
+  pub fn into_output(self) -> Result<Output<P>, EspError> where P: OutputCapable {
+    Output::new(self.release())
+  }
+
+  /// Reconfigure this pin as an open-drain output.
+  pub fn into_open_drain(self) -> Result<OpenDrain<P>, EspError> where P: OutputCapable {
+    OpenDrain::new(self.release())
+  }
+
+  /// Reset this pin to its default state and return it.
+  pub fn release(self) -> P {
+    let _ = esp_ok!(gpio_reset_pin(gpio_num(self.pin.number())));
+    self.pin
+  }
+}
+
+impl<P: Pin> Sealed for Input<P> {}
+
+impl<P: Pin> Pin for Input<P> {
+  fn number(&self) -> u8 {
+    self.pin.number()
+  }
+}
+
+impl<P: Pin> fmt::Debug for Input<P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Input").field("pin", &self.pin.number()).finish()
+  }
+}
+
+/// A pin configured as an output.
+///
+/// The input of the pin stays enabled, so the current output level can be read back.
+pub struct Output<P: OutputCapable> {
+  pin: P,
+}
+
+impl<P: OutputCapable> Output<P> {
+  pub(crate) fn new(pin: P) -> Result<Self, EspError> {
+    configure(pin.number(), gpio_mode_t::GPIO_MODE_INPUT_OUTPUT)?;
+    Ok(Self { pin })
+  }
+
+  /// Set the output level.
+  pub fn set_level(&mut self, high: bool) -> Result<(), EspError> {
+    esp_ok!(gpio_set_level(gpio_num(self.pin.number()), high as u32))
+  }
+
+  /// Set the output level to high.
+  pub fn set_high(&mut self) -> Result<(), EspError> {
+    self.set_level(true)
+  }
+
+  /// Set the output level to low.
+  pub fn set_low(&mut self) -> Result<(), EspError> {
+    self.set_level(false)
+  }
+
+  /// Invert the output level.
+  pub fn toggle(&mut self) -> Result<(), EspError> {
+    let high = self.is_set_high();
+    self.set_level(!high)
+  }
+
+  /// Whether the output level is high.
+  pub fn is_set_high(&self) -> bool {
+    unsafe { gpio_get_level(gpio_num(self.pin.number())) != 0 }
+  }
+
+  /// Whether the output level is low.
+  pub fn is_set_low(&self) -> bool {
+    !self.is_set_high()
+  }
+
+  /// Enable or disable the internal pull resistors.
+  pub fn set_pull(&mut self, pull: Pull) -> Result<(), EspError> {
+    set_pull(self.pin.number(), pull)
+  }
+
+  /// Reconfigure this pin as an input.
+  pub fn into_input(self) -> Result<Input<P>, EspError> {
+    Input::new(self.release())
+  }
+
+  /// Reconfigure this pin as an open-drain output.
+  pub fn into_open_drain(self) -> Result<OpenDrain<P>, EspError> {
+    OpenDrain::new(self.release())
+  }
+
+  /// Reset this pin to its default state and return it.
+  pub fn release(self) -> P {
+    let _ = esp_ok!(gpio_reset_pin(gpio_num(self.pin.number())));
+    self.pin
+  }
+}
+
+impl<P: OutputCapable> Sealed for Output<P> {}
+
+impl<P: OutputCapable> Pin for Output<P> {
+  fn number(&self) -> u8 {
+    self.pin.number()
+  }
+}
+
+impl<P: OutputCapable> fmt::Debug for Output<P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Output").field("pin", &self.pin.number()).finish()
+  }
+}
+
+/// A pin configured as an open-drain output.
+///
+/// The pin only drives the line low. When set high, the line is released and pulled up externally
+/// or by [`Pull::Up`](enum.Pull.html#variant.Up), and the actual level of the line can be read,
+/// e.g. for bit-banged one-wire or I2C bus recovery.
+pub struct OpenDrain<P: OutputCapable> {
+  pin: P,
+}
+
+impl<P: OutputCapable> OpenDrain<P> {
+  pub(crate) fn new(pin: P) -> Result<Self, EspError> {
+    configure(pin.number(), gpio_mode_t::GPIO_MODE_INPUT_OUTPUT_OD)?;
+    Ok(Self { pin })
+  }
+
+  /// Release the line if `high`, otherwise drive it low.
+  pub fn set_level(&mut self, high: bool) -> Result<(), EspError> {
+    esp_ok!(gpio_set_level(gpio_num(self.pin.number()), high as u32))
+  }
+
+  /// Release the line.
+  pub fn set_high(&mut self) -> Result<(), EspError> {
+    self.set_level(true)
+  }
+
+  /// Drive the line low.
+  pub fn set_low(&mut self) -> Result<(), EspError> {
+    self.set_level(false)
+  }
+
+  /// Whether the level of the line is high.
+  pub fn is_high(&self) -> bool {
+    unsafe { gpio_get_level(gpio_num(self.pin.number())) != 0 }
+  }
+
+  /// Whether the level of the line is low, either because this pin or another device drives it low.
+  pub fn is_low(&self) -> bool {
+    !self.is_high()
+  }
+
+  /// Enable or disable the internal pull resistors.
+  pub fn set_pull(&mut self, pull: Pull) -> Result<(), EspError> {
+    set_pull(self.pin.number(), pull)
+  }
+
+  /// Reconfigure this pin as an input.
+  pub fn into_input(self) -> Result<Input<P>, EspError> {
+    Input::new(self.release())
+  }
+
+  /// Reconfigure this pin as a push-pull output.
+  pub fn into_output(self) -> Result<Output<P>, EspError> {
+    Output::new(self.release())
+  }
+
+  /// Reset this pin to its default state and return it.
+  pub fn release(self) -> P {
+    let _ = esp_ok!(gpio_reset_pin(gpio_num(self.pin.number())));
+    self.pin
+  }
+}
+
+impl<P: OutputCapable> Sealed for OpenDrain<P> {}
+
+impl<P: OutputCapable> Pin for OpenDrain<P> {
+  fn number(&self) -> u8 {
+    self.pin.number()
+  }
+}
+
+impl<P: OutputCapable> fmt::Debug for OpenDrain<P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("OpenDrain").field("pin", &self.pin.number()).finish()
+  }
+}
+
+macro_rules! gpio {
+  (@output $name:ident, output) => {
+    impl OutputCapable for $name {}
+
+    impl $name {
+      /// Configure this pin as an output.
+      pub fn into_output(self) -> Result<Output<Self>, EspError> {
+        Output::new(self)
+      }
+
+      /// Configure this pin as an open-drain output.
+      pub fn into_open_drain(self) -> Result<OpenDrain<Self>, EspError> {
+        OpenDrain::new(self)
+      }
+    }
+  };
+  (@output $name:ident, input) => {};
+  (@rtc $name:ident, output, rtc) => {
+    gpio!(@rtc $name, input, rtc);
+
+    impl $name {
+      /// Configure this pin as an output in the RTC domain.
+      pub fn into_rtc_output(self) -> Result<RtcOutput<Self>, EspError> {
+        RtcOutput::new(self)
+      }
+    }
+  };
+  (@rtc $name:ident, input, rtc) => {
+    impl RtcCapable for $name {}
+
+    impl $name {
+      /// Configure this pin as an input in the RTC domain.
+      pub fn into_rtc_input(self) -> Result<RtcInput<Self>, EspError> {
+        RtcInput::new(self)
+      }
+    }
+  };
+  (@rtc $name:ident, $capability:ident, digital) => {};
+  ($($field:ident: $name:ident($number:expr, $capability:ident, $domain:ident),)*) => {
+    $(
+      /// A GPIO pin without a configured mode.
+      #[derive(Debug)]
+      pub struct $name {
+        _private: (),
+      }
+
+      impl $name {
+        /// Create a new instance of this pin.
+        ///
+        /// # Safety
+        ///
+        /// There must only be one instance of this pin at any time, see [`Pins::take`](struct.Pins.html#method.take).
+        pub unsafe fn new() -> Self {
+          Self { _private: () }
+        }
+
+        /// Configure this pin as an input.
+        pub fn into_input(self) -> Result<Input<Self>, EspError> {
+          Input::new(self)
+        }
+      }
+
+      impl Sealed for $name {}
+
+      impl Pin for $name {
+        fn number(&self) -> u8 {
+          $number
+        }
+      }
+
+      gpio!(@output $name, $capability);
+      gpio!(@rtc $name, $capability, $domain);
+    )*
+
+    /// All GPIO pins which are not used for the SPI flash.
+    #[derive(Debug)]
+    pub struct Pins {
+      $(pub $field: $name,)*
+    }
+
+    impl Pins {
+      fn new() -> Self {
+        unsafe { Self { $($field: $name::new(),)* } }
+      }
+    }
+  };
+}
+
+gpio! {
+  gpio0: Gpio0(0, output, rtc),
+  gpio1: Gpio1(1, output, digital),
+  gpio2: Gpio2(2, output, rtc),
+  gpio3: Gpio3(3, output, digital),
+  gpio4: Gpio4(4, output, rtc),
+  gpio5: Gpio5(5, output, digital),
+  gpio12: Gpio12(12, output, rtc),
+  gpio13: Gpio13(13, output, rtc),
+  gpio14: Gpio14(14, output, rtc),
+  gpio15: Gpio15(15, output, rtc),
+  gpio16: Gpio16(16, output, digital),
+  gpio17: Gpio17(17, output, digital),
+  gpio18: Gpio18(18, output, digital),
+  gpio19: Gpio19(19, output, digital),
+  gpio21: Gpio21(21, output, digital),
+  gpio22: Gpio22(22, output, digital),
+  gpio23: Gpio23(23, output, digital),
+  gpio25: Gpio25(25, output, rtc),
+  gpio26: Gpio26(26, output, rtc),
+  gpio27: Gpio27(27, output, rtc),
+  gpio32: Gpio32(32, output, rtc),
+  gpio33: Gpio33(33, output, rtc),
+  gpio34: Gpio34(34, input, rtc),
+  gpio35: Gpio35(35, input, rtc),
+  gpio36: Gpio36(36, input, rtc),
+  gpio37: Gpio37(37, input, rtc),
+  gpio38: Gpio38(38, input, rtc),
+  gpio39: Gpio39(39, input, rtc),
+}
+
+static PINS_TAKEN: AtomicBool = AtomicBool::new(false);
+
+impl Pins {
+  /// Take all GPIO pins. Returns `None` if they were already taken.
+  pub fn take() -> Option<Self> {
+    if PINS_TAKEN.compare_and_swap(false, true, SeqCst) {
+      None
+    } else {
+      Some(Self::new())
+    }
+  }
+}
@@ -0,0 +1,211 @@
+use core::fmt;
+
+use esp_idf_bindgen::{
+  esp_sleep_enable_ext0_wakeup,
+  esp_sleep_enable_ext1_wakeup,
+  esp_sleep_ext1_wakeup_mode_t,
+  rtc_gpio_deinit,
+  rtc_gpio_get_level,
+  rtc_gpio_hold_dis,
+  rtc_gpio_hold_en,
+  rtc_gpio_init,
+  rtc_gpio_mode_t,
+  rtc_gpio_pulldown_dis,
+  rtc_gpio_pulldown_en,
+  rtc_gpio_pullup_dis,
+  rtc_gpio_pullup_en,
+  rtc_gpio_set_direction,
+  rtc_gpio_set_level,
+};
+
+use crate::EspError;
+
+use super::{gpio_num, OutputCapable, Pin, Pull, Sealed};
+
+/// A GPIO pin which can be routed to the RTC domain, so it keeps working during deep sleep.
+pub trait RtcCapable: Pin {}
+
+fn rtc_init(pin: u8, mode: rtc_gpio_mode_t) -> Result<(), EspError> {
+  let gpio = gpio_num(pin);
+  esp_ok!(rtc_gpio_init(gpio))?;
+  esp_ok!(rtc_gpio_set_direction(gpio, mode))
+}
+
+fn rtc_set_pull(pin: u8, pull: Pull) -> Result<(), EspError> {
+  let gpio = gpio_num(pin);
+
+  match pull {
+    Pull::Up | Pull::UpDown => esp_ok!(rtc_gpio_pullup_en(gpio))?,
+    Pull::Floating | Pull::Down => esp_ok!(rtc_gpio_pullup_dis(gpio))?,
+  }
+
+  match pull {
+    Pull::Down | Pull::UpDown => esp_ok!(rtc_gpio_pulldown_en(gpio)),
+    Pull::Floating | Pull::Up => esp_ok!(rtc_gpio_pulldown_dis(gpio)),
+  }
+}
+
+fn rtc_set_hold(pin: u8, hold: bool) -> Result<(), EspError> {
+  if hold {
+    esp_ok!(rtc_gpio_hold_en(gpio_num(pin)))
+  } else {
+    esp_ok!(rtc_gpio_hold_dis(gpio_num(pin)))
+  }
+}
+
+fn rtc_release<P: Pin>(pin: P) -> P {
+  let gpio = gpio_num(pin.number());
+  let _ = esp_ok!(rtc_gpio_hold_dis(gpio));
+  let _ = esp_ok!(rtc_gpio_deinit(gpio));
+  pin
+}
+
+/// A pin configured as an input in the RTC domain.
+pub struct RtcInput<P: RtcCapable> {
+  pin: P,
+}
+
+impl<P: RtcCapable> RtcInput<P> {
+  pub(crate) fn new(pin: P) -> Result<Self, EspError> {
+    rtc_init(pin.number(), rtc_gpio_mode_t::RTC_GPIO_MODE_INPUT_ONLY)?;
+    Ok(Self { pin })
+  }
+
+  /// Whether the input level is high.
+  pub fn is_high(&self) -> bool {
+    unsafe { rtc_gpio_get_level(gpio_num(self.pin.number())) != 0 }
+  }
+
+  /// Whether the input level is low.
+  pub fn is_low(&self) -> bool {
+    !self.is_high()
+  }
+
+  /// Enable or disable the RTC pull resistors, which stay enabled during deep sleep.
+  pub fn set_pull(&mut self, pull: Pull) -> Result<(), EspError> {
+    rtc_set_pull(self.pin.number(), pull)
+  }
+
+  /// Wake up from deep sleep when this pin has the given level.
+  ///
+  /// Only one pin can be used this way, see [`enable_ext1_wakeup`](fn.enable_ext1_wakeup.html) for multiple pins.
+  pub fn enable_wakeup(&mut self, high: bool) -> Result<(), EspError> {
+    esp_ok!(esp_sleep_enable_ext0_wakeup(gpio_num(self.pin.number()), high as i32))
+  }
+
+  /// Move this pin back to the digital domain and return it.
+  pub fn release(self) -> P {
+    rtc_release(self.pin)
+  }
+}
+
+impl<P: RtcCapable> Sealed for RtcInput<P> {}
+
+impl<P: RtcCapable> Pin for RtcInput<P> {
+  fn number(&self) -> u8 {
+    self.pin.number()
+  }
+}
+
+impl<P: RtcCapable> RtcCapable for RtcInput<P> {}
+
+impl<P: RtcCapable> fmt::Debug for RtcInput<P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("RtcInput").field("pin", &self.pin.number()).finish()
+  }
+}
+
+/// A pin configured as an output in the RTC domain.
+///
+/// Use [`set_hold`](#method.set_hold) to keep the output level during deep sleep,
+/// e.g. to keep a MOSFET gate asserted.
+pub struct RtcOutput<P: RtcCapable + OutputCapable> {
+  pin: P,
+}
+
+impl<P: RtcCapable + OutputCapable> RtcOutput<P> {
+  pub(crate) fn new(pin: P) -> Result<Self, EspError> {
+    rtc_init(pin.number(), rtc_gpio_mode_t::RTC_GPIO_MODE_INPUT_OUTPUT)?;
+    Ok(Self { pin })
+  }
+
+  /// Set the output level.
+  pub fn set_level(&mut self, high: bool) -> Result<(), EspError> {
+    esp_ok!(rtc_gpio_set_level(gpio_num(self.pin.number()), high as u32))
+  }
+
+  /// Set the output level to high.
+  pub fn set_high(&mut self) -> Result<(), EspError> {
+    self.set_level(true)
+  }
+
+  /// Set the output level to low.
+  pub fn set_low(&mut self) -> Result<(), EspError> {
+    self.set_level(false)
+  }
+
+  /// Whether the output level is high.
+  pub fn is_set_high(&self) -> bool {
+    unsafe { rtc_gpio_get_level(gpio_num(self.pin.number())) != 0 }
+  }
+
+  /// Whether the output level is low.
+  pub fn is_set_low(&self) -> bool {
+    !self.is_set_high()
+  }
+
+  /// Enable or disable the RTC pull resistors, which stay enabled during deep sleep.
+  pub fn set_pull(&mut self, pull: Pull) -> Result<(), EspError> {
+    rtc_set_pull(self.pin.number(), pull)
+  }
+
+  /// Latch the current output level and pull configuration, so they are kept during deep sleep
+  /// and until the hold is disabled again. Level changes have no effect while the hold is enabled.
+  pub fn set_hold(&mut self, hold: bool) -> Result<(), EspError> {
+    rtc_set_hold(self.pin.number(), hold)
+  }
+
+  /// Disable the hold, move this pin back to the digital domain and return it.
+  pub fn release(self) -> P {
+    rtc_release(self.pin)
+  }
+}
+
+impl<P: RtcCapable + OutputCapable> Sealed for RtcOutput<P> {}
+
+impl<P: RtcCapable + OutputCapable> Pin for RtcOutput<P> {
+  fn number(&self) -> u8 {
+    self.pin.number()
+  }
+}
+
+impl<P: RtcCapable + OutputCapable> RtcCapable for RtcOutput<P> {}
+
+impl<P: RtcCapable + OutputCapable> fmt::Debug for RtcOutput<P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("RtcOutput").field("pin", &self.pin.number()).finish()
+  }
+}
+
+/// The condition for waking up from deep sleep using multiple RTC pins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ext1Wakeup {
+  /// Wake up when all pins are low.
+  AllLow,
+  /// Wake up when any pin is high.
+  AnyHigh,
+}
+
+/// Wake up from deep sleep depending on the levels of `pins`.
+///
+/// Pull resistors can be kept enabled during deep sleep using [`RtcInput::set_pull`](struct.RtcInput.html#method.set_pull).
+pub fn enable_ext1_wakeup(pins: &[&dyn RtcCapable], condition: Ext1Wakeup) -> Result<(), EspError> {
+  let mask = pins.iter().fold(0u64, |mask, pin| mask | 1 << pin.number());
+
+  let mode = match condition {
+    Ext1Wakeup::AllLow => esp_sleep_ext1_wakeup_mode_t::ESP_EXT1_WAKEUP_ALL_LOW,
+    Ext1Wakeup::AnyHigh => esp_sleep_ext1_wakeup_mode_t::ESP_EXT1_WAKEUP_ANY_HIGH,
+  };
+
+  esp_ok!(esp_sleep_enable_ext1_wakeup(mask, mode))
+}
@@ -0,0 +1,70 @@
+use core::cell::UnsafeCell;
+use core::convert::Infallible;
+use core::mem::MaybeUninit;
+use std::sync::atomic::{AtomicU8, Ordering::SeqCst};
+use std::thread;
+use std::time::Duration;
+
+const UNINITIALIZED: u8 = 0;
+const INITIALIZING: u8 = 1;
+const INITIALIZED: u8 = 2;
+
+/// A value which is initialized once, e.g. a driver which is installed on first use.
+///
+/// Unlike `std::sync::Once`, a failed initialization is retried by the next caller.
+pub(crate) struct InitOnce<T> {
+  state: AtomicU8,
+  value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// The value is only written once, before any reference to it is handed out.
+unsafe impl<T: Send + Sync> Sync for InitOnce<T> {}
+
+impl<T> InitOnce<T> {
+  pub const fn new() -> Self {
+    Self { state: AtomicU8::new(UNINITIALIZED), value: UnsafeCell::new(MaybeUninit::uninit()) }
+  }
+
+  /// Return the value, or `None` if it is not initialized yet.
+  pub fn get(&self) -> Option<&T> {
+    if self.state.load(SeqCst) == INITIALIZED {
+      Some(unsafe { &*(*self.value.get()).as_ptr() })
+    } else {
+      None
+    }
+  }
+
+  /// Return the value, initializing it using `init` if it is not initialized yet.
+  pub fn get_or_init(&self, init: impl FnOnce() -> T) -> &T {
+    match self.get_or_try_init(|| Ok::<_, Infallible>(init())) {
+      Ok(value) => value,
+      Err(err) => match err {},
+    }
+  }
+
+  /// Return the value, initializing it using `init` if it is not initialized yet.
+  ///
+  /// Concurrent callers wait until the initialization has finished.
+  pub fn get_or_try_init<E>(&self, init: impl FnOnce() -> Result<T, E>) -> Result<&T, E> {
+    loop {
+      match self.state.compare_and_swap(UNINITIALIZED, INITIALIZING, SeqCst) {
+        UNINITIALIZED => {
+          return match init() {
+            Ok(value) => {
+              unsafe { (*self.value.get()).as_mut_ptr().write(value) };
+              self.state.store(INITIALIZED, SeqCst);
+              Ok(unsafe { &*(*self.value.get()).as_ptr() })
+            },
+            Err(err) => {
+              self.state.store(UNINITIALIZED, SeqCst);
+              Err(err)
+            },
+          }
+        },
+        // Sleep instead of spinning, so a lower priority task running `init` can finish.
+        INITIALIZING => thread::sleep(Duration::from_millis(1)),
+        _ => return Ok(unsafe { &*(*self.value.get()).as_ptr() }),
+      }
+    }
+  }
+}
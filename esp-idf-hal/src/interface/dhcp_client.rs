@@ -0,0 +1,190 @@
+use std::net::Ipv4Addr;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use esp_idf_bindgen::{
+  dhcp,
+  dhcp_renew,
+  dhcp_state_enum_t,
+  esp_err_t,
+  esp_netif_dhcp_status_t,
+  esp_netif_dhcpc_get_status,
+  esp_netif_dhcpc_start,
+  esp_netif_dhcpc_stop,
+  esp_netif_get_netif_impl,
+  lwip_internal_netif_client_data_index,
+  netif,
+  tcpip_callback,
+  DHCP_COARSE_TIMER_SECS,
+  ERR_OK,
+  ESP_ERR_ESP_NETIF_DHCP_ALREADY_STARTED,
+  ESP_ERR_ESP_NETIF_DHCP_ALREADY_STOPPED,
+  ESP_ERR_INVALID_STATE,
+  ESP_FAIL,
+};
+
+use crate::EspError;
+
+use super::Interface;
+
+/// The status of a DHCP client or server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DhcpStatus {
+  /// Not yet started.
+  Init,
+  Started,
+  Stopped,
+}
+
+impl From<esp_netif_dhcp_status_t> for DhcpStatus {
+  fn from(status: esp_netif_dhcp_status_t) -> Self {
+    match status {
+      esp_netif_dhcp_status_t::ESP_NETIF_DHCP_STARTED => Self::Started,
+      esp_netif_dhcp_status_t::ESP_NETIF_DHCP_STOPPED => Self::Stopped,
+      _ => Self::Init,
+    }
+  }
+}
+
+/// A lease obtained by the DHCP client of an [`Interface`](enum.Interface.html).
+#[derive(Debug, Clone)]
+pub struct DhcpLease {
+  server: Ipv4Addr,
+  lease_time: Duration,
+  remaining: Duration,
+}
+
+impl DhcpLease {
+  /// The address of the DHCP server which granted the lease.
+  pub fn server(&self) -> &Ipv4Addr {
+    &self.server
+  }
+
+  /// The total duration of the lease.
+  pub fn lease_time(&self) -> Duration {
+    self.lease_time
+  }
+
+  /// The remaining duration of the lease, with a granularity of one minute.
+  pub fn remaining(&self) -> Duration {
+    self.remaining
+  }
+}
+
+impl Interface {
+  /// Start the DHCP client of this interface.
+  pub fn start_dhcp_client(&self) -> Result<(), EspError> {
+    match esp_ok!(esp_netif_dhcpc_start(self.netif()?)) {
+      Err(err) if err.code != ESP_ERR_ESP_NETIF_DHCP_ALREADY_STARTED as esp_err_t => Err(err),
+      _ => Ok(()),
+    }
+  }
+
+  /// Stop the DHCP client of this interface.
+  pub fn stop_dhcp_client(&self) -> Result<(), EspError> {
+    match esp_ok!(esp_netif_dhcpc_stop(self.netif()?)) {
+      Err(err) if err.code != ESP_ERR_ESP_NETIF_DHCP_ALREADY_STOPPED as esp_err_t => Err(err),
+      _ => Ok(()),
+    }
+  }
+
+  /// Get the status of the DHCP client of this interface.
+  pub fn dhcp_client_status(&self) -> Result<DhcpStatus, EspError> {
+    let mut status = esp_netif_dhcp_status_t::ESP_NETIF_DHCP_INIT;
+    esp_ok!(esp_netif_dhcpc_get_status(self.netif()?, &mut status))?;
+    Ok(status.into())
+  }
+
+  /// Ask the DHCP server to renew the current lease.
+  ///
+  /// The renewal happens asynchronously, a new lease is reported by a `WifiEvent::StaGotIp` event.
+  pub fn renew_dhcp_lease(&self) -> Result<(), EspError> {
+    extern "C" fn renew(ctx: *mut libc::c_void) {
+      unsafe { dhcp_renew(ctx as *mut netif) };
+    }
+
+    if self.dhcp_client_status()? != DhcpStatus::Started {
+      return Err(EspError { code: ESP_ERR_INVALID_STATE as esp_err_t })
+    }
+
+    let netif = self.lwip_netif()?;
+
+    // lwIP functions must be called from the TCP/IP task.
+    if unsafe { tcpip_callback(Some(renew), netif as *mut _) } != ERR_OK as _ {
+      return Err(EspError { code: ESP_FAIL as esp_err_t })
+    }
+
+    Ok(())
+  }
+
+  /// Get the lease currently held by the DHCP client of this interface, if any.
+  pub fn dhcp_lease(&self) -> Result<Option<DhcpLease>, EspError> {
+    let netif = self.lwip_netif()? as usize;
+
+    tcpip_call(move || {
+      let netif = netif as *mut netif;
+
+      let dhcp = unsafe { (*netif).client_data[lwip_internal_netif_client_data_index::LWIP_NETIF_CLIENT_DATA_INDEX_DHCP as usize] } as *const dhcp;
+      if dhcp.is_null() {
+        return None
+      }
+      let dhcp = unsafe { &*dhcp };
+
+      let bound = [
+        dhcp_state_enum_t::DHCP_STATE_BOUND,
+        dhcp_state_enum_t::DHCP_STATE_RENEWING,
+        dhcp_state_enum_t::DHCP_STATE_REBINDING,
+      ].iter().any(|&state| dhcp.state == state as u8);
+
+      if !bound {
+        return None
+      }
+
+      let remaining = dhcp.t0_timeout.saturating_sub(dhcp.lease_used) as u64 * DHCP_COARSE_TIMER_SECS as u64;
+
+      Some(DhcpLease {
+        server: u32::from_be(unsafe { dhcp.server_ip_addr.u_addr.ip4.addr }).into(),
+        lease_time: Duration::from_secs(dhcp.offered_t0_lease as u64),
+        remaining: Duration::from_secs(remaining),
+      })
+    })
+  }
+
+  pub(super) fn lwip_netif(&self) -> Result<*mut netif, EspError> {
+    let netif = unsafe { esp_netif_get_netif_impl(self.netif()?) } as *mut netif;
+
+    if netif.is_null() {
+      Err(EspError { code: ESP_ERR_INVALID_STATE as esp_err_t })
+    } else {
+      Ok(netif)
+    }
+  }
+}
+
+/// Run `f` on the TCP/IP task and wait for its result, since lwIP state must only be accessed from there.
+pub(super) fn tcpip_call<F, R>(f: F) -> Result<R, EspError>
+where
+  F: FnOnce() -> R + Send,
+  R: Send,
+{
+  type Call<'a> = Box<dyn FnOnce() + Send + 'a>;
+
+  extern "C" fn call(ctx: *mut libc::c_void) {
+    let f = unsafe { Box::from_raw(ctx as *mut Call<'_>) };
+    f();
+  }
+
+  let (sender, receiver) = mpsc::sync_channel(1);
+  let f: Call<'_> = Box::new(move || {
+    let _ = sender.send(f());
+  });
+  let ctx = Box::into_raw(Box::new(f));
+
+  // Borrowing from the caller is fine since this blocks until `f` has run.
+  if unsafe { tcpip_callback(Some(call), ctx as *mut _) } != ERR_OK as _ {
+    drop(unsafe { Box::from_raw(ctx) });
+    return Err(EspError { code: ESP_FAIL as esp_err_t })
+  }
+
+  receiver.recv().map_err(|_| EspError { code: ESP_FAIL as esp_err_t })
+}
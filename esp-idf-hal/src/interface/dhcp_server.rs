@@ -0,0 +1,214 @@
+use std::mem::size_of;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use esp_idf_bindgen::{
+  dhcps_lease_t,
+  dhcps_offer_t,
+  dhcps_time_t,
+  esp_err_t,
+  esp_netif_dhcp_option_id_t,
+  esp_netif_dhcp_option_mode_t,
+  esp_netif_dhcps_option,
+  esp_netif_dhcps_start,
+  esp_netif_dhcps_stop,
+  esp_netif_set_ip_info,
+  esp_netif_t,
+  ip4_addr_t,
+  ESP_ERR_ESP_NETIF_DHCP_ALREADY_STARTED,
+  ESP_ERR_ESP_NETIF_DHCP_ALREADY_STOPPED,
+  OFFER_DNS,
+  OFFER_ROUTER,
+};
+
+use crate::EspError;
+
+use super::{DnsServers, Interface, IpInfo};
+
+/// Configuration for the DHCP server of an access point [`Interface`](enum.Interface.html).
+#[derive(Debug, Clone)]
+pub struct DhcpServerConfig {
+  ip_info: IpInfo,
+  pool: Option<(Ipv4Addr, Ipv4Addr)>,
+  lease_time: Duration,
+  dns: Option<Ipv4Addr>,
+  offer_router: bool,
+}
+
+impl DhcpServerConfig {
+  /// The address, netmask and gateway of the access point itself.
+  pub fn ip_info(&self) -> &IpInfo {
+    &self.ip_info
+  }
+
+  /// The first and last address handed out to clients.
+  pub fn pool(&self) -> Option<(Ipv4Addr, Ipv4Addr)> {
+    self.pool
+  }
+
+  pub fn lease_time(&self) -> Duration {
+    self.lease_time
+  }
+
+  /// The DNS server offered to clients.
+  pub fn dns(&self) -> Option<Ipv4Addr> {
+    self.dns
+  }
+
+  /// Whether the gateway is offered to clients as their router.
+  pub fn offer_router(&self) -> bool {
+    self.offer_router
+  }
+
+  pub fn builder() -> DhcpServerConfigBuilder {
+    DhcpServerConfigBuilder::default()
+  }
+}
+
+/// Builder for [`DhcpServerConfig`](struct.DhcpServerConfig.html).
+#[derive(Debug, Clone)]
+pub struct DhcpServerConfigBuilder {
+  ip_info: IpInfo,
+  pool: Option<(Ipv4Addr, Ipv4Addr)>,
+  lease_time: Duration,
+  dns: Option<Ipv4Addr>,
+  offer_router: bool,
+}
+
+impl Default for DhcpServerConfigBuilder {
+  fn default() -> Self {
+    Self {
+      ip_info: IpInfo::new(Ipv4Addr::new(192, 168, 4, 1), Ipv4Addr::new(255, 255, 255, 0), Ipv4Addr::new(192, 168, 4, 1)),
+      pool: None,
+      lease_time: Duration::from_secs(120 * 60),
+      dns: None,
+      offer_router: true,
+    }
+  }
+}
+
+impl DhcpServerConfigBuilder {
+  /// Set the address, netmask and gateway of the access point. Defaults to `192.168.4.1/24`.
+  pub fn ip_info(&mut self, ip_info: IpInfo) -> &mut Self {
+    self.ip_info = ip_info;
+    self
+  }
+
+  /// Set the range of addresses handed out to clients. Both addresses must be in the subnet of the access point.
+  ///
+  /// By default, the pool is chosen automatically based on the access point address.
+  pub fn pool(&mut self, start: Ipv4Addr, end: Ipv4Addr) -> &mut Self {
+    assert!(u32::from(start) <= u32::from(end), "pool start must not be after pool end");
+    self.pool = Some((start, end));
+    self
+  }
+
+  /// Set the lease time, with a granularity of one minute. Defaults to two hours.
+  pub fn lease_time(&mut self, lease_time: Duration) -> &mut Self {
+    let minutes = lease_time.as_secs() / 60;
+    assert!(minutes >= 1 && minutes <= dhcps_time_t::max_value() as u64, "invalid lease time {:?}", lease_time);
+    self.lease_time = lease_time;
+    self
+  }
+
+  /// Set the DNS server offered to clients.
+  pub fn dns(&mut self, dns: Ipv4Addr) -> &mut Self {
+    self.dns = Some(dns);
+    self
+  }
+
+  /// Set whether the gateway is offered to clients as their router. Defaults to `true`.
+  pub fn offer_router(&mut self, offer_router: bool) -> &mut Self {
+    self.offer_router = offer_router;
+    self
+  }
+
+  pub fn build(&self) -> DhcpServerConfig {
+    if let Some((start, end)) = self.pool {
+      let netmask = u32::from(*self.ip_info.netmask());
+      let subnet = u32::from(*self.ip_info.ip()) & netmask;
+      assert!(
+        u32::from(start) & netmask == subnet && u32::from(end) & netmask == subnet,
+        "pool must be in the subnet of the access point",
+      );
+    }
+
+    DhcpServerConfig {
+      ip_info: self.ip_info.clone(),
+      pool: self.pool,
+      lease_time: self.lease_time,
+      dns: self.dns,
+      offer_router: self.offer_router,
+    }
+  }
+}
+
+fn set_option<T>(netif: *mut esp_netif_t, id: esp_netif_dhcp_option_id_t, value: &mut T) -> Result<(), EspError> {
+  esp_ok!(esp_netif_dhcps_option(
+    netif,
+    esp_netif_dhcp_option_mode_t::ESP_NETIF_OP_SET,
+    id,
+    value as *mut T as *mut _,
+    size_of::<T>() as u32,
+  ))
+}
+
+impl Interface {
+  /// Configure the DHCP server of this interface. The server is restarted with the new configuration.
+  ///
+  /// Only the access point interface runs a DHCP server.
+  pub fn set_dhcp_server_config(&self, config: &DhcpServerConfig) -> Result<(), EspError> {
+    let netif = self.netif()?;
+
+    self.stop_dhcp_server()?;
+
+    // Restart the server even if the configuration failed, so clients keep getting leases.
+    let configured = (|| {
+      let ip_info = config.ip_info.to_native();
+      esp_ok!(esp_netif_set_ip_info(netif, &ip_info))?;
+
+      if let Some((start, end)) = config.pool {
+        let mut lease = dhcps_lease_t {
+          enable: true,
+          start_ip: ip4_addr_t { addr: u32::from(start).to_be() },
+          end_ip: ip4_addr_t { addr: u32::from(end).to_be() },
+        };
+        set_option(netif, esp_netif_dhcp_option_id_t::ESP_NETIF_REQUESTED_IP_ADDRESS, &mut lease)?;
+      }
+
+      let mut lease_time = (config.lease_time.as_secs() / 60) as dhcps_time_t;
+      set_option(netif, esp_netif_dhcp_option_id_t::ESP_NETIF_IP_ADDRESS_LEASE_TIME, &mut lease_time)?;
+
+      let mut offer_router: dhcps_offer_t = if config.offer_router { OFFER_ROUTER as _ } else { 0 };
+      set_option(netif, esp_netif_dhcp_option_id_t::ESP_NETIF_ROUTER_SOLICITATION_ADDRESS, &mut offer_router)?;
+
+      let mut offer_dns: dhcps_offer_t = if config.dns.is_some() { OFFER_DNS as _ } else { 0 };
+      set_option(netif, esp_netif_dhcp_option_id_t::ESP_NETIF_DOMAIN_NAME_SERVER, &mut offer_dns)?;
+
+      if let Some(dns) = config.dns {
+        self.set_dns_servers(&DnsServers::new(dns.into()))?;
+      }
+
+      Ok(())
+    })();
+
+    let started = self.start_dhcp_server();
+    configured.and(started)
+  }
+
+  /// Start the DHCP server of this interface.
+  pub fn start_dhcp_server(&self) -> Result<(), EspError> {
+    match esp_ok!(esp_netif_dhcps_start(self.netif()?)) {
+      Err(err) if err.code != ESP_ERR_ESP_NETIF_DHCP_ALREADY_STARTED as esp_err_t => Err(err),
+      _ => Ok(()),
+    }
+  }
+
+  /// Stop the DHCP server of this interface.
+  pub fn stop_dhcp_server(&self) -> Result<(), EspError> {
+    match esp_ok!(esp_netif_dhcps_stop(self.netif()?)) {
+      Err(err) if err.code != ESP_ERR_ESP_NETIF_DHCP_ALREADY_STOPPED as esp_err_t => Err(err),
+      _ => Ok(()),
+    }
+  }
+}
@@ -0,0 +1,128 @@
+use std::mem::MaybeUninit;
+use std::net::IpAddr;
+
+use esp_idf_bindgen::{
+  esp_ip4_addr_t,
+  esp_ip6_addr_t,
+  esp_netif_dns_info_t,
+  esp_netif_dns_type_t,
+  esp_netif_get_dns_info,
+  esp_netif_set_dns_info,
+  ESP_IPADDR_TYPE_V4,
+  ESP_IPADDR_TYPE_V6,
+};
+
+use crate::EspError;
+
+use super::{ip6_from_native, Interface};
+
+/// The DNS servers of an [`Interface`](enum.Interface.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DnsServers {
+  main: Option<IpAddr>,
+  backup: Option<IpAddr>,
+  fallback: Option<IpAddr>,
+}
+
+impl DnsServers {
+  /// Create a DNS server configuration with only a main server.
+  pub fn new(main: IpAddr) -> Self {
+    Self { main: Some(main), backup: None, fallback: None }
+  }
+
+  /// Add a backup server, used if the main server does not respond.
+  pub fn with_backup(mut self, backup: IpAddr) -> Self {
+    self.backup = Some(backup);
+    self
+  }
+
+  /// Add a fallback server, used if neither the main nor the backup server respond.
+  pub fn with_fallback(mut self, fallback: IpAddr) -> Self {
+    self.fallback = Some(fallback);
+    self
+  }
+
+  pub fn main(&self) -> Option<&IpAddr> {
+    self.main.as_ref()
+  }
+
+  pub fn backup(&self) -> Option<&IpAddr> {
+    self.backup.as_ref()
+  }
+
+  pub fn fallback(&self) -> Option<&IpAddr> {
+    self.fallback.as_ref()
+  }
+}
+
+const DNS_TYPES: [esp_netif_dns_type_t; 3] = [
+  esp_netif_dns_type_t::ESP_NETIF_DNS_MAIN,
+  esp_netif_dns_type_t::ESP_NETIF_DNS_BACKUP,
+  esp_netif_dns_type_t::ESP_NETIF_DNS_FALLBACK,
+];
+
+fn dns_from_native(dns_info: &esp_netif_dns_info_t) -> Option<IpAddr> {
+  let ip = if dns_info.ip.type_ as u32 == ESP_IPADDR_TYPE_V6 {
+    IpAddr::V6(ip6_from_native(unsafe { &dns_info.ip.u_addr.ip6 }))
+  } else {
+    IpAddr::V4(u32::from_be(unsafe { dns_info.ip.u_addr.ip4.addr }).into())
+  };
+
+  if ip.is_unspecified() { None } else { Some(ip) }
+}
+
+fn dns_to_native(ip: IpAddr) -> esp_netif_dns_info_t {
+  let mut dns_info: esp_netif_dns_info_t = unsafe { MaybeUninit::zeroed().assume_init() };
+
+  match ip {
+    IpAddr::V4(ip) => {
+      dns_info.ip.u_addr.ip4 = esp_ip4_addr_t { addr: u32::from(ip).to_be() };
+      dns_info.ip.type_ = ESP_IPADDR_TYPE_V4 as _;
+    },
+    IpAddr::V6(ip) => {
+      let mut addr = [0; 4];
+      for (word, chunk) in addr.iter_mut().zip(ip.octets().chunks(4)) {
+        *word = u32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+      }
+      dns_info.ip.u_addr.ip6 = esp_ip6_addr_t { addr, zone: 0 };
+      dns_info.ip.type_ = ESP_IPADDR_TYPE_V6 as _;
+    },
+  }
+
+  dns_info
+}
+
+impl Interface {
+  /// Get the DNS servers of this interface.
+  pub fn dns_servers(&self) -> Result<DnsServers, EspError> {
+    let netif = self.netif()?;
+
+    let mut servers = [None; 3];
+    for (server, dns_type) in servers.iter_mut().zip(DNS_TYPES.iter()) {
+      let mut dns_info = MaybeUninit::<esp_netif_dns_info_t>::uninit();
+      esp_ok!(esp_netif_get_dns_info(netif, *dns_type, dns_info.as_mut_ptr()))?;
+      *server = dns_from_native(unsafe { &dns_info.assume_init() });
+    }
+
+    let [main, backup, fallback] = servers;
+    Ok(DnsServers { main, backup, fallback })
+  }
+
+  /// Set the DNS servers of this interface. Servers which are `None` are left unchanged.
+  ///
+  /// Servers obtained using DHCP replace the ones set here, so a static IP configuration
+  /// should be used with custom DNS servers.
+  pub fn set_dns_servers(&self, servers: &DnsServers) -> Result<(), EspError> {
+    let netif = self.netif()?;
+
+    let servers = [servers.main, servers.backup, servers.fallback];
+    for (server, dns_type) in servers.iter().zip(DNS_TYPES.iter()) {
+      if let Some(server) = server {
+        let mut dns_info = dns_to_native(*server);
+        esp_ok!(esp_netif_set_dns_info(netif, *dns_type, &mut dns_info))?;
+      }
+    }
+
+    Ok(())
+  }
+}
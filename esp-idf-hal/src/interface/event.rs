@@ -0,0 +1,107 @@
+use core::mem::transmute;
+
+use esp_idf_bindgen::{
+  esp_event_base_t,
+  esp_netif_t,
+  eth_event_t,
+  ip_event_got_ip_t,
+  ip_event_t,
+  wifi_event_t,
+  ETH_EVENT,
+  IP_EVENT,
+  WIFI_EVENT,
+};
+
+use crate::EspError;
+use crate::event::{EventReceiver, Subscription};
+
+use super::{Interface, IpInfo};
+
+/// A link or IP address transition of an [`Interface`](enum.Interface.html).
+#[derive(Debug, Clone)]
+pub enum InterfaceEvent {
+  /// The link of the interface is up.
+  Up(Interface),
+  /// The link of the interface is down.
+  Down(Interface),
+  /// The interface obtained an IP address.
+  ///
+  /// The access point interface has a static IP address and does not report this event,
+  /// use [`Interface::ip_info`](enum.Interface.html#method.ip_info) once it is `Up` instead.
+  GotIp { interface: Interface, ip_info: IpInfo, changed: bool },
+  /// The interface lost its IP address.
+  ///
+  /// The access point interface never loses its address. ESP-IDF v4.2 has no event for an
+  /// Ethernet interface losing its address, its address is invalid once it is `Down`.
+  LostIp(Interface),
+}
+
+impl InterfaceEvent {
+  /// The interface this event refers to.
+  pub fn interface(&self) -> Interface {
+    match *self {
+      Self::Up(interface) | Self::Down(interface) | Self::LostIp(interface) => interface,
+      Self::GotIp { interface, .. } => interface,
+    }
+  }
+
+  pub(crate) unsafe fn from_raw(event_base: esp_event_base_t, event_id: i32, event_data: *mut libc::c_void) -> Option<Self> {
+    if event_base == WIFI_EVENT {
+      if event_id < 0 || event_id >= wifi_event_t::WIFI_EVENT_MAX as i32 {
+        return None
+      }
+
+      let event_id: wifi_event_t = transmute(event_id as u32);
+
+      Some(match event_id {
+        wifi_event_t::WIFI_EVENT_STA_CONNECTED => Self::Up(Interface::Sta),
+        wifi_event_t::WIFI_EVENT_STA_DISCONNECTED => Self::Down(Interface::Sta),
+        wifi_event_t::WIFI_EVENT_AP_START => Self::Up(Interface::Ap),
+        wifi_event_t::WIFI_EVENT_AP_STOP => Self::Down(Interface::Ap),
+        _ => return None,
+      })
+    } else if event_base == ETH_EVENT {
+      Some(match event_id {
+        id if id == eth_event_t::ETHERNET_EVENT_CONNECTED as i32 => Self::Up(Interface::Eth),
+        id if id == eth_event_t::ETHERNET_EVENT_DISCONNECTED as i32 => Self::Down(Interface::Eth),
+        _ => return None,
+      })
+    } else if event_base == IP_EVENT {
+      Some(match event_id {
+        id if id == ip_event_t::IP_EVENT_STA_GOT_IP as i32 || id == ip_event_t::IP_EVENT_ETH_GOT_IP as i32 => {
+          let event = &*(event_data as *const ip_event_got_ip_t);
+          let interface = Interface::from_netif(event.esp_netif as *mut esp_netif_t)?;
+          Self::GotIp { interface, ip_info: IpInfo::from_native_unchecked(event.ip_info), changed: event.ip_changed }
+        },
+        id if id == ip_event_t::IP_EVENT_STA_LOST_IP as i32 => {
+          let event = &*(event_data as *const ip_event_got_ip_t);
+          Self::LostIp(Interface::from_netif(event.esp_netif as *mut esp_netif_t).unwrap_or(Interface::Sta))
+        },
+        _ => return None,
+      })
+    } else {
+      None
+    }
+  }
+}
+
+/// Call `callback` for every [`InterfaceEvent`](enum.InterfaceEvent.html) until the returned subscription is dropped.
+///
+/// The callback runs on the event loop task, so it should return quickly.
+pub fn subscribe<F>(mut callback: F) -> Result<Subscription, EspError>
+where
+  F: FnMut(InterfaceEvent) + Send + 'static,
+{
+  Subscription::new(unsafe { &[WIFI_EVENT, ETH_EVENT, IP_EVENT] }, move |event_base, event_id, event_data| {
+    if let Some(event) = unsafe { InterfaceEvent::from_raw(event_base, event_id, event_data) } {
+      callback(event)
+    }
+  })
+}
+
+/// Receive every [`InterfaceEvent`](enum.InterfaceEvent.html) through a channel.
+pub fn events() -> Result<EventReceiver<InterfaceEvent>, EspError> {
+  EventReceiver::new(unsafe { &[WIFI_EVENT, ETH_EVENT, IP_EVENT] }, |event_base, event_id, event_data| {
+    unsafe { InterfaceEvent::from_raw(event_base, event_id, event_data) }
+  })
+}
@@ -0,0 +1,76 @@
+use std::mem::MaybeUninit;
+use std::net::Ipv6Addr;
+
+use esp_idf_bindgen::{
+  esp_ip6_addr_t,
+  esp_netif_create_ip6_linklocal,
+  esp_netif_get_all_ip6,
+  esp_netif_get_ip6_linklocal,
+};
+
+use crate::EspError;
+
+use super::Interface;
+
+/// Upper bound for `LWIP_IPV6_NUM_ADDRESSES`, the number of IPv6 addresses per interface.
+const IP6_ADDRESSES_MAX: usize = 8;
+
+pub(crate) fn ip6_from_native(ip6: &esp_ip6_addr_t) -> Ipv6Addr {
+  // Each word is stored in network byte order.
+  let mut octets = [0; 16];
+  for (chunk, word) in octets.chunks_mut(4).zip(ip6.addr.iter()) {
+    chunk.copy_from_slice(&word.to_ne_bytes());
+  }
+  Ipv6Addr::from(octets)
+}
+
+/// IPv6 information for an [`Interface`](enum.Interface.html).
+#[derive(Debug, Clone)]
+pub struct Ip6Info {
+  link_local: Option<Ipv6Addr>,
+  addresses: Vec<Ipv6Addr>,
+}
+
+impl Ip6Info {
+  /// The preferred link-local address, if one has been assigned.
+  pub fn link_local(&self) -> Option<&Ipv6Addr> {
+    self.link_local.as_ref()
+  }
+
+  /// All preferred addresses, including link-local and global addresses.
+  pub fn addresses(&self) -> &[Ipv6Addr] {
+    &self.addresses
+  }
+
+  /// All preferred addresses which are not link-local.
+  pub fn global(&self) -> impl Iterator<Item = &Ipv6Addr> {
+    self.addresses.iter().filter(|ip| (ip.segments()[0] & 0xffc0) != 0xfe80)
+  }
+}
+
+impl Interface {
+  /// Get the IPv6 addresses of this interface.
+  pub fn ip6_info(&self) -> Result<Ip6Info, EspError> {
+    let netif = self.netif()?;
+
+    let mut link_local = MaybeUninit::<esp_ip6_addr_t>::uninit();
+    let link_local = match esp_ok!(esp_netif_get_ip6_linklocal(netif, link_local.as_mut_ptr())) {
+      Ok(()) => Some(ip6_from_native(unsafe { &link_local.assume_init() })),
+      Err(_) => None,
+    };
+
+    let mut addresses: [esp_ip6_addr_t; IP6_ADDRESSES_MAX] = unsafe { MaybeUninit::zeroed().assume_init() };
+    let count = unsafe { esp_netif_get_all_ip6(netif, addresses.as_mut_ptr()) };
+    let addresses = addresses.iter().take(count.max(0) as usize).map(ip6_from_native).collect();
+
+    Ok(Ip6Info { link_local, addresses })
+  }
+
+  /// Create an IPv6 link-local address for this interface.
+  ///
+  /// The interface must be up, e.g. the station must be connected. Global addresses are then
+  /// configured automatically using SLAAC, each reported by a `WifiEvent::GotIp6` event.
+  pub fn create_ip6_link_local(&self) -> Result<(), EspError> {
+    esp_ok!(esp_netif_create_ip6_linklocal(self.netif()?))
+  }
+}
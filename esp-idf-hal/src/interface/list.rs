@@ -0,0 +1,106 @@
+use std::ffi::CStr;
+use std::mem::MaybeUninit;
+use std::ptr;
+
+use esp_idf_bindgen::{
+  esp_netif_get_desc,
+  esp_netif_get_ifkey,
+  esp_netif_get_ip_info,
+  esp_netif_get_mac,
+  esp_netif_ip_info_t,
+  esp_netif_is_netif_up,
+  esp_netif_next,
+  esp_netif_t,
+};
+use macaddr::MacAddr6;
+
+use super::{Interface, IpInfo};
+
+/// A network interface registered with `esp_netif`, returned by
+/// [`Interface::iter_active`](enum.Interface.html#method.iter_active).
+#[derive(Debug, Clone)]
+pub struct NetifInfo {
+  interface: Option<Interface>,
+  key: String,
+  description: String,
+  mac: Option<MacAddr6>,
+  ip_info: IpInfo,
+  is_up: bool,
+}
+
+impl NetifInfo {
+  /// The corresponding interface, if it is one of the predefined interfaces.
+  pub fn interface(&self) -> Option<Interface> {
+    self.interface
+  }
+
+  /// The unique key of the netif, e.g. `WIFI_STA_DEF`.
+  pub fn key(&self) -> &str {
+    &self.key
+  }
+
+  pub fn description(&self) -> &str {
+    &self.description
+  }
+
+  /// The MAC address, if the netif has one.
+  pub fn mac(&self) -> Option<&MacAddr6> {
+    self.mac.as_ref()
+  }
+
+  pub fn ip_info(&self) -> &IpInfo {
+    &self.ip_info
+  }
+
+  pub fn is_up(&self) -> bool {
+    self.is_up
+  }
+}
+
+fn string_from_ptr(ptr: *const libc::c_char) -> String {
+  if ptr.is_null() {
+    String::new()
+  } else {
+    unsafe { CStr::from_ptr(ptr) }.to_string_lossy().into_owned()
+  }
+}
+
+impl NetifInfo {
+  unsafe fn from_netif(netif: *mut esp_netif_t) -> Self {
+    let mut mac = [0; 6];
+    let mac = match esp_ok!(esp_netif_get_mac(netif, mac.as_mut_ptr())) {
+      Ok(()) => Some(MacAddr6::from(mac)).filter(|mac| !mac.is_nil()),
+      Err(_) => None,
+    };
+
+    let mut ip_info = MaybeUninit::<esp_netif_ip_info_t>::zeroed();
+    let _ = esp_ok!(esp_netif_get_ip_info(netif, ip_info.as_mut_ptr()));
+
+    NetifInfo {
+      interface: Interface::from_netif(netif),
+      key: string_from_ptr(esp_netif_get_ifkey(netif)),
+      description: string_from_ptr(esp_netif_get_desc(netif)),
+      mac,
+      ip_info: IpInfo::from_native_unchecked(ip_info.assume_init()),
+      is_up: esp_netif_is_netif_up(netif),
+    }
+  }
+}
+
+impl Interface {
+  /// List all network interfaces currently registered with `esp_netif`, including
+  /// netifs not covered by `Interface`, such as PPP.
+  ///
+  /// The list is a snapshot taken when this function is called.
+  pub fn iter_active() -> impl Iterator<Item = NetifInfo> {
+    let mut netifs = Vec::new();
+
+    let mut netif = unsafe { esp_netif_next(ptr::null_mut()) };
+    while !netif.is_null() {
+      netifs.push(unsafe { NetifInfo::from_netif(netif) });
+      netif = unsafe { esp_netif_next(netif) };
+    }
+
+    netifs.into_iter()
+  }
+}
@@ -0,0 +1,106 @@
+use esp_idf_bindgen::{
+  esp_base_mac_addr_get,
+  esp_base_mac_addr_set,
+  esp_err_t,
+  esp_interface_t,
+  esp_wifi_get_mac,
+  esp_wifi_set_mac,
+  ESP_ERR_INVALID_ARG,
+};
+#[cfg(target_device = "esp32")]
+use esp_idf_bindgen::{esp_efuse_mac_get_custom, esp_netif_get_mac, esp_netif_set_mac};
+use macaddr::MacAddr6;
+
+use crate::EspError;
+
+use super::Interface;
+
+fn wifi_mac(interface: esp_interface_t) -> Result<MacAddr6, EspError> {
+  let mut mac = [0; 6];
+  esp_ok!(esp_wifi_get_mac(interface, mac.as_mut_ptr()))?;
+  Ok(MacAddr6::from(mac))
+}
+
+impl Interface {
+  /// Get the MAC address currently used by this interface.
+  ///
+  /// Unlike `MacAddr6::from(interface)`, which returns the default MAC address,
+  /// this reflects a MAC address set with [`set_mac`](#method.set_mac).
+  pub fn mac(&self) -> Result<MacAddr6, EspError> {
+    match self {
+      Self::Sta => wifi_mac(esp_interface_t::ESP_IF_WIFI_STA),
+      Self::Ap => wifi_mac(esp_interface_t::ESP_IF_WIFI_AP),
+      #[cfg(target_device = "esp32")]
+      Self::Eth => {
+        let mut mac = [0; 6];
+        esp_ok!(esp_netif_get_mac(self.netif()?, mac.as_mut_ptr()))?;
+        Ok(MacAddr6::from(mac))
+      },
+      #[cfg(target_device = "esp32")]
+      Self::Bt => Ok(MacAddr6::from(*self)),
+    }
+  }
+
+  /// Set the MAC address of this interface.
+  ///
+  /// The address must be a unicast address, and the station and access point interfaces
+  /// must use different addresses. WiFi must be initialized, but the interface must not
+  /// have been started yet. The MAC address of the Bluetooth interface cannot be changed.
+  pub fn set_mac(&self, mac: MacAddr6) -> Result<(), EspError> {
+    let invalid_arg = EspError { code: ESP_ERR_INVALID_ARG as esp_err_t };
+
+    if mac.is_multicast() || mac.is_nil() {
+      return Err(invalid_arg)
+    }
+
+    let (interface, other) = match self {
+      Self::Sta => (esp_interface_t::ESP_IF_WIFI_STA, esp_interface_t::ESP_IF_WIFI_AP),
+      Self::Ap => (esp_interface_t::ESP_IF_WIFI_AP, esp_interface_t::ESP_IF_WIFI_STA),
+      #[cfg(target_device = "esp32")]
+      Self::Eth => return esp_ok!(esp_netif_set_mac(self.netif()?, mac.into_array().as_mut_ptr())),
+      #[cfg(target_device = "esp32")]
+      Self::Bt => return Err(invalid_arg),
+    };
+
+    if wifi_mac(other)? == mac {
+      return Err(invalid_arg)
+    }
+
+    esp_ok!(esp_wifi_set_mac(interface, mac.as_bytes().as_ptr()))
+  }
+
+  /// Override the base MAC address from which the default MAC addresses of all interfaces are derived.
+  ///
+  /// Must be called before WiFi or Ethernet is initialized. `MacAddr6::from(interface)`
+  /// reflects the new base address afterwards.
+  pub fn set_base_mac(mac: MacAddr6) -> Result<(), EspError> {
+    if mac.is_multicast() || mac.is_nil() {
+      return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+    }
+
+    esp_ok!(esp_base_mac_addr_set(mac.into_array().as_mut_ptr()))
+  }
+
+  /// Get the base MAC address set with [`set_base_mac`](#method.set_base_mac).
+  pub fn base_mac() -> Result<MacAddr6, EspError> {
+    let mut mac = [0; 6];
+    esp_ok!(esp_base_mac_addr_get(mac.as_mut_ptr()))?;
+    Ok(MacAddr6::from(mac))
+  }
+
+  /// Read the custom MAC address programmed into eFuse block 3.
+  #[cfg(target_device = "esp32")]
+  pub fn efuse_custom_mac() -> Result<MacAddr6, EspError> {
+    let mut mac = [0; 6];
+    esp_ok!(esp_efuse_mac_get_custom(mac.as_mut_ptr()))?;
+    Ok(MacAddr6::from(mac))
+  }
+
+  /// Use the custom MAC address programmed into eFuse block 3 as the base MAC address.
+  #[cfg(target_device = "esp32")]
+  pub fn use_efuse_custom_mac() -> Result<MacAddr6, EspError> {
+    let mac = Self::efuse_custom_mac()?;
+    Self::set_base_mac(mac)?;
+    Ok(mac)
+  }
+}
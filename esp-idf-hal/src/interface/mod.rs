@@ -0,0 +1,477 @@
+use std::ffi::{CStr, CString};
+use std::mem::MaybeUninit;
+use std::net::Ipv4Addr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(target_device = "esp32")]
+use std::sync::atomic::AtomicBool;
+use std::ptr;
+
+use esp_idf_bindgen::{esp_err_t, esp_mac_type_t, esp_read_mac, ESP_ERR_INVALID_ARG};
+#[cfg(target_device = "esp8266")]
+use esp_idf_bindgen::{tcpip_adapter_get_ip_info, tcpip_adapter_if_t, tcpip_adapter_ip_info_t as ip_info_t};
+#[cfg(target_device = "esp8266")]
+use esp_idf_bindgen::{tcpip_adapter_get_hostname, tcpip_adapter_set_hostname, tcpip_adapter_is_netif_up};
+#[cfg(target_device = "esp32")]
+use esp_idf_bindgen::{esp_netif_get_ip_info, esp_netif_ip_info_t as ip_info_t, esp_netif_t, esp_netif_create_default_wifi_ap, esp_netif_create_default_wifi_sta};
+#[cfg(target_device = "esp32")]
+use esp_idf_bindgen::{esp_netif_get_hostname, esp_netif_set_hostname, esp_netif_is_netif_up};
+#[cfg(target_device = "esp32")]
+use esp_idf_bindgen::{
+  esp_ip4_addr_t,
+  esp_netif_create_default_wifi_mesh_netifs,
+  esp_netif_get_handle_from_ifkey,
+  esp_netif_set_ip_info,
+  ESP_ERR_INVALID_STATE,
+};
+use macaddr::{MacAddr, MacAddr6};
+
+use crate::EspError;
+
+mod mac;
+
+#[cfg(target_device = "esp32")]
+mod dhcp_server;
+#[cfg(target_device = "esp32")]
+pub use dhcp_server::*;
+
+#[cfg(target_device = "esp32")]
+mod ip6;
+#[cfg(target_device = "esp32")]
+pub use ip6::*;
+
+#[cfg(target_device = "esp32")]
+mod dhcp_client;
+#[cfg(target_device = "esp32")]
+pub use dhcp_client::*;
+
+#[cfg(target_device = "esp32")]
+mod event;
+#[cfg(target_device = "esp32")]
+pub use event::*;
+
+#[cfg(target_device = "esp32")]
+mod list;
+#[cfg(target_device = "esp32")]
+pub use list::*;
+
+#[cfg(target_device = "esp32")]
+mod stats;
+#[cfg(target_device = "esp32")]
+pub use stats::*;
+
+#[cfg(target_device = "esp32")]
+mod dns;
+#[cfg(target_device = "esp32")]
+pub use dns::*;
+
+/// Maximum length of a hostname.
+pub const HOSTNAME_MAX_LEN: usize = 32;
+
+static AP_PTR: AtomicUsize = AtomicUsize::new(0);
+static STA_PTR: AtomicUsize = AtomicUsize::new(0);
+#[cfg(target_device = "esp32")]
+static ETH_PTR: AtomicUsize = AtomicUsize::new(0);
+const INIT_SENTINEL: usize = usize::max_value();
+
+/// Enumeration of all available interfaces.
+#[derive(Debug, Clone, Copy)]
+pub enum Interface {
+  /// WiFi interface in station mode.
+  Sta,
+  /// WiFi interface in access point mode.
+  Ap,
+  /// Bluetooth interface.
+  #[cfg(target_device = "esp32")]
+  Bt,
+  /// Ethernet interface.
+  #[cfg(target_device = "esp32")]
+  Eth,
+}
+
+impl Interface {
+  #[cfg(target_device = "esp8266")]
+  pub fn ip_info(&self) -> IpInfo {
+    let interface = match self {
+      Self::Ap => tcpip_adapter_if_t::TCPIP_ADAPTER_IF_AP,
+      Self::Sta => tcpip_adapter_if_t::TCPIP_ADAPTER_IF_STA,
+    };
+
+    let mut ip_info = MaybeUninit::<ip_info_t>::uninit();
+    esp_ok!(tcpip_adapter_get_ip_info(interface, ip_info.as_mut_ptr())).unwrap(); // Can only fail with invalid arguments.
+    unsafe { IpInfo::from_native_unchecked(ip_info.assume_init()) }
+  }
+
+
+  #[cfg(target_device = "esp8266")]
+  pub(crate) fn init(&self) {
+  }
+
+  #[cfg(target_device = "esp8266")]
+  fn adapter(&self) -> tcpip_adapter_if_t {
+    match self {
+      Self::Ap => tcpip_adapter_if_t::TCPIP_ADAPTER_IF_AP,
+      Self::Sta => tcpip_adapter_if_t::TCPIP_ADAPTER_IF_STA,
+    }
+  }
+
+  /// Set the hostname reported to DHCP servers.
+  #[cfg(target_device = "esp8266")]
+  pub fn set_hostname(&self, hostname: &str) -> Result<(), EspError> {
+    let hostname = hostname_cstring(hostname)?;
+    esp_ok!(tcpip_adapter_set_hostname(self.adapter(), hostname.as_ptr()))
+  }
+
+  /// Get the hostname reported to DHCP servers.
+  #[cfg(target_device = "esp8266")]
+  pub fn hostname(&self) -> Result<String, EspError> {
+    let mut hostname = ptr::null();
+    esp_ok!(tcpip_adapter_get_hostname(self.adapter(), &mut hostname))?;
+    Ok(hostname_from_ptr(hostname))
+  }
+
+  /// Whether the link of this interface is up.
+  #[cfg(target_device = "esp8266")]
+  pub fn is_up(&self) -> bool {
+    unsafe { tcpip_adapter_is_netif_up(self.adapter()) }
+  }
+
+  #[cfg(target_device = "esp32")]
+  pub fn ip_info(&self) -> IpInfo {
+    let mut ip_info = MaybeUninit::<ip_info_t>::uninit();
+    esp_ok!(esp_netif_get_ip_info(self.ptr(), ip_info.as_mut_ptr())).unwrap(); // Can only fail if `self.ptr()` returns `NUL`, in which case the interface does not support IPs.
+    unsafe { IpInfo::from_native_unchecked(ip_info.assume_init()) }
+  }
+
+  #[cfg(target_device = "esp32")]
+  fn ptr(&self) -> *mut esp_netif_t {
+    match self {
+      Self::Ap => {
+        loop {
+          match AP_PTR.compare_and_swap(0, INIT_SENTINEL, Ordering::SeqCst) {
+            0 => {
+              let ptr = unsafe { esp_netif_create_default_wifi_ap() };
+              AP_PTR.store(ptr as _, Ordering::SeqCst);
+              return ptr;
+            },
+            INIT_SENTINEL => continue,
+            ptr => return ptr as _,
+          }
+        }
+      },
+      Self::Sta => {
+        loop {
+          match STA_PTR.compare_and_swap(0, INIT_SENTINEL, Ordering::SeqCst) {
+            0 => {
+              let ptr = unsafe { esp_netif_create_default_wifi_sta() };
+              STA_PTR.store(ptr as _, Ordering::SeqCst);
+              return ptr;
+            },
+            INIT_SENTINEL => continue,
+            ptr => return ptr as _,
+          }
+        }
+      },
+      Self::Eth => ETH_PTR.load(Ordering::SeqCst) as _,
+      _ => ptr::null_mut()
+    }
+  }
+
+  #[cfg(target_device = "esp32")]
+  pub(crate) fn init(&self) {
+    self.ptr();
+  }
+
+  /// Find the interface belonging to an already created `esp_netif_t`.
+  #[cfg(target_device = "esp32")]
+  pub(crate) fn from_netif(ptr: *mut esp_netif_t) -> Option<Self> {
+    if ptr.is_null() {
+      return None
+    }
+
+    let ptr = ptr as usize;
+
+    if AP_PTR.load(Ordering::SeqCst) == ptr {
+      Some(Self::Ap)
+    } else if STA_PTR.load(Ordering::SeqCst) == ptr {
+      Some(Self::Sta)
+    } else if ETH_PTR.load(Ordering::SeqCst) == ptr {
+      Some(Self::Eth)
+    } else {
+      None
+    }
+  }
+
+  /// Create the station and access point interfaces for use with ESP-MESH instead of the default ones.
+  #[cfg(target_device = "esp32")]
+  pub(crate) fn init_mesh() -> Result<(), EspError> {
+    static MESH_INIT: AtomicBool = AtomicBool::new(false);
+
+    if MESH_INIT.load(Ordering::SeqCst) {
+      return Ok(())
+    }
+
+    if STA_PTR.compare_and_swap(0, INIT_SENTINEL, Ordering::SeqCst) != 0 {
+      return Err(EspError { code: ESP_ERR_INVALID_STATE as esp_err_t })
+    }
+
+    if AP_PTR.compare_and_swap(0, INIT_SENTINEL, Ordering::SeqCst) != 0 {
+      STA_PTR.store(0, Ordering::SeqCst);
+      return Err(EspError { code: ESP_ERR_INVALID_STATE as esp_err_t })
+    }
+
+    let mut sta_ptr = ptr::null_mut();
+    let mut ap_ptr = ptr::null_mut();
+    if let Err(err) = esp_ok!(esp_netif_create_default_wifi_mesh_netifs(&mut sta_ptr, &mut ap_ptr)) {
+      STA_PTR.store(0, Ordering::SeqCst);
+      AP_PTR.store(0, Ordering::SeqCst);
+      return Err(err)
+    }
+
+    STA_PTR.store(sta_ptr as _, Ordering::SeqCst);
+    AP_PTR.store(ap_ptr as _, Ordering::SeqCst);
+    MESH_INIT.store(true, Ordering::SeqCst);
+    Ok(())
+  }
+
+  /// Register the netif created by the Ethernet driver. Only one Ethernet interface can exist at a time.
+  #[cfg(target_device = "esp32")]
+  pub(crate) fn attach_eth(netif: *mut esp_netif_t) -> Result<(), EspError> {
+    if ETH_PTR.compare_and_swap(0, netif as usize, Ordering::SeqCst) != 0 {
+      return Err(EspError { code: ESP_ERR_INVALID_STATE as esp_err_t })
+    }
+
+    Ok(())
+  }
+
+  #[cfg(target_device = "esp32")]
+  pub(crate) fn detach_eth() {
+    ETH_PTR.store(0, Ordering::SeqCst);
+  }
+
+  #[cfg(target_device = "esp32")]
+  fn netif(&self) -> Result<*mut esp_netif_t, EspError> {
+    let ptr = self.ptr();
+
+    if ptr.is_null() {
+      Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+    } else {
+      Ok(ptr)
+    }
+  }
+
+  /// Set the hostname reported to DHCP servers.
+  #[cfg(target_device = "esp32")]
+  pub fn set_hostname(&self, hostname: &str) -> Result<(), EspError> {
+    let hostname = hostname_cstring(hostname)?;
+    esp_ok!(esp_netif_set_hostname(self.netif()?, hostname.as_ptr()))
+  }
+
+  /// Get the hostname reported to DHCP servers.
+  #[cfg(target_device = "esp32")]
+  pub fn hostname(&self) -> Result<String, EspError> {
+    let mut hostname = ptr::null();
+    esp_ok!(esp_netif_get_hostname(self.netif()?, &mut hostname))?;
+    Ok(hostname_from_ptr(hostname))
+  }
+
+  /// Whether the link of this interface is up.
+  ///
+  /// An interface which has not been created yet is not up.
+  #[cfg(target_device = "esp32")]
+  pub fn is_up(&self) -> bool {
+    let ptr = match self {
+      Self::Ap => AP_PTR.load(Ordering::SeqCst),
+      Self::Sta => STA_PTR.load(Ordering::SeqCst),
+      Self::Eth => ETH_PTR.load(Ordering::SeqCst),
+      _ => 0,
+    };
+
+    match ptr {
+      0 | INIT_SENTINEL => false,
+      ptr => unsafe { esp_netif_is_netif_up(ptr as *mut esp_netif_t) },
+    }
+  }
+}
+
+#[cfg(target_device = "esp32")]
+impl Interface {
+  /// Configure how this interface obtains its IP address.
+  ///
+  /// A static configuration stops the DHCP client, a DHCP configuration restarts it.
+  pub fn set_ip_config(&self, config: &IpConfig) -> Result<(), EspError> {
+    match config {
+      IpConfig::Dhcp => self.start_dhcp_client(),
+      IpConfig::Static { ip_info, dns } => {
+        self.stop_dhcp_client()?;
+
+        let netif = self.netif()?;
+        let native_ip_info = ip_info.to_native();
+        esp_ok!(esp_netif_set_ip_info(netif, &native_ip_info))?;
+
+        if let Some(dns) = dns {
+          self.set_dns_servers(&DnsServers::new((*dns).into()))?;
+        }
+
+        Ok(())
+      },
+    }
+  }
+}
+
+/// IP configuration for an [`Interface`](enum.Interface.html).
+#[cfg(target_device = "esp32")]
+#[derive(Debug, Clone)]
+pub enum IpConfig {
+  /// Obtain an IP address using DHCP.
+  Dhcp,
+  /// Use a static IP address and optionally a static DNS server.
+  Static { ip_info: IpInfo, dns: Option<Ipv4Addr> },
+}
+
+#[cfg(target_device = "esp32")]
+impl Default for IpConfig {
+  fn default() -> Self {
+    Self::Dhcp
+  }
+}
+
+fn hostname_cstring(hostname: &str) -> Result<CString, EspError> {
+  let valid = !hostname.is_empty() && hostname.len() <= HOSTNAME_MAX_LEN &&
+    hostname.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-');
+
+  if !valid {
+    return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+  }
+
+  Ok(CString::new(hostname).unwrap())
+}
+
+fn hostname_from_ptr(hostname: *const libc::c_char) -> String {
+  if hostname.is_null() {
+    String::new()
+  } else {
+    unsafe { CStr::from_ptr(hostname) }.to_string_lossy().into_owned()
+  }
+}
+
+/// Get the default MAC address of an interface, derived from the base MAC address.
+///
+/// If the base MAC address was overridden using [`Interface::set_base_mac`](enum.Interface.html#method.set_base_mac),
+/// the derived address reflects it.
+///
+/// ```no_run
+/// use macaddr::MacAddr6;
+/// use esp32_hal::Interface;
+///
+/// MacAddr6::from(Interface::Ap)
+/// ```
+impl From<Interface> for MacAddr6 {
+  fn from(interface: Interface) -> Self {
+    let mac_address_type = match interface {
+      Interface::Sta => esp_mac_type_t::ESP_MAC_WIFI_STA,
+      Interface::Ap  => esp_mac_type_t::ESP_MAC_WIFI_SOFTAP,
+      #[cfg(target_device = "esp32")]
+      Interface::Bt  => esp_mac_type_t::ESP_MAC_BT,
+      #[cfg(target_device = "esp32")]
+      Interface::Eth => esp_mac_type_t::ESP_MAC_ETH,
+    };
+
+    let mut mac_address = MaybeUninit::<Self>::uninit();
+    esp_ok!(esp_read_mac(mac_address.as_mut_ptr() as *mut _, mac_address_type)).unwrap(); // Can only fail with invalid arguments.
+    unsafe { mac_address.assume_init() }
+  }
+}
+
+/// ```no_run
+/// use macaddr::MacAddr;
+/// use esp32_hal::Interface;
+///
+/// MacAddr::from(Interface::Ap)
+/// ```
+impl From<Interface> for MacAddr {
+  fn from(interface: Interface) -> Self {
+    Self::V6(interface.into())
+  }
+}
+
+/// IP information for an [`Interface`](enum.Interface.html).
+#[derive(Debug, Clone)]
+pub struct IpInfo {
+  ip: Ipv4Addr,
+  netmask: Ipv4Addr,
+  gateway: Ipv4Addr,
+}
+
+impl IpInfo {
+  pub fn new(ip: Ipv4Addr, netmask: Ipv4Addr, gateway: Ipv4Addr) -> Self {
+    Self { ip, netmask, gateway }
+  }
+
+  pub fn ip(&self) -> &Ipv4Addr {
+    &self.ip
+  }
+
+  pub fn netmask(&self) -> &Ipv4Addr {
+    &self.netmask
+  }
+
+  pub fn gateway(&self) -> &Ipv4Addr {
+    &self.gateway
+  }
+}
+
+impl IpInfo {
+  pub(crate) unsafe fn from_native_unchecked(ip_info: ip_info_t) -> Self {
+    IpInfo {
+      ip: u32::from_be(ip_info.ip.addr).into(),
+      netmask: u32::from_be(ip_info.netmask.addr).into(),
+      gateway: u32::from_be(ip_info.gw.addr).into(),
+    }
+  }
+
+  #[cfg(target_device = "esp32")]
+  pub(crate) fn to_native(&self) -> ip_info_t {
+    ip_info_t {
+      ip: esp_ip4_addr_t { addr: u32::from(self.ip).to_be() },
+      netmask: esp_ip4_addr_t { addr: u32::from(self.netmask).to_be() },
+      gw: esp_ip4_addr_t { addr: u32::from(self.gateway).to_be() },
+    }
+  }
+}
+
+#[cfg(target_device = "esp32")]
+const STA_KEY: &'static CStr = unsafe { CStr::from_bytes_with_nul_unchecked(b"WIFI_STA_DEF\0") };
+#[cfg(target_device = "esp32")]
+const AP_KEY: &'static CStr = unsafe { CStr::from_bytes_with_nul_unchecked(b"WIFI_AP_DEF\0") };
+#[cfg(target_device = "esp32")]
+const ETH_KEY: &'static CStr = unsafe { CStr::from_bytes_with_nul_unchecked(b"ETH_DEF\0") };
+
+#[cfg(target_device = "esp32")]
+impl IpInfo {
+  /// Get the IP information of the default station netif, if it has been created.
+  pub fn sta() -> Option<Self> {
+    Self::for_key(STA_KEY)
+  }
+
+  /// Get the IP information of the default access point netif, if it has been created.
+  pub fn ap() -> Option<Self> {
+    Self::for_key(AP_KEY)
+  }
+
+  /// Get the IP information of the default Ethernet netif, if it has been created.
+  pub fn eth() -> Option<Self> {
+    Self::for_key(ETH_KEY)
+  }
+
+  /// Get the IP information of the netif with the given key, if it exists.
+  pub fn for_key(key: &CStr) -> Option<Self> {
+    let netif = unsafe { esp_netif_get_handle_from_ifkey(key.as_ptr()) };
+    if netif.is_null() {
+      return None
+    }
+
+    let mut ip_info = MaybeUninit::<ip_info_t>::uninit();
+    esp_ok!(esp_netif_get_ip_info(netif, ip_info.as_mut_ptr())).ok()?;
+    Some(unsafe { IpInfo::from_native_unchecked(ip_info.assume_init()) })
+  }
+}
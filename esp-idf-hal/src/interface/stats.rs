@@ -0,0 +1,254 @@
+use core::fmt;
+use core::mem::transmute;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering::SeqCst};
+
+use esp_idf_bindgen::{
+  err_t,
+  esp_err_t,
+  netif,
+  netif_input_fn,
+  netif_linkoutput_fn,
+  pbuf,
+  ERR_IF,
+  ERR_OK,
+  ESP_ERR_INVALID_ARG,
+  ESP_ERR_INVALID_STATE,
+};
+
+use crate::EspError;
+use crate::callback_slot::CallbackSlot;
+
+use super::Interface;
+use super::dhcp_client::tcpip_call;
+
+/// Traffic counters of an [`Interface`](enum.Interface.html).
+///
+/// The counters wrap around on overflow.
+#[derive(Debug, Clone, Default)]
+pub struct InterfaceStats {
+  rx_packets: u32,
+  rx_bytes: u32,
+  rx_dropped: u32,
+  tx_packets: u32,
+  tx_bytes: u32,
+  tx_errors: u32,
+}
+
+impl InterfaceStats {
+  /// The number of packets received and passed to the IP stack.
+  pub fn rx_packets(&self) -> u32 {
+    self.rx_packets
+  }
+
+  /// The number of bytes received and passed to the IP stack.
+  pub fn rx_bytes(&self) -> u32 {
+    self.rx_bytes
+  }
+
+  /// The number of received packets rejected by the IP stack.
+  pub fn rx_dropped(&self) -> u32 {
+    self.rx_dropped
+  }
+
+  /// The number of packets passed to the driver successfully.
+  pub fn tx_packets(&self) -> u32 {
+    self.tx_packets
+  }
+
+  /// The number of bytes passed to the driver successfully.
+  pub fn tx_bytes(&self) -> u32 {
+    self.tx_bytes
+  }
+
+  /// The number of packets the driver failed to send.
+  pub fn tx_errors(&self) -> u32 {
+    self.tx_errors
+  }
+}
+
+/// The counters of a netif and its original functions which are wrapped to count traffic.
+///
+/// Hooks are never freed, so wrappers still running after the original functions
+/// were restored can still call them.
+struct Hook {
+  netif: AtomicUsize,
+  input: AtomicUsize,
+  linkoutput: AtomicUsize,
+  counters: CallbackSlot<Counters>,
+}
+
+impl Hook {
+  const fn new() -> Self {
+    Self {
+      netif: AtomicUsize::new(0),
+      input: AtomicUsize::new(0),
+      linkoutput: AtomicUsize::new(0),
+      counters: CallbackSlot::new(),
+    }
+  }
+
+  fn input(&self) -> netif_input_fn {
+    unsafe { transmute(self.input.load(SeqCst)) }
+  }
+
+  fn linkoutput(&self) -> netif_linkoutput_fn {
+    unsafe { transmute(self.linkoutput.load(SeqCst)) }
+  }
+}
+
+#[derive(Default)]
+struct Counters {
+  rx_packets: AtomicU32,
+  rx_bytes: AtomicU32,
+  rx_dropped: AtomicU32,
+  tx_packets: AtomicU32,
+  tx_bytes: AtomicU32,
+  tx_errors: AtomicU32,
+}
+
+static HOOKS: [Hook; 3] = [Hook::new(), Hook::new(), Hook::new()];
+
+fn find_hook(netif: *mut netif) -> Option<&'static Hook> {
+  HOOKS.iter().find(|hook| hook.netif.load(SeqCst) == netif as usize)
+}
+
+unsafe extern "C" fn input(p: *mut pbuf, inp: *mut netif) -> err_t {
+  let hook = match find_hook(inp) {
+    Some(hook) => hook,
+    None => return ERR_IF as err_t,
+  };
+
+  let len = (*p).tot_len as u32;
+  let err = match hook.input() {
+    Some(input) => input(p, inp),
+    None => return ERR_IF as err_t,
+  };
+
+  hook.counters.with(|counters| {
+    if err == ERR_OK as err_t {
+      counters.rx_packets.fetch_add(1, SeqCst);
+      counters.rx_bytes.fetch_add(len, SeqCst);
+    } else {
+      counters.rx_dropped.fetch_add(1, SeqCst);
+    }
+  });
+
+  err
+}
+
+unsafe extern "C" fn linkoutput(netif: *mut netif, p: *mut pbuf) -> err_t {
+  let hook = match find_hook(netif) {
+    Some(hook) => hook,
+    None => return ERR_IF as err_t,
+  };
+
+  let len = (*p).tot_len as u32;
+  let err = match hook.linkoutput() {
+    Some(linkoutput) => linkoutput(netif, p),
+    None => return ERR_IF as err_t,
+  };
+
+  hook.counters.with(|counters| {
+    if err == ERR_OK as err_t {
+      counters.tx_packets.fetch_add(1, SeqCst);
+      counters.tx_bytes.fetch_add(len, SeqCst);
+    } else {
+      counters.tx_errors.fetch_add(1, SeqCst);
+    }
+  });
+
+  err
+}
+
+/// Counts the traffic of an [`Interface`](enum.Interface.html) until it is dropped,
+/// returned by [`Interface::count_traffic`](enum.Interface.html#method.count_traffic).
+pub struct TrafficCounter {
+  interface: Interface,
+  hook: &'static Hook,
+}
+
+impl fmt::Debug for TrafficCounter {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("TrafficCounter")
+      .field("interface", &self.interface)
+      .field("stats", &self.stats())
+      .finish()
+  }
+}
+
+impl TrafficCounter {
+  /// Get the traffic counted since this counter was created.
+  pub fn stats(&self) -> InterfaceStats {
+    self.hook.counters.with(|counters| InterfaceStats {
+      rx_packets: counters.rx_packets.load(SeqCst),
+      rx_bytes: counters.rx_bytes.load(SeqCst),
+      rx_dropped: counters.rx_dropped.load(SeqCst),
+      tx_packets: counters.tx_packets.load(SeqCst),
+      tx_bytes: counters.tx_bytes.load(SeqCst),
+      tx_errors: counters.tx_errors.load(SeqCst),
+    }).unwrap_or_default()
+  }
+}
+
+impl Drop for TrafficCounter {
+  fn drop(&mut self) {
+    let hook = self.hook;
+    let netif = hook.netif.load(SeqCst);
+
+    // The netif may have been recreated in the meantime, in which case it is no longer wrapped.
+    if self.interface.lwip_netif().ok().map_or(false, |current| current as usize == netif) {
+      let _ = tcpip_call(move || unsafe {
+        let netif = netif as *mut netif;
+
+        if (*netif).input == Some(input) {
+          (*netif).input = hook.input();
+        }
+        if (*netif).linkoutput == Some(linkoutput) {
+          (*netif).linkoutput = hook.linkoutput();
+        }
+      });
+    }
+
+    hook.counters.clear();
+  }
+}
+
+impl Interface {
+  /// Start counting the traffic of this interface until the returned counter is dropped.
+  ///
+  /// Only one counter can exist per interface at a time. If an Ethernet driver is
+  /// recreated, a new counter has to be created.
+  pub fn count_traffic(&self) -> Result<TrafficCounter, EspError> {
+    let hook = match self {
+      Self::Sta => &HOOKS[0],
+      Self::Ap => &HOOKS[1],
+      Self::Eth => &HOOKS[2],
+      Self::Bt => return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t }),
+    };
+
+    let netif = self.lwip_netif()? as usize;
+
+    if hook.counters.set(Counters::default()).is_err() {
+      return Err(EspError { code: ESP_ERR_INVALID_STATE as esp_err_t })
+    }
+    let counter = TrafficCounter { interface: *self, hook };
+
+    // lwIP state must only be modified from the TCP/IP task.
+    tcpip_call(move || unsafe {
+      let netif = netif as *mut netif;
+
+      if (*netif).input != Some(input) {
+        hook.input.store((*netif).input.map_or(0, |f| f as usize), SeqCst);
+      }
+      if (*netif).linkoutput != Some(linkoutput) {
+        hook.linkoutput.store((*netif).linkoutput.map_or(0, |f| f as usize), SeqCst);
+      }
+      hook.netif.store(netif as usize, SeqCst);
+
+      (*netif).input = Some(input);
+      (*netif).linkoutput = Some(linkoutput);
+    })?;
+
+    Ok(counter)
+  }
+}
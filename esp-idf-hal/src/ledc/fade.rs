@@ -0,0 +1,151 @@
+use core::future::Future;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  ledc_fade_func_install,
+  ledc_fade_mode_t,
+  ledc_fade_start,
+  ledc_set_fade_with_step,
+  ledc_set_fade_with_time,
+  ESP_ERR_INVALID_ARG,
+  ESP_ERR_INVALID_STATE,
+  ESP_FAIL,
+};
+
+use crate::EspError;
+use crate::gpio::OutputCapable;
+use crate::init_once::InitOnce;
+
+use super::LedcChannel;
+
+/// Install the fade service if it is not installed yet.
+fn fade_func_install() -> Result<(), EspError> {
+  static FADE: InitOnce<()> = InitOnce::new();
+
+  FADE.get_or_try_init(|| match esp_ok!(ledc_fade_func_install(0)) {
+    Err(err) if err.code != ESP_ERR_INVALID_STATE as esp_err_t => Err(err),
+    _ => Ok(()),
+  })?;
+
+  Ok(())
+}
+
+#[derive(Debug)]
+struct FadeState {
+  result: Option<Result<(), EspError>>,
+  waker: Option<Waker>,
+}
+
+/// A future resolving when a hardware fade of an [`LedcChannel`](struct.LedcChannel.html) has finished.
+///
+/// ESP-IDF `release/v4.2` has no fade-end callback, so the end of the fade is awaited on a separate thread.
+/// A running fade cannot be stopped either, so dropping this future blocks until the fade has finished.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct FadeFuture<'a> {
+  state: Arc<Mutex<FadeState>>,
+  thread: Option<JoinHandle<()>>,
+  _channel: PhantomData<&'a mut ()>,
+}
+
+impl Future for FadeFuture<'_> {
+  type Output = Result<(), EspError>;
+
+  fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+    let mut state = self.state.lock().unwrap();
+
+    match state.result.take() {
+      Some(result) => Poll::Ready(result),
+      None => {
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+      },
+    }
+  }
+}
+
+impl Drop for FadeFuture<'_> {
+  fn drop(&mut self) {
+    // Keep the channel borrowed until the fade has finished, so no other fade can be started on it.
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+  }
+}
+
+impl<P: OutputCapable> LedcChannel<'_, P> {
+  fn set_fade_with_time(&mut self, target_duty: u32, duration: Duration) -> Result<(), EspError> {
+    if target_duty > self.max_duty() || duration.as_millis() > i32::MAX as u128 {
+      return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+    }
+
+    fade_func_install()?;
+    esp_ok!(ledc_set_fade_with_time(self.timer.speed_mode.into(), self.channel_num(), target_duty, duration.as_millis() as i32))
+  }
+
+  fn set_fade_with_step(&mut self, target_duty: u32, step: u32, cycles_per_step: u32) -> Result<(), EspError> {
+    if target_duty > self.max_duty() {
+      return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+    }
+
+    fade_func_install()?;
+    esp_ok!(ledc_set_fade_with_step(self.timer.speed_mode.into(), self.channel_num(), target_duty, step, cycles_per_step))
+  }
+
+  /// Start fading to `target_duty` in hardware within `duration` and return immediately.
+  pub fn start_fade_with_time(&mut self, target_duty: u32, duration: Duration) -> Result<(), EspError> {
+    self.set_fade_with_time(target_duty, duration)?;
+    esp_ok!(ledc_fade_start(self.timer.speed_mode.into(), self.channel_num(), ledc_fade_mode_t::LEDC_FADE_NO_WAIT))
+  }
+
+  /// Start fading to `target_duty` in hardware, changing the duty by `step` every `cycles_per_step` PWM periods,
+  /// and return immediately.
+  pub fn start_fade_with_step(&mut self, target_duty: u32, step: u32, cycles_per_step: u32) -> Result<(), EspError> {
+    self.set_fade_with_step(target_duty, step, cycles_per_step)?;
+    esp_ok!(ledc_fade_start(self.timer.speed_mode.into(), self.channel_num(), ledc_fade_mode_t::LEDC_FADE_NO_WAIT))
+  }
+
+  /// Fade to `target_duty` in hardware within `duration`, resolving when the fade has finished.
+  pub fn fade_with_time(&mut self, target_duty: u32, duration: Duration) -> Result<FadeFuture<'_>, EspError> {
+    self.set_fade_with_time(target_duty, duration)?;
+    self.wait_for_fade()
+  }
+
+  /// Fade to `target_duty` in hardware, changing the duty by `step` every `cycles_per_step` PWM periods,
+  /// resolving when the fade has finished.
+  pub fn fade_with_step(&mut self, target_duty: u32, step: u32, cycles_per_step: u32) -> Result<FadeFuture<'_>, EspError> {
+    self.set_fade_with_step(target_duty, step, cycles_per_step)?;
+    self.wait_for_fade()
+  }
+
+  fn wait_for_fade(&mut self) -> Result<FadeFuture<'_>, EspError> {
+    let state = Arc::new(Mutex::new(FadeState { result: None, waker: None }));
+    let thread_state = Arc::clone(&state);
+
+    let speed_mode = self.timer.speed_mode.into();
+    let channel = self.channel_num();
+
+    let thread = thread::Builder::new()
+      .name("ledc_fade".into())
+      .stack_size(2048)
+      .spawn(move || {
+        let result = esp_ok!(ledc_fade_start(speed_mode, channel, ledc_fade_mode_t::LEDC_FADE_WAIT_DONE));
+
+        let mut state = thread_state.lock().unwrap();
+        state.result = Some(result);
+
+        if let Some(waker) = state.waker.take() {
+          waker.wake();
+        }
+      })
+      .map_err(|_| EspError { code: ESP_FAIL as esp_err_t })?;
+
+    Ok(FadeFuture { state, thread: Some(thread), _channel: PhantomData })
+  }
+}
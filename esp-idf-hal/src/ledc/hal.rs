@@ -0,0 +1,66 @@
+use crate::gpio::OutputCapable;
+
+use super::LedcChannel;
+
+#[cfg(feature = "embedded-hal-02")]
+mod v02 {
+  use embedded_hal_02::PwmPin;
+
+  use super::*;
+
+  /// Errors are ignored, since `PwmPin` is infallible.
+  impl<P: OutputCapable> PwmPin for LedcChannel<'_, P> {
+    type Duty = u32;
+
+    fn disable(&mut self) {
+      let _ = self.stop(false);
+    }
+
+    fn enable(&mut self) {
+      let _ = LedcChannel::set_duty(self, self.duty());
+    }
+
+    fn get_duty(&self) -> Self::Duty {
+      self.duty()
+    }
+
+    fn get_max_duty(&self) -> Self::Duty {
+      self.max_duty()
+    }
+
+    fn set_duty(&mut self, duty: Self::Duty) {
+      let _ = LedcChannel::set_duty(self, duty.min(self.max_duty()));
+    }
+  }
+}
+
+#[cfg(feature = "embedded-hal-1")]
+mod v1 {
+  use embedded_hal_1::pwm::{Error, ErrorKind, ErrorType, SetDutyCycle};
+
+  use crate::EspError;
+
+  use super::*;
+
+  impl Error for EspError {
+    fn kind(&self) -> ErrorKind {
+      ErrorKind::Other
+    }
+  }
+
+  impl<P: OutputCapable> ErrorType for LedcChannel<'_, P> {
+    type Error = EspError;
+  }
+
+  /// Resolutions above 16 bits are scaled down to the 16-bit duty of `SetDutyCycle`.
+  impl<P: OutputCapable> SetDutyCycle for LedcChannel<'_, P> {
+    fn max_duty_cycle(&self) -> u16 {
+      self.max_duty().min(u16::max_value() as u32) as u16
+    }
+
+    fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Self::Error> {
+      let duty = duty as u64 * self.max_duty() as u64 / self.max_duty_cycle() as u64;
+      self.set_duty(duty as u32)
+    }
+  }
+}
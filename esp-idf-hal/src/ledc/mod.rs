@@ -0,0 +1,236 @@
+use core::fmt;
+use core::mem::{self, transmute};
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  gpio_reset_pin,
+  ledc_channel_config,
+  ledc_channel_config_t,
+  ledc_channel_t,
+  ledc_clk_cfg_t,
+  ledc_get_duty,
+  ledc_get_freq,
+  ledc_intr_type_t,
+  ledc_mode_t,
+  ledc_set_duty,
+  ledc_set_freq,
+  ledc_stop,
+  ledc_timer_config,
+  ledc_timer_config_t,
+  ledc_timer_pause,
+  ledc_timer_resume,
+  ledc_timer_t,
+  ledc_update_duty,
+  ESP_ERR_INVALID_ARG,
+};
+
+use crate::EspError;
+use crate::gpio::{gpio_num, OutputCapable};
+
+mod fade;
+pub use fade::*;
+mod servo;
+pub use servo::*;
+#[cfg(any(feature = "embedded-hal-02", feature = "embedded-hal-1"))]
+mod hal;
+
+const TIMER_COUNT: u8 = 4;
+const CHANNEL_COUNT: u8 = 8;
+const MAX_RESOLUTION: u8 = 20;
+
+/// The LEDC speed mode. Each mode has its own 4 timers and 8 channels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeedMode {
+  /// Duty and frequency changes take effect immediately.
+  HighSpeed,
+  /// Duty and frequency changes take effect at the end of the current period.
+  LowSpeed,
+}
+
+impl From<SpeedMode> for ledc_mode_t {
+  fn from(speed_mode: SpeedMode) -> Self {
+    match speed_mode {
+      SpeedMode::HighSpeed => ledc_mode_t::LEDC_HIGH_SPEED_MODE,
+      SpeedMode::LowSpeed => ledc_mode_t::LEDC_LOW_SPEED_MODE,
+    }
+  }
+}
+
+/// Options for an [`LedcTimer`](struct.LedcTimer.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimerConfig {
+  frequency: u32,
+  resolution: u8,
+}
+
+impl TimerConfig {
+  /// Create a configuration for a PWM signal with `frequency` Hz and a 10-bit duty resolution.
+  pub fn new(frequency: u32) -> Self {
+    Self { frequency, resolution: 10 }
+  }
+
+  /// Set the duty resolution in bits, between 1 and 20.
+  ///
+  /// The maximum resolution decreases with increasing frequency, e.g. 80 MHz / 2<sup>resolution</sup>
+  /// is the maximum frequency using the APB clock.
+  pub fn with_resolution(mut self, resolution: u8) -> Self {
+    self.resolution = resolution;
+    self
+  }
+
+  pub fn frequency(&self) -> u32 {
+    self.frequency
+  }
+
+  pub fn resolution(&self) -> u8 {
+    self.resolution
+  }
+}
+
+/// An LEDC timer, which generates the PWM period for one or more channels.
+#[derive(Debug)]
+pub struct LedcTimer {
+  speed_mode: SpeedMode,
+  timer: u8,
+  resolution: u8,
+}
+
+impl LedcTimer {
+  /// Configure timer `timer`, between 0 and 3, of the given speed mode.
+  pub fn new(speed_mode: SpeedMode, timer: u8, config: &TimerConfig) -> Result<Self, EspError> {
+    if timer >= TIMER_COUNT || config.resolution == 0 || config.resolution > MAX_RESOLUTION {
+      return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+    }
+
+    let mut timer_config: ledc_timer_config_t = unsafe { mem::zeroed() };
+    timer_config.speed_mode = speed_mode.into();
+    timer_config.__bindgen_anon_1.duty_resolution = unsafe { transmute(config.resolution as u32) };
+    timer_config.timer_num = unsafe { transmute(timer as u32) };
+    timer_config.freq_hz = config.frequency;
+    timer_config.clk_cfg = ledc_clk_cfg_t::LEDC_AUTO_CLK;
+
+    esp_ok!(ledc_timer_config(&timer_config))?;
+
+    Ok(Self { speed_mode, timer, resolution: config.resolution })
+  }
+
+  fn timer_num(&self) -> ledc_timer_t {
+    unsafe { transmute(self.timer as u32) }
+  }
+
+  pub fn speed_mode(&self) -> SpeedMode {
+    self.speed_mode
+  }
+
+  pub fn resolution(&self) -> u8 {
+    self.resolution
+  }
+
+  /// The maximum duty of channels using this timer.
+  pub fn max_duty(&self) -> u32 {
+    (1 << self.resolution) - 1
+  }
+
+  /// Change the frequency of all channels using this timer.
+  pub fn set_frequency(&mut self, frequency: u32) -> Result<(), EspError> {
+    esp_ok!(ledc_set_freq(self.speed_mode.into(), self.timer_num(), frequency))
+  }
+
+  pub fn frequency(&self) -> u32 {
+    unsafe { ledc_get_freq(self.speed_mode.into(), self.timer_num()) }
+  }
+
+  /// Pause all channels using this timer.
+  pub fn pause(&mut self) -> Result<(), EspError> {
+    esp_ok!(ledc_timer_pause(self.speed_mode.into(), self.timer_num()))
+  }
+
+  pub fn resume(&mut self) -> Result<(), EspError> {
+    esp_ok!(ledc_timer_resume(self.speed_mode.into(), self.timer_num()))
+  }
+}
+
+/// An LEDC channel outputting a PWM signal on a pin.
+pub struct LedcChannel<'t, P: OutputCapable> {
+  timer: &'t LedcTimer,
+  channel: u8,
+  pin: P,
+}
+
+impl<P: OutputCapable> fmt::Debug for LedcChannel<'_, P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("LedcChannel")
+      .field("timer", &self.timer)
+      .field("channel", &self.channel)
+      .field("pin", &self.pin.number())
+      .finish()
+  }
+}
+
+impl<'t, P: OutputCapable> LedcChannel<'t, P> {
+  /// Output a PWM signal generated by `timer` on `pin` using channel `channel`, between 0 and 7,
+  /// of the speed mode of the timer. The duty is initially 0.
+  pub fn new(timer: &'t LedcTimer, channel: u8, pin: P) -> Result<Self, EspError> {
+    if channel >= CHANNEL_COUNT {
+      return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+    }
+
+    let channel_config = ledc_channel_config_t {
+      gpio_num: pin.number() as _,
+      speed_mode: timer.speed_mode.into(),
+      channel: unsafe { transmute(channel as u32) },
+      intr_type: ledc_intr_type_t::LEDC_INTR_DISABLE,
+      timer_sel: timer.timer_num(),
+      duty: 0,
+      hpoint: 0,
+    };
+
+    esp_ok!(ledc_channel_config(&channel_config))?;
+
+    Ok(Self { timer, channel, pin })
+  }
+
+  fn channel_num(&self) -> ledc_channel_t {
+    unsafe { transmute(self.channel as u32) }
+  }
+
+  pub fn channel(&self) -> u8 {
+    self.channel
+  }
+
+  pub fn timer(&self) -> &'t LedcTimer {
+    self.timer
+  }
+
+  /// The maximum duty, see [`LedcTimer::max_duty`](struct.LedcTimer.html#method.max_duty).
+  pub fn max_duty(&self) -> u32 {
+    self.timer.max_duty()
+  }
+
+  /// Set the duty, between 0 and [`max_duty`](#method.max_duty).
+  pub fn set_duty(&mut self, duty: u32) -> Result<(), EspError> {
+    if duty > self.max_duty() {
+      return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+    }
+
+    let speed_mode = self.timer.speed_mode.into();
+    esp_ok!(ledc_set_duty(speed_mode, self.channel_num(), duty))?;
+    esp_ok!(ledc_update_duty(speed_mode, self.channel_num()))
+  }
+
+  pub fn duty(&self) -> u32 {
+    unsafe { ledc_get_duty(self.timer.speed_mode.into(), self.channel_num()) }
+  }
+
+  /// Stop the PWM signal and set the output to the given idle level.
+  pub fn stop(&mut self, idle_high: bool) -> Result<(), EspError> {
+    esp_ok!(ledc_stop(self.timer.speed_mode.into(), self.channel_num(), idle_high as u32))
+  }
+
+  /// Stop the PWM signal, reset the pin to its default state and return it.
+  pub fn release(mut self) -> P {
+    let _ = self.stop(false);
+    let _ = esp_ok!(gpio_reset_pin(gpio_num(self.pin.number())));
+    self.pin
+  }
+}
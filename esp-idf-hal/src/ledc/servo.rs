@@ -0,0 +1,131 @@
+use core::fmt;
+
+use esp_idf_bindgen::{esp_err_t, ESP_ERR_INVALID_ARG};
+
+use crate::EspError;
+use crate::gpio::OutputCapable;
+
+use super::{LedcChannel, LedcTimer, SpeedMode, TimerConfig};
+
+/// The PWM frequency of RC servos.
+const SERVO_FREQUENCY: u32 = 50;
+
+/// A 16-bit duty resolution gives a pulse width resolution of 0.3 µs at 50 Hz.
+const SERVO_RESOLUTION: u8 = 16;
+
+/// The pulse widths and angle range of a [`Servo`](struct.Servo.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ServoConfig {
+  min_pulse_us: u32,
+  max_pulse_us: u32,
+  max_angle: f32,
+}
+
+impl Default for ServoConfig {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl ServoConfig {
+  /// Create a configuration for a servo moving from 0° at 1000 µs to 180° at 2000 µs.
+  pub fn new() -> Self {
+    Self { min_pulse_us: 1000, max_pulse_us: 2000, max_angle: 180.0 }
+  }
+
+  /// Set the pulse widths in microseconds for 0° and the maximum angle, e.g. after measuring
+  /// the end stops of a specific servo.
+  pub fn with_pulse_range(mut self, min_pulse_us: u32, max_pulse_us: u32) -> Self {
+    assert!(min_pulse_us < max_pulse_us, "min_pulse_us must be less than max_pulse_us");
+    self.min_pulse_us = min_pulse_us;
+    self.max_pulse_us = max_pulse_us;
+    self
+  }
+
+  /// Set the angle in degrees reached at the maximum pulse width.
+  pub fn with_max_angle(mut self, max_angle: f32) -> Self {
+    assert!(max_angle > 0.0, "max_angle must be positive");
+    self.max_angle = max_angle;
+    self
+  }
+
+  pub fn min_pulse_us(&self) -> u32 {
+    self.min_pulse_us
+  }
+
+  pub fn max_pulse_us(&self) -> u32 {
+    self.max_pulse_us
+  }
+
+  pub fn max_angle(&self) -> f32 {
+    self.max_angle
+  }
+}
+
+/// An RC servo driven by an LEDC channel.
+pub struct Servo<'t, P: OutputCapable> {
+  channel: LedcChannel<'t, P>,
+  config: ServoConfig,
+}
+
+impl<P: OutputCapable> fmt::Debug for Servo<'_, P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Servo")
+      .field("channel", &self.channel)
+      .field("config", &self.config)
+      .finish()
+  }
+}
+
+/// Configure timer `timer` for servos, at 50 Hz with a 16-bit duty resolution.
+///
+/// The timer can be shared by multiple servos.
+pub fn servo_timer(speed_mode: SpeedMode, timer: u8) -> Result<LedcTimer, EspError> {
+  LedcTimer::new(speed_mode, timer, &TimerConfig::new(SERVO_FREQUENCY).with_resolution(SERVO_RESOLUTION))
+}
+
+impl<'t, P: OutputCapable> Servo<'t, P> {
+  /// Drive a servo on `pin` using channel `channel`, see [`LedcChannel::new`](struct.LedcChannel.html#method.new).
+  ///
+  /// `timer` should be created using [`servo_timer`](fn.servo_timer.html). No pulses are output
+  /// until the first call to [`set_angle`](#method.set_angle).
+  pub fn new(timer: &'t LedcTimer, channel: u8, pin: P, config: ServoConfig) -> Result<Self, EspError> {
+    Ok(Self { channel: LedcChannel::new(timer, channel, pin)?, config })
+  }
+
+  pub fn config(&self) -> &ServoConfig {
+    &self.config
+  }
+
+  /// Output pulses with a width of `pulse_us` microseconds.
+  pub fn set_pulse_width(&mut self, pulse_us: u32) -> Result<(), EspError> {
+    let period_us = 1_000_000 / self.channel.timer().frequency().max(1);
+
+    if pulse_us > period_us {
+      return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+    }
+
+    let duty = pulse_us as u64 * (self.channel.max_duty() as u64 + 1) / period_us as u64;
+    self.channel.set_duty(duty.min(self.channel.max_duty() as u64) as u32)
+  }
+
+  /// Move to `angle` degrees, clamped between 0° and the maximum angle.
+  pub fn set_angle(&mut self, angle: f32) -> Result<(), EspError> {
+    let ServoConfig { min_pulse_us, max_pulse_us, max_angle } = self.config;
+    let angle = angle.max(0.0).min(max_angle);
+
+    let pulse_us = min_pulse_us as f32 + (max_pulse_us - min_pulse_us) as f32 * angle / max_angle;
+    self.set_pulse_width(pulse_us as u32)
+  }
+
+  /// Stop outputting pulses, so the servo can be moved freely.
+  pub fn detach(&mut self) -> Result<(), EspError> {
+    self.channel.stop(false)
+  }
+
+  /// Stop outputting pulses and return the channel.
+  pub fn release(mut self) -> LedcChannel<'t, P> {
+    let _ = self.detach();
+    self.channel
+  }
+}
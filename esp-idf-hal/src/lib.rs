@@ -8,12 +8,66 @@ use std::str;
 #[macro_use]
 extern crate alloc;
 
+#[macro_use]
+extern crate bitflags;
+
 #[macro_use]
 mod esp_error;
 pub use esp_error::EspError;
 
+mod callback_slot;
+mod init_once;
+
+pub mod event;
 pub mod interface;
 mod heap;
 pub use heap::Heap;
 pub mod wifi;
 pub mod nvs;
+pub mod fs;
+pub mod partition;
+pub mod ota;
+#[cfg(target_device = "esp32")]
+pub mod gpio;
+#[cfg(target_device = "esp32")]
+pub mod sleep;
+#[cfg(target_device = "esp32")]
+pub mod adc;
+#[cfg(target_device = "esp32")]
+pub mod dac;
+#[cfg(target_device = "esp32")]
+pub mod sensors;
+#[cfg(target_device = "esp32")]
+pub mod touch;
+#[cfg(target_device = "esp32")]
+pub mod ledc;
+#[cfg(target_device = "esp32")]
+pub mod mcpwm;
+#[cfg(target_device = "esp32")]
+pub mod sigma_delta;
+#[cfg(target_device = "esp32")]
+pub mod timer;
+#[cfg(target_device = "esp32")]
+pub mod watchdog;
+#[cfg(target_device = "esp32")]
+pub mod time;
+#[cfg(target_device = "esp32")]
+pub mod uart;
+#[cfg(target_device = "esp32")]
+pub mod spi;
+#[cfg(target_device = "esp32")]
+pub mod sdcard;
+#[cfg(target_device = "esp32")]
+pub mod flash;
+pub mod net;
+pub mod captive_portal;
+#[cfg(target_device = "esp32")]
+pub mod provisioning;
+#[cfg(target_device = "esp32")]
+pub mod espnow;
+#[cfg(target_device = "esp32")]
+pub mod mesh;
+#[cfg(target_device = "esp32")]
+pub mod eth;
+#[cfg(target_device = "esp32")]
+pub mod ppp;
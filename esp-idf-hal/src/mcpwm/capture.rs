@@ -0,0 +1,134 @@
+use core::fmt;
+use core::mem::ManuallyDrop;
+use core::ptr;
+use std::time::Duration;
+
+use esp_idf_bindgen::{
+  mcpwm_capture_disable,
+  mcpwm_capture_enable,
+  mcpwm_capture_on_edge_t,
+  mcpwm_capture_signal_get_edge,
+  mcpwm_capture_signal_get_value,
+  mcpwm_capture_signal_t,
+  mcpwm_gpio_init,
+  mcpwm_io_signals_t,
+};
+
+use crate::EspError;
+use crate::gpio::Pin;
+
+use super::{McpwmInput, McpwmUnit};
+
+/// Capture timestamps count cycles of the 80 MHz APB clock.
+pub const CAPTURE_CLOCK_HZ: u32 = 80_000_000;
+
+/// The edges of the input signal which are captured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureEdge {
+  Rising,
+  Falling,
+  Both,
+}
+
+/// A timestamp captured on an edge of the input signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capture {
+  ticks: u32,
+  rising: bool,
+}
+
+impl Capture {
+  /// The timestamp in cycles of the APB clock, see [`CAPTURE_CLOCK_HZ`](constant.CAPTURE_CLOCK_HZ.html).
+  pub fn ticks(&self) -> u32 {
+    self.ticks
+  }
+
+  /// Whether this capture was triggered by a rising edge.
+  pub fn is_rising(&self) -> bool {
+    self.rising
+  }
+
+  /// The time elapsed since an `earlier` capture, e.g. the period of an encoder or hall sensor signal.
+  ///
+  /// The timestamp overflows after approximately 53 s.
+  pub fn duration_since(&self, earlier: &Capture) -> Duration {
+    let ticks = self.ticks.wrapping_sub(earlier.ticks) as u64;
+    Duration::from_nanos(ticks * 1_000_000_000 / CAPTURE_CLOCK_HZ as u64)
+  }
+}
+
+fn capture_signal(input: McpwmInput) -> mcpwm_capture_signal_t {
+  match input {
+    McpwmInput::Input0 => mcpwm_capture_signal_t::MCPWM_SELECT_CAP0,
+    McpwmInput::Input1 => mcpwm_capture_signal_t::MCPWM_SELECT_CAP1,
+    McpwmInput::Input2 => mcpwm_capture_signal_t::MCPWM_SELECT_CAP2,
+  }
+}
+
+/// A capture input of an MCPWM unit, which timestamps edges of an input signal.
+///
+/// The capture input is disabled when this is dropped.
+pub struct McpwmCapture<P: Pin> {
+  unit: McpwmUnit,
+  input: McpwmInput,
+  pin: P,
+}
+
+impl<P: Pin> fmt::Debug for McpwmCapture<P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("McpwmCapture")
+      .field("unit", &self.unit)
+      .field("input", &self.input)
+      .field("pin", &self.pin.number())
+      .finish()
+  }
+}
+
+impl<P: Pin> McpwmCapture<P> {
+  /// Capture the given edges on `pin` using capture input `input` of `unit`.
+  ///
+  /// Only every `prescale`th edge is captured, `1` captures every edge.
+  pub fn new(unit: McpwmUnit, input: McpwmInput, pin: P, edge: CaptureEdge, prescale: u32) -> Result<Self, EspError> {
+    let signal = match input {
+      McpwmInput::Input0 => mcpwm_io_signals_t::MCPWM_CAP_0,
+      McpwmInput::Input1 => mcpwm_io_signals_t::MCPWM_CAP_1,
+      McpwmInput::Input2 => mcpwm_io_signals_t::MCPWM_CAP_2,
+    };
+
+    let edge = match edge {
+      CaptureEdge::Rising => mcpwm_capture_on_edge_t::MCPWM_POS_EDGE,
+      CaptureEdge::Falling => mcpwm_capture_on_edge_t::MCPWM_NEG_EDGE,
+      CaptureEdge::Both => mcpwm_capture_on_edge_t::MCPWM_BOTH_EDGE,
+    };
+
+    esp_ok!(mcpwm_gpio_init(unit.into(), signal, pin.number() as i32))?;
+    esp_ok!(mcpwm_capture_enable(unit.into(), capture_signal(input), edge, prescale))?;
+
+    Ok(Self { unit, input, pin })
+  }
+
+  /// The most recent capture.
+  pub fn last_capture(&self) -> Capture {
+    let signal = capture_signal(self.input);
+
+    unsafe {
+      Capture {
+        ticks: mcpwm_capture_signal_get_value(self.unit.into(), signal),
+        rising: mcpwm_capture_signal_get_edge(self.unit.into(), signal) == 1,
+      }
+    }
+  }
+
+  /// Disable the capture input and return the pin.
+  pub fn release(self) -> P {
+    let this = ManuallyDrop::new(self);
+    let _ = esp_ok!(mcpwm_capture_disable(this.unit.into(), capture_signal(this.input)));
+    unsafe { ptr::read(&this.pin) }
+  }
+}
+
+impl<P: Pin> Drop for McpwmCapture<P> {
+  fn drop(&mut self) {
+    let _ = esp_ok!(mcpwm_capture_disable(self.unit.into(), capture_signal(self.input)));
+  }
+}
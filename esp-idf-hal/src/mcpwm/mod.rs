@@ -0,0 +1,406 @@
+use core::fmt;
+use core::mem::ManuallyDrop;
+use core::ptr;
+
+use esp_idf_bindgen::{
+  mcpwm_action_on_pwmxa_t,
+  mcpwm_action_on_pwmxb_t,
+  mcpwm_config_t,
+  mcpwm_counter_type_t,
+  mcpwm_deadtime_disable,
+  mcpwm_deadtime_enable,
+  mcpwm_deadtime_type_t,
+  mcpwm_duty_type_t,
+  mcpwm_fault_deinit,
+  mcpwm_fault_init,
+  mcpwm_fault_input_level_t,
+  mcpwm_fault_set_cyc_mode,
+  mcpwm_fault_set_oneshot_mode,
+  mcpwm_fault_signal_t,
+  mcpwm_generator_t,
+  mcpwm_gpio_init,
+  mcpwm_init,
+  mcpwm_io_signals_t,
+  mcpwm_set_duty,
+  mcpwm_set_frequency,
+  mcpwm_start,
+  mcpwm_stop,
+  mcpwm_sync_disable,
+  mcpwm_sync_enable,
+  mcpwm_sync_signal_t,
+  mcpwm_timer_t,
+  mcpwm_unit_t,
+};
+
+use crate::EspError;
+use crate::gpio::{OutputCapable, Pin};
+
+mod capture;
+pub use capture::*;
+
+/// An MCPWM unit. Each unit has 3 timers, 3 fault inputs, 3 sync inputs and 3 capture inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum McpwmUnit {
+  Unit0,
+  Unit1,
+}
+
+impl From<McpwmUnit> for mcpwm_unit_t {
+  fn from(unit: McpwmUnit) -> Self {
+    match unit {
+      McpwmUnit::Unit0 => mcpwm_unit_t::MCPWM_UNIT_0,
+      McpwmUnit::Unit1 => mcpwm_unit_t::MCPWM_UNIT_1,
+    }
+  }
+}
+
+/// A timer of an MCPWM unit, which drives a pair of outputs A and B.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum McpwmTimer {
+  Timer0,
+  Timer1,
+  Timer2,
+}
+
+impl From<McpwmTimer> for mcpwm_timer_t {
+  fn from(timer: McpwmTimer) -> Self {
+    match timer {
+      McpwmTimer::Timer0 => mcpwm_timer_t::MCPWM_TIMER_0,
+      McpwmTimer::Timer1 => mcpwm_timer_t::MCPWM_TIMER_1,
+      McpwmTimer::Timer2 => mcpwm_timer_t::MCPWM_TIMER_2,
+    }
+  }
+}
+
+/// One of the 3 fault, sync or capture inputs of an MCPWM unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum McpwmInput {
+  Input0,
+  Input1,
+  Input2,
+}
+
+/// The counting direction of an MCPWM timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CounterMode {
+  /// Count up, generating asymmetric PWM.
+  Up,
+  /// Count down, generating asymmetric PWM.
+  Down,
+  /// Count up and down, generating symmetric PWM at half the frequency.
+  UpDown,
+}
+
+/// Options for an [`McpwmPair`](struct.McpwmPair.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PwmConfig {
+  frequency: u32,
+  counter_mode: CounterMode,
+  active_low: bool,
+}
+
+impl PwmConfig {
+  /// Create a configuration for an active-high PWM signal with `frequency` Hz, counting up.
+  pub fn new(frequency: u32) -> Self {
+    Self { frequency, counter_mode: CounterMode::Up, active_low: false }
+  }
+
+  pub fn with_counter_mode(mut self, counter_mode: CounterMode) -> Self {
+    self.counter_mode = counter_mode;
+    self
+  }
+
+  /// Set whether the duty is the low time rather than the high time of the signal.
+  pub fn with_active_low(mut self, active_low: bool) -> Self {
+    self.active_low = active_low;
+    self
+  }
+
+  pub fn frequency(&self) -> u32 {
+    self.frequency
+  }
+
+  pub fn counter_mode(&self) -> CounterMode {
+    self.counter_mode
+  }
+
+  pub fn active_low(&self) -> bool {
+    self.active_low
+  }
+}
+
+/// How the outputs of an [`McpwmPair`](struct.McpwmPair.html) are derived from output A when dead time is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeadTimeMode {
+  /// Output A is active high, output B is its complement, e.g. for a half bridge with high-side and low-side switches.
+  ActiveHighComplementary,
+  /// Output A is active low, output B is its complement.
+  ActiveLowComplementary,
+  /// Both outputs are active high.
+  ActiveHigh,
+  /// Both outputs are active low.
+  ActiveLow,
+}
+
+impl From<DeadTimeMode> for mcpwm_deadtime_type_t {
+  fn from(mode: DeadTimeMode) -> Self {
+    match mode {
+      DeadTimeMode::ActiveHighComplementary => mcpwm_deadtime_type_t::MCPWM_ACTIVE_HIGH_COMPLIMENT_MODE,
+      DeadTimeMode::ActiveLowComplementary => mcpwm_deadtime_type_t::MCPWM_ACTIVE_LOW_COMPLIMENT_MODE,
+      DeadTimeMode::ActiveHigh => mcpwm_deadtime_type_t::MCPWM_ACTIVE_HIGH_MODE,
+      DeadTimeMode::ActiveLow => mcpwm_deadtime_type_t::MCPWM_ACTIVE_LOW_MODE,
+    }
+  }
+}
+
+/// The level an output is forced to while a fault is active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultAction {
+  NoChange,
+  ForceLow,
+  ForceHigh,
+  Toggle,
+}
+
+/// How long the fault action is applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultMode {
+  /// Latch the fault action until the outputs are started again, e.g. for overcurrent protection.
+  OneShot,
+  /// Apply the fault action until the end of the PWM period in which the fault input becomes inactive.
+  Cycle,
+}
+
+fn fault_signal(input: McpwmInput) -> mcpwm_fault_signal_t {
+  match input {
+    McpwmInput::Input0 => mcpwm_fault_signal_t::MCPWM_SELECT_F0,
+    McpwmInput::Input1 => mcpwm_fault_signal_t::MCPWM_SELECT_F1,
+    McpwmInput::Input2 => mcpwm_fault_signal_t::MCPWM_SELECT_F2,
+  }
+}
+
+/// A fault input of an MCPWM unit, which forces outputs to a safe level, see [`McpwmPair::set_fault_action`](struct.McpwmPair.html#method.set_fault_action).
+///
+/// The fault input is disabled when this is dropped.
+pub struct McpwmFault<P: Pin> {
+  unit: McpwmUnit,
+  input: McpwmInput,
+  pin: P,
+}
+
+impl<P: Pin> fmt::Debug for McpwmFault<P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("McpwmFault")
+      .field("unit", &self.unit)
+      .field("input", &self.input)
+      .field("pin", &self.pin.number())
+      .finish()
+  }
+}
+
+impl<P: Pin> McpwmFault<P> {
+  /// Use `pin` as fault input `input` of `unit`, which is active while it has the given level.
+  pub fn new(unit: McpwmUnit, input: McpwmInput, pin: P, active_high: bool) -> Result<Self, EspError> {
+    let signal = match input {
+      McpwmInput::Input0 => mcpwm_io_signals_t::MCPWM_FAULT_0,
+      McpwmInput::Input1 => mcpwm_io_signals_t::MCPWM_FAULT_1,
+      McpwmInput::Input2 => mcpwm_io_signals_t::MCPWM_FAULT_2,
+    };
+
+    let level = if active_high {
+      mcpwm_fault_input_level_t::MCPWM_HIGH_LEVEL_TGR
+    } else {
+      mcpwm_fault_input_level_t::MCPWM_LOW_LEVEL_TGR
+    };
+
+    esp_ok!(mcpwm_gpio_init(unit.into(), signal, pin.number() as i32))?;
+    esp_ok!(mcpwm_fault_init(unit.into(), level, fault_signal(input)))?;
+
+    Ok(Self { unit, input, pin })
+  }
+
+  /// Disable the fault input and return the pin.
+  pub fn release(self) -> P {
+    let this = ManuallyDrop::new(self);
+    let _ = esp_ok!(mcpwm_fault_deinit(this.unit.into(), fault_signal(this.input)));
+    unsafe { ptr::read(&this.pin) }
+  }
+}
+
+impl<P: Pin> Drop for McpwmFault<P> {
+  fn drop(&mut self) {
+    let _ = esp_ok!(mcpwm_fault_deinit(self.unit.into(), fault_signal(self.input)));
+  }
+}
+
+/// Use `pin` as sync input `input` of `unit`, see [`McpwmPair::enable_sync`](struct.McpwmPair.html#method.enable_sync).
+pub fn connect_sync_input<P: Pin>(unit: McpwmUnit, input: McpwmInput, pin: &P) -> Result<(), EspError> {
+  let signal = match input {
+    McpwmInput::Input0 => mcpwm_io_signals_t::MCPWM_SYNC_0,
+    McpwmInput::Input1 => mcpwm_io_signals_t::MCPWM_SYNC_1,
+    McpwmInput::Input2 => mcpwm_io_signals_t::MCPWM_SYNC_2,
+  };
+
+  esp_ok!(mcpwm_gpio_init(unit.into(), signal, pin.number() as i32))
+}
+
+/// A pair of PWM outputs A and B driven by the same MCPWM timer.
+///
+/// The outputs are stopped when this is dropped.
+pub struct McpwmPair<A: OutputCapable, B: OutputCapable = A> {
+  unit: McpwmUnit,
+  timer: McpwmTimer,
+  pin_a: A,
+  pin_b: Option<B>,
+}
+
+impl<A: OutputCapable, B: OutputCapable> fmt::Debug for McpwmPair<A, B> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("McpwmPair")
+      .field("unit", &self.unit)
+      .field("timer", &self.timer)
+      .field("pin_a", &self.pin_a.number())
+      .field("pin_b", &self.pin_b.as_ref().map(|pin| pin.number()))
+      .finish()
+  }
+}
+
+impl<A: OutputCapable> McpwmPair<A> {
+  /// Output a PWM signal generated by `timer` of `unit` on `pin_a` only, see [`new`](#method.new).
+  pub fn new_single(unit: McpwmUnit, timer: McpwmTimer, pin_a: A, config: &PwmConfig) -> Result<Self, EspError> {
+    Self::init(unit, timer, pin_a, None, config)
+  }
+}
+
+impl<A: OutputCapable, B: OutputCapable> McpwmPair<A, B> {
+  /// Output PWM signals generated by `timer` of `unit` on `pin_a` and `pin_b`.
+  ///
+  /// The duty of both outputs is initially 0 %.
+  pub fn new(unit: McpwmUnit, timer: McpwmTimer, pin_a: A, pin_b: B, config: &PwmConfig) -> Result<Self, EspError> {
+    Self::init(unit, timer, pin_a, Some(pin_b), config)
+  }
+
+  fn init(unit: McpwmUnit, timer: McpwmTimer, pin_a: A, pin_b: Option<B>, config: &PwmConfig) -> Result<Self, EspError> {
+    let (signal_a, signal_b) = match timer {
+      McpwmTimer::Timer0 => (mcpwm_io_signals_t::MCPWM0A, mcpwm_io_signals_t::MCPWM0B),
+      McpwmTimer::Timer1 => (mcpwm_io_signals_t::MCPWM1A, mcpwm_io_signals_t::MCPWM1B),
+      McpwmTimer::Timer2 => (mcpwm_io_signals_t::MCPWM2A, mcpwm_io_signals_t::MCPWM2B),
+    };
+
+    esp_ok!(mcpwm_gpio_init(unit.into(), signal_a, pin_a.number() as i32))?;
+    if let Some(pin_b) = pin_b.as_ref() {
+      esp_ok!(mcpwm_gpio_init(unit.into(), signal_b, pin_b.number() as i32))?;
+    }
+
+    let mcpwm_config = mcpwm_config_t {
+      frequency: config.frequency,
+      cmpr_a: 0.0,
+      cmpr_b: 0.0,
+      duty_mode: if config.active_low { mcpwm_duty_type_t::MCPWM_DUTY_MODE_1 } else { mcpwm_duty_type_t::MCPWM_DUTY_MODE_0 },
+      counter_mode: match config.counter_mode {
+        CounterMode::Up => mcpwm_counter_type_t::MCPWM_UP_COUNTER,
+        CounterMode::Down => mcpwm_counter_type_t::MCPWM_DOWN_COUNTER,
+        CounterMode::UpDown => mcpwm_counter_type_t::MCPWM_UP_DOWN_COUNTER,
+      },
+    };
+
+    esp_ok!(mcpwm_init(unit.into(), timer.into(), &mcpwm_config))?;
+
+    Ok(Self { unit, timer, pin_a, pin_b })
+  }
+
+  pub fn unit(&self) -> McpwmUnit {
+    self.unit
+  }
+
+  pub fn timer(&self) -> McpwmTimer {
+    self.timer
+  }
+
+  /// Set the duty of output A in percent.
+  pub fn set_duty_a(&mut self, duty: f32) -> Result<(), EspError> {
+    esp_ok!(mcpwm_set_duty(self.unit.into(), self.timer.into(), mcpwm_generator_t::MCPWM_OPR_A, duty))
+  }
+
+  /// Set the duty of output B in percent. Ignored while dead time is enabled.
+  pub fn set_duty_b(&mut self, duty: f32) -> Result<(), EspError> {
+    esp_ok!(mcpwm_set_duty(self.unit.into(), self.timer.into(), mcpwm_generator_t::MCPWM_OPR_B, duty))
+  }
+
+  pub fn set_frequency(&mut self, frequency: u32) -> Result<(), EspError> {
+    esp_ok!(mcpwm_set_frequency(self.unit.into(), self.timer.into(), frequency))
+  }
+
+  /// Start the timer. This also clears a latched one-shot fault.
+  pub fn start(&mut self) -> Result<(), EspError> {
+    esp_ok!(mcpwm_start(self.unit.into(), self.timer.into()))
+  }
+
+  pub fn stop(&mut self) -> Result<(), EspError> {
+    esp_ok!(mcpwm_stop(self.unit.into(), self.timer.into()))
+  }
+
+  /// Derive both outputs from output A, delaying rising and falling edges by the given number
+  /// of 100 ns ticks, so both switches of a half bridge are never on at the same time.
+  pub fn enable_dead_time(&mut self, mode: DeadTimeMode, rising_edge_delay: u32, falling_edge_delay: u32) -> Result<(), EspError> {
+    esp_ok!(mcpwm_deadtime_enable(self.unit.into(), self.timer.into(), mode.into(), rising_edge_delay, falling_edge_delay))
+  }
+
+  pub fn disable_dead_time(&mut self) -> Result<(), EspError> {
+    esp_ok!(mcpwm_deadtime_disable(self.unit.into(), self.timer.into()))
+  }
+
+  /// Apply the given actions to outputs A and B while `fault` is active.
+  pub fn set_fault_action<P: Pin>(&mut self, fault: &McpwmFault<P>, mode: FaultMode, action_a: FaultAction, action_b: FaultAction) -> Result<(), EspError> {
+    let action_a = match action_a {
+      FaultAction::NoChange => mcpwm_action_on_pwmxa_t::MCPWM_NO_CHANGE_IN_MCPWMXA,
+      FaultAction::ForceLow => mcpwm_action_on_pwmxa_t::MCPWM_FORCE_MCPWMXA_LOW,
+      FaultAction::ForceHigh => mcpwm_action_on_pwmxa_t::MCPWM_FORCE_MCPWMXA_HIGH,
+      FaultAction::Toggle => mcpwm_action_on_pwmxa_t::MCPWM_TOG_MCPWMXA,
+    };
+
+    let action_b = match action_b {
+      FaultAction::NoChange => mcpwm_action_on_pwmxb_t::MCPWM_NO_CHANGE_IN_MCPWMXB,
+      FaultAction::ForceLow => mcpwm_action_on_pwmxb_t::MCPWM_FORCE_MCPWMXB_LOW,
+      FaultAction::ForceHigh => mcpwm_action_on_pwmxb_t::MCPWM_FORCE_MCPWMXB_HIGH,
+      FaultAction::Toggle => mcpwm_action_on_pwmxb_t::MCPWM_TOG_MCPWMXB,
+    };
+
+    let signal = fault_signal(fault.input);
+
+    match mode {
+      FaultMode::OneShot => esp_ok!(mcpwm_fault_set_oneshot_mode(self.unit.into(), self.timer.into(), signal, action_a, action_b)),
+      FaultMode::Cycle => esp_ok!(mcpwm_fault_set_cyc_mode(self.unit.into(), self.timer.into(), signal, action_a, action_b)),
+    }
+  }
+
+  /// Reset the timer to `phase` percent of its period on every pulse of sync input `input`,
+  /// e.g. to keep the outputs of multiple units in phase. See [`connect_sync_input`](fn.connect_sync_input.html).
+  pub fn enable_sync(&mut self, input: McpwmInput, phase: f32) -> Result<(), EspError> {
+    let signal = match input {
+      McpwmInput::Input0 => mcpwm_sync_signal_t::MCPWM_SELECT_SYNC0,
+      McpwmInput::Input1 => mcpwm_sync_signal_t::MCPWM_SELECT_SYNC1,
+      McpwmInput::Input2 => mcpwm_sync_signal_t::MCPWM_SELECT_SYNC2,
+    };
+
+    let phase = (phase.max(0.0).min(100.0) * 10.0) as u32;
+    esp_ok!(mcpwm_sync_enable(self.unit.into(), self.timer.into(), signal, phase))
+  }
+
+  pub fn disable_sync(&mut self) -> Result<(), EspError> {
+    esp_ok!(mcpwm_sync_disable(self.unit.into(), self.timer.into()))
+  }
+
+  /// Stop the outputs and return the pins.
+  pub fn release(mut self) -> (A, Option<B>) {
+    let _ = self.stop();
+    let this = ManuallyDrop::new(self);
+    unsafe { (ptr::read(&this.pin_a), ptr::read(&this.pin_b)) }
+  }
+}
+
+impl<A: OutputCapable, B: OutputCapable> Drop for McpwmPair<A, B> {
+  fn drop(&mut self) {
+    let _ = self.stop();
+  }
+}
@@ -0,0 +1,128 @@
+use core::mem::transmute;
+
+use esp_idf_bindgen::{
+  esp_event_base_t,
+  mesh_addr_t,
+  mesh_event_child_connected_t,
+  mesh_event_child_disconnected_t,
+  mesh_event_connected_t,
+  mesh_event_disconnected_t,
+  mesh_event_id_t,
+  mesh_event_layer_change_t,
+  mesh_event_no_parent_found_t,
+  mesh_event_routing_table_change_t,
+  mesh_event_toDS_state_t,
+  MESH_EVENT,
+};
+use macaddr::MacAddr6;
+
+use crate::EspError;
+use crate::event::{EventReceiver, Subscription};
+
+/// An ESP-MESH event.
+#[derive(Debug, Clone)]
+pub enum MeshEvent {
+  /// The mesh has started.
+  Started,
+  /// The mesh has stopped.
+  Stopped,
+  /// A child node connected to this node.
+  ChildConnected { mac: MacAddr6 },
+  /// A child node disconnected from this node.
+  ChildDisconnected { mac: MacAddr6 },
+  /// Descendant nodes were added to the routing table.
+  RoutingTableAdd { added: u16, total: u16 },
+  /// Descendant nodes were removed from the routing table.
+  RoutingTableRemove { removed: u16, total: u16 },
+  /// This node connected to a parent node.
+  ParentConnected { bssid: MacAddr6, layer: u16 },
+  /// This node disconnected from its parent node.
+  ///
+  /// The `reason` is either a WiFi disconnect reason or one of the ESP-MESH specific reasons starting at `100`.
+  ParentDisconnected { bssid: MacAddr6, reason: u32 },
+  /// No parent node was found.
+  NoParentFound { scan_times: i32 },
+  /// The layer of this node changed.
+  LayerChange { layer: u16 },
+  /// The root node can or can no longer reach the external IP network.
+  ToDsStateChange { reachable: bool },
+  /// The root node address was received.
+  RootAddress { mac: MacAddr6 },
+}
+
+impl MeshEvent {
+  pub(crate) unsafe fn from_raw(event_base: esp_event_base_t, event_id: i32, event_data: *mut libc::c_void) -> Option<Self> {
+    if event_base != MESH_EVENT || event_id < 0 || event_id >= mesh_event_id_t::MESH_EVENT_MAX as i32 {
+      return None
+    }
+
+    let event_id: mesh_event_id_t = transmute(event_id as u32);
+
+    Some(match event_id {
+      mesh_event_id_t::MESH_EVENT_STARTED => Self::Started,
+      mesh_event_id_t::MESH_EVENT_STOPPED => Self::Stopped,
+      mesh_event_id_t::MESH_EVENT_CHILD_CONNECTED => {
+        let event = &*(event_data as *const mesh_event_child_connected_t);
+        Self::ChildConnected { mac: MacAddr6::from(event.mac) }
+      },
+      mesh_event_id_t::MESH_EVENT_CHILD_DISCONNECTED => {
+        let event = &*(event_data as *const mesh_event_child_disconnected_t);
+        Self::ChildDisconnected { mac: MacAddr6::from(event.mac) }
+      },
+      mesh_event_id_t::MESH_EVENT_ROUTING_TABLE_ADD => {
+        let event = &*(event_data as *const mesh_event_routing_table_change_t);
+        Self::RoutingTableAdd { added: event.rt_size_change, total: event.rt_size_new }
+      },
+      mesh_event_id_t::MESH_EVENT_ROUTING_TABLE_REMOVE => {
+        let event = &*(event_data as *const mesh_event_routing_table_change_t);
+        Self::RoutingTableRemove { removed: event.rt_size_change, total: event.rt_size_new }
+      },
+      mesh_event_id_t::MESH_EVENT_PARENT_CONNECTED => {
+        let event = &*(event_data as *const mesh_event_connected_t);
+        Self::ParentConnected { bssid: MacAddr6::from(event.connected.bssid), layer: event.self_layer }
+      },
+      mesh_event_id_t::MESH_EVENT_PARENT_DISCONNECTED => {
+        let event = &*(event_data as *const mesh_event_disconnected_t);
+        Self::ParentDisconnected { bssid: MacAddr6::from(event.bssid), reason: event.reason as u32 }
+      },
+      mesh_event_id_t::MESH_EVENT_NO_PARENT_FOUND => {
+        let event = &*(event_data as *const mesh_event_no_parent_found_t);
+        Self::NoParentFound { scan_times: event.scan_times }
+      },
+      mesh_event_id_t::MESH_EVENT_LAYER_CHANGE => {
+        let event = &*(event_data as *const mesh_event_layer_change_t);
+        Self::LayerChange { layer: event.new_layer }
+      },
+      mesh_event_id_t::MESH_EVENT_TODS_STATE => {
+        let state = &*(event_data as *const mesh_event_toDS_state_t);
+        Self::ToDsStateChange { reachable: *state == mesh_event_toDS_state_t::MESH_TODS_REACHABLE }
+      },
+      mesh_event_id_t::MESH_EVENT_ROOT_ADDRESS => {
+        let event = &*(event_data as *const mesh_addr_t);
+        Self::RootAddress { mac: MacAddr6::from(event.addr) }
+      },
+      _ => return None,
+    })
+  }
+}
+
+/// Call `callback` for every [`MeshEvent`](enum.MeshEvent.html) until the returned subscription is dropped.
+///
+/// The callback runs on the event loop task, so it should return quickly.
+pub fn subscribe<F>(mut callback: F) -> Result<Subscription, EspError>
+where
+  F: FnMut(MeshEvent) + Send + 'static,
+{
+  Subscription::new(unsafe { &[MESH_EVENT] }, move |event_base, event_id, event_data| {
+    if let Some(event) = unsafe { MeshEvent::from_raw(event_base, event_id, event_data) } {
+      callback(event)
+    }
+  })
+}
+
+/// Receive every [`MeshEvent`](enum.MeshEvent.html) through a channel.
+pub fn events() -> Result<EventReceiver<MeshEvent>, EspError> {
+  EventReceiver::new(unsafe { &[MESH_EVENT] }, |event_base, event_id, event_data| {
+    unsafe { MeshEvent::from_raw(event_base, event_id, event_data) }
+  })
+}
@@ -0,0 +1,495 @@
+use core::fmt;
+use core::mem::{self, MaybeUninit};
+use core::ptr;
+use std::time::Duration;
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  esp_mesh_deinit,
+  esp_mesh_fix_root,
+  esp_mesh_get_layer,
+  esp_mesh_get_parent_bssid,
+  esp_mesh_get_routing_table,
+  esp_mesh_get_routing_table_size,
+  esp_mesh_get_total_node_num,
+  esp_mesh_init,
+  esp_mesh_is_root,
+  esp_mesh_recv,
+  esp_mesh_send,
+  esp_mesh_set_ap_authmode,
+  esp_mesh_set_config,
+  esp_mesh_set_max_layer,
+  esp_mesh_set_vote_percentage,
+  esp_mesh_start,
+  esp_mesh_stop,
+  esp_mesh_waive_root,
+  esp_wifi_start,
+  esp_wifi_stop,
+  g_wifi_default_mesh_crypto_funcs,
+  mesh_addr_t,
+  mesh_cfg_t,
+  mesh_data_t,
+  mesh_proto_t,
+  mesh_tos_t,
+  mesh_vote_reason_t,
+  ESP_ERR_INVALID_ARG,
+  MESH_DATA_P2P,
+  MESH_MPS,
+};
+use macaddr::MacAddr6;
+
+use crate::{EspError, interface::Interface, wifi::{AuthMode, Password, Ssid, Wifi}};
+
+mod event;
+pub use event::*;
+
+/// The maximum length of a message sent over the mesh.
+pub const MAX_DATA_LEN: usize = MESH_MPS as usize;
+
+/// Configuration for an ESP-MESH network.
+#[derive(Clone)]
+pub struct MeshConfig {
+  id: MacAddr6,
+  channel: u8,
+  allow_channel_switch: bool,
+  router_ssid: Ssid,
+  router_password: Password,
+  router_bssid: Option<MacAddr6>,
+  ap_password: Password,
+  ap_auth_mode: AuthMode,
+  max_connection: u8,
+  max_layer: u8,
+  vote_percentage: f32,
+  fixed_root: bool,
+}
+
+impl fmt::Debug for MeshConfig {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("MeshConfig")
+      .field("id", &self.id)
+      .field("channel", &self.channel)
+      .field("allow_channel_switch", &self.allow_channel_switch)
+      .field("router_ssid", &self.router_ssid)
+      .field("router_password", &"********")
+      .field("router_bssid", &self.router_bssid)
+      .field("ap_password", &"********")
+      .field("ap_auth_mode", &self.ap_auth_mode)
+      .field("max_connection", &self.max_connection)
+      .field("max_layer", &self.max_layer)
+      .field("vote_percentage", &self.vote_percentage)
+      .field("fixed_root", &self.fixed_root)
+      .finish()
+  }
+}
+
+impl MeshConfig {
+  /// The ID shared by all nodes of the mesh network.
+  pub fn id(&self) -> &MacAddr6 {
+    &self.id
+  }
+
+  pub fn router_ssid(&self) -> &Ssid {
+    &self.router_ssid
+  }
+
+  pub fn max_layer(&self) -> u8 {
+    self.max_layer
+  }
+
+  pub fn builder() -> MeshConfigBuilder {
+    MeshConfigBuilder::default()
+  }
+}
+
+impl From<&MeshConfig> for mesh_cfg_t {
+  fn from(config: &MeshConfig) -> Self {
+    let mut cfg: mesh_cfg_t = unsafe { MaybeUninit::zeroed().assume_init() };
+
+    cfg.channel = config.channel;
+    cfg.allow_channel_switch = config.allow_channel_switch;
+    cfg.mesh_id.addr = config.id.into_array();
+
+    let router_ssid = config.router_ssid.as_str().as_bytes();
+    cfg.router.ssid[..router_ssid.len()].copy_from_slice(router_ssid);
+    cfg.router.ssid_len = router_ssid.len() as u8;
+    let router_password = config.router_password.as_str().as_bytes();
+    cfg.router.password[..router_password.len()].copy_from_slice(router_password);
+    if let Some(bssid) = config.router_bssid {
+      cfg.router.bssid = bssid.into_array();
+    }
+
+    let ap_password = config.ap_password.as_str().as_bytes();
+    cfg.mesh_ap.password[..ap_password.len()].copy_from_slice(ap_password);
+    cfg.mesh_ap.max_connection = config.max_connection;
+
+    cfg.crypto_funcs = unsafe { &g_wifi_default_mesh_crypto_funcs };
+
+    cfg
+  }
+}
+
+/// Builder for [`MeshConfig`](struct.MeshConfig.html).
+#[derive(Clone)]
+pub struct MeshConfigBuilder {
+  id: Option<MacAddr6>,
+  channel: u8,
+  allow_channel_switch: bool,
+  router_ssid: Option<Ssid>,
+  router_password: Password,
+  router_bssid: Option<MacAddr6>,
+  ap_password: Password,
+  ap_auth_mode: Option<AuthMode>,
+  max_connection: u8,
+  max_layer: u8,
+  vote_percentage: f32,
+  fixed_root: bool,
+}
+
+impl fmt::Debug for MeshConfigBuilder {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("MeshConfigBuilder")
+      .field("id", &self.id)
+      .field("channel", &self.channel)
+      .field("allow_channel_switch", &self.allow_channel_switch)
+      .field("router_ssid", &self.router_ssid)
+      .field("router_password", &"********")
+      .field("router_bssid", &self.router_bssid)
+      .field("ap_password", &"********")
+      .field("ap_auth_mode", &self.ap_auth_mode)
+      .field("max_connection", &self.max_connection)
+      .field("max_layer", &self.max_layer)
+      .field("vote_percentage", &self.vote_percentage)
+      .field("fixed_root", &self.fixed_root)
+      .finish()
+  }
+}
+
+impl Default for MeshConfigBuilder {
+  fn default() -> Self {
+    Self {
+      id: None,
+      channel: 0,
+      allow_channel_switch: false,
+      router_ssid: None,
+      router_password: Default::default(),
+      router_bssid: None,
+      ap_password: Default::default(),
+      ap_auth_mode: None,
+      max_connection: 6,
+      max_layer: 6,
+      vote_percentage: 0.9,
+      fixed_root: false,
+    }
+  }
+}
+
+impl MeshConfigBuilder {
+  /// Set the ID shared by all nodes of the mesh network.
+  pub fn id(&mut self, id: MacAddr6) -> &mut Self {
+    self.id = Some(id);
+    self
+  }
+
+  /// Set the channel of the mesh network. A channel of `0` uses the channel of the router.
+  pub fn channel(&mut self, channel: u8) -> &mut Self {
+    assert!(channel <= 14, "invalid channel {}", channel);
+    self.channel = channel;
+    self
+  }
+
+  /// Allow the mesh network to switch channels if the router changes its channel.
+  pub fn allow_channel_switch(&mut self, allow_channel_switch: bool) -> &mut Self {
+    self.allow_channel_switch = allow_channel_switch;
+    self
+  }
+
+  /// Set the SSID of the router the root node connects to.
+  pub fn router_ssid(&mut self, router_ssid: Ssid) -> &mut Self {
+    self.router_ssid = Some(router_ssid);
+    self
+  }
+
+  pub fn router_password(&mut self, router_password: Password) -> &mut Self {
+    self.router_password = router_password;
+    self
+  }
+
+  /// Only connect to the router with the given BSSID.
+  pub fn router_bssid(&mut self, router_bssid: impl Into<Option<MacAddr6>>) -> &mut Self {
+    self.router_bssid = router_bssid.into();
+    self
+  }
+
+  /// Set the password used between nodes of the mesh network.
+  pub fn ap_password(&mut self, ap_password: Password) -> &mut Self {
+    self.ap_password = ap_password;
+    self
+  }
+
+  /// Set the authentication mode used between nodes of the mesh network.
+  ///
+  /// Defaults to `AuthMode::Open` without a password and `AuthMode::Wpa2Psk` otherwise.
+  pub fn ap_auth_mode(&mut self, ap_auth_mode: AuthMode) -> &mut Self {
+    self.ap_auth_mode = Some(ap_auth_mode);
+    self
+  }
+
+  /// Set the maximum number of child nodes per node.
+  pub fn max_connection(&mut self, max_connection: u8) -> &mut Self {
+    assert!(max_connection >= 1 && max_connection <= 10, "maximum connections must be between 1 and 10");
+    self.max_connection = max_connection;
+    self
+  }
+
+  /// Set the maximum number of layers of the mesh network.
+  pub fn max_layer(&mut self, max_layer: u8) -> &mut Self {
+    assert!(max_layer >= 1 && max_layer <= 25, "maximum layer must be between 1 and 25");
+    self.max_layer = max_layer;
+    self
+  }
+
+  /// Set the percentage of votes required to elect a root node.
+  pub fn vote_percentage(&mut self, vote_percentage: f32) -> &mut Self {
+    assert!(vote_percentage > 0.0 && vote_percentage <= 1.0, "vote percentage must be greater than 0 and at most 1");
+    self.vote_percentage = vote_percentage;
+    self
+  }
+
+  /// Disable root node election, e.g. when the root is designated manually.
+  pub fn fixed_root(&mut self, fixed_root: bool) -> &mut Self {
+    self.fixed_root = fixed_root;
+    self
+  }
+
+  pub fn build(&self) -> MeshConfig {
+    let ap_password = self.ap_password.as_str();
+
+    let ap_auth_mode = self.ap_auth_mode.unwrap_or(if ap_password.is_empty() { AuthMode::Open } else { AuthMode::Wpa2Psk });
+
+    if ap_auth_mode != AuthMode::Open {
+      assert!(ap_password.len() >= 8, "password must be at least 8 characters long");
+    }
+
+    MeshConfig {
+      id: self.id.expect("missing mesh ID"),
+      channel: self.channel,
+      allow_channel_switch: self.allow_channel_switch,
+      router_ssid: self.router_ssid.clone().expect("missing router SSID"),
+      router_password: self.router_password.clone(),
+      router_bssid: self.router_bssid,
+      ap_password: self.ap_password.clone(),
+      ap_auth_mode,
+      max_connection: self.max_connection,
+      max_layer: self.max_layer,
+      vote_percentage: self.vote_percentage,
+      fixed_root: self.fixed_root,
+    }
+  }
+}
+
+/// The protocol of a mesh message payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshProto {
+  Bin,
+  Http,
+  Json,
+  Mqtt,
+}
+
+impl From<MeshProto> for mesh_proto_t {
+  fn from(proto: MeshProto) -> Self {
+    match proto {
+      MeshProto::Bin => mesh_proto_t::MESH_PROTO_BIN,
+      MeshProto::Http => mesh_proto_t::MESH_PROTO_HTTP,
+      MeshProto::Json => mesh_proto_t::MESH_PROTO_JSON,
+      MeshProto::Mqtt => mesh_proto_t::MESH_PROTO_MQTT,
+    }
+  }
+}
+
+impl From<mesh_proto_t> for MeshProto {
+  fn from(proto: mesh_proto_t) -> Self {
+    match proto {
+      mesh_proto_t::MESH_PROTO_HTTP => MeshProto::Http,
+      mesh_proto_t::MESH_PROTO_JSON => MeshProto::Json,
+      mesh_proto_t::MESH_PROTO_MQTT => MeshProto::Mqtt,
+      _ => MeshProto::Bin,
+    }
+  }
+}
+
+/// A message received over the mesh network.
+#[derive(Debug, Clone)]
+pub struct MeshMessage {
+  src: MacAddr6,
+  proto: MeshProto,
+  data: Vec<u8>,
+}
+
+impl MeshMessage {
+  /// The MAC address of the sending node.
+  pub fn src(&self) -> &MacAddr6 {
+    &self.src
+  }
+
+  pub fn proto(&self) -> MeshProto {
+    self.proto
+  }
+
+  pub fn data(&self) -> &[u8] {
+    &self.data
+  }
+}
+
+/// A running ESP-MESH node, created with [`Mesh::start`](#method.start).
+///
+/// The mesh is stopped when dropped.
+#[derive(Debug)]
+pub struct Mesh {
+  config: MeshConfig,
+  wifi: Wifi,
+}
+
+impl Mesh {
+  /// Join or form the mesh network configured by `config`.
+  pub fn start(wifi: Wifi, config: MeshConfig) -> Result<Mesh, EspError> {
+    Interface::init_mesh()?;
+
+    esp_ok!(esp_wifi_start())?;
+    if let Err(err) = esp_ok!(esp_mesh_init()) {
+      let _ = esp_ok!(esp_wifi_stop());
+      return Err(err)
+    }
+
+    let mesh = Mesh { config, wifi };
+
+    esp_ok!(esp_mesh_set_max_layer(mesh.config.max_layer as i32))?;
+    esp_ok!(esp_mesh_set_vote_percentage(mesh.config.vote_percentage))?;
+    esp_ok!(esp_mesh_set_ap_authmode(mesh.config.ap_auth_mode.into()))?;
+    esp_ok!(esp_mesh_fix_root(mesh.config.fixed_root))?;
+
+    let mut cfg = mesh_cfg_t::from(&mesh.config);
+    esp_ok!(esp_mesh_set_config(&mut cfg))?;
+    esp_ok!(esp_mesh_start())?;
+
+    Ok(mesh)
+  }
+
+  pub fn config(&self) -> &MeshConfig {
+    &self.config
+  }
+
+  /// Whether this node is the root node, i.e. connected to the router.
+  pub fn is_root(&self) -> bool {
+    unsafe { esp_mesh_is_root() }
+  }
+
+  /// The layer of this node, starting with `1` for the root node.
+  pub fn layer(&self) -> u8 {
+    unsafe { esp_mesh_get_layer() as u8 }
+  }
+
+  /// The BSSID of the parent node, or the router for the root node.
+  pub fn parent(&self) -> Option<MacAddr6> {
+    let mut parent = MaybeUninit::<mesh_addr_t>::uninit();
+    esp_ok!(esp_mesh_get_parent_bssid(parent.as_mut_ptr())).ok()?;
+    Some(MacAddr6::from(unsafe { parent.assume_init().addr }))
+  }
+
+  /// The total number of nodes in the mesh network.
+  pub fn total_nodes(&self) -> usize {
+    unsafe { esp_mesh_get_total_node_num() as usize }
+  }
+
+  /// The MAC addresses of all descendant nodes, including this node.
+  pub fn routing_table(&self) -> Result<Vec<MacAddr6>, EspError> {
+    let capacity = unsafe { esp_mesh_get_routing_table_size() } as usize;
+    let mut table = vec![MacAddr6::nil(); capacity];
+    let mut size = 0;
+
+    esp_ok!(esp_mesh_get_routing_table(
+      table.as_mut_ptr() as *mut mesh_addr_t,
+      (capacity * mem::size_of::<MacAddr6>()) as i32,
+      &mut size,
+    ))?;
+
+    table.truncate(size as usize);
+    Ok(table)
+  }
+
+  /// Give up the root role and start a new root election.
+  pub fn waive_root(&mut self) -> Result<(), EspError> {
+    esp_ok!(esp_mesh_waive_root(ptr::null(), mesh_vote_reason_t::MESH_VOTE_REASON_ROOT_INITIATED as i32))
+  }
+
+  /// Send `data` to the node with the given MAC address, or to the root node if `dst` is `None`.
+  pub fn send(&self, dst: Option<&MacAddr6>, proto: MeshProto, data: &[u8]) -> Result<(), EspError> {
+    if data.len() > MAX_DATA_LEN {
+      return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+    }
+
+    let mesh_data = mesh_data_t {
+      data: data.as_ptr() as *mut _,
+      size: data.len() as u16,
+      proto: proto.into(),
+      tos: mesh_tos_t::MESH_TOS_P2P,
+    };
+
+    let (dst, flag) = match dst {
+      Some(dst) => (dst.as_bytes().as_ptr() as *const mesh_addr_t, MESH_DATA_P2P as i32),
+      None => (ptr::null(), 0),
+    };
+
+    esp_ok!(esp_mesh_send(dst, &mesh_data, flag, ptr::null(), 0))
+  }
+
+  /// Block until a message for this node is received or `timeout` has elapsed.
+  ///
+  /// A `timeout` of `None` waits forever.
+  pub fn recv(&self, timeout: Option<Duration>) -> Result<MeshMessage, EspError> {
+    let mut data = vec![0; MAX_DATA_LEN];
+
+    let mut mesh_data = mesh_data_t {
+      data: data.as_mut_ptr(),
+      size: data.len() as u16,
+      proto: mesh_proto_t::MESH_PROTO_BIN,
+      tos: mesh_tos_t::MESH_TOS_P2P,
+    };
+
+    let timeout_ms = timeout.map_or(-1, |timeout| timeout.as_millis() as i32);
+
+    let mut src = MaybeUninit::<mesh_addr_t>::uninit();
+    let mut flag = 0;
+    esp_ok!(esp_mesh_recv(src.as_mut_ptr(), &mut mesh_data, timeout_ms, &mut flag, ptr::null_mut(), 0))?;
+
+    data.truncate(mesh_data.size as usize);
+
+    Ok(MeshMessage {
+      src: MacAddr6::from(unsafe { src.assume_init().addr }),
+      proto: mesh_data.proto.into(),
+      data,
+    })
+  }
+
+  /// Stop this node and return the WiFi instance.
+  pub fn stop(self) -> (MeshConfig, Wifi) {
+    let mesh = mem::ManuallyDrop::new(self);
+    mesh.deinit();
+
+    unsafe { (ptr::read(&mesh.config), ptr::read(&mesh.wifi)) }
+  }
+
+  fn deinit(&self) {
+    unsafe {
+      esp_mesh_stop();
+      esp_mesh_deinit();
+      esp_wifi_stop();
+    }
+  }
+}
+
+impl Drop for Mesh {
+  fn drop(&mut self) {
+    self.deinit();
+  }
+}
@@ -0,0 +1,135 @@
+use core::fmt;
+use core::mem;
+use core::ptr;
+use std::ffi::CString;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use esp_idf_bindgen::{
+  addrinfo,
+  lwip_freeaddrinfo,
+  lwip_getaddrinfo,
+  sockaddr_in,
+  sockaddr_in6,
+  AF_INET,
+  AF_INET6,
+  EAI_FAIL,
+  EAI_NONAME,
+};
+
+#[cfg(target_device = "esp32")]
+mod ping;
+#[cfg(target_device = "esp32")]
+pub use ping::*;
+
+/// The timeout used by [`resolve`](fn.resolve.html).
+pub const DEFAULT_RESOLVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An error returned when resolving a hostname fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DnsError {
+  /// The hostname contains a NUL byte.
+  InvalidHostname,
+  /// The hostname has no A or AAAA records.
+  NotFound,
+  /// The lookup did not complete within its timeout.
+  Timeout,
+  /// The thread running the lookup could not be spawned.
+  Spawn,
+  /// The resolver failed with the given `getaddrinfo` error code.
+  Internal(i32),
+}
+
+impl fmt::Display for DnsError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::InvalidHostname => write!(f, "Invalid hostname"),
+      Self::NotFound => write!(f, "Hostname not found"),
+      Self::Timeout => write!(f, "Timed out resolving hostname"),
+      Self::Spawn => write!(f, "Failed to spawn resolver thread"),
+      Self::Internal(code) => write!(f, "Error resolving hostname: {}", code),
+    }
+  }
+}
+
+/// Look up the addresses of `hostname` for a single address family.
+fn getaddrinfo(hostname: &CString, family: u32) -> Result<Vec<IpAddr>, DnsError> {
+  let mut hints: addrinfo = unsafe { mem::zeroed() };
+  hints.ai_family = family as i32;
+
+  let mut res = ptr::null_mut();
+  let code = unsafe { lwip_getaddrinfo(hostname.as_ptr(), ptr::null(), &hints, &mut res) };
+
+  if code == EAI_NONAME as i32 || code == EAI_FAIL as i32 {
+    return Err(DnsError::NotFound)
+  } else if code != 0 {
+    return Err(DnsError::Internal(code))
+  }
+
+  let mut addrs = Vec::new();
+
+  let mut ai = res;
+  while !ai.is_null() {
+    let info = unsafe { &*ai };
+
+    if !info.ai_addr.is_null() {
+      if info.ai_family == AF_INET as i32 {
+        let addr = unsafe { &*(info.ai_addr as *const sockaddr_in) };
+        addrs.push(IpAddr::V4(Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr))));
+      } else if info.ai_family == AF_INET6 as i32 {
+        let addr = unsafe { &*(info.ai_addr as *const sockaddr_in6) };
+        addrs.push(IpAddr::V6(Ipv6Addr::from(unsafe { addr.sin6_addr.un.u8_addr })));
+      }
+    }
+
+    ai = info.ai_next;
+  }
+
+  unsafe { lwip_freeaddrinfo(res) };
+
+  Ok(addrs)
+}
+
+/// Resolve the IPv4 and IPv6 addresses of `hostname`, waiting at most
+/// [`DEFAULT_RESOLVE_TIMEOUT`](constant.DEFAULT_RESOLVE_TIMEOUT.html).
+pub fn resolve(hostname: &str) -> Result<Vec<IpAddr>, DnsError> {
+  resolve_timeout(hostname, DEFAULT_RESOLVE_TIMEOUT)
+}
+
+/// Resolve the IPv4 and IPv6 addresses of `hostname`, waiting at most `timeout`.
+///
+/// IPv4 addresses are returned first. IPv6 addresses are only returned if IPv6 is enabled.
+pub fn resolve_timeout(hostname: &str, timeout: Duration) -> Result<Vec<IpAddr>, DnsError> {
+  let hostname = CString::new(hostname).map_err(|_| DnsError::InvalidHostname)?;
+
+  let (sender, receiver) = mpsc::sync_channel(1);
+
+  // The lookup cannot be cancelled, so it runs on its own thread which is detached on timeout.
+  thread::Builder::new()
+    .name("dns_resolve".into())
+    .stack_size(4096)
+    .spawn(move || {
+      let v4 = getaddrinfo(&hostname, AF_INET);
+      let v6 = getaddrinfo(&hostname, AF_INET6);
+
+      let result = match (v4, v6) {
+        (Ok(mut v4), Ok(v6)) => {
+          v4.extend(v6);
+          Ok(v4)
+        },
+        (Ok(addrs), Err(_)) | (Err(_), Ok(addrs)) => Ok(addrs),
+        (Err(err), Err(_)) => Err(err),
+      };
+
+      let _ = sender.send(result);
+    })
+    .map_err(|_| DnsError::Spawn)?;
+
+  match receiver.recv_timeout(timeout) {
+    Ok(Ok(addrs)) if addrs.is_empty() => Err(DnsError::NotFound),
+    Ok(result) => result,
+    Err(_) => Err(DnsError::Timeout),
+  }
+}
@@ -0,0 +1,270 @@
+use core::mem::{self, MaybeUninit};
+use std::net::IpAddr;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+use esp_idf_bindgen::{
+  esp_ping_callbacks_t,
+  esp_ping_config_t,
+  esp_ping_delete_session,
+  esp_ping_get_profile,
+  esp_ping_handle_t,
+  esp_ping_new_session,
+  esp_ping_profile_t,
+  esp_ping_start,
+  esp_ping_stop,
+  ip_addr_t,
+  lwip_ip_addr_type,
+};
+
+use crate::EspError;
+use crate::callback_slot::CallbackSlot;
+
+/// A reply to an echo request.
+#[derive(Debug, Clone)]
+pub struct PingReply {
+  seq: u16,
+  ttl: u8,
+  rtt: Duration,
+  size: u32,
+}
+
+impl PingReply {
+  /// The sequence number of the echo request.
+  pub fn seq(&self) -> u16 {
+    self.seq
+  }
+
+  pub fn ttl(&self) -> u8 {
+    self.ttl
+  }
+
+  /// The round-trip time.
+  pub fn rtt(&self) -> Duration {
+    self.rtt
+  }
+
+  /// The size of the reply in bytes.
+  pub fn size(&self) -> u32 {
+    self.size
+  }
+}
+
+/// An echo request which was not answered within the timeout.
+#[derive(Debug, Clone)]
+pub struct PingTimeout {
+  seq: u16,
+}
+
+impl PingTimeout {
+  /// The sequence number of the echo request.
+  pub fn seq(&self) -> u16 {
+    self.seq
+  }
+}
+
+/// The summary of a finished [`Ping`](struct.Ping.html) session.
+#[derive(Debug, Clone)]
+pub struct PingSummary {
+  transmitted: u32,
+  received: u32,
+  duration: Duration,
+}
+
+impl PingSummary {
+  /// The number of echo requests sent.
+  pub fn transmitted(&self) -> u32 {
+    self.transmitted
+  }
+
+  /// The number of replies received.
+  pub fn received(&self) -> u32 {
+    self.received
+  }
+
+  /// The total time spent waiting for replies.
+  pub fn duration(&self) -> Duration {
+    self.duration
+  }
+
+  /// The fraction of echo requests which were not answered.
+  pub fn loss(&self) -> f32 {
+    if self.transmitted == 0 {
+      0.0
+    } else {
+      1.0 - self.received as f32 / self.transmitted as f32
+    }
+  }
+}
+
+#[derive(Debug)]
+enum PingEvent {
+  Reply(PingReply),
+  Timeout(PingTimeout),
+  End(PingSummary),
+}
+
+/// A running ping session, created with [`ping`](fn.ping.html).
+///
+/// Iterating yields the result of every echo request. Once all requests have completed,
+/// the summary is available using [`summary`](#method.summary). The session is stopped when dropped.
+#[derive(Debug)]
+pub struct Ping {
+  handle: esp_ping_handle_t,
+  sender: *mut CallbackSlot<Sender<PingEvent>>,
+  receiver: Receiver<PingEvent>,
+  summary: Option<PingSummary>,
+  request_duration: Duration,
+}
+
+unsafe impl Send for Ping {}
+
+fn profile<T>(handle: esp_ping_handle_t, profile: esp_ping_profile_t) -> T {
+  let mut value = MaybeUninit::<T>::zeroed();
+  let _ = esp_ok!(esp_ping_get_profile(handle, profile, value.as_mut_ptr() as *mut _, mem::size_of::<T>() as u32));
+  unsafe { value.assume_init() }
+}
+
+fn send(args: *mut libc::c_void, event: PingEvent) {
+  let sender = unsafe { &*(args as *const CallbackSlot<Sender<PingEvent>>) };
+  sender.with(|sender| {
+    let _ = sender.send(event);
+  });
+}
+
+extern "C" fn on_ping_success(handle: esp_ping_handle_t, args: *mut libc::c_void) {
+  let reply = PingReply {
+    seq: profile(handle, esp_ping_profile_t::ESP_PING_PROF_SEQNO),
+    ttl: profile(handle, esp_ping_profile_t::ESP_PING_PROF_TTL),
+    rtt: Duration::from_millis(profile::<u32>(handle, esp_ping_profile_t::ESP_PING_PROF_TIMEGAP) as u64),
+    size: profile(handle, esp_ping_profile_t::ESP_PING_PROF_SIZE),
+  };
+
+  send(args, PingEvent::Reply(reply));
+}
+
+extern "C" fn on_ping_timeout(handle: esp_ping_handle_t, args: *mut libc::c_void) {
+  let timeout = PingTimeout { seq: profile(handle, esp_ping_profile_t::ESP_PING_PROF_SEQNO) };
+  send(args, PingEvent::Timeout(timeout));
+}
+
+extern "C" fn on_ping_end(handle: esp_ping_handle_t, args: *mut libc::c_void) {
+  let summary = PingSummary {
+    transmitted: profile(handle, esp_ping_profile_t::ESP_PING_PROF_REQUEST),
+    received: profile(handle, esp_ping_profile_t::ESP_PING_PROF_REPLY),
+    duration: Duration::from_millis(profile::<u32>(handle, esp_ping_profile_t::ESP_PING_PROF_DURATION) as u64),
+  };
+
+  send(args, PingEvent::End(summary));
+}
+
+fn ip_addr_to_native(ip: IpAddr) -> ip_addr_t {
+  let mut addr: ip_addr_t = unsafe { MaybeUninit::zeroed().assume_init() };
+
+  match ip {
+    IpAddr::V4(ip) => {
+      addr.u_addr.ip4.addr = u32::from(ip).to_be();
+      addr.type_ = lwip_ip_addr_type::IPADDR_TYPE_V4 as _;
+    },
+    IpAddr::V6(ip) => {
+      let words = unsafe { &mut addr.u_addr.ip6.addr };
+      for (word, chunk) in words.iter_mut().zip(ip.octets().chunks(4)) {
+        *word = u32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+      }
+      addr.type_ = lwip_ip_addr_type::IPADDR_TYPE_V6 as _;
+    },
+  }
+
+  addr
+}
+
+/// Send `count` echo requests to `target`, one every `interval`, waiting at most `timeout` for each reply.
+pub fn ping(target: IpAddr, count: u32, interval: Duration, timeout: Duration) -> Result<Ping, EspError> {
+  assert!(count > 0, "count must be at least 1");
+
+  let mut config: esp_ping_config_t = unsafe { MaybeUninit::zeroed().assume_init() };
+  config.count = count;
+  config.interval_ms = interval.as_millis() as u32;
+  config.timeout_ms = timeout.as_millis() as u32;
+  config.data_size = 64;
+  config.tos = 0;
+  config.target_addr = ip_addr_to_native(target);
+  // The callbacks format profiles and send through a channel, which needs more than the default stack.
+  config.task_stack_size = 4096;
+  config.task_prio = 2;
+  config.interface = 0;
+
+  let (sender, receiver) = mpsc::channel();
+  let slot = CallbackSlot::new();
+  let _ = slot.set(sender);
+  let sender = Box::into_raw(Box::new(slot));
+
+  let callbacks = esp_ping_callbacks_t {
+    cb_args: sender as *mut _,
+    on_ping_success: Some(on_ping_success),
+    on_ping_timeout: Some(on_ping_timeout),
+    on_ping_end: Some(on_ping_end),
+  };
+
+  let request_duration = interval + timeout;
+  let mut ping = Ping { handle: core::ptr::null_mut(), sender, receiver, summary: None, request_duration };
+
+  esp_ok!(esp_ping_new_session(&config, &callbacks, &mut ping.handle))?;
+  esp_ok!(esp_ping_start(ping.handle))?;
+
+  Ok(ping)
+}
+
+impl Ping {
+  /// The summary of this session, once all echo requests have completed.
+  pub fn summary(&self) -> Option<&PingSummary> {
+    self.summary.as_ref()
+  }
+
+  /// Wait until all echo requests have completed and return the summary.
+  pub fn wait(mut self) -> Option<PingSummary> {
+    while self.next().is_some() {}
+    self.summary.take()
+  }
+}
+
+impl Iterator for Ping {
+  type Item = Result<PingReply, PingTimeout>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.summary.is_some() {
+      return None
+    }
+
+    match self.receiver.recv().ok()? {
+      PingEvent::Reply(reply) => Some(Ok(reply)),
+      PingEvent::Timeout(timeout) => Some(Err(timeout)),
+      PingEvent::End(summary) => {
+        self.summary = Some(summary);
+        None
+      },
+    }
+  }
+}
+
+impl Drop for Ping {
+  fn drop(&mut self) {
+    if !self.handle.is_null() {
+      let _ = esp_ok!(esp_ping_stop(self.handle));
+
+      // Stopping ends the current request, after which the session ends and the ping task is idle.
+      while self.summary.is_none() {
+        match self.receiver.recv_timeout(self.request_duration) {
+          Ok(PingEvent::End(summary)) => self.summary = Some(summary),
+          Ok(_) => continue,
+          Err(_) => break,
+        }
+      }
+
+      let _ = esp_ok!(esp_ping_delete_session(self.handle));
+    }
+
+    let sender = unsafe { Box::from_raw(self.sender) };
+    sender.clear();
+  }
+}
@@ -0,0 +1,327 @@
+use std::convert::TryInto;
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  ESP_ERR_INVALID_SIZE,
+  ESP_ERR_NVS_INVALID_NAME,
+  ESP_ERR_NVS_KEY_TOO_LONG,
+  ESP_ERR_NVS_NOT_FOUND,
+  ESP_ERR_NVS_VALUE_TOO_LONG,
+};
+
+use super::*;
+
+/// Key of the journal used to make batches atomic. Must not be used by applications.
+pub const NVS_BATCH_JOURNAL_KEY: &str = "__nvs_batch";
+
+/// Maximum length of an NVS key.
+const KEY_MAX_LEN: usize = 15;
+/// Maximum length of an NVS blob, strings are stored as blobs as well.
+const BLOB_MAX_LEN: usize = 508_000;
+
+/// A value stored by an [`NvsBatch`](struct.NvsBatch.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NvsValue {
+  U8(u8),
+  I8(i8),
+  U16(u16),
+  I16(i16),
+  U32(u32),
+  I32(i32),
+  U64(u64),
+  I64(i64),
+  Str(String),
+  Blob(Vec<u8>),
+}
+
+macro_rules! nvs_value_from {
+  ($ty:ty, $variant:ident) => {
+    impl From<$ty> for NvsValue {
+      fn from(value: $ty) -> Self {
+        Self::$variant(value)
+      }
+    }
+  };
+}
+
+nvs_value_from!(u8, U8);
+nvs_value_from!(i8, I8);
+nvs_value_from!(u16, U16);
+nvs_value_from!(i16, I16);
+nvs_value_from!(u32, U32);
+nvs_value_from!(i32, I32);
+nvs_value_from!(u64, U64);
+nvs_value_from!(i64, I64);
+nvs_value_from!(String, Str);
+nvs_value_from!(Vec<u8>, Blob);
+
+impl From<&str> for NvsValue {
+  fn from(value: &str) -> Self {
+    Self::Str(value.to_owned())
+  }
+}
+
+impl From<&[u8]> for NvsValue {
+  fn from(value: &[u8]) -> Self {
+    Self::Blob(value.to_vec())
+  }
+}
+
+impl From<bool> for NvsValue {
+  fn from(value: bool) -> Self {
+    Self::U8(value as u8)
+  }
+}
+
+impl NvsValue {
+  fn tag(&self) -> u8 {
+    match self {
+      Self::U8(_) => 1,
+      Self::I8(_) => 2,
+      Self::U16(_) => 3,
+      Self::I16(_) => 4,
+      Self::U32(_) => 5,
+      Self::I32(_) => 6,
+      Self::U64(_) => 7,
+      Self::I64(_) => 8,
+      Self::Str(_) => 9,
+      Self::Blob(_) => 10,
+    }
+  }
+
+  fn to_bytes(&self) -> Vec<u8> {
+    match self {
+      Self::U8(value) => value.to_le_bytes().to_vec(),
+      Self::I8(value) => value.to_le_bytes().to_vec(),
+      Self::U16(value) => value.to_le_bytes().to_vec(),
+      Self::I16(value) => value.to_le_bytes().to_vec(),
+      Self::U32(value) => value.to_le_bytes().to_vec(),
+      Self::I32(value) => value.to_le_bytes().to_vec(),
+      Self::U64(value) => value.to_le_bytes().to_vec(),
+      Self::I64(value) => value.to_le_bytes().to_vec(),
+      Self::Str(value) => value.as_bytes().to_vec(),
+      Self::Blob(value) => value.clone(),
+    }
+  }
+
+  fn from_bytes(tag: u8, bytes: &[u8]) -> Option<Self> {
+    Some(match tag {
+      1 => Self::U8(u8::from_le_bytes(bytes.try_into().ok()?)),
+      2 => Self::I8(i8::from_le_bytes(bytes.try_into().ok()?)),
+      3 => Self::U16(u16::from_le_bytes(bytes.try_into().ok()?)),
+      4 => Self::I16(i16::from_le_bytes(bytes.try_into().ok()?)),
+      5 => Self::U32(u32::from_le_bytes(bytes.try_into().ok()?)),
+      6 => Self::I32(i32::from_le_bytes(bytes.try_into().ok()?)),
+      7 => Self::U64(u64::from_le_bytes(bytes.try_into().ok()?)),
+      8 => Self::I64(i64::from_le_bytes(bytes.try_into().ok()?)),
+      9 => Self::Str(String::from_utf8(bytes.to_vec()).ok()?),
+      10 => Self::Blob(bytes.to_vec()),
+      _ => return None,
+    })
+  }
+
+  /// Check that this value can be stored, so applying a journaled batch cannot fail because of it.
+  fn validate(&self) -> Result<(), EspError> {
+    let len = match self {
+      Self::Str(value) => value.len(),
+      Self::Blob(value) => value.len(),
+      _ => return Ok(()),
+    };
+
+    if len > BLOB_MAX_LEN {
+      return Err(EspError { code: ESP_ERR_NVS_VALUE_TOO_LONG as esp_err_t })
+    }
+
+    Ok(())
+  }
+
+  fn store(&self, namespace: &mut NameSpace, key: &str) -> Result<(), EspError> {
+    match self {
+      Self::U8(value) => namespace.set(key, value),
+      Self::I8(value) => namespace.set(key, value),
+      Self::U16(value) => namespace.set(key, value),
+      Self::I16(value) => namespace.set(key, value),
+      Self::U32(value) => namespace.set(key, value),
+      Self::I32(value) => namespace.set(key, value),
+      Self::U64(value) => namespace.set(key, value),
+      Self::I64(value) => namespace.set(key, value),
+      Self::Str(value) => namespace.set(key, value),
+      Self::Blob(value) => namespace.set(key, value),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+enum Op {
+  Set(String, NvsValue),
+  Remove(String),
+}
+
+impl Op {
+  fn key(&self) -> &str {
+    match self {
+      Self::Set(key, _) | Self::Remove(key) => key,
+    }
+  }
+
+  fn apply(&self, namespace: &mut NameSpace) -> Result<(), EspError> {
+    match self {
+      Self::Set(key, value) => value.store(namespace, key),
+      Self::Remove(key) => match namespace.remove(key) {
+        Err(err) if err.code != ESP_ERR_NVS_NOT_FOUND as esp_err_t => Err(err),
+        _ => Ok(()),
+      },
+    }
+  }
+}
+
+fn encode(ops: &[Op]) -> Vec<u8> {
+  let mut journal = Vec::new();
+
+  for op in ops {
+    let (tag, value) = match op {
+      Op::Set(_, value) => (value.tag(), value.to_bytes()),
+      Op::Remove(_) => (0, Vec::new()),
+    };
+
+    journal.push(tag);
+    journal.push(op.key().len() as u8);
+    journal.extend_from_slice(op.key().as_bytes());
+    journal.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    journal.extend_from_slice(&value);
+  }
+
+  journal
+}
+
+fn decode(mut journal: &[u8]) -> Option<Vec<Op>> {
+  let mut ops = Vec::new();
+
+  while let [tag, key_len, rest @ ..] = journal {
+    let key = rest.get(..*key_len as usize)?;
+    let key = String::from_utf8(key.to_vec()).ok()?;
+    let rest = &rest[*key_len as usize..];
+
+    let value_len = u32::from_le_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+    let value = rest.get(4..(4 + value_len))?;
+
+    ops.push(match tag {
+      0 => Op::Remove(key),
+      tag => Op::Set(key, NvsValue::from_bytes(*tag, value)?),
+    });
+
+    journal = &rest[(4 + value_len)..];
+  }
+
+  if journal.is_empty() { Some(ops) } else { None }
+}
+
+/// A set of writes to a [`NameSpace`](struct.NameSpace.html) which are applied all together or not at all.
+///
+/// The batch is first written to a journal entry, which is atomic. If power is lost while the batch
+/// is applied, [`NameSpace::recover_batch`](struct.NameSpace.html#method.recover_batch) completes
+/// it on the next boot.
+#[derive(Debug, Clone, Default)]
+pub struct NvsBatch {
+  ops: Vec<Op>,
+}
+
+impl NvsBatch {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Set the entry with the given key.
+  pub fn set(&mut self, key: &str, value: impl Into<NvsValue>) -> &mut Self {
+    self.ops.push(Op::Set(key.to_owned(), value.into()));
+    self
+  }
+
+  /// Remove the entry with the given key.
+  pub fn remove(&mut self, key: &str) -> &mut Self {
+    self.ops.push(Op::Remove(key.to_owned()));
+    self
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.ops.is_empty()
+  }
+
+  /// Apply all operations to `namespace` and commit them.
+  ///
+  /// An interrupted batch is completed first. If any key or value is invalid, nothing is written.
+  ///
+  /// If the interrupted batch cannot be completed, this fails until it is dropped using
+  /// [`NameSpace::discard_batch`](struct.NameSpace.html#method.discard_batch).
+  pub fn commit(&self, namespace: &mut NameSpace) -> Result<(), EspError> {
+    namespace.recover_batch()?;
+
+    for op in &self.ops {
+      let key = op.key();
+
+      if key.len() > KEY_MAX_LEN {
+        return Err(EspError { code: ESP_ERR_NVS_KEY_TOO_LONG as esp_err_t })
+      }
+
+      if key.is_empty() || key.contains('\0') || key == NVS_BATCH_JOURNAL_KEY {
+        return Err(EspError { code: ESP_ERR_NVS_INVALID_NAME as esp_err_t })
+      }
+
+      if let Op::Set(_, value) = op {
+        value.validate()?;
+      }
+    }
+
+    let journal = encode(&self.ops);
+    if journal.len() > BLOB_MAX_LEN {
+      return Err(EspError { code: ESP_ERR_NVS_VALUE_TOO_LONG as esp_err_t })
+    }
+
+    namespace.set(NVS_BATCH_JOURNAL_KEY, journal)?;
+    namespace.commit()?;
+
+    apply(namespace, &self.ops)
+  }
+}
+
+fn apply(namespace: &mut NameSpace, ops: &[Op]) -> Result<(), EspError> {
+  for op in ops {
+    op.apply(namespace)?;
+  }
+
+  namespace.remove(NVS_BATCH_JOURNAL_KEY)?;
+  namespace.commit()
+}
+
+impl NameSpace {
+  /// Complete an [`NvsBatch`](struct.NvsBatch.html) which was interrupted, e.g. by a power loss.
+  ///
+  /// Returns `true` if an interrupted batch was found. Should be called once after opening
+  /// a namespace which is written using batches.
+  ///
+  /// If this keeps failing, e.g. because the journal is corrupted, the interrupted batch can be
+  /// dropped using [`discard_batch`](#method.discard_batch).
+  pub fn recover_batch(&mut self) -> Result<bool, EspError> {
+    let journal: Vec<u8> = match self.get(NVS_BATCH_JOURNAL_KEY) {
+      Ok(journal) => journal,
+      Err(err) if err.code == ESP_ERR_NVS_NOT_FOUND as esp_err_t => return Ok(false),
+      Err(err) => return Err(err),
+    };
+
+    let ops = decode(&journal).ok_or(EspError { code: ESP_ERR_INVALID_SIZE as esp_err_t })?;
+    apply(self, &ops)?;
+
+    Ok(true)
+  }
+
+  /// Drop an interrupted [`NvsBatch`](struct.NvsBatch.html) without completing it.
+  ///
+  /// Operations of the batch which were already applied are kept.
+  pub fn discard_batch(&mut self) -> Result<(), EspError> {
+    match self.remove(NVS_BATCH_JOURNAL_KEY) {
+      Err(err) if err.code == ESP_ERR_NVS_NOT_FOUND as esp_err_t => Ok(()),
+      Err(err) => Err(err),
+      Ok(()) => self.commit(),
+    }
+  }
+}
@@ -0,0 +1,119 @@
+use core::cell::Cell;
+use core::fmt;
+
+use esp_idf_bindgen::{
+  esp_partition_find_first,
+  esp_partition_subtype_t,
+  esp_partition_t,
+  esp_partition_type_t,
+  nvs_flash_generate_keys,
+  nvs_flash_read_security_cfg,
+  nvs_flash_secure_init_partition,
+  nvs_sec_cfg_t,
+  ESP_ERR_INVALID_STATE,
+  ESP_ERR_NOT_FOUND,
+  ESP_ERR_NVS_KEYS_NOT_INITIALIZED,
+};
+
+use super::*;
+
+/// Keys used to encrypt non-volatile storage partitions, stored in an `nvs_keys` partition.
+///
+/// The `nvs_keys` partition itself must be protected using flash encryption.
+#[derive(Clone)]
+pub struct NvsKeys {
+  cfg: nvs_sec_cfg_t,
+}
+
+impl fmt::Debug for NvsKeys {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("NvsKeys")
+      .field("eky", &"********")
+      .field("tky", &"********")
+      .finish()
+  }
+}
+
+fn keys_partition() -> Result<*const esp_partition_t, EspError> {
+  let partition = unsafe {
+    esp_partition_find_first(
+      esp_partition_type_t::ESP_PARTITION_TYPE_DATA,
+      esp_partition_subtype_t::ESP_PARTITION_SUBTYPE_DATA_NVS_KEYS,
+      ptr::null(),
+    )
+  };
+
+  if partition.is_null() {
+    Err(EspError { code: ESP_ERR_NOT_FOUND as esp_err_t })
+  } else {
+    Ok(partition)
+  }
+}
+
+impl NvsKeys {
+  /// Read the keys from the first `nvs_keys` partition.
+  ///
+  /// Fails with `ESP_ERR_NVS_KEYS_NOT_INITIALIZED` if no keys have been generated yet.
+  pub fn read() -> Result<Self, EspError> {
+    let partition = keys_partition()?;
+
+    let mut cfg = MaybeUninit::<nvs_sec_cfg_t>::uninit();
+    esp_ok!(nvs_flash_read_security_cfg(partition, cfg.as_mut_ptr()))?;
+    Ok(Self { cfg: unsafe { cfg.assume_init() } })
+  }
+
+  /// Generate new random keys and write them to the first `nvs_keys` partition.
+  ///
+  /// Existing keys are overwritten, which makes all data encrypted with them unreadable.
+  pub fn generate() -> Result<Self, EspError> {
+    let partition = keys_partition()?;
+
+    let mut cfg = MaybeUninit::<nvs_sec_cfg_t>::uninit();
+    esp_ok!(nvs_flash_generate_keys(partition, cfg.as_mut_ptr()))?;
+    Ok(Self { cfg: unsafe { cfg.assume_init() } })
+  }
+
+  /// Read the keys from the first `nvs_keys` partition, generating them on first boot.
+  pub fn read_or_generate() -> Result<Self, EspError> {
+    match Self::read() {
+      Err(err) if err.code == ESP_ERR_NVS_KEYS_NOT_INITIALIZED as esp_err_t => Self::generate(),
+      res => res,
+    }
+  }
+
+  fn init(&self, partition_name: &CStr) -> Result<(), EspError> {
+    let mut cfg = self.cfg.clone();
+    esp_ok!(nvs_flash_secure_init_partition(partition_name.as_ptr(), &mut cfg))
+  }
+}
+
+impl NonVolatileStorage {
+  /// Open a non-volatile storage partition encrypted using `keys`.
+  ///
+  /// To encrypt the default partition, this must be called before WiFi is initialized,
+  /// otherwise it fails with `ESP_ERR_INVALID_STATE` since the partition is already
+  /// in use without encryption.
+  pub fn open_encrypted(name: &str, keys: &NvsKeys) -> Result<NonVolatileStorage, EspError> {
+    let partition_name = CString::new(name).map_err(|_| EspError { code: ESP_ERR_NVS_INVALID_NAME as esp_err_t })?;
+
+    if partition_name.as_c_str() != DEFAULT_PART_NAME {
+      Self::init_partition_with(&partition_name, true, |partition_name| keys.init(partition_name))?;
+      return Ok(Self { partition_name })
+    }
+
+    let initialized = Cell::new(false);
+    Self::init_default_with(|partition_name| {
+      initialized.set(true);
+      keys.init(partition_name)
+    })?;
+
+    if initialized.get() {
+      DEFAULT_ENCRYPTED.store(true, Ordering::SeqCst);
+    } else if !DEFAULT_ENCRYPTED.load(Ordering::SeqCst) {
+      Self::deinit_default();
+      return Err(EspError { code: ESP_ERR_INVALID_STATE as esp_err_t })
+    }
+
+    Ok(Self { partition_name })
+  }
+}
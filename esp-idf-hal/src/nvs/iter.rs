@@ -0,0 +1,183 @@
+use std::ffi::{CStr, CString};
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  nvs_close,
+  nvs_entry_find,
+  nvs_entry_info,
+  nvs_entry_info_t,
+  nvs_entry_next,
+  nvs_get_blob,
+  nvs_get_str,
+  nvs_handle_t,
+  nvs_open_from_partition,
+  nvs_open_mode_t,
+  nvs_type_t,
+  ESP_ERR_NVS_INVALID_NAME,
+};
+
+use super::*;
+
+/// The type of a value in non-volatile storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NvsType {
+  U8,
+  I8,
+  U16,
+  I16,
+  U32,
+  I32,
+  U64,
+  I64,
+  Str,
+  Blob,
+}
+
+impl NvsType {
+  fn from_native(ty: nvs_type_t) -> Option<Self> {
+    Some(match ty {
+      nvs_type_t::NVS_TYPE_U8 => Self::U8,
+      nvs_type_t::NVS_TYPE_I8 => Self::I8,
+      nvs_type_t::NVS_TYPE_U16 => Self::U16,
+      nvs_type_t::NVS_TYPE_I16 => Self::I16,
+      nvs_type_t::NVS_TYPE_U32 => Self::U32,
+      nvs_type_t::NVS_TYPE_I32 => Self::I32,
+      nvs_type_t::NVS_TYPE_U64 => Self::U64,
+      nvs_type_t::NVS_TYPE_I64 => Self::I64,
+      nvs_type_t::NVS_TYPE_STR => Self::Str,
+      nvs_type_t::NVS_TYPE_BLOB => Self::Blob,
+      _ => return None,
+    })
+  }
+}
+
+/// An entry in non-volatile storage, returned by [`NonVolatileStorage::entries`](struct.NonVolatileStorage.html#method.entries).
+#[derive(Debug, Clone)]
+pub struct NvsEntry {
+  namespace: String,
+  key: String,
+  ty: NvsType,
+  size: usize,
+}
+
+impl NvsEntry {
+  pub fn namespace(&self) -> &str {
+    &self.namespace
+  }
+
+  pub fn key(&self) -> &str {
+    &self.key
+  }
+
+  pub fn ty(&self) -> NvsType {
+    self.ty
+  }
+
+  /// The size of the value in bytes, including the terminating NUL byte for strings.
+  pub fn size(&self) -> usize {
+    self.size
+  }
+}
+
+/// A read-only handle used to look up the size of strings and blobs.
+struct ReadOnlyHandle {
+  namespace: String,
+  handle: nvs_handle_t,
+}
+
+impl Drop for ReadOnlyHandle {
+  fn drop(&mut self) {
+    unsafe { nvs_close(self.handle) };
+  }
+}
+
+fn string_from_array(array: &[libc::c_char]) -> String {
+  unsafe { CStr::from_ptr(array.as_ptr()) }.to_string_lossy().into_owned()
+}
+
+impl NonVolatileStorage {
+  /// List all entries on this partition, or only those in `namespace` if given.
+  pub fn entries(&self, namespace: Option<&str>) -> Result<Vec<NvsEntry>, EspError> {
+    let namespace = namespace.map(CString::new).transpose()
+      .map_err(|_| EspError { code: ESP_ERR_NVS_INVALID_NAME as esp_err_t })?;
+
+    let mut raw_entries = Vec::new();
+
+    let mut it = unsafe {
+      nvs_entry_find(
+        self.partition_name.as_ptr(),
+        namespace.as_ref().map_or(ptr::null(), |namespace| namespace.as_ptr()),
+        nvs_type_t::NVS_TYPE_ANY,
+      )
+    };
+
+    while !it.is_null() {
+      let mut info = MaybeUninit::<nvs_entry_info_t>::uninit();
+      unsafe { nvs_entry_info(it, info.as_mut_ptr()) };
+      raw_entries.push(unsafe { info.assume_init() });
+
+      // Releases the iterator after the last entry.
+      it = unsafe { nvs_entry_next(it) };
+    }
+
+    let mut handle: Option<ReadOnlyHandle> = None;
+    let mut entries = Vec::with_capacity(raw_entries.len());
+
+    for info in raw_entries {
+      let ty = match NvsType::from_native(info.type_) {
+        Some(ty) => ty,
+        None => continue,
+      };
+
+      let namespace = string_from_array(&info.namespace_name);
+      let key = string_from_array(&info.key);
+
+      let size = match ty {
+        NvsType::U8 | NvsType::I8 => 1,
+        NvsType::U16 | NvsType::I16 => 2,
+        NvsType::U32 | NvsType::I32 => 4,
+        NvsType::U64 | NvsType::I64 => 8,
+        NvsType::Str | NvsType::Blob => {
+          if handle.as_ref().map_or(true, |handle| handle.namespace != namespace) {
+            drop(handle.take());
+
+            let mut raw_handle = MaybeUninit::<nvs_handle_t>::uninit();
+            esp_ok!(nvs_open_from_partition(
+              self.partition_name.as_ptr(),
+              info.namespace_name.as_ptr(),
+              nvs_open_mode_t::NVS_READONLY,
+              raw_handle.as_mut_ptr(),
+            ))?;
+            handle = Some(ReadOnlyHandle { namespace: namespace.clone(), handle: unsafe { raw_handle.assume_init() } });
+          }
+
+          let raw_handle = handle.as_ref().unwrap().handle;
+          let mut len = 0;
+          if ty == NvsType::Str {
+            esp_ok!(nvs_get_str(raw_handle, info.key.as_ptr(), ptr::null_mut(), &mut len))?;
+          } else {
+            esp_ok!(nvs_get_blob(raw_handle, info.key.as_ptr(), ptr::null_mut(), &mut len))?;
+          }
+          len as usize
+        },
+      };
+
+      entries.push(NvsEntry { namespace, key, ty, size });
+    }
+
+    Ok(entries)
+  }
+
+  /// List all namespaces containing at least one entry on this partition.
+  pub fn namespaces(&self) -> Result<Vec<String>, EspError> {
+    let mut namespaces: Vec<String> = Vec::new();
+
+    for entry in self.entries(None)? {
+      if !namespaces.contains(&entry.namespace) {
+        namespaces.push(entry.namespace);
+      }
+    }
+
+    Ok(namespaces)
+  }
+}
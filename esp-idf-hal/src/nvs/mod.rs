@@ -1,7 +1,10 @@
 use core::ptr;
 use core::mem::MaybeUninit;
 
+use std::sync::{Mutex, Once};
 use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(target_device = "esp32")]
+use std::sync::atomic::AtomicBool;
 use std::ffi::CString;
 
 use esp_idf_bindgen::{
@@ -15,7 +18,11 @@ use esp_idf_bindgen::{
   nvs_flash_deinit_partition,
   nvs_open_from_partition,
   nvs_close,
+  nvs_commit,
+  nvs_erase_all,
+  nvs_erase_key,
   NVS_DEFAULT_PART_NAME,
+  ESP_ERR_INVALID_STATE,
   ESP_ERR_NVS_INVALID_NAME,
 };
 
@@ -24,6 +31,27 @@ use super::*;
 mod get_set;
 pub use get_set::*;
 
+mod batch;
+pub use batch::*;
+
+#[cfg(feature = "nvs-serde")]
+mod serde_struct;
+
+#[cfg(target_device = "esp32")]
+mod encryption;
+#[cfg(target_device = "esp32")]
+pub use encryption::*;
+
+#[cfg(target_device = "esp32")]
+mod iter;
+#[cfg(target_device = "esp32")]
+pub use iter::*;
+
+#[cfg(target_device = "esp32")]
+mod stats;
+#[cfg(target_device = "esp32")]
+pub use stats::*;
+
 /// A non-volatile storage partition.
 #[derive(Debug)]
 pub struct NonVolatileStorage {
@@ -31,12 +59,25 @@ pub struct NonVolatileStorage {
 }
 
 /// A namespace on a non-volatile storage partition.
+///
+/// The partition stays initialized while any of its namespaces is open.
 #[derive(Debug)]
 pub struct NameSpace {
   handle: nvs_handle_t,
+  _nvs: NonVolatileStorage,
 }
 
 impl NameSpace {
+  /// Open a namespace on the default non-volatile storage partition.
+  pub fn open(name: &str) -> Result<NameSpace, EspError> {
+    NonVolatileStorage::default_partition()?.namespace(name)
+  }
+
+  /// Open a namespace on the non-volatile storage partition with the given name.
+  pub fn open_from_partition(partition_name: &str, name: &str) -> Result<NameSpace, EspError> {
+    NonVolatileStorage::open(partition_name)?.namespace(name)
+  }
+
   pub fn get<T: NvsGet>(&self, key: &str) -> Result<T, EspError> {
     let key = CString::new(key).map_err(|_| EspError { code: ESP_ERR_NVS_INVALID_NAME as esp_err_t })?;
     T::nvs_get(self, key.as_ref())
@@ -46,6 +87,24 @@ impl NameSpace {
     let key = CString::new(key).map_err(|_| EspError { code: ESP_ERR_NVS_INVALID_NAME as esp_err_t })?;
     value.nvs_set(self, key.as_ref())
   }
+
+  /// Remove the entry with the given key.
+  pub fn remove(&mut self, key: &str) -> Result<(), EspError> {
+    let key = CString::new(key).map_err(|_| EspError { code: ESP_ERR_NVS_INVALID_NAME as esp_err_t })?;
+    esp_ok!(nvs_erase_key(self.handle, key.as_ptr()))
+  }
+
+  /// Remove all entries in this namespace.
+  pub fn clear(&mut self) -> Result<(), EspError> {
+    esp_ok!(nvs_erase_all(self.handle))
+  }
+
+  /// Write all pending changes to flash.
+  ///
+  /// Changes are not guaranteed to be persisted until this is called.
+  pub fn commit(&mut self) -> Result<(), EspError> {
+    esp_ok!(nvs_commit(self.handle))
+  }
 }
 
 impl Drop for NameSpace {
@@ -56,19 +115,44 @@ impl Drop for NameSpace {
 
 const DEFAULT_PART_NAME: &'static CStr = unsafe { CStr::from_bytes_with_nul_unchecked(NVS_DEFAULT_PART_NAME) };
 static DEFAULT_INSTANCES: AtomicUsize = AtomicUsize::new(0);
+#[cfg(target_device = "esp32")]
+static DEFAULT_ENCRYPTED: AtomicBool = AtomicBool::new(false);
+
+/// An initialized partition other than the default partition.
+struct PartitionRef {
+  name: CString,
+  instances: usize,
+  encrypted: bool,
+}
+
+fn partitions() -> &'static Mutex<Vec<PartitionRef>> {
+  static INIT: Once = Once::new();
+  static mut PARTITIONS: Option<Mutex<Vec<PartitionRef>>> = None;
+
+  unsafe {
+    INIT.call_once(|| PARTITIONS = Some(Mutex::new(Vec::new())));
+    PARTITIONS.as_ref().unwrap()
+  }
+}
 
 impl NonVolatileStorage {
-  /// Open a non-volatile storage partition.
+  /// Open a non-volatile storage partition, e.g. a separate partition for factory calibration data.
+  ///
+  /// The partition must be listed in the partition table with type `data` and subtype `nvs`.
   pub fn open(name: &str) -> Result<NonVolatileStorage, EspError> {
     let partition_name = CString::new(name).map_err(|_| EspError { code: ESP_ERR_NVS_INVALID_NAME as esp_err_t })?;
     Self::open_cstring(partition_name)
   }
 
+  fn default_partition() -> Result<NonVolatileStorage, EspError> {
+    Self::open_cstring(DEFAULT_PART_NAME.to_owned())
+  }
+
   fn open_cstring(partition_name: CString) -> Result<NonVolatileStorage, EspError> {
     if partition_name.as_c_str() == DEFAULT_PART_NAME {
       Self::init_default()?;
     } else {
-      Self::init(&partition_name)?;
+      Self::init_partition_with(&partition_name, false, Self::init)?;
     }
 
     Ok(Self { partition_name })
@@ -76,6 +160,17 @@ impl NonVolatileStorage {
 
   /// Open a namespace on a non-volatile storage partition.
   pub fn namespace(&mut self, name: &str) -> Result<NameSpace, EspError> {
+    self.open_namespace(name, nvs_open_mode_t::NVS_READWRITE)
+  }
+
+  /// Open a namespace on a non-volatile storage partition for reading only.
+  ///
+  /// The namespace must already exist. Writing to it fails with `ESP_ERR_NVS_READ_ONLY`.
+  pub fn namespace_read_only(&self, name: &str) -> Result<NameSpace, EspError> {
+    self.open_namespace(name, nvs_open_mode_t::NVS_READONLY)
+  }
+
+  fn open_namespace(&self, name: &str, mode: nvs_open_mode_t) -> Result<NameSpace, EspError> {
     let name = CString::new(name).map_err(|_| EspError { code: ESP_ERR_NVS_INVALID_NAME as esp_err_t })?;
 
     let mut handle = MaybeUninit::<nvs_handle_t>::uninit();
@@ -83,11 +178,25 @@ impl NonVolatileStorage {
     esp_ok!(nvs_open_from_partition(
       self.partition_name.as_ptr(),
       name.as_ptr(),
-      nvs_open_mode_t::NVS_READWRITE,
+      mode,
       handle.as_mut_ptr(),
     ))?;
 
-    Ok(NameSpace { handle: unsafe { handle.assume_init() } })
+    Ok(NameSpace { handle: unsafe { handle.assume_init() }, _nvs: self.add_instance() })
+  }
+
+  /// Create another instance of this already initialized partition.
+  fn add_instance(&self) -> NonVolatileStorage {
+    if self.partition_name.as_c_str() == DEFAULT_PART_NAME {
+      DEFAULT_INSTANCES.fetch_add(1, Ordering::SeqCst);
+    } else {
+      let mut partitions = partitions().lock().unwrap();
+      if let Some(partition) = partitions.iter_mut().find(|partition| partition.name == self.partition_name) {
+        partition.instances += 1;
+      }
+    }
+
+    Self { partition_name: self.partition_name.clone() }
   }
 
   fn init(partition_name: &CStr) -> Result<(), EspError> {
@@ -99,13 +208,18 @@ impl NonVolatileStorage {
   }
 
   pub(crate) fn init_default() -> Result<(), EspError> {
+    Self::init_default_with(Self::init)
+  }
+
+  /// Initialize the default partition using `init` unless it is already initialized.
+  fn init_default_with(init: impl Fn(&CStr) -> Result<(), EspError>) -> Result<(), EspError> {
     loop {
       match DEFAULT_INSTANCES.compare_and_swap(0, 1, Ordering::SeqCst) {
         0 => {
-          let res = match Self::init(DEFAULT_PART_NAME) {
+          let res = match init(DEFAULT_PART_NAME) {
             Err(err) if err.code == ESP_ERR_NVS_NO_FREE_PAGES as esp_err_t || err.code == ESP_ERR_NVS_NEW_VERSION_FOUND as esp_err_t => {
               let _ = Self::erase(DEFAULT_PART_NAME);
-              Self::init(DEFAULT_PART_NAME)
+              init(DEFAULT_PART_NAME)
             },
             res => res,
           };
@@ -130,11 +244,47 @@ impl NonVolatileStorage {
     }
   }
 
+  /// Initialize a partition other than the default partition using `init` unless it is already initialized.
+  ///
+  /// Opening an already initialized unencrypted partition as `encrypted` fails with `ESP_ERR_INVALID_STATE`.
+  fn init_partition_with(partition_name: &CStr, encrypted: bool, init: impl Fn(&CStr) -> Result<(), EspError>) -> Result<(), EspError> {
+    let mut partitions = partitions().lock().unwrap();
+
+    if let Some(partition) = partitions.iter_mut().find(|partition| partition.name.as_c_str() == partition_name) {
+      if encrypted && !partition.encrypted {
+        return Err(EspError { code: ESP_ERR_INVALID_STATE as esp_err_t })
+      }
+
+      partition.instances += 1;
+      return Ok(())
+    }
+
+    init(partition_name)?;
+    partitions.push(PartitionRef { name: partition_name.to_owned(), instances: 1, encrypted });
+    Ok(())
+  }
+
+  /// Deinitialize a partition other than the default partition once it is no longer used.
+  fn deinit_partition(partition_name: &CStr) {
+    let mut partitions = partitions().lock().unwrap();
+
+    if let Some(i) = partitions.iter().position(|partition| partition.name.as_c_str() == partition_name) {
+      partitions[i].instances -= 1;
+
+      if partitions[i].instances == 0 {
+        unsafe { nvs_flash_deinit_partition(partition_name.as_ptr()) };
+        partitions.remove(i);
+      }
+    }
+  }
+
   pub(crate) fn deinit_default() {
     loop {
       match DEFAULT_INSTANCES.compare_and_swap(2, 1, Ordering::SeqCst) {
         2 => {
           unsafe { nvs_flash_deinit_partition(DEFAULT_PART_NAME.as_ptr()) };
+          #[cfg(target_device = "esp32")]
+          DEFAULT_ENCRYPTED.store(false, Ordering::SeqCst);
           DEFAULT_INSTANCES.fetch_sub(1, Ordering::SeqCst);
           return;
         },
@@ -150,7 +300,7 @@ impl NonVolatileStorage {
 
 impl Default for NonVolatileStorage {
   fn default() -> Self {
-    Self::open_cstring(DEFAULT_PART_NAME.to_owned()).expect("failed to initialize default NVS partition")
+    Self::default_partition().expect("failed to initialize default NVS partition")
   }
 }
 
@@ -159,7 +309,7 @@ impl Drop for NonVolatileStorage {
     if self.partition_name.as_c_str() == DEFAULT_PART_NAME {
       Self::deinit_default();
     } else {
-      unsafe { nvs_flash_deinit_partition(self.partition_name.as_ptr()) };
+      Self::deinit_partition(&self.partition_name);
     }
   }
 }
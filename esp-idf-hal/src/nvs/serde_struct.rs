@@ -0,0 +1,51 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  ESP_ERR_INVALID_ARG,
+  ESP_ERR_INVALID_SIZE,
+  ESP_ERR_INVALID_VERSION,
+};
+
+use super::*;
+
+/// Length of the version header preceding the serialized value.
+const VERSION_LEN: usize = 2;
+
+impl NameSpace {
+  /// Serialize `value` using `postcard` and store it as a blob, tagged with `version`.
+  pub fn set_struct<T: Serialize>(&mut self, key: &str, version: u16, value: &T) -> Result<(), EspError> {
+    let mut blob = version.to_le_bytes().to_vec();
+    let bytes = postcard::to_allocvec(value).map_err(|_| EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })?;
+    blob.extend_from_slice(&bytes);
+
+    self.set(key, blob)
+  }
+
+  /// Get the version a value was stored with using [`set_struct`](#method.set_struct).
+  pub fn struct_version(&self, key: &str) -> Result<u16, EspError> {
+    let blob: Vec<u8> = self.get(key)?;
+    version(&blob)
+  }
+
+  /// Load a value stored using [`set_struct`](#method.set_struct).
+  ///
+  /// Fails with `ESP_ERR_INVALID_VERSION` if the value was stored with a different version,
+  /// use [`struct_version`](#method.struct_version) to migrate older values.
+  pub fn get_struct<T: DeserializeOwned>(&self, key: &str, version: u16) -> Result<T, EspError> {
+    let blob: Vec<u8> = self.get(key)?;
+
+    if self::version(&blob)? != version {
+      return Err(EspError { code: ESP_ERR_INVALID_VERSION as esp_err_t })
+    }
+
+    postcard::from_bytes(&blob[VERSION_LEN..]).map_err(|_| EspError { code: ESP_ERR_INVALID_SIZE as esp_err_t })
+  }
+}
+
+fn version(blob: &[u8]) -> Result<u16, EspError> {
+  match blob {
+    [low, high, ..] => Ok(u16::from_le_bytes([*low, *high])),
+    _ => Err(EspError { code: ESP_ERR_INVALID_SIZE as esp_err_t }),
+  }
+}
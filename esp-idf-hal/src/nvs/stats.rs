@@ -0,0 +1,58 @@
+use esp_idf_bindgen::{nvs_get_stats, nvs_get_used_entry_count, nvs_stats_t};
+
+use super::*;
+
+/// Entry usage of a non-volatile storage partition.
+#[derive(Debug, Clone)]
+pub struct NvsStats {
+  used_entries: usize,
+  free_entries: usize,
+  total_entries: usize,
+  namespace_count: usize,
+}
+
+impl NvsStats {
+  pub fn used_entries(&self) -> usize {
+    self.used_entries
+  }
+
+  pub fn free_entries(&self) -> usize {
+    self.free_entries
+  }
+
+  pub fn total_entries(&self) -> usize {
+    self.total_entries
+  }
+
+  /// The number of namespaces on the partition.
+  pub fn namespace_count(&self) -> usize {
+    self.namespace_count
+  }
+}
+
+impl NonVolatileStorage {
+  /// Get the entry usage of this partition.
+  ///
+  /// Entries are 32 bytes each. Strings and blobs occupy multiple entries.
+  pub fn stats(&self) -> Result<NvsStats, EspError> {
+    let mut stats = MaybeUninit::<nvs_stats_t>::uninit();
+    esp_ok!(nvs_get_stats(self.partition_name.as_ptr(), stats.as_mut_ptr()))?;
+    let stats = unsafe { stats.assume_init() };
+
+    Ok(NvsStats {
+      used_entries: stats.used_entries as usize,
+      free_entries: stats.free_entries as usize,
+      total_entries: stats.total_entries as usize,
+      namespace_count: stats.namespace_count as usize,
+    })
+  }
+}
+
+impl NameSpace {
+  /// Get the number of entries used by this namespace.
+  pub fn used_entries(&self) -> Result<usize, EspError> {
+    let mut used_entries = 0;
+    esp_ok!(nvs_get_used_entry_count(self.handle, &mut used_entries))?;
+    Ok(used_entries as usize)
+  }
+}
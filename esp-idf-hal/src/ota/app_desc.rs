@@ -0,0 +1,118 @@
+use core::mem::{size_of, MaybeUninit};
+use core::ptr;
+
+use esp_idf_bindgen::{
+  esp_app_desc_t,
+  esp_image_header_t,
+  esp_image_segment_header_t,
+  esp_ota_get_app_description,
+  esp_ota_get_partition_description,
+  ESP_APP_DESC_MAGIC_WORD,
+  ESP_IMAGE_HEADER_MAGIC,
+};
+use memchr::memchr;
+
+use crate::EspError;
+use crate::partition::Partition;
+
+const APP_DESC_OFFSET: usize = size_of::<esp_image_header_t>() + size_of::<esp_image_segment_header_t>();
+
+/// The length of the app image prefix containing the [`AppDescription`](struct.AppDescription.html).
+pub(super) const IMAGE_HEADER_LEN: usize = APP_DESC_OFFSET + size_of::<esp_app_desc_t>();
+
+/// The description embedded in an app image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppDescription {
+  project_name: String,
+  version: String,
+  idf_version: String,
+  date: String,
+  time: String,
+  elf_sha256: [u8; 32],
+  secure_version: u32,
+}
+
+fn string_from_c_chars(chars: &[libc::c_char]) -> String {
+  let bytes = unsafe { &*(chars as *const [libc::c_char] as *const [u8]) };
+  let len = memchr(0, bytes).unwrap_or(bytes.len());
+  String::from_utf8_lossy(&bytes[..len]).into_owned()
+}
+
+impl AppDescription {
+  fn from_native(desc: &esp_app_desc_t) -> Self {
+    Self {
+      project_name: string_from_c_chars(&desc.project_name),
+      version: string_from_c_chars(&desc.version),
+      idf_version: string_from_c_chars(&desc.idf_ver),
+      date: string_from_c_chars(&desc.date),
+      time: string_from_c_chars(&desc.time),
+      elf_sha256: desc.app_elf_sha256,
+      secure_version: desc.secure_version,
+    }
+  }
+
+  /// The description of the running app.
+  pub fn running() -> Self {
+    Self::from_native(unsafe { &*esp_ota_get_app_description() })
+  }
+
+  /// Parse the description from the beginning of an app image, e.g. while it is being downloaded.
+  ///
+  /// Returns `None` if `image` is shorter than the image header or is not an app image.
+  pub fn from_image(image: &[u8]) -> Option<Self> {
+    if image.len() < IMAGE_HEADER_LEN || image[0] as u32 != ESP_IMAGE_HEADER_MAGIC {
+      return None
+    }
+
+    let desc = unsafe { ptr::read_unaligned(image[APP_DESC_OFFSET..].as_ptr() as *const esp_app_desc_t) };
+    if desc.magic_word != ESP_APP_DESC_MAGIC_WORD {
+      return None
+    }
+
+    Some(Self::from_native(&desc))
+  }
+
+  /// The `PROJECT_NAME` of the app.
+  pub fn project_name(&self) -> &str {
+    &self.project_name
+  }
+
+  /// The app version, e.g. from `git describe` or `PROJECT_VER`.
+  pub fn version(&self) -> &str {
+    &self.version
+  }
+
+  /// The ESP-IDF version the app was built with.
+  pub fn idf_version(&self) -> &str {
+    &self.idf_version
+  }
+
+  /// The compile date.
+  pub fn date(&self) -> &str {
+    &self.date
+  }
+
+  /// The compile time.
+  pub fn time(&self) -> &str {
+    &self.time
+  }
+
+  /// The SHA-256 hash of the app ELF file.
+  pub fn elf_sha256(&self) -> &[u8; 32] {
+    &self.elf_sha256
+  }
+
+  /// The secure version used for anti-rollback.
+  pub fn secure_version(&self) -> u32 {
+    self.secure_version
+  }
+}
+
+impl Partition {
+  /// Read the description of the app image in this app partition.
+  pub fn app_description(&self) -> Result<AppDescription, EspError> {
+    let mut desc = MaybeUninit::<esp_app_desc_t>::uninit();
+    esp_ok!(esp_ota_get_partition_description(self.as_ptr(), desc.as_mut_ptr()))?;
+    Ok(AppDescription::from_native(unsafe { &desc.assume_init() }))
+  }
+}
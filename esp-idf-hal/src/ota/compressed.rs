@@ -0,0 +1,203 @@
+use core::fmt;
+
+use esp_idf_bindgen::{esp_err_t, ESP_ERR_INVALID_SIZE, ESP_ERR_OTA_VALIDATE_FAILED};
+
+use crate::EspError;
+use crate::partition::Partition;
+
+use super::OtaUpdate;
+
+// The ESP32 ROM contains the `tinfl` decompressor of miniz, which has no generated bindings.
+extern "C" {
+  fn tinfl_decompress(
+    r: *mut libc::c_void,
+    in_buf_next: *const u8,
+    in_buf_size: *mut usize,
+    out_buf_start: *mut u8,
+    out_buf_next: *mut u8,
+    out_buf_size: *mut usize,
+    decomp_flags: u32,
+  ) -> i32;
+}
+
+const TINFL_FLAG_PARSE_ZLIB_HEADER: u32 = 1;
+const TINFL_FLAG_HAS_MORE_INPUT: u32 = 2;
+const TINFL_FLAG_COMPUTE_ADLER32: u32 = 8;
+
+const TINFL_STATUS_DONE: i32 = 0;
+const TINFL_STATUS_NEEDS_MORE_INPUT: i32 = 1;
+const TINFL_STATUS_HAS_MORE_OUTPUT: i32 = 2;
+
+/// The size of the output buffer, which must be at least the maximum deflate distance.
+const DICT_SIZE: usize = 32768;
+
+/// At least `sizeof(tinfl_decompressor)`. Zeroing it is equivalent to `tinfl_init`.
+const DECOMPRESSOR_WORDS: usize = 2816;
+
+/// The compression format of an app image, see [`CompressedOtaUpdate`](struct.CompressedOtaUpdate.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+  /// A gzip stream, e.g. created using `gzip firmware.bin`.
+  Gzip,
+  /// A zlib stream.
+  Zlib,
+  /// A raw deflate stream.
+  Deflate,
+}
+
+/// An over-the-air update which decompresses the app image while it is written.
+///
+/// Decompression uses the miniz functions in ROM and a 32 KiB buffer.
+pub struct CompressedOtaUpdate {
+  update: OtaUpdate,
+  compression: Compression,
+  gzip_header: Option<Vec<u8>>,
+  decompressor: Box<[u32]>,
+  dict: Box<[u8]>,
+  dict_pos: usize,
+  done: bool,
+}
+
+impl fmt::Debug for CompressedOtaUpdate {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("CompressedOtaUpdate")
+      .field("update", &self.update)
+      .field("compression", &self.compression)
+      .field("done", &self.done)
+      .finish()
+  }
+}
+
+/// Returns the length of the gzip header at the beginning of `buf`, or `None` if `buf` is not long enough.
+fn gzip_header_len(buf: &[u8]) -> Option<Result<usize, EspError>> {
+  const FHCRC: u8 = 1 << 1;
+  const FEXTRA: u8 = 1 << 2;
+  const FNAME: u8 = 1 << 3;
+  const FCOMMENT: u8 = 1 << 4;
+
+  if buf.len() < 10 {
+    return None
+  }
+
+  if buf[0] != 0x1f || buf[1] != 0x8b || buf[2] != 8 {
+    return Some(Err(EspError { code: ESP_ERR_OTA_VALIDATE_FAILED as esp_err_t }))
+  }
+
+  let flags = buf[3];
+  let mut len = 10;
+
+  if flags & FEXTRA != 0 {
+    let extra = buf.get(len..(len + 2))?;
+    len += 2 + u16::from_le_bytes([extra[0], extra[1]]) as usize;
+  }
+
+  for &flag in &[FNAME, FCOMMENT] {
+    if flags & flag != 0 {
+      len += buf.get(len..)?.iter().position(|&b| b == 0)? + 1;
+    }
+  }
+
+  if flags & FHCRC != 0 {
+    len += 2;
+  }
+
+  if buf.len() < len { None } else { Some(Ok(len)) }
+}
+
+impl CompressedOtaUpdate {
+  /// Begin an update of the next OTA app partition after the currently running one.
+  pub fn begin(compression: Compression) -> Result<Self, EspError> {
+    Ok(Self::new(OtaUpdate::begin()?, compression))
+  }
+
+  /// Begin an update of the given OTA app partition.
+  pub fn begin_partition(partition: Partition, compression: Compression) -> Result<Self, EspError> {
+    Ok(Self::new(OtaUpdate::begin_partition(partition, None)?, compression))
+  }
+
+  fn new(update: OtaUpdate, compression: Compression) -> Self {
+    Self {
+      update,
+      compression,
+      gzip_header: if compression == Compression::Gzip { Some(Vec::new()) } else { None },
+      decompressor: vec![0; DECOMPRESSOR_WORDS].into_boxed_slice(),
+      dict: vec![0; DICT_SIZE].into_boxed_slice(),
+      dict_pos: 0,
+      done: false,
+    }
+  }
+
+  /// The partition this update is written to.
+  pub fn partition(&self) -> &Partition {
+    self.update.partition()
+  }
+
+  /// The number of decompressed bytes written so far.
+  pub fn written(&self) -> usize {
+    self.update.written()
+  }
+
+  /// Decompress and write the next chunk of the compressed app image.
+  pub fn write(&mut self, mut data: &[u8]) -> Result<(), EspError> {
+    if let Some(header) = self.gzip_header.as_mut() {
+      let consumed = header.len();
+      header.extend_from_slice(data);
+
+      match gzip_header_len(header) {
+        None => return Ok(()),
+        Some(Err(err)) => return Err(err),
+        Some(Ok(len)) => {
+          data = &data[(len - consumed)..];
+          self.gzip_header = None;
+        },
+      }
+    }
+
+    let flags = TINFL_FLAG_HAS_MORE_INPUT | match self.compression {
+      Compression::Zlib => TINFL_FLAG_PARSE_ZLIB_HEADER | TINFL_FLAG_COMPUTE_ADLER32,
+      Compression::Gzip | Compression::Deflate => 0,
+    };
+
+    while !self.done {
+      let mut in_size = data.len();
+      let mut out_size = DICT_SIZE - self.dict_pos;
+
+      let status = unsafe {
+        tinfl_decompress(
+          self.decompressor.as_mut_ptr() as *mut _,
+          data.as_ptr(),
+          &mut in_size,
+          self.dict.as_mut_ptr(),
+          self.dict.as_mut_ptr().add(self.dict_pos),
+          &mut out_size,
+          flags,
+        )
+      };
+
+      data = &data[in_size..];
+
+      if out_size > 0 {
+        self.update.write(&self.dict[self.dict_pos..(self.dict_pos + out_size)])?;
+        self.dict_pos = (self.dict_pos + out_size) % DICT_SIZE;
+      }
+
+      match status {
+        TINFL_STATUS_DONE => self.done = true,
+        TINFL_STATUS_NEEDS_MORE_INPUT if data.is_empty() => break,
+        TINFL_STATUS_NEEDS_MORE_INPUT | TINFL_STATUS_HAS_MORE_OUTPUT => continue,
+        _ => return Err(EspError { code: ESP_ERR_OTA_VALIDATE_FAILED as esp_err_t }),
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Validate the decompressed app image and boot from the updated partition after the next restart.
+  pub fn finish(self) -> Result<Partition, EspError> {
+    if !self.done {
+      return Err(EspError { code: ESP_ERR_INVALID_SIZE as esp_err_t })
+    }
+
+    self.update.finish()
+  }
+}
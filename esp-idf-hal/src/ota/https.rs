@@ -0,0 +1,259 @@
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::ptr;
+use std::ffi::CString;
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  esp_http_client_cleanup,
+  esp_http_client_close,
+  esp_http_client_config_t,
+  esp_http_client_fetch_headers,
+  esp_http_client_get_status_code,
+  esp_http_client_handle_t,
+  esp_http_client_init,
+  esp_http_client_is_complete_data_received,
+  esp_http_client_open,
+  esp_http_client_read,
+  esp_http_client_set_header,
+  ESP_ERR_INVALID_ARG,
+  ESP_FAIL,
+};
+
+use crate::EspError;
+use crate::partition::Partition;
+
+use super::{next_update_partition, AppDescription, OtaUpdate, IMAGE_HEADER_LEN};
+
+const MAX_RESUME_ATTEMPTS: usize = 5;
+const BUFFER_SIZE: usize = 1024;
+
+/// TLS options for [`from_url`](fn.from_url.html).
+#[derive(Debug, Clone, Default)]
+pub struct TlsConfig {
+  ca_cert_pem: Option<CString>,
+  use_global_ca_store: bool,
+  skip_common_name_check: bool,
+}
+
+impl TlsConfig {
+  /// Create a TLS configuration without any trusted certificates.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Trust the given PEM encoded CA certificate.
+  ///
+  /// Fails with `ESP_ERR_INVALID_ARG` if the certificate contains a NUL byte.
+  pub fn with_ca_cert_pem(mut self, ca_cert_pem: &str) -> Result<Self, EspError> {
+    let ca_cert_pem = CString::new(ca_cert_pem).map_err(|_| EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })?;
+    self.ca_cert_pem = Some(ca_cert_pem);
+    Ok(self)
+  }
+
+  /// Trust the certificates in the global CA store.
+  pub fn with_global_ca_store(mut self, use_global_ca_store: bool) -> Self {
+    self.use_global_ca_store = use_global_ca_store;
+    self
+  }
+
+  /// Do not check whether the common name of the server certificate matches the host name.
+  pub fn with_skip_common_name_check(mut self, skip_common_name_check: bool) -> Self {
+    self.skip_common_name_check = skip_common_name_check;
+    self
+  }
+}
+
+/// An error returned by [`from_url`](fn.from_url.html).
+#[derive(Debug, Clone)]
+pub enum OtaError {
+  /// An internal error, e.g. a connection or flash error.
+  Internal(EspError),
+  /// The server responded with an unexpected HTTP status code.
+  HttpStatus(i32),
+  /// The downloaded data is not an app image.
+  InvalidImage,
+}
+
+impl From<EspError> for OtaError {
+  fn from(esp_error: EspError) -> Self {
+    Self::Internal(esp_error)
+  }
+}
+
+impl fmt::Display for OtaError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Internal(esp_error) => esp_error.fmt(f),
+      Self::HttpStatus(status) => write!(f, "Unexpected HTTP status {}", status),
+      Self::InvalidImage => write!(f, "Invalid app image"),
+    }
+  }
+}
+
+struct Client {
+  handle: esp_http_client_handle_t,
+  open: bool,
+}
+
+impl Client {
+  fn new(url: &CString, tls_config: &TlsConfig) -> Result<Self, EspError> {
+    let mut config: esp_http_client_config_t = unsafe { MaybeUninit::zeroed().assume_init() };
+    config.url = url.as_ptr();
+    config.cert_pem = tls_config.ca_cert_pem.as_ref().map_or(ptr::null(), |cert| cert.as_ptr());
+    config.use_global_ca_store = tls_config.use_global_ca_store;
+    config.skip_cert_common_name_check = tls_config.skip_common_name_check;
+    config.buffer_size = BUFFER_SIZE as i32;
+
+    let handle = unsafe { esp_http_client_init(&config) };
+    if handle.is_null() {
+      return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+    }
+
+    Ok(Self { handle, open: false })
+  }
+
+  /// Request the resource starting at `offset`. Returns the HTTP status and content length.
+  fn open(&mut self, offset: usize) -> Result<(i32, Option<usize>), EspError> {
+    if offset > 0 {
+      let range = CString::new(format!("bytes={}-", offset)).unwrap();
+      esp_ok!(esp_http_client_set_header(self.handle, b"Range\0".as_ptr() as *const _, range.as_ptr()))?;
+    }
+
+    esp_ok!(esp_http_client_open(self.handle, 0))?;
+    self.open = true;
+
+    let content_length = unsafe { esp_http_client_fetch_headers(self.handle) };
+    if content_length < 0 {
+      return Err(EspError { code: ESP_FAIL as esp_err_t })
+    }
+
+    let status = unsafe { esp_http_client_get_status_code(self.handle) };
+    Ok((status, if content_length > 0 { Some(content_length as usize) } else { None }))
+  }
+
+  /// Read the next chunk of the response. Returns `None` on a connection error.
+  fn read(&mut self, buf: &mut [u8]) -> Option<usize> {
+    let len = unsafe { esp_http_client_read(self.handle, buf.as_mut_ptr() as *mut _, buf.len() as i32) };
+    if len < 0 { None } else { Some(len as usize) }
+  }
+
+  fn is_complete(&self) -> bool {
+    unsafe { esp_http_client_is_complete_data_received(self.handle) }
+  }
+
+  fn close(&mut self) {
+    if self.open {
+      unsafe { esp_http_client_close(self.handle) };
+      self.open = false;
+    }
+  }
+}
+
+impl Drop for Client {
+  fn drop(&mut self) {
+    self.close();
+    unsafe { esp_http_client_cleanup(self.handle) };
+  }
+}
+
+/// Download an app image from `url` and write it to the next OTA app partition, which is
+/// booted after the next restart.
+///
+/// `progress` is called with the number of bytes written and the total image size, if known.
+/// If the connection is lost, the download is resumed using a range request, or restarted if
+/// the server does not support range requests. The image header is validated before the
+/// partition is erased.
+pub fn from_url<F>(url: &str, tls_config: &TlsConfig, mut progress: F) -> Result<Partition, OtaError>
+where
+  F: FnMut(usize, Option<usize>),
+{
+  let url = CString::new(url).map_err(|_| EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })?;
+  let mut client = Client::new(&url, tls_config)?;
+
+  let mut header = Vec::with_capacity(IMAGE_HEADER_LEN);
+  let mut update: Option<OtaUpdate> = None;
+  let mut total = None;
+  let mut buf = vec![0; BUFFER_SIZE];
+  let mut attempts = 0;
+
+  loop {
+    let mut offset = update.as_ref().map_or(header.len(), |update| update.written());
+
+    let (status, content_length) = match client.open(offset) {
+      Ok(response) => response,
+      Err(err) => {
+        client.close();
+        attempts += 1;
+        if attempts > MAX_RESUME_ATTEMPTS {
+          return Err(err.into())
+        }
+        continue
+      },
+    };
+
+    match (offset, status) {
+      (0, 200) | (_, 206) => (),
+      (_, 200) => {
+        // The server ignored the range request and sent the whole image, so start over.
+        update = None;
+        header.clear();
+        total = None;
+        offset = 0;
+      },
+      (_, status) => return Err(OtaError::HttpStatus(status)),
+    }
+
+    if total.is_none() {
+      total = content_length.map(|len| offset + len);
+    }
+
+    loop {
+      let len = match client.read(&mut buf) {
+        Some(0) | None => break,
+        Some(len) => len,
+      };
+
+      let mut data = &buf[..len];
+
+      if update.is_none() {
+        let missing = IMAGE_HEADER_LEN - header.len();
+        let (head, rest) = data.split_at(missing.min(data.len()));
+        header.extend_from_slice(head);
+        data = rest;
+
+        if header.len() < IMAGE_HEADER_LEN {
+          continue
+        }
+
+        AppDescription::from_image(&header).ok_or(OtaError::InvalidImage)?;
+
+        let mut new_update = OtaUpdate::begin_partition(next_update_partition()?, total)?;
+        new_update.write(&header)?;
+        update = Some(new_update);
+      }
+
+      if let Some(update) = update.as_mut() {
+        update.write(data)?;
+        progress(update.written(), total);
+      }
+    }
+
+    let complete = client.is_complete();
+    client.close();
+
+    if complete {
+      break
+    }
+
+    attempts += 1;
+    if attempts > MAX_RESUME_ATTEMPTS {
+      return Err(EspError { code: ESP_FAIL as esp_err_t }.into())
+    }
+  }
+
+  match update {
+    Some(update) => Ok(update.finish()?),
+    None => Err(OtaError::InvalidImage),
+  }
+}
@@ -0,0 +1,119 @@
+use core::ptr;
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  esp_ota_begin,
+  esp_ota_end,
+  esp_ota_get_next_update_partition,
+  esp_ota_get_running_partition,
+  esp_ota_handle_t,
+  esp_ota_set_boot_partition,
+  esp_ota_write,
+  ESP_ERR_NOT_FOUND,
+  OTA_SIZE_UNKNOWN,
+};
+
+use crate::EspError;
+use crate::partition::Partition;
+
+#[cfg(target_device = "esp32")]
+mod app_desc;
+#[cfg(target_device = "esp32")]
+pub use app_desc::*;
+
+#[cfg(target_device = "esp32")]
+mod compressed;
+#[cfg(target_device = "esp32")]
+pub use compressed::*;
+
+#[cfg(target_device = "esp32")]
+mod https;
+#[cfg(target_device = "esp32")]
+pub use https::*;
+
+#[cfg(target_device = "esp32")]
+mod rollback;
+#[cfg(target_device = "esp32")]
+pub use rollback::*;
+
+#[cfg(target_device = "esp32")]
+mod slots;
+#[cfg(target_device = "esp32")]
+pub use slots::*;
+
+/// The app partition the running app was loaded from.
+pub fn running_partition() -> Result<Partition, EspError> {
+  Partition::from_ptr(unsafe { esp_ota_get_running_partition() })
+    .ok_or(EspError { code: ESP_ERR_NOT_FOUND as esp_err_t })
+}
+
+fn next_update_partition() -> Result<Partition, EspError> {
+  Partition::from_ptr(unsafe { esp_ota_get_next_update_partition(ptr::null()) })
+    .ok_or(EspError { code: ESP_ERR_NOT_FOUND as esp_err_t })
+}
+
+/// An over-the-air update which writes a new app image to an OTA app partition.
+///
+/// The update is aborted if it is dropped before calling [`finish`](#method.finish).
+#[derive(Debug)]
+pub struct OtaUpdate {
+  handle: esp_ota_handle_t,
+  partition: Partition,
+  written: usize,
+  finished: bool,
+}
+
+impl OtaUpdate {
+  /// Begin an update of the next OTA app partition after the currently running one.
+  ///
+  /// The whole partition is erased first.
+  pub fn begin() -> Result<Self, EspError> {
+    Self::begin_partition(next_update_partition()?, None)
+  }
+
+  /// Begin an update of the given OTA app partition.
+  ///
+  /// If `image_size` is known, only the required part of the partition is erased.
+  pub fn begin_partition(partition: Partition, image_size: Option<usize>) -> Result<Self, EspError> {
+    let image_size = image_size.map_or(OTA_SIZE_UNKNOWN as usize, |image_size| image_size);
+
+    let mut handle: esp_ota_handle_t = 0;
+    esp_ok!(esp_ota_begin(partition.as_ptr(), image_size as _, &mut handle))?;
+
+    Ok(Self { handle, partition, written: 0, finished: false })
+  }
+
+  /// The partition this update is written to.
+  pub fn partition(&self) -> &Partition {
+    &self.partition
+  }
+
+  /// The number of bytes written so far.
+  pub fn written(&self) -> usize {
+    self.written
+  }
+
+  /// Write the next chunk of the app image.
+  pub fn write(&mut self, data: &[u8]) -> Result<(), EspError> {
+    esp_ok!(esp_ota_write(self.handle, data.as_ptr() as *const _, data.len() as _))?;
+    self.written += data.len();
+    Ok(())
+  }
+
+  /// Validate the written app image and boot from the updated partition after the next restart.
+  pub fn finish(mut self) -> Result<Partition, EspError> {
+    self.finished = true;
+    esp_ok!(esp_ota_end(self.handle))?;
+    esp_ok!(esp_ota_set_boot_partition(self.partition.as_ptr()))?;
+    Ok(self.partition)
+  }
+}
+
+impl Drop for OtaUpdate {
+  fn drop(&mut self) {
+    if !self.finished {
+      // There is no `esp_ota_abort` in ESP-IDF v4.2, so release the handle by ending the update.
+      let _ = esp_ok!(esp_ota_end(self.handle));
+    }
+  }
+}
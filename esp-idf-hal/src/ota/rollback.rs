@@ -0,0 +1,83 @@
+use esp_idf_bindgen::{
+  esp_ota_check_rollback_is_possible,
+  esp_ota_get_last_invalid_partition,
+  esp_ota_get_state_partition,
+  esp_ota_img_states_t,
+  esp_ota_mark_app_invalid_rollback_and_reboot,
+  esp_ota_mark_app_valid_cancel_rollback,
+};
+
+use crate::EspError;
+use crate::partition::Partition;
+
+use super::running_partition;
+
+/// The state of an app image in an OTA partition.
+///
+/// States are only tracked if `CONFIG_BOOTLOADER_APP_ROLLBACK_ENABLE` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtaState {
+  /// The image was written but has not been booted yet.
+  New,
+  /// The image was booted once and must be marked as valid or invalid.
+  PendingVerify,
+  /// The image was marked as valid.
+  Valid,
+  /// The image was marked as invalid and will not be booted.
+  Invalid,
+  /// The image was not marked as valid before the next restart and will not be booted.
+  Aborted,
+  /// The image is not tracked.
+  Undefined,
+}
+
+impl From<esp_ota_img_states_t> for OtaState {
+  fn from(state: esp_ota_img_states_t) -> Self {
+    match state {
+      esp_ota_img_states_t::ESP_OTA_IMG_NEW => Self::New,
+      esp_ota_img_states_t::ESP_OTA_IMG_PENDING_VERIFY => Self::PendingVerify,
+      esp_ota_img_states_t::ESP_OTA_IMG_VALID => Self::Valid,
+      esp_ota_img_states_t::ESP_OTA_IMG_INVALID => Self::Invalid,
+      esp_ota_img_states_t::ESP_OTA_IMG_ABORTED => Self::Aborted,
+      esp_ota_img_states_t::ESP_OTA_IMG_UNDEFINED => Self::Undefined,
+    }
+  }
+}
+
+impl Partition {
+  /// Get the state of the app image in this OTA partition.
+  pub fn ota_state(&self) -> Result<OtaState, EspError> {
+    let mut state = esp_ota_img_states_t::ESP_OTA_IMG_UNDEFINED;
+    esp_ok!(esp_ota_get_state_partition(self.as_ptr(), &mut state))?;
+    Ok(state.into())
+  }
+}
+
+/// Whether the running app was just updated and must be marked as valid, otherwise
+/// the previous app is booted after the next restart.
+pub fn is_pending_verify() -> Result<bool, EspError> {
+  Ok(running_partition()?.ota_state()? == OtaState::PendingVerify)
+}
+
+/// Mark the running app as valid, e.g. once the network is up, and cancel the rollback.
+pub fn mark_app_valid() -> Result<(), EspError> {
+  esp_ok!(esp_ota_mark_app_valid_cancel_rollback())
+}
+
+/// Mark the running app as invalid and restart into the previous app.
+///
+/// Only returns if there is no previous app to roll back to.
+pub fn mark_app_invalid_and_rollback() -> Result<!, EspError> {
+  esp_ok!(esp_ota_mark_app_invalid_rollback_and_reboot())?;
+  unreachable!()
+}
+
+/// Whether there is a valid app to roll back to.
+pub fn is_rollback_possible() -> bool {
+  unsafe { esp_ota_check_rollback_is_possible() }
+}
+
+/// The partition of the app which was last marked as invalid or aborted.
+pub fn last_invalid_partition() -> Option<Partition> {
+  Partition::from_ptr(unsafe { esp_ota_get_last_invalid_partition() })
+}
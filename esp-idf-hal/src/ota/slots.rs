@@ -0,0 +1,72 @@
+use esp_idf_bindgen::{esp_ota_get_boot_partition, esp_ota_set_boot_partition};
+
+use crate::EspError;
+use crate::partition::{Partition, PartitionType};
+
+use super::{running_partition, OtaState};
+
+const SUBTYPE_FACTORY: u8 = 0x00;
+const SUBTYPE_OTA_MIN: u8 = 0x10;
+const SUBTYPE_OTA_MAX: u8 = 0x1f;
+
+/// An app partition which can be booted, see [`slots`](fn.slots.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OtaSlot {
+  partition: Partition,
+  state: Option<OtaState>,
+  running: bool,
+  boot: bool,
+}
+
+impl OtaSlot {
+  pub fn partition(&self) -> &Partition {
+    &self.partition
+  }
+
+  /// Whether this is the factory app partition rather than an OTA app partition.
+  pub fn is_factory(&self) -> bool {
+    self.partition.subtype() == SUBTYPE_FACTORY
+  }
+
+  /// The state of the app image, or `None` for the factory partition or an empty OTA partition.
+  pub fn state(&self) -> Option<OtaState> {
+    self.state
+  }
+
+  /// Whether the running app was loaded from this partition.
+  pub fn is_running(&self) -> bool {
+    self.running
+  }
+
+  /// Whether this partition is booted after the next restart.
+  pub fn is_boot(&self) -> bool {
+    self.boot
+  }
+}
+
+/// List the factory and OTA app partitions.
+pub fn slots() -> Result<Vec<OtaSlot>, EspError> {
+  let running = running_partition()?;
+  let boot = Partition::from_ptr(unsafe { esp_ota_get_boot_partition() });
+
+  Ok(Partition::iter()
+    .filter(|partition| partition.partition_type() == PartitionType::App)
+    .filter(|partition| {
+      let subtype = partition.subtype();
+      subtype == SUBTYPE_FACTORY || (SUBTYPE_OTA_MIN..=SUBTYPE_OTA_MAX).contains(&subtype)
+    })
+    .map(|partition| OtaSlot {
+      partition,
+      state: partition.ota_state().ok(),
+      running: partition == running,
+      boot: Some(partition) == boot,
+    })
+    .collect())
+}
+
+/// Boot from `slot` after the next restart.
+///
+/// The app image in the partition is verified first.
+pub fn set_boot(slot: &OtaSlot) -> Result<(), EspError> {
+  esp_ok!(esp_ota_set_boot_partition(slot.partition.as_ptr()))
+}
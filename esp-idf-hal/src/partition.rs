@@ -0,0 +1,298 @@
+use core::fmt;
+#[cfg(target_device = "esp32")]
+use core::ops::Deref;
+use core::ptr;
+#[cfg(target_device = "esp32")]
+use core::slice;
+use std::ffi::CStr;
+use std::str;
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  esp_partition_erase_range,
+  esp_partition_get,
+  esp_partition_iterator_release,
+  esp_partition_iterator_t,
+  esp_partition_next,
+  esp_partition_read,
+  esp_partition_t,
+  esp_partition_write,
+  ESP_ERR_INVALID_ARG,
+  ESP_ERR_INVALID_SIZE,
+};
+#[cfg(target_device = "esp32")]
+use esp_idf_bindgen::{
+  esp_partition_mmap,
+  esp_partition_mmap_memory_t,
+  spi_flash_mmap_handle_t,
+  spi_flash_munmap,
+};
+
+use crate::EspError;
+use crate::fs::c_string;
+
+// Custom types and arbitrary subtypes are not variants of `esp_partition_type_t` and `esp_partition_subtype_t`,
+// so pass them as raw integers.
+extern "C" {
+  #[link_name = "esp_partition_find_first"]
+  fn esp_partition_find_first_raw(type_: u32, subtype: u32, label: *const libc::c_char) -> *const esp_partition_t;
+  #[link_name = "esp_partition_find"]
+  fn esp_partition_find_raw(type_: u32, subtype: u32, label: *const libc::c_char) -> esp_partition_iterator_t;
+}
+
+const PARTITION_TYPE_ANY: u8 = 0xff;
+const PARTITION_SUBTYPE_ANY: u8 = 0xff;
+
+/// The sector size of the SPI flash. Erased ranges must be aligned to this.
+pub const SECTOR_SIZE: usize = 4096;
+
+/// The type of a [`Partition`](struct.Partition.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionType {
+  App,
+  Data,
+  /// A custom partition type in the range `0x40` to `0xfe`.
+  Custom(u8),
+}
+
+impl From<u8> for PartitionType {
+  fn from(value: u8) -> Self {
+    match value {
+      0x00 => Self::App,
+      0x01 => Self::Data,
+      value => Self::Custom(value),
+    }
+  }
+}
+
+impl From<PartitionType> for u8 {
+  fn from(partition_type: PartitionType) -> Self {
+    match partition_type {
+      PartitionType::App => 0x00,
+      PartitionType::Data => 0x01,
+      PartitionType::Custom(value) => value,
+    }
+  }
+}
+
+/// A partition listed in the partition table.
+#[derive(Clone, Copy)]
+pub struct Partition {
+  ptr: *const esp_partition_t,
+}
+
+// Partition table entries are never freed and only accessed read-only.
+unsafe impl Send for Partition {}
+unsafe impl Sync for Partition {}
+
+impl Partition {
+  /// Find the first partition with the given type.
+  ///
+  /// If `subtype` is `None` or `label` is `None`, partitions with any subtype or label match.
+  pub fn find(partition_type: PartitionType, subtype: Option<u8>, label: Option<&str>) -> Result<Option<Self>, EspError> {
+    let label = label.map(c_string).transpose()?;
+
+    let ptr = unsafe {
+      esp_partition_find_first_raw(
+        u8::from(partition_type) as u32,
+        subtype.unwrap_or(PARTITION_SUBTYPE_ANY) as u32,
+        label.as_ref().map_or(ptr::null(), |label| label.as_ptr()),
+      )
+    };
+
+    Ok(Self::from_ptr(ptr))
+  }
+
+  /// Iterate over all partitions, including ones with custom types, in partition table order.
+  pub fn iter() -> PartitionIter {
+    let iterator = unsafe {
+      esp_partition_find_raw(PARTITION_TYPE_ANY as u32, PARTITION_SUBTYPE_ANY as u32, ptr::null())
+    };
+
+    PartitionIter { iterator, started: false }
+  }
+
+  pub(crate) fn from_ptr(ptr: *const esp_partition_t) -> Option<Self> {
+    if ptr.is_null() {
+      None
+    } else {
+      Some(Self { ptr })
+    }
+  }
+
+  pub(crate) fn as_ptr(&self) -> *const esp_partition_t {
+    self.ptr
+  }
+
+  fn raw(&self) -> &esp_partition_t {
+    unsafe { &*self.ptr }
+  }
+
+  pub fn partition_type(&self) -> PartitionType {
+    // Read the raw value, since custom types are not variants of `esp_partition_type_t`.
+    let value = unsafe { ptr::read(&self.raw().type_ as *const _ as *const u32) };
+    PartitionType::from(value as u8)
+  }
+
+  pub fn subtype(&self) -> u8 {
+    let value = unsafe { ptr::read(&self.raw().subtype as *const _ as *const u32) };
+    value as u8
+  }
+
+  pub fn label(&self) -> &str {
+    let label = unsafe { CStr::from_ptr(self.raw().label.as_ptr()) };
+    str::from_utf8(label.to_bytes()).unwrap_or("")
+  }
+
+  /// The flash address of this partition.
+  pub fn address(&self) -> u32 {
+    self.raw().address
+  }
+
+  /// The size of this partition in bytes.
+  pub fn size(&self) -> usize {
+    self.raw().size as usize
+  }
+
+  /// Whether this partition is encrypted using flash encryption.
+  pub fn is_encrypted(&self) -> bool {
+    self.raw().encrypted
+  }
+
+  fn check_bounds(&self, offset: usize, len: usize) -> Result<(), EspError> {
+    match offset.checked_add(len) {
+      Some(end) if end <= self.size() => Ok(()),
+      _ => Err(EspError { code: ESP_ERR_INVALID_SIZE as esp_err_t }),
+    }
+  }
+
+  /// Read `buf.len()` bytes starting at `offset`.
+  pub fn read(&self, offset: usize, buf: &mut [u8]) -> Result<(), EspError> {
+    self.check_bounds(offset, buf.len())?;
+    esp_ok!(esp_partition_read(self.ptr, offset as _, buf.as_mut_ptr() as *mut _, buf.len() as _))
+  }
+
+  /// Write `data` starting at `offset`. The range must be erased first.
+  pub fn write(&self, offset: usize, data: &[u8]) -> Result<(), EspError> {
+    self.check_bounds(offset, data.len())?;
+    esp_ok!(esp_partition_write(self.ptr, offset as _, data.as_ptr() as *const _, data.len() as _))
+  }
+
+  /// Erase `len` bytes starting at `offset`. Both must be multiples of [`SECTOR_SIZE`](constant.SECTOR_SIZE.html).
+  pub fn erase_range(&self, offset: usize, len: usize) -> Result<(), EspError> {
+    self.check_bounds(offset, len)?;
+
+    if offset % SECTOR_SIZE != 0 || len % SECTOR_SIZE != 0 {
+      return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+    }
+
+    esp_ok!(esp_partition_erase_range(self.ptr, offset as _, len as _))
+  }
+
+  /// Map `len` bytes starting at `offset` into the data address space, so they can be read
+  /// directly without copying.
+  ///
+  /// The mapping is read-only and reflects the flash contents at the time of the call.
+  #[cfg(target_device = "esp32")]
+  pub fn mmap(&self, offset: usize, len: usize) -> Result<PartitionMmap, EspError> {
+    self.check_bounds(offset, len)?;
+
+    let mut data = ptr::null();
+    let mut handle: spi_flash_mmap_handle_t = 0;
+    esp_ok!(esp_partition_mmap(
+      self.ptr,
+      offset as _,
+      len as _,
+      esp_partition_mmap_memory_t::ESP_PARTITION_MMAP_DATA,
+      &mut data,
+      &mut handle,
+    ))?;
+
+    Ok(PartitionMmap { data: data as *const u8, len, handle })
+  }
+}
+
+impl PartialEq for Partition {
+  fn eq(&self, other: &Self) -> bool {
+    self.ptr == other.ptr
+  }
+}
+
+impl Eq for Partition {}
+
+impl fmt::Debug for Partition {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Partition")
+      .field("type", &self.partition_type())
+      .field("subtype", &self.subtype())
+      .field("label", &self.label())
+      .field("address", &self.address())
+      .field("size", &self.size())
+      .finish()
+  }
+}
+
+/// An iterator over partitions, see [`Partition::iter`](struct.Partition.html#method.iter).
+#[derive(Debug)]
+pub struct PartitionIter {
+  iterator: esp_partition_iterator_t,
+  started: bool,
+}
+
+impl Iterator for PartitionIter {
+  type Item = Partition;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.iterator.is_null() {
+      return None
+    }
+
+    if self.started {
+      // The iterator is released once there is no next partition.
+      self.iterator = unsafe { esp_partition_next(self.iterator) };
+
+      if self.iterator.is_null() {
+        return None
+      }
+    }
+
+    self.started = true;
+    Partition::from_ptr(unsafe { esp_partition_get(self.iterator) })
+  }
+}
+
+impl Drop for PartitionIter {
+  fn drop(&mut self) {
+    if !self.iterator.is_null() {
+      unsafe { esp_partition_iterator_release(self.iterator) };
+    }
+  }
+}
+
+/// A memory-mapped range of a [`Partition`](struct.Partition.html), unmapped when dropped.
+#[cfg(target_device = "esp32")]
+#[derive(Debug)]
+pub struct PartitionMmap {
+  data: *const u8,
+  len: usize,
+  handle: spi_flash_mmap_handle_t,
+}
+
+#[cfg(target_device = "esp32")]
+unsafe impl Send for PartitionMmap {}
+
+#[cfg(target_device = "esp32")]
+impl Deref for PartitionMmap {
+  type Target = [u8];
+
+  fn deref(&self) -> &Self::Target {
+    unsafe { slice::from_raw_parts(self.data, self.len) }
+  }
+}
+
+#[cfg(target_device = "esp32")]
+impl Drop for PartitionMmap {
+  fn drop(&mut self) {
+    unsafe { spi_flash_munmap(self.handle) };
+  }
+}
@@ -0,0 +1,483 @@
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::ptr;
+use std::ffi::CString;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering::SeqCst};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use esp_idf_bindgen::{
+  _g_esp_netif_inherent_ppp_config,
+  _g_esp_netif_netstack_default_ppp,
+  esp_err_t,
+  esp_event_base_t,
+  esp_netif_action_connected,
+  esp_netif_action_start,
+  esp_netif_action_stop,
+  esp_netif_attach,
+  esp_netif_auth_type_t,
+  esp_netif_config_t,
+  esp_netif_destroy,
+  esp_netif_driver_base_t,
+  esp_netif_driver_ifconfig_t,
+  esp_netif_get_ip_info,
+  esp_netif_ip_info_t,
+  esp_netif_is_netif_up,
+  esp_netif_new,
+  esp_netif_ppp_set_auth,
+  esp_netif_receive,
+  esp_netif_set_driver_config,
+  esp_netif_t,
+  ip_event_got_ip_t,
+  ip_event_t,
+  uart_config_t,
+  uart_driver_delete,
+  uart_driver_install,
+  uart_hw_flowcontrol_t,
+  uart_param_config,
+  uart_parity_t,
+  uart_read_bytes,
+  uart_set_pin,
+  uart_stop_bits_t,
+  uart_word_length_t,
+  uart_write_bytes,
+  CONFIG_FREERTOS_HZ,
+  ESP_ERR_INVALID_ARG,
+  ESP_ERR_INVALID_STATE,
+  ESP_ERR_TIMEOUT,
+  ESP_FAIL,
+  IP_EVENT,
+  NETIF_PPP_STATUS,
+  NETIF_PP_PHASE_OFFSET,
+};
+
+use crate::EspError;
+use crate::event::{EventReceiver, Subscription};
+use crate::interface::IpInfo;
+use crate::wifi::initialize_network_interface;
+
+static PPP_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+const UART_BUFFER_SIZE: usize = 2048;
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+const DIAL_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn ticks(duration: Duration) -> u32 {
+  (duration.as_millis() as u32 * CONFIG_FREERTOS_HZ / 1000).max(1)
+}
+
+/// Configuration for a PPP connection over a UART.
+#[derive(Clone)]
+pub struct PppConfig {
+  uart: u8,
+  tx_pin: u8,
+  rx_pin: u8,
+  baud_rate: u32,
+  apn: Option<String>,
+  credentials: Option<(String, String)>,
+}
+
+impl fmt::Debug for PppConfig {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("PppConfig")
+      .field("uart", &self.uart)
+      .field("tx_pin", &self.tx_pin)
+      .field("rx_pin", &self.rx_pin)
+      .field("baud_rate", &self.baud_rate)
+      .field("apn", &self.apn)
+      .field("credentials", &self.credentials.as_ref().map(|(username, _)| (username, "********")))
+      .finish()
+  }
+}
+
+impl PppConfig {
+  /// The UART port connected to the modem.
+  pub fn uart(&self) -> u8 {
+    self.uart
+  }
+
+  pub fn tx_pin(&self) -> u8 {
+    self.tx_pin
+  }
+
+  pub fn rx_pin(&self) -> u8 {
+    self.rx_pin
+  }
+
+  pub fn baud_rate(&self) -> u32 {
+    self.baud_rate
+  }
+
+  /// The access point name used to dial the modem.
+  pub fn apn(&self) -> Option<&str> {
+    self.apn.as_deref()
+  }
+
+  /// The username used for PAP authentication.
+  pub fn username(&self) -> Option<&str> {
+    self.credentials.as_ref().map(|(username, _)| username.as_str())
+  }
+
+  pub fn builder() -> PppConfigBuilder {
+    PppConfigBuilder::default()
+  }
+}
+
+/// Builder for [`PppConfig`](struct.PppConfig.html).
+#[derive(Debug, Clone)]
+pub struct PppConfigBuilder {
+  uart: u8,
+  tx_pin: u8,
+  rx_pin: u8,
+  baud_rate: u32,
+  apn: Option<String>,
+  credentials: Option<(String, String)>,
+}
+
+impl Default for PppConfigBuilder {
+  fn default() -> Self {
+    Self {
+      uart: 1,
+      tx_pin: 25,
+      rx_pin: 26,
+      baud_rate: 115_200,
+      apn: None,
+      credentials: None,
+    }
+  }
+}
+
+impl PppConfigBuilder {
+  /// Set the UART port connected to the modem. Defaults to `1`.
+  pub fn uart(&mut self, uart: u8) -> &mut Self {
+    assert!(uart <= 2, "invalid UART port {}", uart);
+    self.uart = uart;
+    self
+  }
+
+  /// Set the pin connected to the RX input of the modem. Defaults to GPIO 25.
+  pub fn tx_pin(&mut self, tx_pin: u8) -> &mut Self {
+    assert!(tx_pin <= 33, "invalid TX pin {}", tx_pin);
+    self.tx_pin = tx_pin;
+    self
+  }
+
+  /// Set the pin connected to the TX output of the modem. Defaults to GPIO 26.
+  pub fn rx_pin(&mut self, rx_pin: u8) -> &mut Self {
+    assert!(rx_pin <= 39, "invalid RX pin {}", rx_pin);
+    self.rx_pin = rx_pin;
+    self
+  }
+
+  /// Set the baud rate. Defaults to 115200.
+  pub fn baud_rate(&mut self, baud_rate: u32) -> &mut Self {
+    self.baud_rate = baud_rate;
+    self
+  }
+
+  /// Set the access point name and dial the modem when connecting.
+  ///
+  /// If no APN is set, the modem must already be in data mode.
+  pub fn apn(&mut self, apn: impl Into<String>) -> &mut Self {
+    let apn = apn.into();
+    assert!(!apn.contains('"'), "APN must not contain quotes");
+    self.apn = Some(apn);
+    self
+  }
+
+  /// Authenticate using PAP with the given username and password.
+  pub fn credentials(&mut self, username: impl Into<String>, password: impl Into<String>) -> &mut Self {
+    self.credentials = Some((username.into(), password.into()));
+    self
+  }
+
+  pub fn build(&self) -> PppConfig {
+    PppConfig {
+      uart: self.uart,
+      tx_pin: self.tx_pin,
+      rx_pin: self.rx_pin,
+      baud_rate: self.baud_rate,
+      apn: self.apn.clone(),
+      credentials: self.credentials.clone(),
+    }
+  }
+}
+
+/// The IO driver glue connecting the netif to the UART.
+#[repr(C)]
+struct PppDriver {
+  base: esp_netif_driver_base_t,
+  uart: i32,
+}
+
+extern "C" fn post_attach(netif: *mut esp_netif_t, args: *mut libc::c_void) -> esp_err_t {
+  let driver = unsafe { &mut *(args as *mut PppDriver) };
+  driver.base.netif = netif;
+
+  let mut config: esp_netif_driver_ifconfig_t = unsafe { MaybeUninit::zeroed().assume_init() };
+  config.handle = driver as *mut PppDriver as *mut _;
+  config.transmit = Some(transmit);
+
+  unsafe { esp_netif_set_driver_config(netif, &config) }
+}
+
+extern "C" fn transmit(handle: *mut libc::c_void, buffer: *mut libc::c_void, len: usize) -> esp_err_t {
+  let driver = unsafe { &*(handle as *const PppDriver) };
+
+  if unsafe { uart_write_bytes(driver.uart, buffer as *const _, len) } < 0 {
+    ESP_FAIL as esp_err_t
+  } else {
+    0
+  }
+}
+
+fn write(uart: i32, bytes: &[u8]) -> Result<(), EspError> {
+  if unsafe { uart_write_bytes(uart, bytes.as_ptr() as *const _, bytes.len()) } < 0 {
+    Err(EspError { code: ESP_FAIL as esp_err_t })
+  } else {
+    Ok(())
+  }
+}
+
+/// Send an AT command and wait for a line equal to `expect`.
+fn command(uart: i32, command: &str, expect: &str, timeout: Duration) -> Result<(), EspError> {
+  write(uart, command.as_bytes())?;
+  write(uart, b"\r")?;
+
+  let deadline = Instant::now() + timeout;
+  let mut response = Vec::new();
+  let mut buffer = [0; 64];
+
+  while Instant::now() < deadline {
+    let len = unsafe { uart_read_bytes(uart, buffer.as_mut_ptr(), buffer.len() as u32, ticks(Duration::from_millis(100))) };
+    if len <= 0 {
+      continue
+    }
+
+    response.extend_from_slice(&buffer[..len as usize]);
+
+    for line in response.split(|&b| b == b'\r' || b == b'\n') {
+      let line = trim(line);
+
+      if line.starts_with(expect.as_bytes()) {
+        return Ok(())
+      }
+
+      if line == b"ERROR" || line == b"NO CARRIER" {
+        return Err(EspError { code: ESP_FAIL as esp_err_t })
+      }
+    }
+  }
+
+  Err(EspError { code: ESP_ERR_TIMEOUT as esp_err_t })
+}
+
+fn trim(bytes: &[u8]) -> &[u8] {
+  let start = bytes.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(bytes.len());
+  let end = bytes.iter().rposition(|b| !b.is_ascii_whitespace()).map_or(start, |end| end + 1);
+  &bytes[start..end]
+}
+
+/// A PPP connection over a UART connected to a cellular modem.
+///
+/// IP changes are reported as [`PppEvent`](enum.PppEvent.html)s.
+/// The connection is closed and the UART driver is deleted when dropped.
+#[derive(Debug)]
+pub struct Ppp {
+  config: PppConfig,
+  netif: *mut esp_netif_t,
+  driver: *mut PppDriver,
+  driver_installed: bool,
+  stopped: Arc<AtomicBool>,
+  rx_thread: Option<JoinHandle<()>>,
+}
+
+unsafe impl Send for Ppp {}
+
+impl Ppp {
+  /// Dial the modem, if an APN is configured, and start a PPP connection.
+  ///
+  /// Only one PPP connection can exist at a time.
+  pub fn connect(config: PppConfig) -> Result<Self, EspError> {
+    if PPP_ACTIVE.compare_and_swap(false, true, SeqCst) {
+      return Err(EspError { code: ESP_ERR_INVALID_STATE as esp_err_t })
+    }
+
+    let mut ppp = Ppp {
+      config,
+      netif: ptr::null_mut(),
+      driver: ptr::null_mut(),
+      driver_installed: false,
+      stopped: Arc::new(AtomicBool::new(false)),
+      rx_thread: None,
+    };
+
+    initialize_network_interface();
+    crate::event::event_loop_create_default();
+
+    let uart = ppp.config.uart as i32;
+
+    let mut uart_config: uart_config_t = unsafe { MaybeUninit::zeroed().assume_init() };
+    uart_config.baud_rate = ppp.config.baud_rate as i32;
+    uart_config.data_bits = uart_word_length_t::UART_DATA_8_BITS;
+    uart_config.parity = uart_parity_t::UART_PARITY_DISABLE;
+    uart_config.stop_bits = uart_stop_bits_t::UART_STOP_BITS_1;
+    uart_config.flow_ctrl = uart_hw_flowcontrol_t::UART_HW_FLOWCTRL_DISABLE;
+    esp_ok!(uart_param_config(uart, &uart_config))?;
+    esp_ok!(uart_set_pin(uart, ppp.config.tx_pin as i32, ppp.config.rx_pin as i32, -1, -1))?;
+    esp_ok!(uart_driver_install(uart, UART_BUFFER_SIZE as i32 * 2, UART_BUFFER_SIZE as i32, 0, ptr::null_mut(), 0))?;
+    ppp.driver_installed = true;
+
+    if let Some(apn) = &ppp.config.apn {
+      command(uart, "AT", "OK", COMMAND_TIMEOUT)?;
+      command(uart, &format!("AT+CGDCONT=1,\"IP\",\"{}\"", apn), "OK", COMMAND_TIMEOUT)?;
+      command(uart, "ATD*99#", "CONNECT", DIAL_TIMEOUT)?;
+    }
+
+    let netif_config = esp_netif_config_t {
+      base: unsafe { &_g_esp_netif_inherent_ppp_config },
+      driver: ptr::null(),
+      stack: unsafe { _g_esp_netif_netstack_default_ppp },
+    };
+    ppp.netif = unsafe { esp_netif_new(&netif_config) };
+    if ppp.netif.is_null() {
+      return Err(EspError { code: ESP_FAIL as esp_err_t })
+    }
+
+    if let Some((username, password)) = &ppp.config.credentials {
+      let invalid_arg = || EspError { code: ESP_ERR_INVALID_ARG as esp_err_t };
+      let username = CString::new(username.as_str()).map_err(|_| invalid_arg())?;
+      let password = CString::new(password.as_str()).map_err(|_| invalid_arg())?;
+      esp_ok!(esp_netif_ppp_set_auth(ppp.netif, esp_netif_auth_type_t::NETIF_PPP_AUTHTYPE_PAP, username.as_ptr(), password.as_ptr()))?;
+    }
+
+    let mut driver = Box::new(PppDriver { base: unsafe { MaybeUninit::zeroed().assume_init() }, uart });
+    driver.base.post_attach = Some(post_attach);
+    ppp.driver = Box::into_raw(driver);
+    esp_ok!(esp_netif_attach(ppp.netif, ppp.driver as *mut _))?;
+
+    let netif = ppp.netif as usize;
+    let stopped = Arc::clone(&ppp.stopped);
+    let rx_thread = thread::Builder::new()
+      .name("ppp_rx".into())
+      .stack_size(4096)
+      .spawn(move || receive(uart, netif as *mut esp_netif_t, stopped))
+      .map_err(|_| EspError { code: ESP_FAIL as esp_err_t })?;
+    ppp.rx_thread = Some(rx_thread);
+
+    unsafe {
+      esp_netif_action_start(ppp.netif as *mut _, ptr::null(), 0, ptr::null_mut());
+      esp_netif_action_connected(ppp.netif as *mut _, ptr::null(), 0, ptr::null_mut());
+    }
+
+    Ok(ppp)
+  }
+
+  pub fn config(&self) -> &PppConfig {
+    &self.config
+  }
+
+  /// Whether the PPP link is up.
+  pub fn is_up(&self) -> bool {
+    unsafe { esp_netif_is_netif_up(self.netif) }
+  }
+
+  pub fn ip_info(&self) -> IpInfo {
+    let mut ip_info = MaybeUninit::<esp_netif_ip_info_t>::uninit();
+    esp_ok!(esp_netif_get_ip_info(self.netif, ip_info.as_mut_ptr())).unwrap(); // Can only fail with invalid arguments.
+    unsafe { IpInfo::from_native_unchecked(ip_info.assume_init()) }
+  }
+}
+
+/// Feed everything received from the modem into the netif until `stopped` is set.
+fn receive(uart: i32, netif: *mut esp_netif_t, stopped: Arc<AtomicBool>) {
+  let mut buffer = vec![0; UART_BUFFER_SIZE];
+
+  while !stopped.load(SeqCst) {
+    let len = unsafe { uart_read_bytes(uart, buffer.as_mut_ptr(), buffer.len() as u32, ticks(Duration::from_millis(100))) };
+
+    if len > 0 {
+      unsafe { esp_netif_receive(netif, buffer.as_mut_ptr() as *mut _, len as usize, ptr::null_mut()) };
+    }
+  }
+}
+
+impl Drop for Ppp {
+  fn drop(&mut self) {
+    if let Some(rx_thread) = self.rx_thread.take() {
+      unsafe { esp_netif_action_stop(self.netif as *mut _, ptr::null(), 0, ptr::null_mut()) };
+      self.stopped.store(true, SeqCst);
+      let _ = rx_thread.join();
+    }
+
+    if !self.netif.is_null() {
+      unsafe { esp_netif_destroy(self.netif) };
+    }
+
+    if !self.driver.is_null() {
+      drop(unsafe { Box::from_raw(self.driver) });
+    }
+
+    if self.driver_installed {
+      let _ = esp_ok!(uart_driver_delete(self.config.uart as i32));
+    }
+
+    PPP_ACTIVE.store(false, SeqCst);
+  }
+}
+
+/// A PPP connection event.
+#[derive(Debug, Clone)]
+pub enum PppEvent {
+  /// The connection obtained an IP address.
+  GotIp { ip_info: IpInfo, changed: bool },
+  /// The connection lost its IP address.
+  LostIp,
+  /// The connection failed with the given lwIP PPP error code.
+  Error { code: i32 },
+}
+
+impl PppEvent {
+  pub(crate) unsafe fn from_raw(event_base: esp_event_base_t, event_id: i32, event_data: *mut libc::c_void) -> Option<Self> {
+    if event_base == IP_EVENT {
+      Some(match event_id {
+        id if id == ip_event_t::IP_EVENT_PPP_GOT_IP as i32 => {
+          let event = &*(event_data as *const ip_event_got_ip_t);
+          Self::GotIp { ip_info: IpInfo::from_native_unchecked(event.ip_info), changed: event.ip_changed }
+        },
+        id if id == ip_event_t::IP_EVENT_PPP_LOST_IP as i32 => Self::LostIp,
+        _ => return None,
+      })
+    } else if event_base == NETIF_PPP_STATUS {
+      match event_id {
+        // Phase changes start at `NETIF_PP_PHASE_OFFSET`, everything below except `0` is an error.
+        code if code > 0 && code < NETIF_PP_PHASE_OFFSET as i32 => Some(Self::Error { code }),
+        _ => None,
+      }
+    } else {
+      None
+    }
+  }
+}
+
+/// Call `callback` for every [`PppEvent`](enum.PppEvent.html) until the returned subscription is dropped.
+///
+/// The callback runs on the event loop task, so it should return quickly.
+pub fn subscribe<F>(mut callback: F) -> Result<Subscription, EspError>
+where
+  F: FnMut(PppEvent) + Send + 'static,
+{
+  Subscription::new(unsafe { &[IP_EVENT, NETIF_PPP_STATUS] }, move |event_base, event_id, event_data| {
+    if let Some(event) = unsafe { PppEvent::from_raw(event_base, event_id, event_data) } {
+      callback(event)
+    }
+  })
+}
+
+/// Receive every [`PppEvent`](enum.PppEvent.html) through a channel.
+pub fn events() -> Result<EventReceiver<PppEvent>, EspError> {
+  EventReceiver::new(unsafe { &[IP_EVENT, NETIF_PPP_STATUS] }, |event_base, event_id, event_data| {
+    unsafe { PppEvent::from_raw(event_base, event_id, event_data) }
+  })
+}
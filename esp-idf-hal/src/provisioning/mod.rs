@@ -0,0 +1,68 @@
+use core::fmt;
+
+use macaddr::MacAddr6;
+
+use crate::EspError;
+use crate::wifi::{Ssid, Password};
+
+mod smartconfig;
+pub use smartconfig::*;
+
+mod wps;
+pub use wps::*;
+
+/// WiFi credentials received during provisioning.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+  ssid: Ssid,
+  password: Password,
+  bssid: Option<MacAddr6>,
+}
+
+impl Credentials {
+  pub fn ssid(&self) -> &Ssid {
+    &self.ssid
+  }
+
+  pub fn password(&self) -> &Password {
+    &self.password
+  }
+
+  /// The BSSID of the access point, if it was provided.
+  pub fn bssid(&self) -> Option<&MacAddr6> {
+    self.bssid.as_ref()
+  }
+}
+
+/// The error type for provisioning operations.
+#[derive(Debug, Clone)]
+pub enum ProvisioningError {
+  /// An internal error not directly related to provisioning.
+  Internal(EspError),
+  /// Provisioning failed.
+  Failed,
+  /// Provisioning timed out.
+  Timeout,
+  /// More than one access point is in WPS push-button mode.
+  PbcOverlap,
+  /// The received credentials are not a valid SSID and password.
+  InvalidCredentials,
+}
+
+impl From<EspError> for ProvisioningError {
+  fn from(esp_error: EspError) -> Self {
+    Self::Internal(esp_error)
+  }
+}
+
+impl fmt::Display for ProvisioningError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Internal(esp_error) => esp_error.fmt(f),
+      Self::Failed => "provisioning failed".fmt(f),
+      Self::Timeout => "provisioning timed out".fmt(f),
+      Self::PbcOverlap => "more than one access point is in WPS push-button mode".fmt(f),
+      Self::InvalidCredentials => "received invalid credentials".fmt(f),
+    }
+  }
+}
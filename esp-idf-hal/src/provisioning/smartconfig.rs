@@ -0,0 +1,112 @@
+use core::future::Future;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use esp_idf_bindgen::{
+  esp_smartconfig_set_type,
+  esp_smartconfig_start,
+  esp_smartconfig_stop,
+  esp_wifi_start,
+  smart_config_event_t,
+  smartconfig_event_got_ssid_pswd_t,
+  smartconfig_start_config_t,
+  smartconfig_type_t,
+  SC_EVENT,
+};
+use macaddr::MacAddr6;
+
+use crate::event::EventFuture;
+use crate::wifi::{enter_sta_mode, leave_sta_mode, Ssid, Password, Wifi};
+use super::*;
+
+/// The SmartConfig protocol used to receive credentials.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmartConfigType {
+  EspTouch,
+  AirKiss,
+  EspTouchAirKiss,
+}
+
+impl From<SmartConfigType> for smartconfig_type_t {
+  fn from(smartconfig_type: SmartConfigType) -> Self {
+    match smartconfig_type {
+      SmartConfigType::EspTouch => smartconfig_type_t::SC_TYPE_ESPTOUCH,
+      SmartConfigType::AirKiss => smartconfig_type_t::SC_TYPE_AIRKISS,
+      SmartConfigType::EspTouchAirKiss => smartconfig_type_t::SC_TYPE_ESPTOUCH_AIRKISS,
+    }
+  }
+}
+
+/// A future representing a running SmartConfig session.
+///
+/// The phone app only receives an acknowledgement once the station is connected to the
+/// received network, so keep this alive until then. SmartConfig is stopped when it is dropped.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct SmartConfig<'w> {
+  credentials: EventFuture<Result<Credentials, ProvisioningError>>,
+  _wifi: PhantomData<&'w ()>,
+}
+
+/// Start SmartConfig and wait for credentials sent by the ESP-Touch or AirKiss phone app.
+///
+/// ```no_run
+/// use esp_idf_hal::{provisioning::{self, SmartConfigType}, wifi::Wifi};
+///
+/// # async {
+/// let wifi = Wifi::take().unwrap();
+/// let mut smartconfig = provisioning::smartconfig(&wifi, SmartConfigType::EspTouch).unwrap();
+/// let credentials = (&mut smartconfig).await.unwrap();
+/// # };
+/// ```
+pub fn smartconfig(_wifi: &Wifi, smartconfig_type: SmartConfigType) -> Result<SmartConfig<'_>, ProvisioningError> {
+  let credentials = EventFuture::new(unsafe { &[SC_EVENT] }, |_, event_id, event_data| {
+    if event_id != smart_config_event_t::SC_EVENT_GOT_SSID_PSWD as i32 {
+      return None
+    }
+
+    let event = unsafe { &*(event_data as *const smartconfig_event_got_ssid_pswd_t) };
+    Some(credentials_from_event(event))
+  })?;
+
+  enter_sta_mode();
+  let smartconfig = SmartConfig { credentials, _wifi: PhantomData };
+
+  esp_ok!(esp_wifi_start())?;
+  esp_ok!(esp_smartconfig_set_type(smartconfig_type.into()))?;
+
+  let mut config: smartconfig_start_config_t = unsafe { MaybeUninit::zeroed().assume_init() };
+  config.enable_log = false;
+  esp_ok!(esp_smartconfig_start(&config))?;
+
+  Ok(smartconfig)
+}
+
+fn credentials_from_event(event: &smartconfig_event_got_ssid_pswd_t) -> Result<Credentials, ProvisioningError> {
+  let ssid_len = memchr::memchr(0, &event.ssid).unwrap_or(event.ssid.len());
+  let ssid = Ssid::from_bytes(&event.ssid[..ssid_len]).map_err(|_| ProvisioningError::InvalidCredentials)?;
+
+  let password_len = memchr::memchr(0, &event.password).unwrap_or(event.password.len());
+  let password = Password::from_bytes(&event.password[..password_len]).map_err(|_| ProvisioningError::InvalidCredentials)?;
+
+  let bssid = if event.bssid_set { Some(MacAddr6::from(event.bssid)) } else { None };
+
+  Ok(Credentials { ssid, password, bssid })
+}
+
+impl Future for SmartConfig<'_> {
+  type Output = Result<Credentials, ProvisioningError>;
+
+  fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+    Pin::new(&mut self.credentials).poll(cx)
+  }
+}
+
+impl Drop for SmartConfig<'_> {
+  fn drop(&mut self) {
+    let _ = esp_ok!(esp_smartconfig_stop());
+    leave_sta_mode();
+  }
+}
@@ -0,0 +1,149 @@
+use core::future::Future;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use std::sync::{Arc, Mutex};
+
+use esp_idf_bindgen::{
+  esp_interface_t,
+  esp_wifi_get_config,
+  esp_wifi_start,
+  esp_wifi_wps_disable,
+  esp_wifi_wps_enable,
+  esp_wifi_wps_start,
+  esp_wps_config_t,
+  wifi_config_t,
+  wifi_event_t,
+  wifi_event_sta_wps_er_pin_t,
+  wifi_event_sta_wps_er_success_t,
+  wps_type_t,
+  WIFI_EVENT,
+};
+
+use crate::event::EventFuture;
+use crate::wifi::{enter_sta_mode, leave_sta_mode, Ssid, Password, Wifi};
+use super::*;
+
+/// The WPS method used to negotiate credentials.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WpsMode {
+  /// Push-button configuration.
+  Pbc,
+  /// PIN configuration. The PIN to enter on the access point is available from
+  /// [`Wps::pin`](struct.Wps.html#method.pin) once it has been generated.
+  Pin,
+}
+
+impl From<WpsMode> for wps_type_t {
+  fn from(mode: WpsMode) -> Self {
+    match mode {
+      WpsMode::Pbc => wps_type_t::WPS_TYPE_PBC,
+      WpsMode::Pin => wps_type_t::WPS_TYPE_PIN,
+    }
+  }
+}
+
+/// A future representing a running WPS session.
+///
+/// WPS is disabled when it is dropped.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct Wps<'w> {
+  credentials: EventFuture<Result<Credentials, ProvisioningError>>,
+  pin: Arc<Mutex<Option<[u8; 8]>>>,
+  _wifi: PhantomData<&'w ()>,
+}
+
+impl Wps<'_> {
+  /// The PIN to enter on the access point in `WpsMode::Pin` mode once it has been generated.
+  pub fn pin(&self) -> Option<String> {
+    self.pin.lock().unwrap().map(|pin| String::from_utf8_lossy(&pin).into_owned())
+  }
+}
+
+/// Start WPS and wait for credentials negotiated with an access point.
+pub fn wps(_wifi: &Wifi, mode: WpsMode) -> Result<Wps<'_>, ProvisioningError> {
+  let pin = Arc::new(Mutex::new(None));
+  let handler_pin = Arc::clone(&pin);
+
+  let credentials = EventFuture::new(unsafe { &[WIFI_EVENT] }, move |_, event_id, event_data| {
+    match event_id {
+      id if id == wifi_event_t::WIFI_EVENT_STA_WPS_ER_SUCCESS as i32 => {
+        let event = event_data as *const wifi_event_sta_wps_er_success_t;
+        Some(credentials_from_event(unsafe { event.as_ref() }))
+      },
+      id if id == wifi_event_t::WIFI_EVENT_STA_WPS_ER_PIN as i32 => {
+        let event = unsafe { &*(event_data as *const wifi_event_sta_wps_er_pin_t) };
+        *handler_pin.lock().unwrap() = Some(event.pin_code);
+        None
+      },
+      id if id == wifi_event_t::WIFI_EVENT_STA_WPS_ER_FAILED as i32 => Some(Err(ProvisioningError::Failed)),
+      id if id == wifi_event_t::WIFI_EVENT_STA_WPS_ER_TIMEOUT as i32 => Some(Err(ProvisioningError::Timeout)),
+      id if id == wifi_event_t::WIFI_EVENT_STA_WPS_ER_PBC_OVERLAP as i32 => Some(Err(ProvisioningError::PbcOverlap)),
+      _ => None,
+    }
+  })?;
+
+  enter_sta_mode();
+  let wps = Wps { credentials, pin, _wifi: PhantomData };
+
+  esp_ok!(esp_wifi_start())?;
+
+  let mut config: esp_wps_config_t = unsafe { MaybeUninit::zeroed().assume_init() };
+  config.wps_type = mode.into();
+  copy_str(&mut config.factory_info.manufacturer, "ESPRESSIF");
+  copy_str(&mut config.factory_info.model_number, "ESP32");
+  copy_str(&mut config.factory_info.model_name, "ESPRESSIF IOT");
+  copy_str(&mut config.factory_info.device_name, "ESP STATION");
+
+  esp_ok!(esp_wifi_wps_enable(&config))?;
+  esp_ok!(esp_wifi_wps_start(0))?;
+
+  Ok(wps)
+}
+
+fn copy_str(dest: &mut [libc::c_char], s: &str) {
+  for (d, &b) in dest.iter_mut().zip(s.as_bytes()) {
+    *d = b as _;
+  }
+}
+
+fn credentials_from_event(event: Option<&wifi_event_sta_wps_er_success_t>) -> Result<Credentials, ProvisioningError> {
+  let (ssid, password) = match event {
+    Some(event) if event.ap_cred_cnt > 0 => {
+      let cred = &event.ap_cred[0];
+      (cred.ssid, cred.passphrase)
+    },
+    _ => {
+      // Only a single set of credentials was received, which is stored in the station configuration.
+      let mut config = MaybeUninit::<wifi_config_t>::uninit();
+      esp_ok!(esp_wifi_get_config(esp_interface_t::ESP_IF_WIFI_STA, config.as_mut_ptr()))?;
+      let sta = unsafe { config.assume_init().sta };
+      (sta.ssid, sta.password)
+    },
+  };
+
+  let ssid_len = memchr::memchr(0, &ssid).unwrap_or(ssid.len());
+  let ssid = Ssid::from_bytes(&ssid[..ssid_len]).map_err(|_| ProvisioningError::InvalidCredentials)?;
+
+  let password_len = memchr::memchr(0, &password).unwrap_or(password.len());
+  let password = Password::from_bytes(&password[..password_len]).map_err(|_| ProvisioningError::InvalidCredentials)?;
+
+  Ok(Credentials { ssid, password, bssid: None })
+}
+
+impl Future for Wps<'_> {
+  type Output = Result<Credentials, ProvisioningError>;
+
+  fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+    Pin::new(&mut self.credentials).poll(cx)
+  }
+}
+
+impl Drop for Wps<'_> {
+  fn drop(&mut self) {
+    let _ = esp_ok!(esp_wifi_wps_disable());
+    leave_sta_mode();
+  }
+}
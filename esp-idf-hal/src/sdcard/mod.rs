@@ -0,0 +1,164 @@
+use std::ffi::{CStr, CString};
+
+use esp_idf_bindgen::{esp_vfs_fat_sdcard_unmount, gpio_num_t, sdmmc_card_t};
+use memchr::memchr;
+
+pub mod spi;
+pub mod sdmmc;
+
+fn gpio_num(pin: Option<u8>) -> gpio_num_t {
+  match pin {
+    Some(pin) => {
+      assert!(crate::gpio::is_valid_pin(pin), "invalid GPIO {}", pin);
+      crate::gpio::gpio_num(pin)
+    },
+    None => gpio_num_t::GPIO_NUM_NC,
+  }
+}
+
+/// The card identification register of an SD card.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cid {
+  manufacturer_id: u8,
+  oem_id: u16,
+  name: String,
+  revision: u8,
+  serial: u32,
+  year: u16,
+  month: u8,
+}
+
+impl Cid {
+  pub fn manufacturer_id(&self) -> u8 {
+    self.manufacturer_id
+  }
+
+  pub fn oem_id(&self) -> u16 {
+    self.oem_id
+  }
+
+  /// The product name.
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn revision(&self) -> u8 {
+    self.revision
+  }
+
+  pub fn serial(&self) -> u32 {
+    self.serial
+  }
+
+  /// The manufacturing date as `(year, month)`.
+  pub fn date(&self) -> (u16, u8) {
+    (self.year, self.month)
+  }
+}
+
+/// The card specific data register of an SD card.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Csd {
+  version: u8,
+  sectors: u64,
+  sector_size: u32,
+  read_block_len: u32,
+  command_class: u32,
+  transfer_speed: u32,
+}
+
+impl Csd {
+  /// The CSD structure version.
+  pub fn version(&self) -> u8 {
+    self.version
+  }
+
+  /// The number of sectors.
+  pub fn sectors(&self) -> u64 {
+    self.sectors
+  }
+
+  /// The sector size in bytes.
+  pub fn sector_size(&self) -> u32 {
+    self.sector_size
+  }
+
+  /// The maximum read block length in bytes.
+  pub fn read_block_len(&self) -> u32 {
+    self.read_block_len
+  }
+
+  /// The supported command classes as a bit mask.
+  pub fn command_class(&self) -> u32 {
+    self.command_class
+  }
+
+  /// The maximum transfer speed in kHz.
+  pub fn transfer_speed(&self) -> u32 {
+    self.transfer_speed
+  }
+}
+
+/// A mounted SD card. The card is unmounted when this is dropped.
+#[derive(Debug)]
+pub struct SdCard {
+  card: *mut sdmmc_card_t,
+  base_path: CString,
+  _bus: Option<spi::SpiBus>,
+}
+
+unsafe impl Send for SdCard {}
+
+impl SdCard {
+  /// The path the FAT filesystem of this card is mounted at.
+  pub fn base_path(&self) -> &CStr {
+    &self.base_path
+  }
+
+  /// The capacity of the card in bytes.
+  pub fn capacity(&self) -> u64 {
+    let csd = self.csd();
+    csd.sectors() * csd.sector_size() as u64
+  }
+
+  /// Whether the card is an MMC card rather than an SD card.
+  pub fn is_mmc(&self) -> bool {
+    unsafe { (*self.card).is_mmc() != 0 }
+  }
+
+  pub fn cid(&self) -> Cid {
+    let cid = unsafe { &(*self.card).cid };
+
+    let name_bytes = unsafe { &*(&cid.name as *const _ as *const [u8; 8]) };
+    let name_len = memchr(0, name_bytes).unwrap_or(name_bytes.len());
+
+    Cid {
+      manufacturer_id: cid.mfg_id as u8,
+      oem_id: cid.oem_id as u16,
+      name: String::from_utf8_lossy(&name_bytes[..name_len]).into_owned(),
+      revision: cid.revision as u8,
+      serial: cid.serial as u32,
+      year: 2000 + (cid.date >> 4) as u16,
+      month: (cid.date & 0xf) as u8,
+    }
+  }
+
+  pub fn csd(&self) -> Csd {
+    let csd = unsafe { &(*self.card).csd };
+
+    Csd {
+      version: csd.csd_ver as u8,
+      sectors: csd.capacity as u64,
+      sector_size: csd.sector_size as u32,
+      read_block_len: csd.read_block_len as u32,
+      command_class: csd.card_command_class as u32,
+      transfer_speed: csd.tr_speed as u32,
+    }
+  }
+}
+
+impl Drop for SdCard {
+  fn drop(&mut self) {
+    let _ = esp_ok!(esp_vfs_fat_sdcard_unmount(self.base_path.as_ptr(), self.card));
+  }
+}
@@ -0,0 +1,206 @@
+use core::mem::MaybeUninit;
+use core::ptr;
+
+use esp_idf_bindgen::{
+  esp_vfs_fat_sdmmc_mount,
+  sdmmc_card_t,
+  sdmmc_host_deinit,
+  sdmmc_host_do_transaction,
+  sdmmc_host_get_slot_width,
+  sdmmc_host_init,
+  sdmmc_host_io_int_enable,
+  sdmmc_host_io_int_wait,
+  sdmmc_host_set_bus_ddr_mode,
+  sdmmc_host_set_bus_width,
+  sdmmc_host_set_card_clk,
+  sdmmc_host_t,
+  sdmmc_slot_config_t,
+  SDMMC_FREQ_DEFAULT,
+  SDMMC_FREQ_HIGHSPEED,
+  SDMMC_HOST_FLAG_1BIT,
+  SDMMC_HOST_FLAG_4BIT,
+  SDMMC_HOST_FLAG_8BIT,
+  SDMMC_HOST_FLAG_DDR,
+  SDMMC_HOST_SLOT_1,
+  SDMMC_SLOT_FLAG_INTERNAL_PULLUP,
+};
+
+use crate::EspError;
+use crate::gpio::is_valid_pin;
+use crate::fs::{c_string, fatfs::FatMountConfig};
+
+use super::{gpio_num, SdCard};
+
+/// The data bus width used by the SDMMC host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusWidth {
+  /// Only `D0` is connected.
+  One,
+  /// `D0` to `D3` are connected.
+  Four,
+}
+
+/// Configuration for an SD card connected to the native SDMMC host.
+///
+/// Slot 1 is used, so the card must be connected to the fixed pins
+/// CLK 14, CMD 15, D0 2, D1 4, D2 12 and D3 13.
+#[derive(Debug, Clone)]
+pub struct SdmmcConfig {
+  bus_width: BusWidth,
+  high_speed: bool,
+  cd_pin: Option<u8>,
+  wp_pin: Option<u8>,
+  internal_pullups: bool,
+}
+
+impl SdmmcConfig {
+  pub fn bus_width(&self) -> BusWidth {
+    self.bus_width
+  }
+
+  /// Whether the bus is clocked at 40 MHz instead of 20 MHz.
+  pub fn high_speed(&self) -> bool {
+    self.high_speed
+  }
+
+  /// The card detect pin.
+  pub fn cd_pin(&self) -> Option<u8> {
+    self.cd_pin
+  }
+
+  /// The write protect pin.
+  pub fn wp_pin(&self) -> Option<u8> {
+    self.wp_pin
+  }
+
+  /// Whether the internal pull-ups are enabled on the bus pins.
+  pub fn internal_pullups(&self) -> bool {
+    self.internal_pullups
+  }
+
+  pub fn builder() -> SdmmcConfigBuilder {
+    SdmmcConfigBuilder::default()
+  }
+}
+
+/// Builder for [`SdmmcConfig`](struct.SdmmcConfig.html).
+#[derive(Debug, Clone)]
+pub struct SdmmcConfigBuilder {
+  bus_width: BusWidth,
+  high_speed: bool,
+  cd_pin: Option<u8>,
+  wp_pin: Option<u8>,
+  internal_pullups: bool,
+}
+
+impl Default for SdmmcConfigBuilder {
+  fn default() -> Self {
+    Self {
+      bus_width: BusWidth::Four,
+      high_speed: false,
+      cd_pin: None,
+      wp_pin: None,
+      internal_pullups: false,
+    }
+  }
+}
+
+impl SdmmcConfigBuilder {
+  /// Set the data bus width. Defaults to `BusWidth::Four`.
+  pub fn bus_width(&mut self, bus_width: BusWidth) -> &mut Self {
+    self.bus_width = bus_width;
+    self
+  }
+
+  /// Set whether the bus is clocked at 40 MHz instead of 20 MHz. Defaults to `false`.
+  pub fn high_speed(&mut self, high_speed: bool) -> &mut Self {
+    self.high_speed = high_speed;
+    self
+  }
+
+  /// Set the card detect pin, which is low while a card is inserted.
+  pub fn cd_pin(&mut self, cd_pin: impl Into<Option<u8>>) -> &mut Self {
+    let cd_pin = cd_pin.into();
+    if let Some(cd_pin) = cd_pin {
+      assert!(is_valid_pin(cd_pin), "invalid card detect pin {}", cd_pin);
+    }
+    self.cd_pin = cd_pin;
+    self
+  }
+
+  /// Set the write protect pin, which is high while the card is write protected.
+  pub fn wp_pin(&mut self, wp_pin: impl Into<Option<u8>>) -> &mut Self {
+    let wp_pin = wp_pin.into();
+    if let Some(wp_pin) = wp_pin {
+      assert!(is_valid_pin(wp_pin), "invalid write protect pin {}", wp_pin);
+    }
+    self.wp_pin = wp_pin;
+    self
+  }
+
+  /// Set whether the internal pull-ups are enabled on the bus pins. Defaults to `false`.
+  ///
+  /// The internal pull-ups are too weak for reliable operation, so external 10 kΩ pull-ups should be used instead.
+  pub fn internal_pullups(&mut self, internal_pullups: bool) -> &mut Self {
+    self.internal_pullups = internal_pullups;
+    self
+  }
+
+  pub fn build(&self) -> SdmmcConfig {
+    SdmmcConfig {
+      bus_width: self.bus_width,
+      high_speed: self.high_speed,
+      cd_pin: self.cd_pin,
+      wp_pin: self.wp_pin,
+      internal_pullups: self.internal_pullups,
+    }
+  }
+}
+
+/// Equivalent of `SDMMC_HOST_DEFAULT`.
+fn host_config(high_speed: bool) -> sdmmc_host_t {
+  let mut config: sdmmc_host_t = unsafe { MaybeUninit::zeroed().assume_init() };
+  config.flags = SDMMC_HOST_FLAG_8BIT | SDMMC_HOST_FLAG_4BIT | SDMMC_HOST_FLAG_1BIT | SDMMC_HOST_FLAG_DDR;
+  config.slot = SDMMC_HOST_SLOT_1 as i32;
+  config.max_freq_khz = if high_speed { SDMMC_FREQ_HIGHSPEED } else { SDMMC_FREQ_DEFAULT } as i32;
+  config.io_voltage = 3.3;
+  config.init = Some(sdmmc_host_init);
+  config.set_bus_width = Some(sdmmc_host_set_bus_width);
+  config.get_bus_width = Some(sdmmc_host_get_slot_width);
+  config.set_bus_ddr_mode = Some(sdmmc_host_set_bus_ddr_mode);
+  config.set_card_clk = Some(sdmmc_host_set_card_clk);
+  config.do_transaction = Some(sdmmc_host_do_transaction);
+  config.__bindgen_anon_1.deinit = Some(sdmmc_host_deinit);
+  config.io_int_enable = Some(sdmmc_host_io_int_enable);
+  config.io_int_wait = Some(sdmmc_host_io_int_wait);
+  config
+}
+
+/// Mount the FAT filesystem of an SD card connected to the SDMMC host at `mount_point`, e.g. `/sdcard`,
+/// so it can be accessed using `std::fs`.
+pub fn mount(config: &SdmmcConfig, mount_point: &str, mount_config: &FatMountConfig) -> Result<SdCard, EspError> {
+  let base_path = c_string(mount_point)?;
+
+  let host = host_config(config.high_speed);
+
+  let slot_config = sdmmc_slot_config_t {
+    gpio_cd: gpio_num(config.cd_pin),
+    gpio_wp: gpio_num(config.wp_pin),
+    width: match config.bus_width {
+      BusWidth::One => 1,
+      BusWidth::Four => 4,
+    },
+    flags: if config.internal_pullups { SDMMC_SLOT_FLAG_INTERNAL_PULLUP } else { 0 },
+  };
+
+  let mut card: *mut sdmmc_card_t = ptr::null_mut();
+  esp_ok!(esp_vfs_fat_sdmmc_mount(
+    base_path.as_ptr(),
+    &host,
+    &slot_config as *const _ as *const _,
+    &mount_config.to_native(),
+    &mut card,
+  ))?;
+
+  Ok(SdCard { card, base_path, _bus: None })
+}
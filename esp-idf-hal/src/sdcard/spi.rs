@@ -0,0 +1,67 @@
+use core::mem::MaybeUninit;
+use core::ptr;
+
+use esp_idf_bindgen::{
+  esp_vfs_fat_sdspi_mount,
+  sdmmc_card_t,
+  sdmmc_host_t,
+  sdspi_device_config_t,
+  sdspi_host_do_transaction,
+  sdspi_host_init,
+  sdspi_host_io_int_enable,
+  sdspi_host_io_int_wait,
+  sdspi_host_remove_device,
+  sdspi_host_set_card_clk,
+  spi_host_device_t,
+  SDMMC_FREQ_DEFAULT,
+  SDMMC_HOST_FLAG_DEINIT_ARG,
+  SDMMC_HOST_FLAG_SPI,
+};
+
+pub use crate::spi::{SpiBus, SpiDmaChannel, SpiHost};
+use crate::EspError;
+use crate::gpio::is_output_pin;
+use crate::fs::{c_string, fatfs::FatMountConfig};
+
+use super::{gpio_num, SdCard};
+
+/// Equivalent of `SDSPI_HOST_DEFAULT`.
+fn host_config(host: spi_host_device_t) -> sdmmc_host_t {
+  let mut config: sdmmc_host_t = unsafe { MaybeUninit::zeroed().assume_init() };
+  config.flags = SDMMC_HOST_FLAG_SPI | SDMMC_HOST_FLAG_DEINIT_ARG;
+  config.slot = host as i32;
+  config.max_freq_khz = SDMMC_FREQ_DEFAULT as i32;
+  config.io_voltage = 3.3;
+  config.init = Some(sdspi_host_init);
+  config.set_card_clk = Some(sdspi_host_set_card_clk);
+  config.do_transaction = Some(sdspi_host_do_transaction);
+  config.__bindgen_anon_1.deinit_p = Some(sdspi_host_remove_device);
+  config.io_int_enable = Some(sdspi_host_io_int_enable);
+  config.io_int_wait = Some(sdspi_host_io_int_wait);
+  config
+}
+
+/// Mount the FAT filesystem of an SD card connected to `spi_bus` at `mount_point`, e.g. `/sdcard`,
+/// so it can be accessed using `std::fs`.
+///
+/// The SPI bus is used exclusively by the card until it is unmounted.
+pub fn mount(spi_bus: SpiBus, cs_pin: u8, mount_point: &str, config: &FatMountConfig) -> Result<SdCard, EspError> {
+  assert!(is_output_pin(cs_pin), "invalid CS pin {}", cs_pin);
+
+  let base_path = c_string(mount_point)?;
+
+  let host = host_config(spi_bus.host);
+
+  let slot_config = sdspi_device_config_t {
+    host_id: spi_bus.host,
+    gpio_cs: gpio_num(Some(cs_pin)),
+    gpio_cd: gpio_num(None),
+    gpio_wp: gpio_num(None),
+    gpio_int: gpio_num(None),
+  };
+
+  let mut card: *mut sdmmc_card_t = ptr::null_mut();
+  esp_ok!(esp_vfs_fat_sdspi_mount(base_path.as_ptr(), &host, &slot_config, &config.to_native(), &mut card))?;
+
+  Ok(SdCard { card, base_path, _bus: Some(spi_bus) })
+}
@@ -0,0 +1,27 @@
+use esp_idf_bindgen::{adc1_config_width, adc_bits_width_t, esp_err_t, hall_sensor_read, ESP_ERR_INVALID_ARG};
+
+use crate::EspError;
+
+/// Read the built-in hall effect sensor.
+///
+/// The value increases with the strength of a magnetic field pointing into the chip, and decreases
+/// for the opposite direction. Readings are noisy, see [`hall_average`](fn.hall_average.html).
+///
+/// The sensor is read through ADC1 channels 0 and 3, so GPIOs 36 and 39 must not be used
+/// while reading. The ADC1 bit width is set to 12 bits.
+pub fn hall() -> Result<i32, EspError> {
+  esp_ok!(adc1_config_width(adc_bits_width_t::ADC_WIDTH_BIT_12))?;
+  Ok(unsafe { hall_sensor_read() })
+}
+
+/// Read the built-in hall effect sensor `samples` times and return the average.
+pub fn hall_average(samples: usize) -> Result<i32, EspError> {
+  if samples == 0 {
+    return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+  }
+
+  esp_ok!(adc1_config_width(adc_bits_width_t::ADC_WIDTH_BIT_12))?;
+
+  let sum = (0..samples).fold(0i64, |sum, _| sum + unsafe { hall_sensor_read() } as i64);
+  Ok((sum / samples as i64) as i32)
+}
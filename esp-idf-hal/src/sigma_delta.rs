@@ -0,0 +1,82 @@
+use core::fmt;
+use core::mem::transmute;
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  gpio_reset_pin,
+  sigmadelta_channel_t,
+  sigmadelta_config,
+  sigmadelta_config_t,
+  sigmadelta_set_duty,
+  sigmadelta_set_prescale,
+  ESP_ERR_INVALID_ARG,
+};
+
+use crate::EspError;
+use crate::gpio::{gpio_num, OutputCapable};
+
+const CHANNEL_COUNT: u8 = 8;
+
+/// A sigma-delta modulated output on any output-capable pin.
+///
+/// The density of high pulses is proportional to the duty, so a low-pass filter turns the output
+/// into an analog voltage. The pulse frequency is 80 MHz / (prescale + 1).
+pub struct SigmaDelta<P: OutputCapable> {
+  pin: P,
+  channel: u8,
+}
+
+impl<P: OutputCapable> fmt::Debug for SigmaDelta<P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("SigmaDelta")
+      .field("pin", &self.pin.number())
+      .field("channel", &self.channel)
+      .finish()
+  }
+}
+
+impl<P: OutputCapable> SigmaDelta<P> {
+  /// Output a modulated signal on `pin` using channel `channel`, between 0 and 7.
+  ///
+  /// See [`set_duty`](#method.set_duty) and [`set_prescale`](#method.set_prescale).
+  pub fn new(channel: u8, pin: P, duty: i8, prescale: u8) -> Result<Self, EspError> {
+    if channel >= CHANNEL_COUNT {
+      return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+    }
+
+    let config = sigmadelta_config_t {
+      channel: unsafe { transmute(channel as u32) },
+      sigmadelta_duty: duty,
+      sigmadelta_prescale: prescale,
+      sigmadelta_gpio: pin.number(),
+    };
+
+    esp_ok!(sigmadelta_config(&config))?;
+
+    Ok(Self { pin, channel })
+  }
+
+  fn channel_num(&self) -> sigmadelta_channel_t {
+    unsafe { transmute(self.channel as u32) }
+  }
+
+  pub fn channel(&self) -> u8 {
+    self.channel
+  }
+
+  /// Set the duty, from `-128` for an output which is always low to `127` for an output which is almost always high.
+  pub fn set_duty(&mut self, duty: i8) -> Result<(), EspError> {
+    esp_ok!(sigmadelta_set_duty(self.channel_num(), duty))
+  }
+
+  /// Set the prescaler of the 80 MHz pulse clock.
+  pub fn set_prescale(&mut self, prescale: u8) -> Result<(), EspError> {
+    esp_ok!(sigmadelta_set_prescale(self.channel_num(), prescale))
+  }
+
+  /// Reset the pin to its default state and return it.
+  pub fn release(self) -> P {
+    let _ = esp_ok!(gpio_reset_pin(gpio_num(self.pin.number())));
+    self.pin
+  }
+}
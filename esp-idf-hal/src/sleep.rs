@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+use esp_idf_bindgen::{
+  esp_deep_sleep_start,
+  esp_err_t,
+  esp_light_sleep_start,
+  esp_pm_config_esp32_t,
+  esp_pm_configure,
+  esp_sleep_enable_gpio_wakeup,
+  esp_sleep_enable_timer_wakeup,
+  esp_sleep_enable_uart_wakeup,
+  esp_sleep_get_wakeup_cause,
+  esp_sleep_wakeup_cause_t,
+  gpio_int_type_t,
+  gpio_wakeup_disable,
+  gpio_wakeup_enable,
+  uart_port_t,
+  uart_set_wakeup_threshold,
+  ESP_ERR_INVALID_ARG,
+};
+
+use crate::EspError;
+use crate::gpio::{gpio_num, Input, Pin};
+
+/// The number of UART ports.
+const UART_COUNT: u8 = 3;
+
+/// The minimum number of RX edges which wake up the chip from light sleep.
+const MIN_UART_WAKEUP_THRESHOLD: u32 = 3;
+
+/// The source which woke up the chip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WakeupCause {
+  /// The chip was not woken up from sleep, e.g. after a reset.
+  Undefined,
+  /// A single RTC pin, see [`RtcInput::enable_wakeup`](../gpio/struct.RtcInput.html#method.enable_wakeup).
+  Ext0,
+  /// Multiple RTC pins, see [`enable_ext1_wakeup`](../gpio/fn.enable_ext1_wakeup.html).
+  Ext1,
+  /// The sleep timer, see [`enable_timer_wakeup`](fn.enable_timer_wakeup.html).
+  Timer,
+  /// A touch pad.
+  Touchpad,
+  /// The ULP coprocessor.
+  Ulp,
+  /// A GPIO level, see [`Input::enable_light_sleep_wakeup`](../gpio/struct.Input.html#method.enable_light_sleep_wakeup).
+  Gpio,
+  /// Data received on a UART, see [`enable_uart_wakeup`](fn.enable_uart_wakeup.html).
+  Uart,
+}
+
+impl From<esp_sleep_wakeup_cause_t> for WakeupCause {
+  fn from(cause: esp_sleep_wakeup_cause_t) -> Self {
+    match cause {
+      esp_sleep_wakeup_cause_t::ESP_SLEEP_WAKEUP_EXT0 => Self::Ext0,
+      esp_sleep_wakeup_cause_t::ESP_SLEEP_WAKEUP_EXT1 => Self::Ext1,
+      esp_sleep_wakeup_cause_t::ESP_SLEEP_WAKEUP_TIMER => Self::Timer,
+      esp_sleep_wakeup_cause_t::ESP_SLEEP_WAKEUP_TOUCHPAD => Self::Touchpad,
+      esp_sleep_wakeup_cause_t::ESP_SLEEP_WAKEUP_ULP => Self::Ulp,
+      esp_sleep_wakeup_cause_t::ESP_SLEEP_WAKEUP_GPIO => Self::Gpio,
+      esp_sleep_wakeup_cause_t::ESP_SLEEP_WAKEUP_UART => Self::Uart,
+      _ => Self::Undefined,
+    }
+  }
+}
+
+/// The source which woke up the chip from the last light or deep sleep.
+pub fn wakeup_cause() -> WakeupCause {
+  unsafe { esp_sleep_get_wakeup_cause() }.into()
+}
+
+/// Wake up after `duration` has elapsed.
+pub fn enable_timer_wakeup(duration: Duration) -> Result<(), EspError> {
+  esp_ok!(esp_sleep_enable_timer_wakeup(duration.as_micros() as u64))
+}
+
+/// Wake up from light sleep when at least `threshold` rising edges are received on the RX pin of UART `port`.
+///
+/// The characters causing the wakeup are lost. The threshold must be at least 3.
+pub fn enable_uart_wakeup(port: u8, threshold: u32) -> Result<(), EspError> {
+  if port >= UART_COUNT || threshold < MIN_UART_WAKEUP_THRESHOLD {
+    return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+  }
+
+  let port = port as uart_port_t;
+  esp_ok!(uart_set_wakeup_threshold(port, threshold as i32))?;
+  esp_ok!(esp_sleep_enable_uart_wakeup(port as i32))
+}
+
+impl<P: Pin> Input<P> {
+  /// Wake up from light sleep while this pin has the given level.
+  ///
+  /// Any GPIO can be used, unlike deep sleep wakeup, which requires an RTC pin.
+  pub fn enable_light_sleep_wakeup(&mut self, high: bool) -> Result<(), EspError> {
+    let intr_type = if high { gpio_int_type_t::GPIO_INTR_HIGH_LEVEL } else { gpio_int_type_t::GPIO_INTR_LOW_LEVEL };
+    esp_ok!(gpio_wakeup_enable(gpio_num(self.number()), intr_type))?;
+    esp_ok!(esp_sleep_enable_gpio_wakeup())
+  }
+
+  /// Stop waking up from light sleep using this pin.
+  pub fn disable_light_sleep_wakeup(&mut self) -> Result<(), EspError> {
+    esp_ok!(gpio_wakeup_disable(gpio_num(self.number())))
+  }
+}
+
+/// Enter light sleep until one of the enabled wakeup sources triggers.
+///
+/// Execution continues after the call, RAM and peripheral state are preserved.
+pub fn light_sleep() -> Result<WakeupCause, EspError> {
+  esp_ok!(esp_light_sleep_start())?;
+  Ok(wakeup_cause())
+}
+
+/// Enter deep sleep. The chip restarts when one of the enabled wakeup sources triggers.
+pub fn deep_sleep() -> ! {
+  unsafe { esp_deep_sleep_start() }
+}
+
+/// Configure dynamic frequency scaling and automatic light sleep, which is entered whenever
+/// all tasks are idle and no power management lock is held.
+///
+/// The enabled wakeup sources also resume from automatic light sleep.
+/// Requires `CONFIG_PM_ENABLE`, and `CONFIG_FREERTOS_USE_TICKLESS_IDLE` for automatic light sleep.
+pub fn configure_power_management(max_freq_mhz: u32, min_freq_mhz: u32, light_sleep: bool) -> Result<(), EspError> {
+  let config = esp_pm_config_esp32_t {
+    max_freq_mhz: max_freq_mhz as _,
+    min_freq_mhz: min_freq_mhz as _,
+    light_sleep_enable: light_sleep,
+  };
+
+  esp_ok!(esp_pm_configure(&config as *const _ as *const libc::c_void))
+}
@@ -0,0 +1,78 @@
+use core::mem::MaybeUninit;
+
+use esp_idf_bindgen::{spi_bus_config_t, spi_bus_free, spi_bus_initialize, spi_host_device_t};
+
+use crate::EspError;
+
+/// A general purpose SPI peripheral.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpiHost {
+  Hspi,
+  Vspi,
+}
+
+impl From<SpiHost> for spi_host_device_t {
+  fn from(host: SpiHost) -> Self {
+    match host {
+      SpiHost::Hspi => spi_host_device_t::HSPI_HOST,
+      SpiHost::Vspi => spi_host_device_t::VSPI_HOST,
+    }
+  }
+}
+
+/// The DMA channel used by an [`SpiBus`](struct.SpiBus.html).
+///
+/// Each channel can only be used by one bus at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpiDmaChannel {
+  /// Transfer without DMA, which limits transfers to 64 bytes.
+  Disabled,
+  Channel1,
+  Channel2,
+}
+
+impl From<SpiDmaChannel> for libc::c_int {
+  fn from(dma_channel: SpiDmaChannel) -> Self {
+    match dma_channel {
+      SpiDmaChannel::Disabled => 0,
+      SpiDmaChannel::Channel1 => 1,
+      SpiDmaChannel::Channel2 => 2,
+    }
+  }
+}
+
+/// An initialized SPI bus, freed when dropped.
+#[derive(Debug)]
+pub struct SpiBus {
+  pub(crate) host: spi_host_device_t,
+}
+
+impl SpiBus {
+  /// Initialize the SPI peripheral `host` using the given pins and DMA channel.
+  pub fn new(host: SpiHost, sclk_pin: u8, mosi_pin: u8, miso_pin: u8, dma_channel: SpiDmaChannel) -> Result<Self, EspError> {
+    assert!(sclk_pin <= 33, "invalid SCLK pin {}", sclk_pin);
+    assert!(mosi_pin <= 33, "invalid MOSI pin {}", mosi_pin);
+    assert!(miso_pin <= 39, "invalid MISO pin {}", miso_pin);
+
+    let host = spi_host_device_t::from(host);
+
+    let mut bus_config: spi_bus_config_t = unsafe { MaybeUninit::zeroed().assume_init() };
+    bus_config.sclk_io_num = sclk_pin as i32;
+    bus_config.mosi_io_num = mosi_pin as i32;
+    bus_config.miso_io_num = miso_pin as i32;
+    bus_config.quadwp_io_num = -1;
+    bus_config.quadhd_io_num = -1;
+    if dma_channel != SpiDmaChannel::Disabled {
+      bus_config.max_transfer_sz = 4000;
+    }
+    esp_ok!(spi_bus_initialize(host, &bus_config, dma_channel.into()))?;
+
+    Ok(Self { host })
+  }
+}
+
+impl Drop for SpiBus {
+  fn drop(&mut self) {
+    let _ = esp_ok!(spi_bus_free(self.host));
+  }
+}
@@ -0,0 +1,157 @@
+use core::ptr;
+use std::sync::atomic::{AtomicI32, AtomicU32, Ordering::SeqCst};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use esp_idf_bindgen::{esp_clk_rtc_time, esp_err_t, ESP_ERR_INVALID_ARG, ESP_FAIL};
+
+use crate::EspError;
+
+pub mod sntp;
+mod timezone;
+pub use timezone::*;
+
+/// The Unix time of 2020-01-01, before which the system time is considered to be unset.
+const MIN_VALID_TIME: u64 = 1_577_836_800;
+
+/// Marks the RTC memory below as valid, since it is not initialized after a power-on reset.
+const CORRECTION_MAGIC: u32 = 0x7469_6d65;
+
+const NO_CORRECTION: i32 = i32::min_value();
+
+// Placed in RTC slow memory, which keeps its contents during deep sleep and software resets.
+#[link_section = ".rtc_noinit"]
+static CORRECTION_VALID: AtomicU32 = AtomicU32::new(0);
+#[link_section = ".rtc_noinit"]
+static LAST_CORRECTION_MS: AtomicI32 = AtomicI32::new(NO_CORRECTION);
+
+// The system time at an RTC time, used to estimate the time before SNTP stepped it.
+#[link_section = ".rtc_noinit"]
+static REFERENCE_VALID: AtomicU32 = AtomicU32::new(0);
+#[link_section = ".rtc_noinit"]
+static REFERENCE_SECS: AtomicU32 = AtomicU32::new(0);
+#[link_section = ".rtc_noinit"]
+static REFERENCE_MILLIS: AtomicU32 = AtomicU32::new(0);
+#[link_section = ".rtc_noinit"]
+static REFERENCE_RTC_MS: AtomicU32 = AtomicU32::new(0);
+
+/// A step applied to the system time, see [`last_correction`](fn.last_correction.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Correction {
+  /// The clock was behind and was moved forward.
+  Forward(Duration),
+  /// The clock was ahead and was moved backward.
+  Backward(Duration),
+}
+
+/// The current system time.
+///
+/// The system time is kept by the RTC timer, so it survives deep sleep and software resets,
+/// but not a power-on reset.
+pub fn now() -> SystemTime {
+  let mut tv = libc::timeval { tv_sec: 0, tv_usec: 0 };
+  unsafe { libc::gettimeofday(&mut tv, ptr::null_mut()) };
+  UNIX_EPOCH + Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1000)
+}
+
+/// Whether the system time has been set since the last power-on reset, by [`set`](fn.set.html) or SNTP.
+pub fn is_set() -> bool {
+  now().duration_since(UNIX_EPOCH).map(|time| time.as_secs() >= MIN_VALID_TIME).unwrap_or(false)
+}
+
+/// Set the system time.
+///
+/// If the time was already set, the difference to the previous time is recorded as the drift correction,
+/// see [`last_correction`](fn.last_correction.html).
+pub fn set(time: SystemTime) -> Result<(), EspError> {
+  let was_set = is_set();
+  let previous = now();
+
+  let since_epoch = time.duration_since(UNIX_EPOCH).map_err(|_| EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })?;
+  let tv = libc::timeval { tv_sec: since_epoch.as_secs() as _, tv_usec: since_epoch.subsec_micros() as _ };
+
+  if unsafe { libc::settimeofday(&tv, ptr::null()) } != 0 {
+    return Err(EspError { code: ESP_FAIL as esp_err_t })
+  }
+
+  if was_set {
+    record_correction(time, previous);
+  }
+
+  store_reference(time);
+
+  Ok(())
+}
+
+fn record_correction(time: SystemTime, previous: SystemTime) {
+  let correction_ms = match time.duration_since(previous) {
+    Ok(forward) => forward.as_millis().min(i32::max_value() as u128) as i32,
+    Err(err) => -(err.duration().as_millis().min(i32::max_value() as u128) as i32),
+  };
+
+  LAST_CORRECTION_MS.store(correction_ms, SeqCst);
+  CORRECTION_VALID.store(CORRECTION_MAGIC, SeqCst);
+}
+
+fn rtc_time_ms() -> u32 {
+  rtc_time().as_millis() as u32
+}
+
+fn store_reference(time: SystemTime) {
+  let since_epoch = match time.duration_since(UNIX_EPOCH) {
+    Ok(since_epoch) => since_epoch,
+    Err(_) => return,
+  };
+
+  REFERENCE_VALID.store(0, SeqCst);
+  REFERENCE_SECS.store(since_epoch.as_secs() as u32, SeqCst);
+  REFERENCE_MILLIS.store(since_epoch.subsec_millis(), SeqCst);
+  REFERENCE_RTC_MS.store(rtc_time_ms(), SeqCst);
+  REFERENCE_VALID.store(CORRECTION_MAGIC, SeqCst);
+}
+
+/// The current system time extrapolated from the last reference using the RTC timer.
+fn reference_now() -> Option<SystemTime> {
+  if REFERENCE_VALID.load(SeqCst) != CORRECTION_MAGIC {
+    return None
+  }
+
+  let elapsed_ms = rtc_time_ms().wrapping_sub(REFERENCE_RTC_MS.load(SeqCst));
+  let reference = Duration::new(REFERENCE_SECS.load(SeqCst) as u64, REFERENCE_MILLIS.load(SeqCst) * 1_000_000);
+
+  Some(UNIX_EPOCH + reference + Duration::from_millis(elapsed_ms as u64))
+}
+
+/// Record the correction applied by an SNTP sync, which has already set the system time to `time`.
+///
+/// The previous time is extrapolated from the time set last, so the drift of the RTC is recorded.
+pub(crate) fn record_sync(time: SystemTime) {
+  if let Some(previous) = reference_now() {
+    record_correction(time, previous);
+  }
+
+  store_reference(time);
+}
+
+/// The correction applied the last time the system time was set while it was already valid,
+/// e.g. the drift of the RTC during deep sleep corrected by the first SNTP sync after waking up.
+///
+/// For SNTP syncs, the correction is relative to the time extrapolated from the previous
+/// [`set`](fn.set.html) or sync using the RTC timer.
+///
+/// The correction has millisecond resolution and is kept across deep sleep and software resets.
+pub fn last_correction() -> Option<Correction> {
+  if CORRECTION_VALID.load(SeqCst) != CORRECTION_MAGIC {
+    return None
+  }
+
+  match LAST_CORRECTION_MS.load(SeqCst) {
+    NO_CORRECTION => None,
+    ms if ms < 0 => Some(Correction::Backward(Duration::from_millis(-(ms as i64) as u64))),
+    ms => Some(Correction::Forward(Duration::from_millis(ms as u64))),
+  }
+}
+
+/// The time the RTC timer has been running, which includes time spent in deep sleep.
+pub fn rtc_time() -> Duration {
+  Duration::from_micros(unsafe { esp_clk_rtc_time() })
+}
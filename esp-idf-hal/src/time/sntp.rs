@@ -0,0 +1,330 @@
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use std::ffi::CString;
+use std::sync::{Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering::SeqCst};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  sntp_get_sync_status,
+  sntp_init,
+  sntp_set_sync_interval,
+  sntp_set_sync_mode,
+  sntp_set_time_sync_notification_cb,
+  sntp_setoperatingmode,
+  sntp_setservername,
+  sntp_stop,
+  sntp_sync_mode_t,
+  sntp_sync_status_t,
+  timeval,
+  ESP_ERR_INVALID_ARG,
+  ESP_ERR_INVALID_STATE,
+  SNTP_OPMODE_POLL,
+};
+
+use crate::EspError;
+use crate::init_once::InitOnce;
+
+/// The maximum number of servers, see `CONFIG_LWIP_DHCP_MAX_NTP_SERVERS`.
+const MAX_SERVERS: usize = 4;
+
+/// The shortest sync interval accepted by LwIP.
+const MIN_SYNC_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How the system time is corrected when a new time is received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncMode {
+  /// Set the time immediately.
+  Immediate,
+  /// Gradually adjust the time using `adjtime`, unless the difference is larger than 35 minutes.
+  Smooth,
+}
+
+impl From<SyncMode> for sntp_sync_mode_t {
+  fn from(sync_mode: SyncMode) -> Self {
+    match sync_mode {
+      SyncMode::Immediate => sntp_sync_mode_t::SNTP_SYNC_MODE_IMMED,
+      SyncMode::Smooth => sntp_sync_mode_t::SNTP_SYNC_MODE_SMOOTH,
+    }
+  }
+}
+
+/// The state of the current synchronization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncStatus {
+  /// No time has been received yet.
+  Reset,
+  /// The time is being adjusted in [`Smooth`](enum.SyncMode.html#variant.Smooth) mode.
+  InProgress,
+  /// The time has been synchronized.
+  Completed,
+}
+
+/// Options for [`Sntp::start`](struct.Sntp.html#method.start).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SntpConfig {
+  servers: Vec<String>,
+  sync_mode: SyncMode,
+  sync_interval: Duration,
+}
+
+impl Default for SntpConfig {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl SntpConfig {
+  /// Create a configuration using `pool.ntp.org`, immediate sync mode and a sync interval of one hour.
+  pub fn new() -> Self {
+    Self { servers: Vec::new(), sync_mode: SyncMode::Immediate, sync_interval: Duration::from_secs(3600) }
+  }
+
+  /// Add a server host name or IP address. Servers are queried in the order they were added.
+  ///
+  /// More than one server requires increasing `CONFIG_LWIP_DHCP_MAX_NTP_SERVERS`.
+  pub fn with_server(mut self, server: impl Into<String>) -> Self {
+    self.servers.push(server.into());
+    self
+  }
+
+  pub fn with_sync_mode(mut self, sync_mode: SyncMode) -> Self {
+    self.sync_mode = sync_mode;
+    self
+  }
+
+  /// Set the interval between synchronizations, which must be at least 15 seconds.
+  pub fn with_sync_interval(mut self, sync_interval: Duration) -> Self {
+    self.sync_interval = sync_interval;
+    self
+  }
+
+  pub fn servers(&self) -> &[String] {
+    &self.servers
+  }
+
+  pub fn sync_mode(&self) -> SyncMode {
+    self.sync_mode
+  }
+
+  pub fn sync_interval(&self) -> Duration {
+    self.sync_interval
+  }
+}
+
+type Callback = Box<dyn FnMut(SystemTime) + Send>;
+
+#[derive(Default)]
+struct Inner {
+  sync_count: usize,
+  last_sync: Option<SystemTime>,
+  callback: Option<Callback>,
+  wakers: Vec<Waker>,
+}
+
+/// The synchronization state shared with the notification callback, which is called on the LwIP task.
+struct SyncState {
+  inner: Mutex<Inner>,
+  synced: Condvar,
+}
+
+static SYNC_STATE: InitOnce<SyncState> = InitOnce::new();
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+impl SyncState {
+  fn get() -> &'static Self {
+    SYNC_STATE.get_or_init(|| Self { inner: Mutex::new(Inner::default()), synced: Condvar::new() })
+  }
+}
+
+extern "C" fn time_sync_notification_cb(tv: *mut timeval) {
+  let tv = unsafe { &*tv };
+  let time = UNIX_EPOCH + Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1000);
+
+  super::record_sync(time);
+
+  let state = SyncState::get();
+
+  // The callback is called without holding the lock, so it may replace itself.
+  let mut callback = {
+    let mut inner = state.inner.lock().unwrap();
+    inner.sync_count += 1;
+    inner.last_sync = Some(time);
+
+    for waker in inner.wakers.drain(..) {
+      waker.wake();
+    }
+
+    inner.callback.take()
+  };
+
+  state.synced.notify_all();
+
+  if let Some(callback) = callback.as_mut() {
+    callback(time);
+  }
+
+  let mut inner = state.inner.lock().unwrap();
+  if inner.callback.is_none() {
+    inner.callback = callback;
+  }
+}
+
+/// The SNTP client, which periodically synchronizes the system time while it is running.
+///
+/// Only one client can run at a time. It is stopped when this is dropped.
+pub struct Sntp {
+  _servers: Vec<CString>,
+}
+
+impl fmt::Debug for Sntp {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Sntp").field("status", &self.status()).finish()
+  }
+}
+
+impl Sntp {
+  /// Start the SNTP client. The first synchronization starts as soon as the network is up.
+  ///
+  /// Returns `ESP_ERR_INVALID_STATE` if a client is already running, or `ESP_ERR_INVALID_ARG`
+  /// if the configuration is invalid.
+  pub fn start(config: &SntpConfig) -> Result<Self, EspError> {
+    const INVALID_ARG: EspError = EspError { code: ESP_ERR_INVALID_ARG as esp_err_t };
+
+    if config.servers.len() > MAX_SERVERS || config.sync_interval < MIN_SYNC_INTERVAL {
+      return Err(INVALID_ARG)
+    }
+
+    let mut servers = config.servers.iter()
+      .map(|server| CString::new(server.as_str()).map_err(|_| INVALID_ARG))
+      .collect::<Result<Vec<_>, _>>()?;
+
+    if servers.is_empty() {
+      servers.push(CString::new("pool.ntp.org").unwrap());
+    }
+
+    if RUNNING.compare_and_swap(false, true, SeqCst) {
+      return Err(EspError { code: ESP_ERR_INVALID_STATE as esp_err_t })
+    }
+
+    {
+      let mut inner = SyncState::get().inner.lock().unwrap();
+      inner.sync_count = 0;
+      inner.last_sync = None;
+    }
+
+    unsafe {
+      sntp_setoperatingmode(SNTP_OPMODE_POLL as _);
+
+      // LwIP only stores the pointers, so the names are kept alive by the client.
+      for (i, server) in servers.iter().enumerate() {
+        sntp_setservername(i as _, server.as_ptr() as *mut _);
+      }
+
+      sntp_set_sync_mode(config.sync_mode.into());
+      sntp_set_sync_interval(config.sync_interval.as_millis() as u32);
+      sntp_set_time_sync_notification_cb(Some(time_sync_notification_cb));
+      sntp_init();
+    }
+
+    Ok(Self { _servers: servers })
+  }
+
+  /// The state of the current synchronization.
+  pub fn status(&self) -> SyncStatus {
+    match unsafe { sntp_get_sync_status() } {
+      sntp_sync_status_t::SNTP_SYNC_STATUS_COMPLETED => SyncStatus::Completed,
+      sntp_sync_status_t::SNTP_SYNC_STATUS_IN_PROGRESS => SyncStatus::InProgress,
+      _ => SyncStatus::Reset,
+    }
+  }
+
+  /// The time received by the last synchronization since the client was started.
+  pub fn last_sync(&self) -> Option<SystemTime> {
+    SyncState::get().inner.lock().unwrap().last_sync
+  }
+
+  /// Call `callback` with the received time after every synchronization, replacing any previous callback.
+  ///
+  /// The callback is called on the LwIP task, so it should not block.
+  pub fn on_sync<F>(&mut self, callback: F)
+  where
+    F: FnMut(SystemTime) + Send + 'static,
+  {
+    SyncState::get().inner.lock().unwrap().callback = Some(Box::new(callback));
+  }
+
+  /// Return a future resolving with the received time when the next synchronization completes.
+  pub fn synced(&self) -> SyncFuture<'_> {
+    let sync_count = SyncState::get().inner.lock().unwrap().sync_count;
+    SyncFuture { sntp: self, sync_count }
+  }
+}
+
+impl Drop for Sntp {
+  fn drop(&mut self) {
+    unsafe {
+      sntp_stop();
+      sntp_set_time_sync_notification_cb(None);
+    }
+
+    SyncState::get().inner.lock().unwrap().callback = None;
+    RUNNING.store(false, SeqCst);
+  }
+}
+
+/// A future resolving when the next synchronization completes, see [`Sntp::synced`](struct.Sntp.html#method.synced).
+#[must_use = "futures do nothing unless polled"]
+pub struct SyncFuture<'a> {
+  sntp: &'a Sntp,
+  sync_count: usize,
+}
+
+impl fmt::Debug for SyncFuture<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("SyncFuture").field("sntp", self.sntp).finish()
+  }
+}
+
+impl Future for SyncFuture<'_> {
+  type Output = SystemTime;
+
+  fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+    let mut inner = SyncState::get().inner.lock().unwrap();
+
+    match inner.last_sync {
+      Some(last_sync) if inner.sync_count > self.sync_count => Poll::Ready(last_sync),
+      _ => {
+        inner.wakers.push(cx.waker().clone());
+        Poll::Pending
+      },
+    }
+  }
+}
+
+/// Block until the system time is valid or `timeout` has elapsed, e.g. before validating TLS certificates.
+///
+/// Returns immediately if the time is already valid, e.g. because it was kept by the RTC during deep sleep.
+/// Returns whether the time is valid.
+pub fn wait_for_time(timeout: Duration) -> bool {
+  let state = SyncState::get();
+  let deadline = Instant::now() + timeout;
+  let mut inner = state.inner.lock().unwrap();
+
+  loop {
+    if super::is_set() {
+      return true
+    }
+
+    let now = Instant::now();
+    if now >= deadline {
+      return false
+    }
+
+    inner = state.synced.wait_timeout(inner, deadline - now).unwrap().0;
+  }
+}
@@ -0,0 +1,90 @@
+use core::mem;
+use std::ffi::CString;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use esp_idf_bindgen::{esp_err_t, ESP_ERR_INVALID_ARG, ESP_FAIL};
+
+use crate::EspError;
+
+// `tzset` has no `libc` bindings for newlib.
+extern "C" {
+  fn tzset();
+}
+
+/// Set the local timezone using a POSIX `TZ` string including its DST rules,
+/// e.g. `CET-1CEST,M3.5.0,M10.5.0/3` for Central European Time.
+pub fn set_timezone(tz: &str) -> Result<(), EspError> {
+  let tz = CString::new(tz).map_err(|_| EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })?;
+
+  if unsafe { libc::setenv(b"TZ\0".as_ptr() as *const _, tz.as_ptr(), 1) } != 0 {
+    return Err(EspError { code: ESP_FAIL as esp_err_t })
+  }
+
+  unsafe { tzset() };
+  Ok(())
+}
+
+/// The offset of the local timezone from UTC at a point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalOffset {
+  seconds: i32,
+  dst: bool,
+}
+
+impl LocalOffset {
+  /// The offset in seconds east of UTC.
+  pub fn seconds(&self) -> i32 {
+    self.seconds
+  }
+
+  /// Whether daylight saving time is in effect.
+  pub fn is_dst(&self) -> bool {
+    self.dst
+  }
+}
+
+/// The number of days since 1970-01-01 of a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+  let year = if month <= 2 { year - 1 } else { year };
+  let era = if year >= 0 { year } else { year - 399 } / 400;
+  let year_of_era = year - era * 400;
+  let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+  let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+  era * 146_097 + day_of_era - 719_468
+}
+
+fn tm_to_seconds(tm: &libc::tm) -> i64 {
+  let days = days_from_civil(tm.tm_year as i64 + 1900, tm.tm_mon as i64 + 1, tm.tm_mday as i64);
+  days * 86_400 + tm.tm_hour as i64 * 3600 + tm.tm_min as i64 * 60 + tm.tm_sec as i64
+}
+
+/// The offset of the local timezone from UTC at `time`, see [`set_timezone`](fn.set_timezone.html).
+pub fn local_offset(time: SystemTime) -> LocalOffset {
+  let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or(Duration::from_secs(0));
+  let secs = since_epoch.as_secs() as libc::time_t;
+
+  let mut local: libc::tm = unsafe { mem::zeroed() };
+  unsafe { libc::localtime_r(&secs, &mut local) };
+
+  LocalOffset {
+    seconds: (tm_to_seconds(&local) - secs as i64) as i32,
+    dst: local.tm_isdst > 0,
+  }
+}
+
+/// Convert `time` to the local timezone, see [`set_timezone`](fn.set_timezone.html).
+#[cfg(feature = "chrono")]
+pub fn to_local(time: SystemTime) -> chrono::DateTime<chrono::FixedOffset> {
+  use chrono::{FixedOffset, NaiveDateTime, TimeZone};
+
+  let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or(Duration::from_secs(0));
+  let utc = NaiveDateTime::from_timestamp(since_epoch.as_secs() as i64, since_epoch.subsec_nanos());
+
+  FixedOffset::east(local_offset(time).seconds()).from_utc_datetime(&utc)
+}
+
+/// The current time in the local timezone, see [`set_timezone`](fn.set_timezone.html).
+#[cfg(feature = "chrono")]
+pub fn local_now() -> chrono::DateTime<chrono::FixedOffset> {
+  to_local(super::now())
+}
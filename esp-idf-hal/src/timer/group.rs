@@ -0,0 +1,345 @@
+use core::fmt;
+use std::sync::{mpsc, Arc};
+use std::sync::atomic::{AtomicBool, Ordering::SeqCst};
+use std::thread::{self, JoinHandle};
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  timer_alarm_t,
+  timer_autoreload_t,
+  timer_config_t,
+  timer_count_dir_t,
+  timer_deinit,
+  timer_get_counter_value,
+  timer_group_t,
+  timer_idx_t,
+  timer_init,
+  timer_intr_mode_t,
+  timer_isr_callback_add,
+  timer_isr_callback_remove,
+  timer_pause,
+  timer_set_alarm,
+  timer_set_alarm_value,
+  timer_set_auto_reload,
+  timer_set_counter_value,
+  timer_set_divider,
+  timer_start,
+  timer_start_t,
+  ulTaskNotifyTake,
+  vTaskNotifyGiveFromISR,
+  xTaskGetCurrentTaskHandle,
+  BaseType_t,
+  TaskHandle_t,
+  ESP_ERR_INVALID_ARG,
+  ESP_ERR_INVALID_STATE,
+  ESP_FAIL,
+};
+
+use crate::EspError;
+
+/// The timer groups are clocked by the 80 MHz APB clock.
+const APB_CLK_FREQ: u64 = 80_000_000;
+
+/// The number of ticks the alarm thread waits for a notification before checking whether it was stopped.
+const NOTIFY_TIMEOUT_TICKS: u32 = 10;
+
+/// A hardware timer group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerGroup {
+  Group0,
+  Group1,
+}
+
+impl From<TimerGroup> for timer_group_t {
+  fn from(group: TimerGroup) -> Self {
+    match group {
+      TimerGroup::Group0 => timer_group_t::TIMER_GROUP_0,
+      TimerGroup::Group1 => timer_group_t::TIMER_GROUP_1,
+    }
+  }
+}
+
+/// A timer within a [`TimerGroup`](enum.TimerGroup.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerIndex {
+  Timer0,
+  Timer1,
+}
+
+impl From<TimerIndex> for timer_idx_t {
+  fn from(index: TimerIndex) -> Self {
+    match index {
+      TimerIndex::Timer0 => timer_idx_t::TIMER_0,
+      TimerIndex::Timer1 => timer_idx_t::TIMER_1,
+    }
+  }
+}
+
+/// The direction in which a [`HwTimer`](struct.HwTimer.html) counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountDirection {
+  Up,
+  Down,
+}
+
+impl From<CountDirection> for timer_count_dir_t {
+  fn from(direction: CountDirection) -> Self {
+    match direction {
+      CountDirection::Up => timer_count_dir_t::TIMER_COUNT_UP,
+      CountDirection::Down => timer_count_dir_t::TIMER_COUNT_DOWN,
+    }
+  }
+}
+
+/// Options for a [`HwTimer`](struct.HwTimer.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HwTimerConfig {
+  divider: u32,
+  direction: CountDirection,
+  auto_reload: bool,
+}
+
+impl Default for HwTimerConfig {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl HwTimerConfig {
+  /// Create a configuration counting up at 1 MHz without auto-reload.
+  pub fn new() -> Self {
+    Self { divider: 80, direction: CountDirection::Up, auto_reload: false }
+  }
+
+  /// Set the prescaler dividing the 80 MHz APB clock, between 2 and 65536.
+  pub fn with_divider(mut self, divider: u32) -> Self {
+    self.divider = divider;
+    self
+  }
+
+  pub fn with_direction(mut self, direction: CountDirection) -> Self {
+    self.direction = direction;
+    self
+  }
+
+  /// Set whether the counter is reset to its initial value when the alarm triggers.
+  pub fn with_auto_reload(mut self, auto_reload: bool) -> Self {
+    self.auto_reload = auto_reload;
+    self
+  }
+
+  pub fn divider(&self) -> u32 {
+    self.divider
+  }
+
+  pub fn direction(&self) -> CountDirection {
+    self.direction
+  }
+
+  pub fn auto_reload(&self) -> bool {
+    self.auto_reload
+  }
+}
+
+fn check_divider(divider: u32) -> Result<(), EspError> {
+  if (2..=65536).contains(&divider) {
+    Ok(())
+  } else {
+    Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+  }
+}
+
+/// The task notified by the alarm ISR.
+struct AlarmTask {
+  task: TaskHandle_t,
+}
+
+extern "C" fn alarm_isr(arg: *mut libc::c_void) -> bool {
+  let alarm_task = unsafe { &*(arg as *const AlarmTask) };
+
+  let mut higher_priority_task_woken: BaseType_t = 0;
+  unsafe { vTaskNotifyGiveFromISR(alarm_task.task, &mut higher_priority_task_woken) };
+
+  // The driver yields after the ISR if a higher priority task was woken.
+  higher_priority_task_woken != 0
+}
+
+/// A callback called on a separate thread whenever the alarm of a timer triggers.
+struct Alarm {
+  task: *mut AlarmTask,
+  stopped: Arc<AtomicBool>,
+  thread: Option<JoinHandle<()>>,
+}
+
+/// A 64-bit general-purpose hardware timer.
+///
+/// The timer is paused after it is created. It is deinitialized when this is dropped.
+pub struct HwTimer {
+  group: TimerGroup,
+  index: TimerIndex,
+  divider: u32,
+  alarm: Option<Alarm>,
+}
+
+unsafe impl Send for HwTimer {}
+
+impl fmt::Debug for HwTimer {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("HwTimer")
+      .field("group", &self.group)
+      .field("index", &self.index)
+      .field("divider", &self.divider)
+      .finish()
+  }
+}
+
+impl HwTimer {
+  /// Initialize timer `index` of `group`.
+  ///
+  /// Returns `ESP_ERR_INVALID_ARG` if the divider is out of range.
+  pub fn new(group: TimerGroup, index: TimerIndex, config: &HwTimerConfig) -> Result<Self, EspError> {
+    check_divider(config.divider)?;
+
+    let timer_config = timer_config_t {
+      alarm_en: timer_alarm_t::TIMER_ALARM_DIS,
+      counter_en: timer_start_t::TIMER_PAUSE,
+      intr_type: timer_intr_mode_t::TIMER_INTR_LEVEL,
+      counter_dir: config.direction.into(),
+      auto_reload: if config.auto_reload { timer_autoreload_t::TIMER_AUTORELOAD_EN } else { timer_autoreload_t::TIMER_AUTORELOAD_DIS },
+      divider: config.divider,
+    };
+
+    esp_ok!(timer_init(group.into(), index.into(), &timer_config))?;
+
+    Ok(Self { group, index, divider: config.divider, alarm: None })
+  }
+
+  pub fn group(&self) -> TimerGroup {
+    self.group
+  }
+
+  pub fn index(&self) -> TimerIndex {
+    self.index
+  }
+
+  /// The number of counter ticks per second.
+  pub fn tick_rate(&self) -> u64 {
+    APB_CLK_FREQ / self.divider as u64
+  }
+
+  /// Set the prescaler dividing the 80 MHz APB clock, between 2 and 65536.
+  pub fn set_divider(&mut self, divider: u32) -> Result<(), EspError> {
+    check_divider(divider)?;
+    esp_ok!(timer_set_divider(self.group.into(), self.index.into(), divider))?;
+    self.divider = divider;
+    Ok(())
+  }
+
+  /// Start counting.
+  pub fn start(&mut self) -> Result<(), EspError> {
+    esp_ok!(timer_start(self.group.into(), self.index.into()))
+  }
+
+  /// Stop counting, keeping the current counter value.
+  pub fn pause(&mut self) -> Result<(), EspError> {
+    esp_ok!(timer_pause(self.group.into(), self.index.into()))
+  }
+
+  /// The current counter value in ticks, e.g. to timestamp an input event.
+  pub fn counter(&self) -> Result<u64, EspError> {
+    let mut value = 0;
+    esp_ok!(timer_get_counter_value(self.group.into(), self.index.into(), &mut value))?;
+    Ok(value)
+  }
+
+  /// Set the counter value in ticks. This is also the value the counter is reset to by auto-reload.
+  pub fn set_counter(&mut self, value: u64) -> Result<(), EspError> {
+    esp_ok!(timer_set_counter_value(self.group.into(), self.index.into(), value))
+  }
+
+  /// Set whether the counter is reset when the alarm triggers.
+  pub fn set_auto_reload(&mut self, auto_reload: bool) -> Result<(), EspError> {
+    let auto_reload = if auto_reload { timer_autoreload_t::TIMER_AUTORELOAD_EN } else { timer_autoreload_t::TIMER_AUTORELOAD_DIS };
+    esp_ok!(timer_set_auto_reload(self.group.into(), self.index.into(), auto_reload))
+  }
+
+  /// Trigger the alarm when the counter reaches `value` ticks, or disable it if `value` is `None`.
+  ///
+  /// Without auto-reload, the alarm is disabled after it has triggered and must be set again.
+  pub fn set_alarm(&mut self, value: Option<u64>) -> Result<(), EspError> {
+    if let Some(value) = value {
+      esp_ok!(timer_set_alarm_value(self.group.into(), self.index.into(), value))?;
+      esp_ok!(timer_set_alarm(self.group.into(), self.index.into(), timer_alarm_t::TIMER_ALARM_EN))
+    } else {
+      esp_ok!(timer_set_alarm(self.group.into(), self.index.into(), timer_alarm_t::TIMER_ALARM_DIS))
+    }
+  }
+
+  /// Call `callback` whenever the alarm triggers, replacing any previous callback.
+  ///
+  /// The interrupt only notifies a separate thread, which calls the callback in task context.
+  pub fn on_alarm<F>(&mut self, mut callback: F) -> Result<(), EspError>
+  where
+    F: FnMut() + Send + 'static,
+  {
+    self.remove_alarm_callback();
+
+    let (sender, receiver) = mpsc::sync_channel(1);
+    let stopped = Arc::new(AtomicBool::new(false));
+    let thread_stopped = Arc::clone(&stopped);
+
+    let thread = thread::Builder::new()
+      .name("timer_alarm".into())
+      .stack_size(3072)
+      .spawn(move || {
+        let _ = sender.send(unsafe { xTaskGetCurrentTaskHandle() } as usize);
+
+        while !thread_stopped.load(SeqCst) {
+          if unsafe { ulTaskNotifyTake(1, NOTIFY_TIMEOUT_TICKS) } != 0 {
+            callback();
+          }
+        }
+      })
+      .map_err(|_| EspError { code: ESP_FAIL as esp_err_t })?;
+
+    let task = receiver.recv().map_err(|_| EspError { code: ESP_ERR_INVALID_STATE as esp_err_t })?;
+    let task = Box::into_raw(Box::new(AlarmTask { task: task as TaskHandle_t }));
+
+    let mut alarm = Alarm { task, stopped, thread: Some(thread) };
+
+    esp_ok!(timer_isr_callback_add(self.group.into(), self.index.into(), Some(alarm_isr), task as *mut _, 0))
+      .map_err(|err| {
+        Self::stop_alarm(&mut alarm);
+        drop(unsafe { Box::from_raw(alarm.task) });
+        err
+      })?;
+
+    self.alarm = Some(alarm);
+    Ok(())
+  }
+
+  /// Stop calling the alarm callback.
+  pub fn remove_alarm_callback(&mut self) {
+    if let Some(mut alarm) = self.alarm.take() {
+      let _ = esp_ok!(timer_isr_callback_remove(self.group.into(), self.index.into()));
+      Self::stop_alarm(&mut alarm);
+      drop(unsafe { Box::from_raw(alarm.task) });
+    }
+  }
+
+  fn stop_alarm(alarm: &mut Alarm) {
+    alarm.stopped.store(true, SeqCst);
+
+    if let Some(thread) = alarm.thread.take() {
+      let _ = thread.join();
+    }
+  }
+}
+
+impl Drop for HwTimer {
+  fn drop(&mut self) {
+    let _ = self.pause();
+    self.remove_alarm_callback();
+    let _ = esp_ok!(timer_deinit(self.group.into(), self.index.into()));
+  }
+}
@@ -0,0 +1,252 @@
+use core::fmt;
+use core::future::Future;
+use core::mem;
+use core::pin::Pin;
+use core::ptr;
+use core::task::{Context, Poll, Waker};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use esp_idf_bindgen::{
+  esp_timer_create,
+  esp_timer_create_args_t,
+  esp_timer_delete,
+  esp_timer_dispatch_t,
+  esp_timer_get_time,
+  esp_timer_handle_t,
+  esp_timer_start_once,
+  esp_timer_start_periodic,
+  esp_timer_stop,
+};
+
+use crate::EspError;
+use crate::init_once::InitOnce;
+
+mod group;
+pub use group::*;
+
+type Callback = Box<dyn FnMut() + Send>;
+
+/// An `esp_timer` whose callback is called on the `esp_timer` task.
+struct Timer {
+  handle: esp_timer_handle_t,
+  callback: *mut Callback,
+}
+
+unsafe impl Send for Timer {}
+
+extern "C" fn timer_callback(arg: *mut libc::c_void) {
+  let callback = unsafe { &mut *(arg as *mut Callback) };
+  callback();
+}
+
+impl Timer {
+  fn new(callback: Callback) -> Result<Self, EspError> {
+    let callback: *mut Callback = Box::into_raw(Box::new(callback));
+
+    let args = esp_timer_create_args_t {
+      callback: Some(timer_callback),
+      arg: callback as *mut _,
+      dispatch_method: esp_timer_dispatch_t::ESP_TIMER_TASK,
+      name: b"rust_timer\0".as_ptr() as *const _,
+    };
+
+    let mut handle = ptr::null_mut();
+    if let Err(err) = esp_ok!(esp_timer_create(&args, &mut handle)) {
+      drop(unsafe { Box::from_raw(callback) });
+      return Err(err)
+    }
+
+    Ok(Self { handle, callback })
+  }
+}
+
+/// Frees the callbacks of dropped timers on the `esp_timer` task.
+///
+/// `esp_timer_stop` does not wait for a running callback, but the `esp_timer` task calls
+/// callbacks one after another, so once the reaper runs, no dropped callback is running anymore.
+struct Reaper {
+  handle: usize,
+  callbacks: Mutex<Vec<usize>>,
+}
+
+static REAPER: InitOnce<Reaper> = InitOnce::new();
+
+extern "C" fn reaper_callback(_arg: *mut libc::c_void) {
+  if let Some(reaper) = REAPER.get() {
+    let callbacks = mem::replace(&mut *reaper.callbacks.lock().unwrap(), Vec::new());
+
+    for callback in callbacks {
+      drop(unsafe { Box::from_raw(callback as *mut Callback) });
+    }
+  }
+}
+
+impl Reaper {
+  fn get() -> Result<&'static Self, EspError> {
+    REAPER.get_or_try_init(|| {
+      let args = esp_timer_create_args_t {
+        callback: Some(reaper_callback),
+        arg: ptr::null_mut(),
+        dispatch_method: esp_timer_dispatch_t::ESP_TIMER_TASK,
+        name: b"rust_timer_reaper\0".as_ptr() as *const _,
+      };
+
+      let mut handle = ptr::null_mut();
+      esp_ok!(esp_timer_create(&args, &mut handle))?;
+
+      Ok(Self { handle: handle as usize, callbacks: Mutex::new(Vec::new()) })
+    })
+  }
+}
+
+impl Drop for Timer {
+  fn drop(&mut self) {
+    let _ = esp_ok!(esp_timer_stop(self.handle));
+    let _ = esp_ok!(esp_timer_delete(self.handle));
+
+    // If the reaper cannot be created, leak the callback rather than freeing it while it may be running.
+    if let Ok(reaper) = Reaper::get() {
+      reaper.callbacks.lock().unwrap().push(self.callback as usize);
+
+      // Fails if the reaper is already scheduled, in which case it also frees this callback.
+      let _ = esp_ok!(esp_timer_start_once(reaper.handle as esp_timer_handle_t, 0));
+    }
+  }
+}
+
+/// The time since startup with microsecond resolution.
+pub fn uptime() -> Duration {
+  Duration::from_micros(unsafe { esp_timer_get_time() } as u64)
+}
+
+/// A callback which is called once after a delay.
+///
+/// The callback runs on the `esp_timer` task, so it should not block. It is cancelled when this is dropped.
+#[must_use = "the timer is cancelled immediately if it is dropped"]
+pub struct OneShot {
+  timer: Timer,
+}
+
+impl fmt::Debug for OneShot {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("OneShot").finish()
+  }
+}
+
+impl OneShot {
+  /// Call `callback` once `delay` has elapsed.
+  pub fn after<F>(delay: Duration, callback: F) -> Result<Self, EspError>
+  where
+    F: FnOnce() + Send + 'static,
+  {
+    let mut callback = Some(callback);
+    let timer = Timer::new(Box::new(move || {
+      if let Some(callback) = callback.take() {
+        callback();
+      }
+    }))?;
+
+    esp_ok!(esp_timer_start_once(timer.handle, delay.as_micros() as u64))?;
+    Ok(Self { timer })
+  }
+
+  /// Cancel the timer if the callback was not called yet.
+  pub fn cancel(self) {}
+}
+
+/// A callback which is called periodically.
+///
+/// The callback runs on the `esp_timer` task, so it should not block. It is cancelled when this is dropped.
+#[must_use = "the timer is cancelled immediately if it is dropped"]
+pub struct Periodic {
+  timer: Timer,
+}
+
+impl fmt::Debug for Periodic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Periodic").finish()
+  }
+}
+
+impl Periodic {
+  /// Call `callback` every `period`, starting after the first period.
+  pub fn every<F>(period: Duration, callback: F) -> Result<Self, EspError>
+  where
+    F: FnMut() + Send + 'static,
+  {
+    let timer = Timer::new(Box::new(callback))?;
+    esp_ok!(esp_timer_start_periodic(timer.handle, period.as_micros() as u64))?;
+    Ok(Self { timer })
+  }
+
+  /// Stop calling the callback.
+  pub fn cancel(self) {}
+}
+
+#[derive(Debug)]
+struct SleepState {
+  elapsed: bool,
+  waker: Option<Waker>,
+}
+
+/// A future resolving once a duration has elapsed, see [`sleep`](fn.sleep.html).
+#[must_use = "futures do nothing unless polled"]
+pub struct Sleep {
+  duration: Duration,
+  state: Arc<Mutex<SleepState>>,
+  timer: Option<OneShot>,
+}
+
+impl fmt::Debug for Sleep {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Sleep").field("duration", &self.duration).finish()
+  }
+}
+
+impl Future for Sleep {
+  type Output = Result<(), EspError>;
+
+  fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+    {
+      let mut state = self.state.lock().unwrap();
+
+      if state.elapsed {
+        return Poll::Ready(Ok(()))
+      }
+
+      state.waker = Some(cx.waker().clone());
+    }
+
+    if self.timer.is_none() {
+      let state = Arc::clone(&self.state);
+
+      let timer = OneShot::after(self.duration, move || {
+        let mut state = state.lock().unwrap();
+        state.elapsed = true;
+
+        if let Some(waker) = state.waker.take() {
+          waker.wake();
+        }
+      });
+
+      match timer {
+        Ok(timer) => self.timer = Some(timer),
+        Err(err) => return Poll::Ready(Err(err)),
+      }
+    }
+
+    Poll::Pending
+  }
+}
+
+/// Wait asynchronously until `duration` has elapsed.
+///
+/// Unlike `std::thread::sleep`, this has microsecond resolution and does not block the task.
+pub fn sleep(duration: Duration) -> Sleep {
+  Sleep {
+    duration,
+    state: Arc::new(Mutex::new(SleepState { elapsed: false, waker: None })),
+    timer: None,
+  }
+}
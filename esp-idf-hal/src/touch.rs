@@ -0,0 +1,135 @@
+use core::fmt;
+use core::mem::transmute;
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  esp_sleep_enable_touchpad_wakeup,
+  esp_sleep_get_touchpad_wakeup_status,
+  touch_fsm_mode_t,
+  touch_pad_config,
+  touch_pad_filter_start,
+  touch_pad_get_thresh,
+  touch_pad_init,
+  touch_pad_read_filtered,
+  touch_pad_read_raw_data,
+  touch_pad_set_fsm_mode,
+  touch_pad_set_thresh,
+  touch_pad_t,
+  ESP_ERR_INVALID_ARG,
+};
+
+use crate::EspError;
+use crate::gpio::Pin;
+use crate::init_once::InitOnce;
+
+/// The period of the software IIR filter in milliseconds.
+const FILTER_PERIOD_MS: u32 = 10;
+
+/// The GPIO of each touch channel.
+const TOUCH_PINS: [u8; 10] = [4, 0, 2, 15, 13, 12, 14, 27, 33, 32];
+
+/// Initialize the touch pad driver with the measurement timer and filter if it is not initialized yet.
+fn touch_init() -> Result<(), EspError> {
+  static TOUCH: InitOnce<()> = InitOnce::new();
+
+  TOUCH.get_or_try_init(|| {
+    esp_ok!(touch_pad_init())?;
+    esp_ok!(touch_pad_set_fsm_mode(touch_fsm_mode_t::TOUCH_FSM_MODE_TIMER))?;
+    esp_ok!(touch_pad_filter_start(FILTER_PERIOD_MS))
+  })?;
+
+  Ok(())
+}
+
+/// A capacitive touch pad.
+///
+/// Touch channels 0 to 9 are connected to GPIOs 4, 0, 2, 15, 13, 12, 14, 27, 33 and 32.
+/// Touching a pad lowers its value.
+pub struct TouchPad<P: Pin> {
+  pin: P,
+  channel: u8,
+}
+
+impl<P: Pin> fmt::Debug for TouchPad<P> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("TouchPad")
+      .field("pin", &self.pin.number())
+      .field("channel", &self.channel)
+      .finish()
+  }
+}
+
+impl<P: Pin> TouchPad<P> {
+  /// Configure `pin` as a touch pad with the given threshold, see [`set_threshold`](#method.set_threshold).
+  ///
+  /// Returns `ESP_ERR_INVALID_ARG` if the pin is not connected to a touch channel.
+  pub fn new(pin: P, threshold: u16) -> Result<Self, EspError> {
+    let channel = TOUCH_PINS.iter().position(|&gpio| gpio == pin.number())
+      .ok_or(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })? as u8;
+
+    touch_init()?;
+
+    let touch_pad = Self { pin, channel };
+    esp_ok!(touch_pad_config(touch_pad.pad(), threshold))?;
+    Ok(touch_pad)
+  }
+
+  fn pad(&self) -> touch_pad_t {
+    unsafe { transmute(self.channel as u32) }
+  }
+
+  /// The touch channel of this pad.
+  pub fn channel(&self) -> u8 {
+    self.channel
+  }
+
+  /// Read the unfiltered value.
+  pub fn read_raw(&self) -> Result<u16, EspError> {
+    let mut value = 0;
+    esp_ok!(touch_pad_read_raw_data(self.pad(), &mut value))?;
+    Ok(value)
+  }
+
+  /// Read the filtered value.
+  pub fn read_filtered(&self) -> Result<u16, EspError> {
+    let mut value = 0;
+    esp_ok!(touch_pad_read_filtered(self.pad(), &mut value))?;
+    Ok(value)
+  }
+
+  /// Whether the filtered value is below the threshold.
+  pub fn is_touched(&self) -> Result<bool, EspError> {
+    Ok(self.read_filtered()? < self.threshold()?)
+  }
+
+  /// Set the threshold below which the pad is considered touched, e.g. two thirds of the untouched value.
+  ///
+  /// The threshold also determines the wakeup from deep sleep, see [`enable_wakeup`](fn.enable_wakeup.html).
+  /// No touch interrupt is registered, so pads have to be polled using [`is_touched`](#method.is_touched).
+  pub fn set_threshold(&mut self, threshold: u16) -> Result<(), EspError> {
+    esp_ok!(touch_pad_set_thresh(self.pad(), threshold))
+  }
+
+  pub fn threshold(&self) -> Result<u16, EspError> {
+    let mut threshold = 0;
+    esp_ok!(touch_pad_get_thresh(self.pad(), &mut threshold))?;
+    Ok(threshold)
+  }
+
+  /// Return the pin.
+  pub fn release(self) -> P {
+    self.pin
+  }
+}
+
+/// Wake up from deep sleep when any configured touch pad falls below its threshold.
+pub fn enable_wakeup() -> Result<(), EspError> {
+  esp_ok!(esp_sleep_enable_touchpad_wakeup())
+}
+
+/// The channel of the touch pad which caused the wakeup from deep sleep.
+pub fn wakeup_channel() -> Option<u8> {
+  let pad = unsafe { esp_sleep_get_touchpad_wakeup_status() } as u32;
+
+  if (pad as usize) < TOUCH_PINS.len() { Some(pad as u8) } else { None }
+}
@@ -0,0 +1,153 @@
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::ptr;
+use std::time::{Duration, Instant};
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  uart_disable_pattern_det_intr,
+  uart_enable_pattern_det_baud_intr,
+  uart_event_t,
+  uart_event_type_t,
+  uart_pattern_pop_pos,
+  uart_pattern_queue_reset,
+  xQueueReceive,
+  ESP_ERR_INVALID_ARG,
+  ESP_ERR_INVALID_STATE,
+};
+
+use crate::EspError;
+use crate::gpio::{OutputCapable, Pin};
+
+use super::{ticks, Uart, UartConfig, UartPort};
+
+/// The number of bit periods between pattern characters within which they are considered consecutive.
+const PATTERN_CHAR_TIMEOUT: i32 = 9;
+
+/// An event received by a [`Uart`](struct.Uart.html) created using [`with_events`](struct.Uart.html#method.with_events).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UartEvent {
+  /// Data was received and can be read. `timeout` is set if the sender paused after it.
+  Data { len: usize, timeout: bool },
+  /// A break condition was detected on the RX line, after `len` bytes of data were received.
+  Break { len: usize },
+  /// The pattern set using [`enable_pattern_detection`](struct.Uart.html#method.enable_pattern_detection)
+  /// was received, starting `position` bytes into the unread data.
+  ///
+  /// `position` is `None` if the pattern positions were not read fast enough to fit in the position queue.
+  Pattern { position: Option<usize> },
+}
+
+/// A UART receive error.
+///
+/// After an overflow, received data was lost. The input should be cleared using
+/// [`clear_input`](struct.Uart.html#method.clear_input) to resynchronize.
+#[derive(Debug, Clone)]
+pub enum UartError {
+  /// An internal error.
+  Internal(EspError),
+  /// The hardware FIFO overflowed because the driver could not empty it in time.
+  FifoOverflow,
+  /// The RX buffer is full because data is not read fast enough.
+  BufferFull,
+  /// A character without a valid stop bit was received, e.g. because of a baud rate mismatch.
+  Frame,
+  /// A character with an invalid parity bit was received.
+  Parity,
+}
+
+impl From<EspError> for UartError {
+  fn from(esp_error: EspError) -> Self {
+    Self::Internal(esp_error)
+  }
+}
+
+impl fmt::Display for UartError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Internal(esp_error) => esp_error.fmt(f),
+      Self::FifoOverflow => write!(f, "UART FIFO overflow"),
+      Self::BufferFull => write!(f, "UART RX buffer full"),
+      Self::Frame => write!(f, "UART frame error"),
+      Self::Parity => write!(f, "UART parity error"),
+    }
+  }
+}
+
+impl Uart {
+  /// Install the driver like [`new`](#method.new), with a queue of `queue_size` events
+  /// which are received using [`next_event`](#method.next_event).
+  pub fn with_events<TX: OutputCapable, RX: Pin>(
+    port: UartPort, tx_pin: TX, rx_pin: RX, config: &UartConfig, queue_size: usize,
+  ) -> Result<Self, EspError> {
+    if queue_size == 0 {
+      return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+    }
+
+    let mut queue = ptr::null_mut();
+    let mut uart = Self::install(port, tx_pin.number(), rx_pin.number(), config, queue_size, &mut queue)?;
+    uart.event_queue = queue;
+    uart.event_queue_size = queue_size;
+    Ok(uart)
+  }
+
+  /// Block until the next event is received, or return `None` if `timeout` has elapsed.
+  ///
+  /// Overflows and receive errors are returned as errors. Returns `ESP_ERR_INVALID_STATE`
+  /// if the driver was not created using [`with_events`](#method.with_events).
+  pub fn next_event(&mut self, timeout: Option<Duration>) -> Result<Option<UartEvent>, UartError> {
+    if self.event_queue.is_null() {
+      return Err(EspError { code: ESP_ERR_INVALID_STATE as esp_err_t }.into())
+    }
+
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+    // Skip events which are not exposed, only returning `None` once the timeout has elapsed.
+    loop {
+      let remaining = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+      let mut event = MaybeUninit::<uart_event_t>::uninit();
+      if unsafe { xQueueReceive(self.event_queue, event.as_mut_ptr() as *mut _, ticks(remaining)) } == 0 {
+        return Ok(None)
+      }
+      let event = unsafe { event.assume_init() };
+
+      if let Some(event) = self.convert_event(&event)? {
+        return Ok(Some(event))
+      }
+    }
+  }
+
+  fn convert_event(&self, event: &uart_event_t) -> Result<Option<UartEvent>, UartError> {
+    Ok(Some(match event.type_ {
+      uart_event_type_t::UART_DATA => UartEvent::Data { len: event.size, timeout: event.timeout_flag },
+      uart_event_type_t::UART_BREAK | uart_event_type_t::UART_DATA_BREAK => UartEvent::Break { len: event.size },
+      uart_event_type_t::UART_PATTERN_DET => {
+        let position = unsafe { uart_pattern_pop_pos(self.port.into()) };
+        UartEvent::Pattern { position: if position < 0 { None } else { Some(position as usize) } }
+      },
+      uart_event_type_t::UART_FIFO_OVF => return Err(UartError::FifoOverflow),
+      uart_event_type_t::UART_BUFFER_FULL => return Err(UartError::BufferFull),
+      uart_event_type_t::UART_FRAME_ERR => return Err(UartError::Frame),
+      uart_event_type_t::UART_PARITY_ERR => return Err(UartError::Parity),
+      _ => return Ok(None),
+    }))
+  }
+
+  /// Emit a [`Pattern`](enum.UartEvent.html#variant.Pattern) event whenever `count` consecutive `byte`s
+  /// are received, e.g. `b'\n'` once for line-oriented protocols, or `b'+'` three times for AT escapes.
+  ///
+  /// The positions of up to as many patterns as the event queue size are kept until they are read.
+  pub fn enable_pattern_detection(&mut self, byte: u8, count: u8) -> Result<(), EspError> {
+    if self.event_queue.is_null() {
+      return Err(EspError { code: ESP_ERR_INVALID_STATE as esp_err_t })
+    }
+
+    esp_ok!(uart_enable_pattern_det_baud_intr(self.port.into(), byte as _, count, PATTERN_CHAR_TIMEOUT, 0, 0))?;
+    esp_ok!(uart_pattern_queue_reset(self.port.into(), self.event_queue_size as i32))
+  }
+
+  pub fn disable_pattern_detection(&mut self) -> Result<(), EspError> {
+    esp_ok!(uart_disable_pattern_det_intr(self.port.into()))
+  }
+}
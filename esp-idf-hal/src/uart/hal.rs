@@ -0,0 +1,38 @@
+use embedded_hal_02::serial::{Read, Write};
+use esp_idf_bindgen::{esp_err_t, uart_read_bytes, uart_wait_tx_done, uart_write_bytes, ESP_ERR_TIMEOUT, ESP_FAIL};
+
+use crate::EspError;
+
+use super::Uart;
+
+impl Read<u8> for Uart {
+  type Error = EspError;
+
+  fn read(&mut self) -> nb::Result<u8, Self::Error> {
+    let mut byte = 0;
+
+    match unsafe { uart_read_bytes(self.port.into(), &mut byte, 1, 0) } {
+      1 => Ok(byte),
+      0 => Err(nb::Error::WouldBlock),
+      _ => Err(nb::Error::Other(EspError { code: ESP_FAIL as esp_err_t })),
+    }
+  }
+}
+
+impl Write<u8> for Uart {
+  type Error = EspError;
+
+  fn write(&mut self, byte: u8) -> nb::Result<(), Self::Error> {
+    match unsafe { uart_write_bytes(self.port.into(), &byte as *const u8 as *const _, 1) } {
+      1 => Ok(()),
+      _ => Err(nb::Error::Other(EspError { code: ESP_FAIL as esp_err_t })),
+    }
+  }
+
+  fn flush(&mut self) -> nb::Result<(), Self::Error> {
+    match esp_ok!(uart_wait_tx_done(self.port.into(), 0)) {
+      Err(err) if err.code == ESP_ERR_TIMEOUT as esp_err_t => Err(nb::Error::WouldBlock),
+      result => result.map_err(nb::Error::Other),
+    }
+  }
+}
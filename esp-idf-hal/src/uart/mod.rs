@@ -0,0 +1,394 @@
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::ptr;
+use std::io;
+use std::time::{Duration, Instant};
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  uart_config_t,
+  uart_driver_delete,
+  uart_driver_install,
+  uart_flush_input,
+  uart_get_buffered_data_len,
+  uart_hw_flowcontrol_t,
+  uart_param_config,
+  uart_parity_t,
+  uart_port_t,
+  uart_read_bytes,
+  uart_set_baudrate,
+  uart_set_pin,
+  uart_stop_bits_t,
+  uart_wait_tx_done,
+  uart_word_length_t,
+  uart_write_bytes,
+  QueueHandle_t,
+  TickType_t,
+  CONFIG_FREERTOS_HZ,
+  ESP_ERR_INVALID_ARG,
+  ESP_ERR_TIMEOUT,
+  ESP_FAIL,
+};
+
+use crate::EspError;
+use crate::gpio::{OutputCapable, Pin};
+
+mod event;
+pub use event::*;
+mod rs485;
+pub use rs485::*;
+
+#[cfg(feature = "embedded-hal-02")]
+mod hal;
+
+const PORT_MAX_DELAY: TickType_t = TickType_t::max_value();
+
+/// The size of the hardware RX FIFO, which the RX buffer must be larger than.
+const FIFO_LEN: usize = 128;
+
+/// Leave a pin unchanged in `uart_set_pin`.
+const PIN_NO_CHANGE: i32 = -1;
+
+/// Convert `timeout` to FreeRTOS ticks, rounding up so a short timeout still waits for at least one tick.
+fn ticks(timeout: Option<Duration>) -> TickType_t {
+  match timeout {
+    Some(timeout) => {
+      let ticks = (timeout.as_micros() * CONFIG_FREERTOS_HZ as u128 + 999_999) / 1_000_000;
+      // `PORT_MAX_DELAY` waits forever, so saturate just below it.
+      ticks.min(PORT_MAX_DELAY as u128 - 1) as TickType_t
+    },
+    None => PORT_MAX_DELAY,
+  }
+}
+
+/// A UART peripheral.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UartPort {
+  /// UART0, which is usually used for the console.
+  Uart0,
+  Uart1,
+  Uart2,
+}
+
+impl From<UartPort> for uart_port_t {
+  fn from(port: UartPort) -> Self {
+    match port {
+      UartPort::Uart0 => 0,
+      UartPort::Uart1 => 1,
+      UartPort::Uart2 => 2,
+    }
+  }
+}
+
+/// The number of data bits per character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataBits {
+  Bits5,
+  Bits6,
+  Bits7,
+  Bits8,
+}
+
+impl From<DataBits> for uart_word_length_t {
+  fn from(data_bits: DataBits) -> Self {
+    match data_bits {
+      DataBits::Bits5 => uart_word_length_t::UART_DATA_5_BITS,
+      DataBits::Bits6 => uart_word_length_t::UART_DATA_6_BITS,
+      DataBits::Bits7 => uart_word_length_t::UART_DATA_7_BITS,
+      DataBits::Bits8 => uart_word_length_t::UART_DATA_8_BITS,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+  None,
+  Even,
+  Odd,
+}
+
+impl From<Parity> for uart_parity_t {
+  fn from(parity: Parity) -> Self {
+    match parity {
+      Parity::None => uart_parity_t::UART_PARITY_DISABLE,
+      Parity::Even => uart_parity_t::UART_PARITY_EVEN,
+      Parity::Odd => uart_parity_t::UART_PARITY_ODD,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopBits {
+  Bits1,
+  Bits1_5,
+  Bits2,
+}
+
+impl From<StopBits> for uart_stop_bits_t {
+  fn from(stop_bits: StopBits) -> Self {
+    match stop_bits {
+      StopBits::Bits1 => uart_stop_bits_t::UART_STOP_BITS_1,
+      StopBits::Bits1_5 => uart_stop_bits_t::UART_STOP_BITS_1_5,
+      StopBits::Bits2 => uart_stop_bits_t::UART_STOP_BITS_2,
+    }
+  }
+}
+
+/// Options for a [`Uart`](struct.Uart.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UartConfig {
+  baud_rate: u32,
+  data_bits: DataBits,
+  parity: Parity,
+  stop_bits: StopBits,
+  flow_control: Option<(u8, u8)>,
+  rx_buffer_size: usize,
+  tx_buffer_size: usize,
+}
+
+impl UartConfig {
+  /// Create a configuration for `baud_rate` using 8 data bits, no parity, one stop bit and no flow control,
+  /// with a 1 KiB RX buffer and no TX buffer.
+  pub fn new(baud_rate: u32) -> Self {
+    Self {
+      baud_rate,
+      data_bits: DataBits::Bits8,
+      parity: Parity::None,
+      stop_bits: StopBits::Bits1,
+      flow_control: None,
+      rx_buffer_size: 1024,
+      tx_buffer_size: 0,
+    }
+  }
+
+  pub fn with_data_bits(mut self, data_bits: DataBits) -> Self {
+    self.data_bits = data_bits;
+    self
+  }
+
+  pub fn with_parity(mut self, parity: Parity) -> Self {
+    self.parity = parity;
+    self
+  }
+
+  pub fn with_stop_bits(mut self, stop_bits: StopBits) -> Self {
+    self.stop_bits = stop_bits;
+    self
+  }
+
+  /// Enable hardware flow control using the given RTS and CTS pins.
+  pub fn with_flow_control<RTS: OutputCapable, CTS: Pin>(mut self, rts_pin: RTS, cts_pin: CTS) -> Self {
+    self.flow_control = Some((rts_pin.number(), cts_pin.number()));
+    self
+  }
+
+  /// Set the size of the RX buffer, which must be larger than the 128 byte hardware FIFO.
+  pub fn with_rx_buffer_size(mut self, rx_buffer_size: usize) -> Self {
+    self.rx_buffer_size = rx_buffer_size;
+    self
+  }
+
+  /// Set the size of the TX buffer. If it is zero, writes block until all data has been sent.
+  pub fn with_tx_buffer_size(mut self, tx_buffer_size: usize) -> Self {
+    self.tx_buffer_size = tx_buffer_size;
+    self
+  }
+
+  pub fn baud_rate(&self) -> u32 {
+    self.baud_rate
+  }
+
+  pub fn data_bits(&self) -> DataBits {
+    self.data_bits
+  }
+
+  pub fn parity(&self) -> Parity {
+    self.parity
+  }
+
+  pub fn stop_bits(&self) -> StopBits {
+    self.stop_bits
+  }
+
+  /// The RTS and CTS pins if hardware flow control is enabled.
+  pub fn flow_control(&self) -> Option<(u8, u8)> {
+    self.flow_control
+  }
+
+  pub fn rx_buffer_size(&self) -> usize {
+    self.rx_buffer_size
+  }
+
+  pub fn tx_buffer_size(&self) -> usize {
+    self.tx_buffer_size
+  }
+}
+
+/// A UART driver, deleted when dropped.
+///
+/// Reads block until at least one byte has been received or the read timeout has elapsed,
+/// see [`set_read_timeout`](#method.set_read_timeout).
+pub struct Uart {
+  port: UartPort,
+  read_timeout: Option<Duration>,
+  event_queue: QueueHandle_t,
+  event_queue_size: usize,
+}
+
+unsafe impl Send for Uart {}
+
+impl fmt::Debug for Uart {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Uart")
+      .field("port", &self.port)
+      .field("read_timeout", &self.read_timeout)
+      .finish()
+  }
+}
+
+impl Uart {
+  /// Install the driver for `port` using the given TX and RX pins.
+  ///
+  /// Returns `ESP_ERR_INVALID_ARG` if the RX buffer is not larger than the hardware FIFO.
+  pub fn new<TX: OutputCapable, RX: Pin>(port: UartPort, tx_pin: TX, rx_pin: RX, config: &UartConfig) -> Result<Self, EspError> {
+    Self::install(port, tx_pin.number(), rx_pin.number(), config, 0, ptr::null_mut())
+  }
+
+  fn install(
+    port: UartPort, tx_pin: u8, rx_pin: u8, config: &UartConfig, queue_size: usize, queue: *mut QueueHandle_t,
+  ) -> Result<Self, EspError> {
+    if config.rx_buffer_size <= FIFO_LEN || (config.tx_buffer_size != 0 && config.tx_buffer_size <= FIFO_LEN) {
+      return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+    }
+
+    let uart_port = uart_port_t::from(port);
+
+    let mut uart_config: uart_config_t = unsafe { MaybeUninit::zeroed().assume_init() };
+    uart_config.baud_rate = config.baud_rate as i32;
+    uart_config.data_bits = config.data_bits.into();
+    uart_config.parity = config.parity.into();
+    uart_config.stop_bits = config.stop_bits.into();
+    uart_config.flow_ctrl = if config.flow_control.is_some() {
+      uart_hw_flowcontrol_t::UART_HW_FLOWCTRL_CTS_RTS
+    } else {
+      uart_hw_flowcontrol_t::UART_HW_FLOWCTRL_DISABLE
+    };
+    uart_config.rx_flow_ctrl_thresh = (FIFO_LEN - 16) as _;
+    esp_ok!(uart_param_config(uart_port, &uart_config))?;
+
+    let (rts_pin, cts_pin) = config.flow_control.map_or((PIN_NO_CHANGE, PIN_NO_CHANGE), |(rts, cts)| (rts as i32, cts as i32));
+    esp_ok!(uart_set_pin(uart_port, tx_pin as i32, rx_pin as i32, rts_pin, cts_pin))?;
+
+    esp_ok!(uart_driver_install(
+      uart_port, config.rx_buffer_size as i32, config.tx_buffer_size as i32, queue_size as i32, queue, 0,
+    ))?;
+
+    Ok(Self { port, read_timeout: None, event_queue: ptr::null_mut(), event_queue_size: 0 })
+  }
+
+  pub fn port(&self) -> UartPort {
+    self.port
+  }
+
+  pub fn set_baud_rate(&mut self, baud_rate: u32) -> Result<(), EspError> {
+    esp_ok!(uart_set_baudrate(self.port.into(), baud_rate))
+  }
+
+  /// Set the time after which reads fail with `ErrorKind::TimedOut`, or block forever if the timeout is `None`.
+  pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+    self.read_timeout = timeout;
+  }
+
+  pub fn read_timeout(&self) -> Option<Duration> {
+    self.read_timeout
+  }
+
+  /// The number of received bytes which have not been read yet.
+  pub fn buffered_len(&self) -> Result<usize, EspError> {
+    let mut len = 0;
+    esp_ok!(uart_get_buffered_data_len(self.port.into(), &mut len))?;
+    Ok(len)
+  }
+
+  /// Discard all received bytes which have not been read yet.
+  pub fn clear_input(&mut self) -> Result<(), EspError> {
+    esp_ok!(uart_flush_input(self.port.into()))
+  }
+
+  /// Read up to `buf.len()` bytes, waiting at most `timeout` for each call, and return the number of bytes read.
+  fn read_bytes(&mut self, buf: &mut [u8], timeout: TickType_t) -> io::Result<usize> {
+    match unsafe { uart_read_bytes(self.port.into(), buf.as_mut_ptr(), buf.len() as u32, timeout) } {
+      len if len < 0 => Err(io::Error::new(io::ErrorKind::Other, EspError { code: ESP_FAIL as esp_err_t })),
+      len => Ok(len as usize),
+    }
+  }
+
+  /// Fill `buf` completely, failing with `ErrorKind::TimedOut` if this takes longer than `timeout`.
+  ///
+  /// If the timeout elapses, the bytes which were already read are left at the start of `buf`.
+  pub fn read_exact_timeout(&mut self, buf: &mut [u8], timeout: Duration) -> io::Result<()> {
+    let deadline = Instant::now() + timeout;
+    let mut filled = 0;
+
+    while filled < buf.len() {
+      let remaining = deadline.saturating_duration_since(Instant::now());
+      let len = self.read_bytes(&mut buf[filled..], ticks(Some(remaining)))?;
+      filled += len;
+
+      if filled < buf.len() && Instant::now() >= deadline {
+        return Err(io::ErrorKind::TimedOut.into())
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Block until all buffered data has been sent or `timeout` has elapsed.
+  pub fn wait_tx_done(&mut self, timeout: Option<Duration>) -> Result<(), EspError> {
+    esp_ok!(uart_wait_tx_done(self.port.into(), ticks(timeout)))
+  }
+}
+
+impl io::Read for Uart {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    if buf.is_empty() {
+      return Ok(0)
+    }
+
+    // Wait for the first byte, then return whatever else has already been received.
+    if self.read_bytes(&mut buf[..1], ticks(self.read_timeout))? == 0 {
+      return Err(io::ErrorKind::TimedOut.into())
+    }
+
+    let available = self.buffered_len().map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+    let len = (buf.len() - 1).min(available);
+    if len == 0 {
+      return Ok(1)
+    }
+
+    Ok(1 + self.read_bytes(&mut buf[1..(1 + len)], 0)?)
+  }
+}
+
+impl io::Write for Uart {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    match unsafe { uart_write_bytes(self.port.into(), buf.as_ptr() as *const _, buf.len()) } {
+      len if len < 0 => Err(io::Error::new(io::ErrorKind::Other, EspError { code: ESP_FAIL as esp_err_t })),
+      len => Ok(len as usize),
+    }
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    match self.wait_tx_done(None) {
+      Err(err) if err.code == ESP_ERR_TIMEOUT as esp_err_t => Err(io::ErrorKind::TimedOut.into()),
+      Err(err) => Err(io::Error::new(io::ErrorKind::Other, err)),
+      Ok(()) => Ok(()),
+    }
+  }
+}
+
+impl Drop for Uart {
+  fn drop(&mut self) {
+    let _ = esp_ok!(uart_driver_delete(self.port.into()));
+  }
+}
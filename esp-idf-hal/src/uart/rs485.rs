@@ -0,0 +1,67 @@
+use esp_idf_bindgen::{
+  uart_get_collision_flag,
+  uart_mode_t,
+  uart_set_mode,
+  uart_set_pin,
+  uart_set_rx_timeout,
+  uart_set_tx_idle_num,
+};
+
+use crate::EspError;
+use crate::gpio::OutputCapable;
+
+use super::{Uart, PIN_NO_CHANGE};
+
+/// The RS-485 mode of a [`Uart`](struct.Uart.html), see [`enable_rs485`](struct.Uart.html#method.enable_rs485).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rs485Mode {
+  /// The driver-enable pin is asserted while transmitting.
+  HalfDuplex,
+  /// Like [`HalfDuplex`](#variant.HalfDuplex), but the transmitted data is also received and compared,
+  /// so collisions with other nodes are detected, see [`collision_detected`](struct.Uart.html#method.collision_detected).
+  CollisionDetect,
+}
+
+impl From<Rs485Mode> for uart_mode_t {
+  fn from(mode: Rs485Mode) -> Self {
+    match mode {
+      Rs485Mode::HalfDuplex => uart_mode_t::UART_MODE_RS485_HALF_DUPLEX,
+      Rs485Mode::CollisionDetect => uart_mode_t::UART_MODE_RS485_COLLISION_DETECT,
+    }
+  }
+}
+
+impl Uart {
+  /// Switch to RS-485 half-duplex mode, driving the transceiver driver-enable (DE) input using the RTS signal on `de_pin`.
+  ///
+  /// Hardware flow control must be disabled.
+  pub fn enable_rs485<P: OutputCapable>(&mut self, de_pin: P, mode: Rs485Mode) -> Result<(), EspError> {
+    esp_ok!(uart_set_pin(self.port.into(), PIN_NO_CHANGE, PIN_NO_CHANGE, de_pin.number() as i32, PIN_NO_CHANGE))?;
+    esp_ok!(uart_set_mode(self.port.into(), mode.into()))
+  }
+
+  /// Switch back to normal UART mode.
+  pub fn disable_rs485(&mut self) -> Result<(), EspError> {
+    esp_ok!(uart_set_mode(self.port.into(), uart_mode_t::UART_MODE_UART))
+  }
+
+  /// Set the idle time between the end of a received frame and the start of a transmission, in bit periods,
+  /// to give other nodes time to release the bus.
+  pub fn set_turnaround(&mut self, bits: u16) -> Result<(), EspError> {
+    esp_ok!(uart_set_tx_idle_num(self.port.into(), bits))
+  }
+
+  /// Set the idle time in character periods after which received data is delivered,
+  /// e.g. 3 or 4 for the 3.5 character frame gap of Modbus RTU.
+  pub fn set_rx_timeout(&mut self, chars: u8) -> Result<(), EspError> {
+    esp_ok!(uart_set_rx_timeout(self.port.into(), chars))
+  }
+
+  /// Whether a collision was detected during the last transmission in
+  /// [`CollisionDetect`](enum.Rs485Mode.html#variant.CollisionDetect) mode.
+  pub fn collision_detected(&self) -> Result<bool, EspError> {
+    let mut collision = false;
+    esp_ok!(uart_get_collision_flag(self.port.into(), &mut collision))?;
+    Ok(collision)
+  }
+}
@@ -0,0 +1,60 @@
+use core::marker::PhantomData;
+use core::ptr;
+use std::time::Duration;
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  esp_task_wdt_add,
+  esp_task_wdt_delete,
+  esp_task_wdt_init,
+  esp_task_wdt_reset,
+  ESP_ERR_INVALID_ARG,
+};
+
+use crate::EspError;
+
+/// Configure the task watchdog timer (TWDT), initializing it if it is not enabled in `sdkconfig`.
+///
+/// If a subscribed task is not fed within `timeout`, an error is logged and, if `panic` is set,
+/// the chip is restarted. The timeout has a resolution of one second.
+pub fn configure(timeout: Duration, panic: bool) -> Result<(), EspError> {
+  let timeout_s = timeout.as_secs();
+
+  if timeout_s == 0 || timeout_s > u32::max_value() as u64 {
+    return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+  }
+
+  esp_ok!(esp_task_wdt_init(timeout_s as u32, panic))
+}
+
+/// A subscription of the current task to the task watchdog timer.
+///
+/// The task must call [`feed`](#method.feed) periodically. It is unsubscribed when this is dropped,
+/// which must happen on the same task, so this is neither `Send` nor `Sync`.
+#[derive(Debug)]
+#[must_use = "the task is unsubscribed immediately if this is dropped"]
+pub struct Task {
+  _not_send: PhantomData<*const ()>,
+}
+
+impl Task {
+  /// Subscribe the current task to the task watchdog timer.
+  ///
+  /// Returns `ESP_ERR_INVALID_ARG` if the task is already subscribed, or `ESP_ERR_INVALID_STATE`
+  /// if the watchdog timer is not initialized, see [`configure`](fn.configure.html).
+  pub fn subscribe() -> Result<Self, EspError> {
+    esp_ok!(esp_task_wdt_add(ptr::null_mut()))?;
+    Ok(Self { _not_send: PhantomData })
+  }
+
+  /// Reset the watchdog timer for the current task.
+  pub fn feed(&self) -> Result<(), EspError> {
+    esp_ok!(esp_task_wdt_reset())
+  }
+}
+
+impl Drop for Task {
+  fn drop(&mut self) {
+    let _ = esp_ok!(esp_task_wdt_delete(ptr::null_mut()));
+  }
+}
@@ -0,0 +1,72 @@
+use std::mem::MaybeUninit;
+use std::net::Ipv4Addr;
+
+use esp_idf_bindgen::{
+  esp_wifi_ap_get_sta_list,
+  esp_netif_get_sta_list,
+  esp_netif_sta_list_t,
+  wifi_sta_list_t,
+};
+use macaddr::MacAddr6;
+
+use super::*;
+
+/// A station connected to an access point.
+#[derive(Debug, Clone)]
+pub struct StationInfo {
+  mac: MacAddr6,
+  rssi: i8,
+  ip: Option<Ipv4Addr>,
+}
+
+impl StationInfo {
+  pub fn mac(&self) -> &MacAddr6 {
+    &self.mac
+  }
+
+  /// The signal strength of the station in dBm.
+  pub fn rssi(&self) -> i8 {
+    self.rssi
+  }
+
+  /// The IP address leased to the station by the DHCP server, if any.
+  pub fn ip(&self) -> Option<&Ipv4Addr> {
+    self.ip.as_ref()
+  }
+}
+
+impl Wifi<ApConfig> {
+  /// Get a list of all stations currently connected to this access point.
+  pub fn connected_stations(&self) -> Result<Vec<StationInfo>, EspError> {
+    connected_stations()
+  }
+}
+
+impl Wifi<ApStaConfig> {
+  /// Get a list of all stations currently connected to the access point.
+  pub fn connected_stations(&self) -> Result<Vec<StationInfo>, EspError> {
+    connected_stations()
+  }
+}
+
+fn connected_stations() -> Result<Vec<StationInfo>, EspError> {
+  let mut wifi_sta_list = MaybeUninit::<wifi_sta_list_t>::uninit();
+  esp_ok!(esp_wifi_ap_get_sta_list(wifi_sta_list.as_mut_ptr()))?;
+  let wifi_sta_list = unsafe { wifi_sta_list.assume_init() };
+
+  let mut netif_sta_list = MaybeUninit::<esp_netif_sta_list_t>::uninit();
+  esp_ok!(esp_netif_get_sta_list(&wifi_sta_list, netif_sta_list.as_mut_ptr()))?;
+  let netif_sta_list = unsafe { netif_sta_list.assume_init() };
+
+  let num = wifi_sta_list.num as usize;
+
+  Ok(wifi_sta_list.sta[..num].iter().zip(netif_sta_list.sta[..num].iter()).map(|(wifi_sta, netif_sta)| {
+    let ip = Ipv4Addr::from(u32::from_be(netif_sta.ip.addr));
+
+    StationInfo {
+      mac: MacAddr6::from(wifi_sta.mac),
+      rssi: wifi_sta.rssi,
+      ip: if ip.is_unspecified() { None } else { Some(ip) },
+    }
+  }).collect())
+}
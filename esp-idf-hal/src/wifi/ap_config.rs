@@ -2,8 +2,16 @@ use core::fmt;
 
 use esp_idf_bindgen::{wifi_config_t, wifi_ap_config_t};
 
+#[cfg(target_device = "esp32")]
+use crate::interface::DhcpServerConfig;
+
 use super::{AuthMode, Ssid, Password};
 
+#[cfg(target_device = "esp32")]
+const MAX_CONNECTION: u8 = 10;
+#[cfg(target_device = "esp8266")]
+const MAX_CONNECTION: u8 = 4;
+
 /// Configuration for an access point.
 #[derive(Clone)]
 pub struct ApConfig {
@@ -14,19 +22,25 @@ pub struct ApConfig {
   max_connection: u8,
   ssid_hidden: bool,
   beacon_interval: u16,
+  #[cfg(target_device = "esp32")]
+  dhcp_server: Option<DhcpServerConfig>,
 }
 
 impl fmt::Debug for ApConfig {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    f.debug_struct("ApConfigBuilder")
-      .field("ssid", &self.ssid)
-      .field("password", &"********")
-      .field("channel", &self.channel)
-      .field("auth_mode", &self.auth_mode)
-      .field("max_connection", &self.max_connection)
-      .field("ssid_hidden", &self.ssid_hidden)
-      .field("beacon_interval", &self.beacon_interval)
-      .finish()
+    let mut f = f.debug_struct("ApConfig");
+
+    f.field("ssid", &self.ssid);
+    f.field("password", &"********");
+    f.field("channel", &self.channel);
+    f.field("auth_mode", &self.auth_mode);
+    f.field("max_connection", &self.max_connection);
+    f.field("ssid_hidden", &self.ssid_hidden);
+    f.field("beacon_interval", &self.beacon_interval);
+    #[cfg(target_device = "esp32")]
+    f.field("dhcp_server", &self.dhcp_server);
+
+    f.finish()
   }
 }
 
@@ -39,9 +53,38 @@ impl ApConfig {
     &self.password
   }
 
+  pub fn channel(&self) -> u8 {
+    self.channel
+  }
+
+  pub fn auth_mode(&self) -> AuthMode {
+    self.auth_mode
+  }
+
+  pub fn max_connection(&self) -> u8 {
+    self.max_connection
+  }
+
+  pub fn ssid_hidden(&self) -> bool {
+    self.ssid_hidden
+  }
+
+  pub fn beacon_interval(&self) -> u16 {
+    self.beacon_interval
+  }
+
+  #[cfg(target_device = "esp32")]
+  pub fn dhcp_server(&self) -> Option<&DhcpServerConfig> {
+    self.dhcp_server.as_ref()
+  }
+
   pub fn builder() -> ApConfigBuilder {
     ApConfigBuilder::default()
   }
+
+  pub(crate) fn set_channel(&mut self, channel: u8) {
+    self.channel = channel;
+  }
 }
 
 impl From<&ApConfig> for wifi_config_t {
@@ -66,23 +109,29 @@ pub struct ApConfigBuilder {
   ssid: Option<Ssid>,
   password: Password,
   channel: u8,
-  auth_mode: AuthMode,
+  auth_mode: Option<AuthMode>,
   max_connection: u8,
   ssid_hidden: bool,
   beacon_interval: u16,
+  #[cfg(target_device = "esp32")]
+  dhcp_server: Option<DhcpServerConfig>,
 }
 
 impl fmt::Debug for ApConfigBuilder {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    f.debug_struct("ApConfigBuilder")
-      .field("ssid", &self.ssid)
-      .field("password", &"********")
-      .field("channel", &self.channel)
-      .field("auth_mode", &self.auth_mode)
-      .field("max_connection", &self.max_connection)
-      .field("ssid_hidden", &self.ssid_hidden)
-      .field("beacon_interval", &self.beacon_interval)
-      .finish()
+    let mut f = f.debug_struct("ApConfigBuilder");
+
+    f.field("ssid", &self.ssid);
+    f.field("password", &"********");
+    f.field("channel", &self.channel);
+    f.field("auth_mode", &self.auth_mode);
+    f.field("max_connection", &self.max_connection);
+    f.field("ssid_hidden", &self.ssid_hidden);
+    f.field("beacon_interval", &self.beacon_interval);
+    #[cfg(target_device = "esp32")]
+    f.field("dhcp_server", &self.dhcp_server);
+
+    f.finish()
   }
 }
 
@@ -92,10 +141,12 @@ impl Default for ApConfigBuilder {
       ssid: None,
       password: Default::default(),
       channel: 0,
-      auth_mode: AuthMode::Open,
+      auth_mode: None,
       max_connection: 4,
       ssid_hidden: false,
       beacon_interval: 100,
+      #[cfg(target_device = "esp32")]
+      dhcp_server: None,
     }
   }
 }
@@ -111,15 +162,67 @@ impl ApConfigBuilder {
     self
   }
 
+  /// Set the channel of the access point. A channel of `0` selects the default channel.
+  pub fn channel(&mut self, channel: u8) -> &mut Self {
+    assert!(channel <= 14, "invalid channel {}", channel);
+    self.channel = channel;
+    self
+  }
+
+  /// Set the authentication mode. Any mode other than `AuthMode::Open` requires a password.
+  ///
+  /// Defaults to `AuthMode::Open` without a password and `AuthMode::Wpa2Psk` otherwise.
+  pub fn auth_mode(&mut self, auth_mode: AuthMode) -> &mut Self {
+    self.auth_mode = Some(auth_mode);
+    self
+  }
+
+  /// Set the maximum number of simultaneously connected stations.
+  pub fn max_connection(&mut self, max_connection: u8) -> &mut Self {
+    assert!(max_connection >= 1 && max_connection <= MAX_CONNECTION, "maximum connections must be between 1 and {}", MAX_CONNECTION);
+    self.max_connection = max_connection;
+    self
+  }
+
+  /// Hide the SSID from beacon frames.
+  pub fn ssid_hidden(&mut self, ssid_hidden: bool) -> &mut Self {
+    self.ssid_hidden = ssid_hidden;
+    self
+  }
+
+  /// Set the beacon interval in time units (1 TU = 1024 µs).
+  pub fn beacon_interval(&mut self, beacon_interval: u16) -> &mut Self {
+    assert!(beacon_interval >= 100 && beacon_interval <= 60000, "beacon interval must be between 100 and 60000");
+    self.beacon_interval = beacon_interval;
+    self
+  }
+
+  /// Set the configuration of the DHCP server. By default, the access point uses `192.168.4.1/24`.
+  #[cfg(target_device = "esp32")]
+  pub fn dhcp_server(&mut self, dhcp_server: DhcpServerConfig) -> &mut Self {
+    self.dhcp_server = Some(dhcp_server);
+    self
+  }
+
   pub fn build(&self) -> ApConfig {
+    let password = self.password.as_str();
+
+    let auth_mode = self.auth_mode.unwrap_or(if password.is_empty() { AuthMode::Open } else { AuthMode::Wpa2Psk });
+
+    if auth_mode != AuthMode::Open {
+      assert!(password.len() >= 8, "password must be at least 8 characters long");
+    }
+
     ApConfig {
       ssid: self.ssid.clone().expect("missing SSID"),
       password: self.password.clone(),
       channel: self.channel,
-      auth_mode: self.auth_mode,
+      auth_mode,
       max_connection: self.max_connection,
       ssid_hidden: self.ssid_hidden,
       beacon_interval: self.beacon_interval,
+      #[cfg(target_device = "esp32")]
+      dhcp_server: self.dhcp_server.clone(),
     }
   }
 }
@@ -0,0 +1,135 @@
+use core::fmt;
+
+use esp_idf_bindgen::{esp_interface_t, esp_wifi_set_config, wifi_config_t};
+
+use crate::{EspError, interface::{Interface, IpInfo}};
+
+use super::{enter_ap_mode, leave_ap_mode, leave_sta_mode, sta_ap_info, ApConfig, ApRecord, StaConfig, Wifi, WifiError};
+
+/// Configuration for a WiFi instance running a station and an access point concurrently.
+#[derive(Debug, Clone)]
+pub struct ApStaConfig {
+  sta: StaConfig,
+  ap: ApConfig,
+}
+
+impl ApStaConfig {
+  pub fn sta(&self) -> &StaConfig {
+    &self.sta
+  }
+
+  pub fn ap(&self) -> &ApConfig {
+    &self.ap
+  }
+}
+
+/// The error type returned when starting an access point next to a connected station fails.
+///
+/// The station is not affected and can be recovered using [`StartApError::wifi`](#method.wifi).
+#[derive(Debug)]
+pub struct StartApError {
+  error: WifiError,
+  wifi: Wifi<StaConfig>,
+}
+
+impl StartApError {
+  pub fn error(&self) -> &WifiError {
+    &self.error
+  }
+
+  /// Get back the [`Wifi`](struct.Wifi.html) instance with the still connected station.
+  pub fn wifi(self) -> Wifi<StaConfig> {
+    self.wifi
+  }
+}
+
+impl fmt::Display for StartApError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    self.error.fmt(f)
+  }
+}
+
+impl Wifi<StaConfig> {
+  /// Start an access point in addition to the connected station.
+  ///
+  /// Both interfaces share a single radio, so the access point always uses the channel
+  /// of the access point the station is connected to. The channel of the given
+  /// [`ApConfig`](struct.ApConfig.html) is ignored and the access point follows the
+  /// station if it later reconnects on a different channel.
+  pub fn start_ap(mut self, mut config: ApConfig) -> Result<Wifi<ApStaConfig>, StartApError> {
+    let channel = match self.ap_info() {
+      Ok(ap_info) => ap_info.channel(),
+      Err(err) => return Err(StartApError { error: err.into(), wifi: self }),
+    };
+    config.set_channel(channel);
+
+    let interface = Interface::Ap;
+    interface.init();
+    let mut ap_config = wifi_config_t::from(&config);
+    enter_ap_mode();
+
+    let res = esp_ok!(esp_wifi_set_config(esp_interface_t::ESP_IF_WIFI_AP, &mut ap_config));
+
+    #[cfg(target_device = "esp32")]
+    let res = res.and_then(|()| config.dhcp_server().map_or(Ok(()), |dhcp_server| interface.set_dhcp_server_config(dhcp_server)));
+
+    if let Err(err) = res {
+      leave_ap_mode();
+      return Err(StartApError { error: err.into(), wifi: self })
+    }
+
+    let ip_info = self.ip_info.take();
+    let reconnector = self.reconnector.take();
+    let sta = self.into_config();
+
+    Ok(Wifi {
+      config: ApStaConfig { sta, ap: config },
+      deinit_on_drop: true,
+      ip_info,
+      reconnector,
+    })
+  }
+}
+
+impl Wifi<ApStaConfig> {
+  /// IP information of the station interface.
+  pub fn ip_info(&self) -> &IpInfo {
+    self.ip_info.as_ref().unwrap()
+  }
+
+  /// IP information of the access point interface.
+  pub fn ap_ip_info(&self) -> IpInfo {
+    Interface::Ap.ip_info()
+  }
+
+  /// Get information about the access point the station is currently connected to.
+  pub fn ap_info(&self) -> Result<ApRecord, EspError> {
+    sta_ap_info()
+  }
+
+  /// Stop the access point and keep the station connected.
+  pub fn stop_ap(mut self) -> (ApConfig, Wifi<StaConfig>) {
+    leave_ap_mode();
+    let ip_info = self.ip_info.take();
+    let reconnector = self.reconnector.take();
+    let config = self.into_config();
+
+    let wifi = Wifi {
+      config: config.sta,
+      deinit_on_drop: true,
+      ip_info,
+      reconnector,
+    };
+
+    (config.ap, wifi)
+  }
+
+  /// Stop both the access point and the station.
+  pub fn stop(mut self) -> (ApStaConfig, Wifi) {
+    self.deinit_on_drop = false;
+    self.reconnector = None;
+    leave_ap_mode();
+    leave_sta_mode();
+    (self.into_config(), Wifi { config: (), deinit_on_drop: true, ip_info: None, reconnector: None })
+  }
+}
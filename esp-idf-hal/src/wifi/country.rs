@@ -0,0 +1,181 @@
+use core::fmt;
+use std::mem::MaybeUninit;
+
+use esp_idf_bindgen::{
+  esp_wifi_get_country,
+  esp_wifi_set_country,
+  wifi_country_policy_t,
+  wifi_country_t,
+};
+
+use super::*;
+
+/// The environment a [`Country`](struct.Country.html) configuration applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Environment {
+  Any,
+  Indoor,
+  Outdoor,
+}
+
+/// Policy for applying a [`Country`](struct.Country.html) configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountryPolicy {
+  /// Use the country information of the access point the station is connected to.
+  Auto,
+  /// Always use the configured country information.
+  Manual,
+}
+
+impl From<CountryPolicy> for wifi_country_policy_t {
+  fn from(policy: CountryPolicy) -> Self {
+    match policy {
+      CountryPolicy::Auto => wifi_country_policy_t::WIFI_COUNTRY_POLICY_AUTO,
+      CountryPolicy::Manual => wifi_country_policy_t::WIFI_COUNTRY_POLICY_MANUAL,
+    }
+  }
+}
+
+impl From<wifi_country_policy_t> for CountryPolicy {
+  fn from(policy: wifi_country_policy_t) -> Self {
+    match policy {
+      wifi_country_policy_t::WIFI_COUNTRY_POLICY_AUTO => CountryPolicy::Auto,
+      wifi_country_policy_t::WIFI_COUNTRY_POLICY_MANUAL => CountryPolicy::Manual,
+    }
+  }
+}
+
+/// WiFi country/regulatory domain configuration.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Country {
+  code: [u8; 2],
+  environment: Environment,
+  start_channel: u8,
+  channels: u8,
+  max_tx_power: i8,
+  policy: CountryPolicy,
+}
+
+impl fmt::Debug for Country {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Country")
+      .field("code", &self.code())
+      .field("environment", &self.environment)
+      .field("start_channel", &self.start_channel)
+      .field("channels", &self.channels)
+      .field("max_tx_power", &self.max_tx_power)
+      .field("policy", &self.policy)
+      .finish()
+  }
+}
+
+impl Country {
+  /// Create a configuration for the given ISO 3166-1 alpha-2 country `code` allowing
+  /// `channels` channels starting at `start_channel`.
+  pub fn new(code: &str, start_channel: u8, channels: u8) -> Self {
+    let bytes = code.as_bytes();
+    assert!(bytes.len() == 2 && bytes.iter().all(u8::is_ascii_uppercase), "invalid country code '{}'", code);
+    assert!(start_channel >= 1 && channels >= 1 && start_channel as u16 + channels as u16 - 1 <= 14, "invalid channel range");
+
+    Self {
+      code: [bytes[0], bytes[1]],
+      environment: Environment::Any,
+      start_channel,
+      channels,
+      max_tx_power: 20,
+      policy: CountryPolicy::Auto,
+    }
+  }
+
+  pub fn code(&self) -> &str {
+    unsafe { str::from_utf8_unchecked(&self.code) }
+  }
+
+  pub fn environment(&self) -> Environment {
+    self.environment
+  }
+
+  pub fn start_channel(&self) -> u8 {
+    self.start_channel
+  }
+
+  pub fn channels(&self) -> u8 {
+    self.channels
+  }
+
+  /// The maximum transmit power in dBm.
+  pub fn max_tx_power(&self) -> i8 {
+    self.max_tx_power
+  }
+
+  pub fn policy(&self) -> CountryPolicy {
+    self.policy
+  }
+
+  pub fn with_environment(mut self, environment: Environment) -> Self {
+    self.environment = environment;
+    self
+  }
+
+  pub fn with_max_tx_power(mut self, max_tx_power: i8) -> Self {
+    self.max_tx_power = max_tx_power;
+    self
+  }
+
+  pub fn with_policy(mut self, policy: CountryPolicy) -> Self {
+    self.policy = policy;
+    self
+  }
+}
+
+impl From<&Country> for wifi_country_t {
+  fn from(country: &Country) -> Self {
+    let environment = match country.environment {
+      Environment::Any => b' ',
+      Environment::Indoor => b'I',
+      Environment::Outdoor => b'O',
+    };
+
+    Self {
+      cc: [country.code[0] as _, country.code[1] as _, environment as _],
+      schan: country.start_channel,
+      nchan: country.channels,
+      max_tx_power: country.max_tx_power,
+      policy: country.policy.into(),
+    }
+  }
+}
+
+impl From<wifi_country_t> for Country {
+  fn from(country: wifi_country_t) -> Self {
+    let environment = match country.cc[2] as u8 {
+      b'I' => Environment::Indoor,
+      b'O' => Environment::Outdoor,
+      _ => Environment::Any,
+    };
+
+    Self {
+      code: [country.cc[0] as u8, country.cc[1] as u8],
+      environment,
+      start_channel: country.schan,
+      channels: country.nchan,
+      max_tx_power: country.max_tx_power,
+      policy: country.policy.into(),
+    }
+  }
+}
+
+impl<T> Wifi<T> {
+  /// Set the country/regulatory domain.
+  pub fn set_country(&mut self, country: &Country) -> Result<(), EspError> {
+    let country = wifi_country_t::from(country);
+    esp_ok!(esp_wifi_set_country(&country))
+  }
+
+  /// Get the current country/regulatory domain.
+  pub fn country(&self) -> Result<Country, EspError> {
+    let mut country = MaybeUninit::<wifi_country_t>::uninit();
+    esp_ok!(esp_wifi_get_country(country.as_mut_ptr()))?;
+    Ok(Country::from(unsafe { country.assume_init() }))
+  }
+}
@@ -0,0 +1,221 @@
+use esp_idf_bindgen::{ESP_ERR_INVALID_SIZE, ESP_ERR_NOT_FOUND, ESP_ERR_NVS_NOT_FOUND};
+
+use crate::nvs::NameSpace;
+
+use super::*;
+
+/// The NVS namespace used by [`CredentialStore::open`](struct.CredentialStore.html#method.open).
+pub const CREDENTIAL_STORE_NAMESPACE: &str = "wifi_creds";
+
+const NETWORKS_KEY: &str = "networks";
+
+/// A known WiFi network stored in a [`CredentialStore`](struct.CredentialStore.html).
+#[derive(Debug, Clone)]
+pub struct KnownNetwork {
+  ssid: Ssid,
+  password: Password,
+  priority: u8,
+  last_bssid: Option<MacAddr6>,
+}
+
+impl KnownNetwork {
+  /// Create a known network with priority `0`.
+  pub fn new(ssid: Ssid, password: Password) -> Self {
+    Self { ssid, password, priority: 0, last_bssid: None }
+  }
+
+  /// Networks with a higher priority are tried first.
+  pub fn with_priority(mut self, priority: u8) -> Self {
+    self.priority = priority;
+    self
+  }
+
+  pub fn ssid(&self) -> &Ssid {
+    &self.ssid
+  }
+
+  pub fn password(&self) -> &Password {
+    &self.password
+  }
+
+  pub fn priority(&self) -> u8 {
+    self.priority
+  }
+
+  /// The BSSID of the access point this network was last connected to.
+  pub fn last_bssid(&self) -> Option<&MacAddr6> {
+    self.last_bssid.as_ref()
+  }
+
+  fn encode(&self, bytes: &mut Vec<u8>) {
+    let ssid = self.ssid.as_str().as_bytes();
+    bytes.push(ssid.len() as u8);
+    bytes.extend_from_slice(ssid);
+
+    let password = self.password.as_str().as_bytes();
+    bytes.push(password.len() as u8);
+    bytes.extend_from_slice(password);
+
+    bytes.push(self.priority);
+
+    match self.last_bssid {
+      Some(bssid) => {
+        bytes.push(1);
+        bytes.extend_from_slice(bssid.as_bytes());
+      },
+      None => bytes.push(0),
+    }
+  }
+
+  fn decode(bytes: &mut &[u8]) -> Option<Self> {
+    fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+      let taken = bytes.get(..len)?;
+      *bytes = &bytes[len..];
+      Some(taken)
+    }
+
+    let ssid_len = take(bytes, 1)?[0] as usize;
+    let ssid = Ssid::from_bytes(take(bytes, ssid_len)?).ok()?;
+    let password_len = take(bytes, 1)?[0] as usize;
+    let password = Password::from_bytes(take(bytes, password_len)?).ok()?;
+    let priority = take(bytes, 1)?[0];
+    let last_bssid = match take(bytes, 1)?[0] {
+      0 => None,
+      _ => {
+        let mut bssid = [0; 6];
+        bssid.copy_from_slice(take(bytes, 6)?);
+        Some(MacAddr6::from(bssid))
+      },
+    };
+
+    Some(Self { ssid, password, priority, last_bssid })
+  }
+}
+
+/// Persistent storage for multiple known WiFi networks.
+///
+/// All networks are stored in a single NVS blob, so every update is atomic.
+#[derive(Debug)]
+pub struct CredentialStore {
+  namespace: NameSpace,
+  networks: Vec<KnownNetwork>,
+}
+
+impl CredentialStore {
+  /// Open the credential store in the default NVS partition.
+  pub fn open() -> Result<Self, EspError> {
+    Self::new(NameSpace::open(CREDENTIAL_STORE_NAMESPACE)?)
+  }
+
+  /// Use `namespace` to store known networks.
+  pub fn new(namespace: NameSpace) -> Result<Self, EspError> {
+    let networks = match namespace.get::<Vec<u8>>(NETWORKS_KEY) {
+      Ok(bytes) => {
+        let mut bytes = bytes.as_slice();
+        let mut networks = Vec::new();
+        while !bytes.is_empty() {
+          let network = KnownNetwork::decode(&mut bytes).ok_or(EspError { code: ESP_ERR_INVALID_SIZE as esp_err_t })?;
+          networks.push(network);
+        }
+        networks
+      },
+      Err(err) if err.code == ESP_ERR_NVS_NOT_FOUND as esp_err_t => Vec::new(),
+      Err(err) => return Err(err),
+    };
+
+    Ok(Self { namespace, networks })
+  }
+
+  /// All known networks in the order they are tried when connecting, i.e. by priority
+  /// and then the most recently connected first.
+  pub fn networks(&self) -> &[KnownNetwork] {
+    &self.networks
+  }
+
+  /// Add a network, replacing a known network with the same SSID.
+  pub fn add(&mut self, network: KnownNetwork) -> Result<(), EspError> {
+    self.networks.retain(|known| known.ssid != network.ssid);
+    self.networks.insert(0, network);
+    self.save()
+  }
+
+  /// Remove the network with the given SSID. Returns `false` if it was not known.
+  pub fn remove(&mut self, ssid: &Ssid) -> Result<bool, EspError> {
+    let len = self.networks.len();
+    self.networks.retain(|known| known.ssid != *ssid);
+
+    if self.networks.len() == len {
+      return Ok(false)
+    }
+
+    self.save()?;
+    Ok(true)
+  }
+
+  /// Remove all known networks.
+  pub fn clear(&mut self) -> Result<(), EspError> {
+    self.networks.clear();
+    self.save()
+  }
+
+  /// Record a successful connection to the access point `bssid`. The network is tried
+  /// first among the networks with the same priority from now on.
+  pub fn set_connected(&mut self, ssid: &Ssid, bssid: MacAddr6) -> Result<(), EspError> {
+    let index = self.networks.iter().position(|known| known.ssid == *ssid)
+      .ok_or(EspError { code: ESP_ERR_NOT_FOUND as esp_err_t })?;
+
+    let mut network = self.networks.remove(index);
+    network.last_bssid = Some(bssid);
+    self.networks.insert(0, network);
+    self.save()
+  }
+
+  fn save(&mut self) -> Result<(), EspError> {
+    // The sort is stable, so the most recently connected network stays first within each priority.
+    self.networks.sort_by(|a, b| b.priority.cmp(&a.priority));
+
+    let mut bytes = Vec::new();
+    for network in &self.networks {
+      network.encode(&mut bytes);
+    }
+
+    self.namespace.set(NETWORKS_KEY, bytes)?;
+    self.namespace.commit()
+  }
+}
+
+impl Wifi {
+  /// Connect to the first reachable network in `store`, trying them in the order
+  /// returned by [`CredentialStore::networks`](struct.CredentialStore.html#method.networks).
+  ///
+  /// On success, the BSSID of the access point is recorded in the store. If no network
+  /// is known, this fails with `ESP_ERR_NOT_FOUND`, otherwise with the error of the last attempt.
+  pub async fn connect_known(mut self, store: &mut CredentialStore) -> Result<WifiRunning, WifiError> {
+    self.deinit_on_drop = false;
+
+    let mut wifi = self;
+    let mut last_error = WifiError::Internal(EspError { code: ESP_ERR_NOT_FOUND as esp_err_t });
+
+    for network in store.networks().to_vec() {
+      let config = StaConfig::builder()
+        .ssid(network.ssid.clone())
+        .password(network.password.clone())
+        .build();
+
+      match wifi.connect_sta(config).await {
+        Ok(running) => {
+          let ap_info = sta_ap_info()?;
+          store.set_connected(&network.ssid, *ap_info.bssid())?;
+          return Ok(running)
+        },
+        Err(err @ WifiError::Internal(_)) => return Err(err),
+        Err(err) => {
+          last_error = err;
+          wifi = Wifi { config: (), deinit_on_drop: false, ip_info: None, reconnector: None };
+        },
+      }
+    }
+
+    Err(last_error)
+  }
+}
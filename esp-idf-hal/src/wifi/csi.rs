@@ -0,0 +1,235 @@
+use core::marker::PhantomData;
+use core::slice;
+use std::sync::mpsc::{self, Receiver, RecvError, RecvTimeoutError, SyncSender, TryRecvError};
+use std::time::Duration;
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  esp_wifi_set_csi,
+  esp_wifi_set_csi_config,
+  esp_wifi_set_csi_rx_cb,
+  wifi_csi_config_t,
+  wifi_csi_info_t,
+  ESP_ERR_INVALID_STATE,
+};
+use macaddr::MacAddr6;
+
+use crate::callback_slot::CallbackSlot;
+
+use super::*;
+
+/// Configuration for capturing Channel State Information.
+#[derive(Debug, Clone)]
+pub struct CsiConfig {
+  lltf: bool,
+  htltf: bool,
+  stbc_htltf2: bool,
+  ltf_merge: bool,
+  channel_filter: bool,
+  manual_scale: Option<u8>,
+}
+
+impl Default for CsiConfig {
+  fn default() -> Self {
+    Self {
+      lltf: true,
+      htltf: true,
+      stbc_htltf2: true,
+      ltf_merge: true,
+      channel_filter: true,
+      manual_scale: None,
+    }
+  }
+}
+
+impl CsiConfig {
+  pub fn builder() -> CsiConfigBuilder {
+    CsiConfigBuilder { config: Self::default() }
+  }
+}
+
+impl From<&CsiConfig> for wifi_csi_config_t {
+  fn from(config: &CsiConfig) -> Self {
+    Self {
+      lltf_en: config.lltf,
+      htltf_en: config.htltf,
+      stbc_htltf2_en: config.stbc_htltf2,
+      ltf_merge_en: config.ltf_merge,
+      channel_filter_en: config.channel_filter,
+      manu_scale: config.manual_scale.is_some(),
+      shift: config.manual_scale.unwrap_or(0),
+    }
+  }
+}
+
+/// Builder for [`CsiConfig`](struct.CsiConfig.html).
+#[derive(Debug, Clone)]
+pub struct CsiConfigBuilder {
+  config: CsiConfig,
+}
+
+impl CsiConfigBuilder {
+  /// Capture the legacy long training field.
+  pub fn lltf(&mut self, lltf: bool) -> &mut Self {
+    self.config.lltf = lltf;
+    self
+  }
+
+  /// Capture the HT long training field.
+  pub fn htltf(&mut self, htltf: bool) -> &mut Self {
+    self.config.htltf = htltf;
+    self
+  }
+
+  /// Capture the second HT long training field of STBC packets.
+  pub fn stbc_htltf2(&mut self, stbc_htltf2: bool) -> &mut Self {
+    self.config.stbc_htltf2 = stbc_htltf2;
+    self
+  }
+
+  /// Merge the legacy and HT long training fields.
+  pub fn ltf_merge(&mut self, ltf_merge: bool) -> &mut Self {
+    self.config.ltf_merge = ltf_merge;
+    self
+  }
+
+  /// Smooth adjacent sub-carriers.
+  pub fn channel_filter(&mut self, channel_filter: bool) -> &mut Self {
+    self.config.channel_filter = channel_filter;
+    self
+  }
+
+  /// Scale CSI data by shifting right by `shift` bits instead of scaling automatically.
+  pub fn manual_scale(&mut self, shift: impl Into<Option<u8>>) -> &mut Self {
+    let shift = shift.into();
+    if let Some(shift) = shift {
+      assert!(shift <= 15, "shift must be between 0 and 15");
+    }
+    self.config.manual_scale = shift;
+    self
+  }
+
+  pub fn build(&self) -> CsiConfig {
+    self.config.clone()
+  }
+}
+
+/// A Channel State Information record.
+#[derive(Debug, Clone)]
+pub struct CsiRecord {
+  mac: MacAddr6,
+  rssi: i8,
+  channel: u8,
+  first_word_invalid: bool,
+  data: Vec<i8>,
+}
+
+impl CsiRecord {
+  /// The source MAC address of the packet.
+  pub fn mac(&self) -> &MacAddr6 {
+    &self.mac
+  }
+
+  /// The signal strength of the packet in dBm.
+  pub fn rssi(&self) -> i8 {
+    self.rssi
+  }
+
+  pub fn channel(&self) -> u8 {
+    self.channel
+  }
+
+  /// Whether the first four bytes of the data are invalid due to a hardware limitation.
+  pub fn first_word_invalid(&self) -> bool {
+    self.first_word_invalid
+  }
+
+  /// Interleaved imaginary and real parts of each sub-carrier.
+  pub fn data(&self) -> &[i8] {
+    &self.data
+  }
+}
+
+static SENDER: CallbackSlot<SyncSender<CsiRecord>> = CallbackSlot::new();
+
+/// A stream of CSI records, returned by [`Wifi::csi`](struct.Wifi.html#method.csi).
+///
+/// CSI capture is disabled when the receiver is dropped.
+#[derive(Debug)]
+pub struct CsiReceiver<'w> {
+  receiver: Receiver<CsiRecord>,
+  _wifi: PhantomData<&'w mut ()>,
+}
+
+impl<T> Wifi<T> {
+  /// Start capturing Channel State Information using the given [`CsiConfig`](struct.CsiConfig.html).
+  ///
+  /// Records are buffered in a channel holding up to `capacity` records,
+  /// further records are dropped until the channel has room again.
+  ///
+  /// Requires `CONFIG_ESP32_WIFI_CSI_ENABLED`.
+  pub fn csi(&mut self, config: &CsiConfig, capacity: usize) -> Result<CsiReceiver<'_>, EspError> {
+    let (sender, receiver) = mpsc::sync_channel(capacity);
+
+    if SENDER.set(sender).is_err() {
+      return Err(EspError { code: ESP_ERR_INVALID_STATE as esp_err_t })
+    }
+
+    let csi_receiver = CsiReceiver { receiver, _wifi: PhantomData };
+
+    let config = wifi_csi_config_t::from(config);
+    esp_ok!(esp_wifi_set_csi_config(&config))?;
+    esp_ok!(esp_wifi_set_csi_rx_cb(Some(csi_rx_cb), ptr::null_mut()))?;
+    esp_ok!(esp_wifi_set_csi(true))?;
+
+    Ok(csi_receiver)
+  }
+}
+
+impl CsiReceiver<'_> {
+  /// Block until the next record is received.
+  pub fn recv(&self) -> Result<CsiRecord, RecvError> {
+    self.receiver.recv()
+  }
+
+  /// Return the next record if one has already been received.
+  pub fn try_recv(&self) -> Result<CsiRecord, TryRecvError> {
+    self.receiver.try_recv()
+  }
+
+  /// Block until the next record is received or `timeout` has elapsed.
+  pub fn recv_timeout(&self, timeout: Duration) -> Result<CsiRecord, RecvTimeoutError> {
+    self.receiver.recv_timeout(timeout)
+  }
+}
+
+impl Drop for CsiReceiver<'_> {
+  fn drop(&mut self) {
+    let _ = esp_ok!(esp_wifi_set_csi(false));
+    let _ = esp_ok!(esp_wifi_set_csi_rx_cb(None, ptr::null_mut()));
+
+    SENDER.clear();
+  }
+}
+
+extern "C" fn csi_rx_cb(_ctx: *mut libc::c_void, info: *mut wifi_csi_info_t) {
+  let info = unsafe { &*info };
+
+  let data = if info.buf.is_null() {
+    Vec::new()
+  } else {
+    unsafe { slice::from_raw_parts(info.buf as *const i8, info.len as usize) }.to_vec()
+  };
+
+  let record = CsiRecord {
+    mac: MacAddr6::from(info.mac),
+    rssi: info.rx_ctrl.rssi() as i8,
+    channel: info.rx_ctrl.channel() as u8,
+    first_word_invalid: info.first_word_invalid,
+    data,
+  };
+
+  SENDER.with(|sender| {
+    let _ = sender.try_send(record);
+  });
+}
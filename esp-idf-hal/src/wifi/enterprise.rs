@@ -0,0 +1,158 @@
+use core::fmt;
+
+use esp_idf_bindgen::{
+  esp_wifi_sta_wpa2_ent_enable,
+  esp_wifi_sta_wpa2_ent_disable,
+  esp_wifi_sta_wpa2_ent_set_identity,
+  esp_wifi_sta_wpa2_ent_set_username,
+  esp_wifi_sta_wpa2_ent_set_password,
+  esp_wifi_sta_wpa2_ent_set_ca_cert,
+  esp_wifi_sta_wpa2_ent_set_cert_key,
+};
+
+use crate::EspError;
+
+/// A client certificate and private key used for EAP-TLS authentication.
+#[derive(Clone)]
+struct ClientCert {
+  cert: Vec<u8>,
+  private_key: Vec<u8>,
+  private_key_password: Option<Vec<u8>>,
+}
+
+/// WPA2-Enterprise (EAP) configuration for a station.
+///
+/// Certificates must be PEM-encoded and `NUL`-terminated or DER-encoded.
+#[derive(Clone)]
+pub struct EnterpriseConfig {
+  identity: Vec<u8>,
+  username: Vec<u8>,
+  password: Vec<u8>,
+  ca_cert: Option<Vec<u8>>,
+  client_cert: Option<ClientCert>,
+}
+
+impl fmt::Debug for EnterpriseConfig {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("EnterpriseConfig")
+      .field("identity", &String::from_utf8_lossy(&self.identity))
+      .field("username", &String::from_utf8_lossy(&self.username))
+      .field("password", &"********")
+      .field("ca_cert", &self.ca_cert.as_ref().map(|cert| cert.len()))
+      .field("client_cert", &self.client_cert.as_ref().map(|client_cert| client_cert.cert.len()))
+      .finish()
+  }
+}
+
+impl EnterpriseConfig {
+  pub fn builder() -> EnterpriseConfigBuilder {
+    EnterpriseConfigBuilder::default()
+  }
+
+  /// Apply this configuration and enable WPA2-Enterprise authentication.
+  ///
+  /// The ESP-IDF only stores pointers to the certificates, so `self` must
+  /// outlive the connection.
+  pub(crate) fn enable(&self) -> Result<(), EspError> {
+    if !self.identity.is_empty() {
+      esp_ok!(esp_wifi_sta_wpa2_ent_set_identity(self.identity.as_ptr(), self.identity.len() as _))?;
+    }
+
+    if !self.username.is_empty() {
+      esp_ok!(esp_wifi_sta_wpa2_ent_set_username(self.username.as_ptr(), self.username.len() as _))?;
+    }
+
+    if !self.password.is_empty() {
+      esp_ok!(esp_wifi_sta_wpa2_ent_set_password(self.password.as_ptr(), self.password.len() as _))?;
+    }
+
+    if let Some(ca_cert) = &self.ca_cert {
+      esp_ok!(esp_wifi_sta_wpa2_ent_set_ca_cert(ca_cert.as_ptr(), ca_cert.len() as _))?;
+    }
+
+    if let Some(client_cert) = &self.client_cert {
+      let (password_ptr, password_len) = client_cert.private_key_password.as_ref()
+        .map_or((core::ptr::null(), 0), |password| (password.as_ptr(), password.len()));
+
+      esp_ok!(esp_wifi_sta_wpa2_ent_set_cert_key(
+        client_cert.cert.as_ptr(), client_cert.cert.len() as _,
+        client_cert.private_key.as_ptr(), client_cert.private_key.len() as _,
+        password_ptr, password_len as _,
+      ))?;
+    }
+
+    esp_ok!(esp_wifi_sta_wpa2_ent_enable())
+  }
+
+  pub(crate) fn disable() {
+    let _ = esp_ok!(esp_wifi_sta_wpa2_ent_disable());
+  }
+}
+
+/// Builder for [`EnterpriseConfig`](struct.EnterpriseConfig.html).
+#[derive(Default)]
+pub struct EnterpriseConfigBuilder {
+  identity: Vec<u8>,
+  username: Vec<u8>,
+  password: Vec<u8>,
+  ca_cert: Option<Vec<u8>>,
+  client_cert: Option<ClientCert>,
+}
+
+impl fmt::Debug for EnterpriseConfigBuilder {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("EnterpriseConfigBuilder")
+      .field("identity", &String::from_utf8_lossy(&self.identity))
+      .field("username", &String::from_utf8_lossy(&self.username))
+      .field("password", &"********")
+      .field("ca_cert", &self.ca_cert.as_ref().map(|cert| cert.len()))
+      .field("client_cert", &self.client_cert.as_ref().map(|client_cert| client_cert.cert.len()))
+      .finish()
+  }
+}
+
+impl EnterpriseConfigBuilder {
+  /// Set the outer (anonymous) identity sent in the EAP identity response.
+  pub fn identity(&mut self, identity: &str) -> &mut Self {
+    self.identity = identity.as_bytes().to_vec();
+    self
+  }
+
+  /// Set the username used for PEAP/TTLS authentication.
+  pub fn username(&mut self, username: &str) -> &mut Self {
+    self.username = username.as_bytes().to_vec();
+    self
+  }
+
+  /// Set the password used for PEAP/TTLS authentication.
+  pub fn password(&mut self, password: &str) -> &mut Self {
+    self.password = password.as_bytes().to_vec();
+    self
+  }
+
+  /// Set the CA certificate used to validate the authentication server.
+  pub fn ca_cert(&mut self, ca_cert: impl Into<Vec<u8>>) -> &mut Self {
+    self.ca_cert = Some(ca_cert.into());
+    self
+  }
+
+  /// Set the client certificate and private key used for EAP-TLS authentication.
+  pub fn client_cert(&mut self, cert: impl Into<Vec<u8>>, private_key: impl Into<Vec<u8>>, private_key_password: Option<&str>) -> &mut Self {
+    self.client_cert = Some(ClientCert {
+      cert: cert.into(),
+      private_key: private_key.into(),
+      private_key_password: private_key_password.map(|password| password.as_bytes().to_vec()),
+    });
+    self
+  }
+
+  pub fn build(&self) -> EnterpriseConfig {
+    EnterpriseConfig {
+      identity: self.identity.clone(),
+      username: self.username.clone(),
+      password: self.password.clone(),
+      ca_cert: self.ca_cert.clone(),
+      client_cert: self.client_cert.clone(),
+    }
+  }
+}
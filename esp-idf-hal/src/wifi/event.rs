@@ -0,0 +1,166 @@
+use core::mem::transmute;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use esp_idf_bindgen::{
+  esp_event_base_t,
+  ip_event_t,
+  ip_event_got_ip_t,
+  ip_event_got_ip6_t,
+  ip_event_ap_staipassigned_t,
+  wifi_event_t,
+  wifi_event_sta_connected_t,
+  wifi_event_sta_disconnected_t,
+  wifi_event_sta_authmode_change_t,
+  wifi_event_ap_staconnected_t,
+  wifi_event_ap_stadisconnected_t,
+  wifi_event_sta_scan_done_t,
+  IP_EVENT,
+  WIFI_EVENT,
+};
+use macaddr::MacAddr6;
+
+use crate::event::{EventReceiver, Subscription};
+use crate::interface::ip6_from_native;
+use super::*;
+
+/// A WiFi or IP event.
+#[derive(Debug, Clone)]
+pub enum WifiEvent {
+  /// A scan has finished.
+  ScanDone { success: bool, number: u8 },
+  /// The station has started.
+  StaStart,
+  /// The station has stopped.
+  StaStop,
+  /// The station connected to an access point.
+  StaConnected { ssid: Ssid, bssid: MacAddr6, channel: u8, auth_mode: AuthMode },
+  /// The station disconnected from an access point.
+  ///
+  /// `reason` is the raw disconnect reason, which is a `wifi_err_reason_t` value for reasons known to the driver,
+  /// but may be any reason code sent by the access point.
+  StaDisconnected { ssid: Ssid, bssid: MacAddr6, reason: u8 },
+  /// The authentication mode of the access point the station is connected to changed.
+  StaAuthModeChange { old_mode: AuthMode, new_mode: AuthMode },
+  /// The station received an IP address.
+  StaGotIp { ip_info: IpInfo, changed: bool },
+  /// The station lost its IP address.
+  StaLostIp,
+  /// The access point has started.
+  ApStart,
+  /// The access point has stopped.
+  ApStop,
+  /// A station connected to the access point.
+  ApStaConnected { mac: MacAddr6, aid: u8 },
+  /// A station disconnected from the access point.
+  ApStaDisconnected { mac: MacAddr6, aid: u8 },
+  /// The access point assigned an IP address to a station.
+  ApStaIpAssigned { ip: Ipv4Addr },
+  /// An interface received an IPv6 address.
+  GotIp6 { ip: Ipv6Addr, index: i32 },
+}
+
+impl WifiEvent {
+  /// Convert a raw `WIFI_EVENT` or `IP_EVENT`, returning `None` for unsupported events.
+  pub(crate) unsafe fn from_raw(event_base: esp_event_base_t, event_id: i32, event_data: *mut libc::c_void) -> Option<Self> {
+    if event_base == WIFI_EVENT {
+      if event_id < 0 || event_id >= wifi_event_t::WIFI_EVENT_MAX as i32 {
+        return None
+      }
+
+      let event_id: wifi_event_t = transmute(event_id as u32);
+
+      Some(match event_id {
+        wifi_event_t::WIFI_EVENT_SCAN_DONE => {
+          let event = &*(event_data as *const wifi_event_sta_scan_done_t);
+          Self::ScanDone { success: event.status == 0, number: event.number }
+        },
+        wifi_event_t::WIFI_EVENT_STA_START => Self::StaStart,
+        wifi_event_t::WIFI_EVENT_STA_STOP => Self::StaStop,
+        wifi_event_t::WIFI_EVENT_STA_CONNECTED => {
+          let event = &*(event_data as *const wifi_event_sta_connected_t);
+          Self::StaConnected {
+            ssid: Ssid { ssid: event.ssid, ssid_len: event.ssid_len as usize },
+            bssid: MacAddr6::from(event.bssid),
+            channel: event.channel,
+            auth_mode: AuthMode::from(event.authmode),
+          }
+        },
+        wifi_event_t::WIFI_EVENT_STA_DISCONNECTED => {
+          let event = &*(event_data as *const wifi_event_sta_disconnected_t);
+          Self::StaDisconnected {
+            ssid: Ssid { ssid: event.ssid, ssid_len: event.ssid_len as usize },
+            bssid: MacAddr6::from(event.bssid),
+            reason: event.reason,
+          }
+        },
+        wifi_event_t::WIFI_EVENT_STA_AUTHMODE_CHANGE => {
+          let event = &*(event_data as *const wifi_event_sta_authmode_change_t);
+          Self::StaAuthModeChange { old_mode: AuthMode::from(event.old_mode), new_mode: AuthMode::from(event.new_mode) }
+        },
+        wifi_event_t::WIFI_EVENT_AP_START => Self::ApStart,
+        wifi_event_t::WIFI_EVENT_AP_STOP => Self::ApStop,
+        wifi_event_t::WIFI_EVENT_AP_STACONNECTED => {
+          let event = &*(event_data as *const wifi_event_ap_staconnected_t);
+          Self::ApStaConnected { mac: MacAddr6::from(event.mac), aid: event.aid }
+        },
+        wifi_event_t::WIFI_EVENT_AP_STADISCONNECTED => {
+          let event = &*(event_data as *const wifi_event_ap_stadisconnected_t);
+          Self::ApStaDisconnected { mac: MacAddr6::from(event.mac), aid: event.aid }
+        },
+        _ => return None,
+      })
+    } else if event_base == IP_EVENT {
+      Some(match event_id {
+        id if id == ip_event_t::IP_EVENT_STA_GOT_IP as i32 => {
+          let event = &*(event_data as *const ip_event_got_ip_t);
+          Self::StaGotIp { ip_info: IpInfo::from_native_unchecked(event.ip_info), changed: event.ip_changed }
+        },
+        id if id == ip_event_t::IP_EVENT_STA_LOST_IP as i32 => Self::StaLostIp,
+        id if id == ip_event_t::IP_EVENT_AP_STAIPASSIGNED as i32 => {
+          let event = &*(event_data as *const ip_event_ap_staipassigned_t);
+          Self::ApStaIpAssigned { ip: Ipv4Addr::from(u32::from_be(event.ip.addr)) }
+        },
+        id if id == ip_event_t::IP_EVENT_GOT_IP6 as i32 => {
+          let event = &*(event_data as *const ip_event_got_ip6_t);
+          Self::GotIp6 { ip: ip6_from_native(&event.ip6_info.ip), index: event.ip_index }
+        },
+        _ => return None,
+      })
+    } else {
+      None
+    }
+  }
+}
+
+/// Call `callback` for every [`WifiEvent`](enum.WifiEvent.html) until the returned subscription is dropped.
+///
+/// The callback runs on the event loop task, so it should return quickly.
+pub fn subscribe<F>(mut callback: F) -> Result<Subscription, EspError>
+where
+  F: FnMut(WifiEvent) + Send + 'static,
+{
+  Subscription::new(unsafe { &[WIFI_EVENT, IP_EVENT] }, move |event_base, event_id, event_data| {
+    if let Some(event) = unsafe { WifiEvent::from_raw(event_base, event_id, event_data) } {
+      callback(event)
+    }
+  })
+}
+
+/// Receive every [`WifiEvent`](enum.WifiEvent.html) through a channel.
+///
+/// ```no_run
+/// use esp_idf_hal::wifi::{self, WifiEvent};
+///
+/// let events = wifi::events().unwrap();
+///
+/// while let Ok(event) = events.recv() {
+///   if let WifiEvent::StaDisconnected { reason, .. } = event {
+///     eprintln!("Disconnected: {:?}", reason);
+///   }
+/// }
+/// ```
+pub fn events() -> Result<EventReceiver<WifiEvent>, EspError> {
+  EventReceiver::new(unsafe { &[WIFI_EVENT, IP_EVENT] }, |event_base, event_id, event_data| {
+    unsafe { WifiEvent::from_raw(event_base, event_id, event_data) }
+  })
+}
@@ -3,11 +3,14 @@ use std::str::{self, FromStr, Utf8Error};
 use std::ops::Deref;
 use std::cmp::{Eq, Ord, Ordering};
 use core::ptr;
-use std::mem::{self, MaybeUninit};
+use std::mem::{self, ManuallyDrop, MaybeUninit};
 use std::sync::atomic::{AtomicBool, AtomicU8, Ordering::SeqCst};
 use core::task::{Poll, Context, Waker};
 use core::pin::Pin;
-use alloc::boxed::Box;
+#[cfg(target_device = "esp32")]
+use std::sync::{Arc, Mutex};
+#[cfg(target_device = "esp32")]
+use std::time::Duration;
 
 use core::fmt;
 use macaddr::MacAddr6;
@@ -22,9 +25,68 @@ pub use sta_config::*;
 mod ap_config;
 pub use ap_config::*;
 
+mod ap_sta;
+pub use ap_sta::*;
+
+#[cfg(target_device = "esp32")]
+mod ap;
+#[cfg(target_device = "esp32")]
+pub use ap::*;
+
 mod scan;
 pub use scan::*;
 
+mod protocol;
+pub use protocol::*;
+
+mod country;
+pub use country::*;
+
+mod power_save;
+pub use power_save::*;
+
+mod tx_power;
+pub use tx_power::*;
+
+mod storage;
+pub use storage::*;
+
+mod reconnect;
+pub use reconnect::*;
+
+mod credentials;
+pub use credentials::*;
+
+#[cfg(target_device = "esp32")]
+mod event;
+#[cfg(target_device = "esp32")]
+pub use event::*;
+
+#[cfg(target_device = "esp32")]
+mod promiscuous;
+#[cfg(target_device = "esp32")]
+pub use promiscuous::*;
+
+#[cfg(target_device = "esp32")]
+mod csi;
+#[cfg(target_device = "esp32")]
+pub use csi::*;
+
+#[cfg(target_device = "esp32")]
+mod vendor_ie;
+#[cfg(target_device = "esp32")]
+pub use vendor_ie::*;
+
+#[cfg(target_device = "esp32")]
+mod raw_tx;
+#[cfg(target_device = "esp32")]
+pub use raw_tx::*;
+
+#[cfg(target_device = "esp32")]
+mod enterprise;
+#[cfg(target_device = "esp32")]
+pub use enterprise::*;
+
 const SSID_MAX_LEN: usize = 32;
 const PASSWORD_MAX_LEN: usize = 64;
 
@@ -212,7 +274,7 @@ impl fmt::Display for Password {
 }
 
 /// A WiFi authentication mode.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AuthMode {
   Open,
   Wep,
@@ -270,43 +332,30 @@ pub struct Wifi<T = ()> {
   config: T,
   deinit_on_drop: bool,
   ip_info: Option<IpInfo>,
+  reconnector: Option<Reconnector>,
 }
 
 #[cfg(target_device = "esp8266")]
-fn initialize_network_interface() {
+pub(crate) fn initialize_network_interface() {
   unsafe { tcpip_adapter_init() };
 }
 
 #[cfg(target_device = "esp32")]
-fn initialize_network_interface() {
-  static NETIF_STATE: AtomicU8 = AtomicU8::new(0);
-
-  loop {
-    match NETIF_STATE.compare_and_swap(0, 1, SeqCst) {
-      0 => {
-        esp_ok!(esp_netif_init()).expect("failed to initialize network interface");
-        NETIF_STATE.store(2, SeqCst);
-        return;
-      },
-      1 => continue,
-      _ => return,
-    }
-  }
-}
+pub(crate) fn initialize_network_interface() {
+  static NETIF: crate::init_once::InitOnce<()> = crate::init_once::InitOnce::new();
 
-fn event_loop_create_default() {
-  static EVENT_LOOP_STATE: AtomicU8 = AtomicU8::new(0);
+  NETIF.get_or_init(|| {
+    esp_ok!(esp_netif_init()).expect("failed to initialize network interface");
+  });
+}
 
-  loop {
-    match EVENT_LOOP_STATE.compare_and_swap(0, 1, SeqCst) {
-      0 => {
-        esp_ok!(esp_event_loop_create_default()).expect("failed to initialize default event loop");
-        EVENT_LOOP_STATE.store(2, SeqCst);
-        return;
-      },
-      1 => continue,
-      _ => return,
-    }
+/// Get the WiFi interface corresponding to `interface`.
+fn wifi_interface(interface: Interface) -> Result<esp_interface_t, EspError> {
+  match interface {
+    Interface::Sta => Ok(esp_interface_t::ESP_IF_WIFI_STA),
+    Interface::Ap => Ok(esp_interface_t::ESP_IF_WIFI_AP),
+    #[cfg(target_device = "esp32")]
+    _ => Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t }),
   }
 }
 
@@ -319,7 +368,7 @@ fn get_mode() -> Result<wifi_mode_t, EspError> {
   Ok(mode)
 }
 
-fn enter_ap_mode() {
+pub(crate) fn enter_ap_mode() {
   if AP_COUNT.fetch_add(1, SeqCst) > 0 {
     return
   }
@@ -336,7 +385,7 @@ fn enter_ap_mode() {
   esp_ok!(esp_wifi_set_mode(new_mode)).expect("failed to set WiFi mode");
 }
 
-fn leave_ap_mode() {
+pub(crate) fn leave_ap_mode() {
   if AP_COUNT.fetch_sub(1, SeqCst) != 1 {
     return
   }
@@ -354,7 +403,7 @@ fn leave_ap_mode() {
   };
 }
 
-fn enter_sta_mode() {
+pub(crate) fn enter_sta_mode() {
   if STA_COUNT.fetch_add(1, SeqCst) > 0 {
     return
   }
@@ -371,11 +420,14 @@ fn enter_sta_mode() {
   esp_ok!(esp_wifi_set_mode(new_mode)).expect("failed to set WiFi mode");
 }
 
-fn leave_sta_mode() {
+pub(crate) fn leave_sta_mode() {
   if STA_COUNT.fetch_sub(1, SeqCst) != 1 {
     return
   }
 
+  #[cfg(target_device = "esp32")]
+  EnterpriseConfig::disable();
+
   let current_mode = get_mode().expect("failed to get WiFi mode");
 
   match current_mode {
@@ -399,13 +451,13 @@ impl Wifi {
     } else {
       initialize_network_interface();
 
-      event_loop_create_default();
+      crate::event::event_loop_create_default();
 
       NonVolatileStorage::init_default().expect("failed to initialize default NVS partition");
       let config = wifi_init_config_t::default();
       esp_ok!(esp_wifi_init(&config)).expect("failed to initialize WiFi with default configuration");
 
-      Some(Wifi { config: (), deinit_on_drop: true, ip_info: None })
+      Some(Wifi { config: (), deinit_on_drop: true, ip_info: None, reconnector: None })
     }
   }
 
@@ -418,8 +470,14 @@ impl Wifi {
     let mut ap_config = wifi_config_t::from(&config);
     enter_ap_mode();
     esp_ok!(esp_wifi_set_config(esp_interface_t::ESP_IF_WIFI_AP, &mut ap_config))?;
+    #[cfg(target_device = "esp32")]
+    {
+      if let Some(dhcp_server) = config.dhcp_server() {
+        interface.set_dhcp_server_config(dhcp_server)?;
+      }
+    }
     esp_ok!(esp_wifi_start())?;
-    Ok(WifiRunning::Ap(Wifi { config, deinit_on_drop: true, ip_info: Some(interface.ip_info()) }))
+    Ok(WifiRunning::Ap(Wifi { config, deinit_on_drop: true, ip_info: Some(interface.ip_info()), reconnector: None }))
   }
 
   /// Connect to a WiFi network using the specified [`StaConfig`](struct.StaConfig.html).
@@ -431,13 +489,27 @@ impl Wifi {
 
     enter_sta_mode();
 
-    let state = if let Err(err) = esp_ok!(esp_wifi_set_config(esp_interface_t::ESP_IF_WIFI_STA, &mut sta_config)) {
+    let res = esp_ok!(esp_wifi_set_config(esp_interface_t::ESP_IF_WIFI_STA, &mut sta_config));
+
+    #[cfg(target_device = "esp32")]
+    let res = res
+      .and_then(|()| Interface::Sta.set_ip_config(config.ip_config()))
+      .and_then(|()| config.enterprise().map_or(Ok(()), |enterprise| enterprise.enable()));
+
+    let state = if let Err(err) = res {
       ConnectFutureState::Failed(err.into())
     } else {
       ConnectFutureState::Starting
     };
 
-    ConnectFuture { config, state }
+    ConnectFuture {
+      config: Some(config),
+      state,
+      #[cfg(target_device = "esp32")]
+      timer: None,
+      #[cfg(target_device = "esp32")]
+      handlers: None,
+    }
   }
 }
 
@@ -474,6 +546,17 @@ impl<T> Wifi<T> {
   pub fn config(&self) -> &T {
     &self.config
   }
+
+  /// Move the configuration out without running `Drop`, which would stop or deinitialize WiFi.
+  pub(crate) fn into_config(self) -> T {
+    let this = ManuallyDrop::new(self);
+
+    unsafe {
+      drop(ptr::read(&this.ip_info));
+      drop(ptr::read(&this.reconnector));
+      ptr::read(&this.config)
+    }
+  }
 }
 
 impl<T> Drop for Wifi<T> {
@@ -498,16 +581,26 @@ impl Wifi<StaConfig> {
     self.ip_info.as_ref().unwrap()
   }
 
+  /// Get information about the access point this station is currently connected to.
+  pub fn ap_info(&self) -> Result<ApRecord, EspError> {
+    sta_ap_info()
+  }
+
   /// Stop a running WiFi in station mode.
   pub fn stop(mut self) -> (StaConfig, Wifi) {
     self.deinit_on_drop = false;
+    self.reconnector = None;
     leave_sta_mode();
-    let config = MaybeUninit::uninit();
-    let config = mem::replace(&mut self.config, unsafe { config.assume_init() });
-    (config, Wifi { config: (), deinit_on_drop: true, ip_info: None })
+    (self.into_config(), Wifi { config: (), deinit_on_drop: true, ip_info: None, reconnector: None })
   }
 }
 
+fn sta_ap_info() -> Result<ApRecord, EspError> {
+  let mut ap_info = MaybeUninit::<wifi_ap_record_t>::uninit();
+  esp_ok!(esp_wifi_sta_get_ap_info(ap_info.as_mut_ptr()))?;
+  Ok(ApRecord::from_native(unsafe { &ap_info.assume_init() }))
+}
+
 impl Wifi<ApConfig> {
   pub fn ip_info(&self) -> &IpInfo {
     self.ip_info.as_ref().unwrap()
@@ -517,9 +610,7 @@ impl Wifi<ApConfig> {
   pub fn stop(mut self) -> (ApConfig, Wifi) {
     self.deinit_on_drop = false;
     leave_ap_mode();
-    let config = MaybeUninit::uninit();
-    let config = mem::replace(&mut self.config, unsafe { config.assume_init() });
-    (config, Wifi { config: (), deinit_on_drop: true, ip_info: None })
+    (self.into_config(), Wifi { config: (), deinit_on_drop: true, ip_info: None, reconnector: None })
   }
 }
 
@@ -529,14 +620,185 @@ enum ConnectFutureState {
   Starting,
   ConnectedWithoutIp { ssid: Ssid, bssid: MacAddr6, channel: u8, auth_mode: AuthMode },
   Connected { ip_info: IpInfo, ssid: Ssid, bssid: MacAddr6, channel: u8, auth_mode: AuthMode },
+  Finished,
 }
 
 /// A future representing an ongoing connection to an access point.
 #[must_use = "futures do nothing unless polled"]
 #[derive(Debug)]
 pub struct ConnectFuture {
-  config: StaConfig,
+  config: Option<StaConfig>,
   state: ConnectFutureState,
+  #[cfg(target_device = "esp32")]
+  timer: Option<ConnectTimer>,
+  #[cfg(target_device = "esp32")]
+  handlers: Option<StaHandlers>,
+}
+
+#[cfg(target_device = "esp32")]
+impl ConnectFuture {
+  fn start(&mut self, waker: &Waker) -> Result<(), EspError> {
+    let handlers = StaHandlers::register(waker.clone())?;
+
+    if let Some(timeout) = self.config.as_ref().and_then(|config| config.connect_timeout()) {
+      self.timer = Some(ConnectTimer::start(timeout, Arc::clone(&handlers.context))?);
+    }
+
+    esp_ok!(esp_wifi_start())?;
+
+    self.handlers = Some(handlers);
+    Ok(())
+  }
+}
+
+/// State shared between a [`ConnectFuture`](struct.ConnectFuture.html) and its event handlers.
+#[cfg(target_device = "esp32")]
+#[derive(Debug)]
+struct HandlerContext {
+  state: Mutex<(ConnectFutureState, Option<Waker>)>,
+  timed_out: AtomicBool,
+}
+
+/// The event handlers of a [`ConnectFuture`](struct.ConnectFuture.html).
+///
+/// The handlers are unregistered and their context is freed only when this is dropped.
+#[cfg(target_device = "esp32")]
+#[derive(Debug)]
+struct StaHandlers {
+  context: Arc<HandlerContext>,
+}
+
+#[cfg(target_device = "esp32")]
+impl StaHandlers {
+  fn register(waker: Waker) -> Result<Self, EspError> {
+    let context = HandlerContext {
+      state: Mutex::new((ConnectFutureState::Starting, Some(waker))),
+      timed_out: AtomicBool::new(false),
+    };
+    let handlers = Self { context: Arc::new(context) };
+
+    for &(event_base, event_id) in &Self::events() {
+      let arg = Arc::as_ptr(&handlers.context) as *mut _;
+      esp_ok!(esp_event_handler_register(event_base, event_id, Some(wifi_sta_handler), arg))?;
+    }
+
+    Ok(handlers)
+  }
+
+  fn events() -> [(esp_event_base_t, i32); 4] {
+    unsafe {
+      [
+        (WIFI_EVENT, wifi_event_t::WIFI_EVENT_STA_START as _),
+        (WIFI_EVENT, wifi_event_t::WIFI_EVENT_STA_CONNECTED as _),
+        (WIFI_EVENT, wifi_event_t::WIFI_EVENT_STA_DISCONNECTED as _),
+        (IP_EVENT, ip_event_t::IP_EVENT_STA_GOT_IP as _),
+      ]
+    }
+  }
+
+  fn context(&self) -> &HandlerContext {
+    &self.context
+  }
+}
+
+#[cfg(target_device = "esp32")]
+impl Drop for StaHandlers {
+  fn drop(&mut self) {
+    // Unregistering waits for a running handler to return, so the context is no longer used afterwards.
+    for &(event_base, event_id) in &Self::events() {
+      let _ = esp_ok!(esp_event_handler_unregister(event_base, event_id, Some(wifi_sta_handler)));
+    }
+  }
+}
+
+/// Timer aborting a connection attempt after the timeout set with
+/// [`StaConfigBuilder::connect_timeout`](struct.StaConfigBuilder.html#method.connect_timeout).
+#[cfg(target_device = "esp32")]
+#[derive(Debug)]
+struct ConnectTimer {
+  handle: esp_timer_handle_t,
+  context: Arc<HandlerContext>,
+}
+
+#[cfg(target_device = "esp32")]
+impl ConnectTimer {
+  fn start(timeout: Duration, context: Arc<HandlerContext>) -> Result<Self, EspError> {
+    extern "C" fn callback(arg: *mut libc::c_void) {
+      let context = unsafe { &*(arg as *const HandlerContext) };
+
+      // Only abort an attempt which is still in progress, not a connection which has just succeeded.
+      let connecting = context.state.lock().map_or(false, |state| match state.0 {
+        ConnectFutureState::Starting | ConnectFutureState::ConnectedWithoutIp { .. } => {
+          context.timed_out.store(true, SeqCst);
+          true
+        },
+        _ => false,
+      });
+
+      // Aborts the connection attempt, which is then reported by a `WIFI_EVENT_STA_DISCONNECTED` event.
+      if connecting {
+        let _ = esp_ok!(esp_wifi_disconnect());
+      }
+    }
+
+    let args = esp_timer_create_args_t {
+      callback: Some(callback),
+      arg: Arc::as_ptr(&context) as *mut _,
+      dispatch_method: esp_timer_dispatch_t::ESP_TIMER_TASK,
+      name: b"wifi_connect\0".as_ptr() as *const _,
+    };
+
+    let mut handle = ptr::null_mut();
+    esp_ok!(esp_timer_create(&args, &mut handle))?;
+
+    let timer = Self { handle, context };
+    esp_ok!(esp_timer_start_once(timer.handle, timeout.as_micros() as u64))?;
+    Ok(timer)
+  }
+}
+
+#[cfg(target_device = "esp32")]
+impl Drop for ConnectTimer {
+  fn drop(&mut self) {
+    unsafe {
+      esp_timer_stop(self.handle);
+      esp_timer_delete(self.handle);
+    }
+  }
+}
+
+/// The kind of a [`ConnectionError`](struct.ConnectionError.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionErrorKind {
+  /// No access point with the configured SSID was found.
+  ApNotFound,
+  /// The password is wrong, i.e. the 4-way handshake failed.
+  WrongPassword,
+  /// The access point rejected the authentication.
+  AuthFailed,
+  /// The access point did not respond to the authentication in time.
+  AuthTimeout,
+  /// The association with the access point failed.
+  AssocFailed,
+  /// The connection failed for another reason, see [`ConnectionError::reason`](struct.ConnectionError.html#method.reason).
+  Other,
+}
+
+impl From<wifi_err_reason_t> for ConnectionErrorKind {
+  fn from(reason: wifi_err_reason_t) -> Self {
+    match reason {
+      wifi_err_reason_t::WIFI_REASON_NO_AP_FOUND => Self::ApNotFound,
+      wifi_err_reason_t::WIFI_REASON_4WAY_HANDSHAKE_TIMEOUT |
+      wifi_err_reason_t::WIFI_REASON_HANDSHAKE_TIMEOUT |
+      wifi_err_reason_t::WIFI_REASON_MIC_FAILURE => Self::WrongPassword,
+      wifi_err_reason_t::WIFI_REASON_AUTH_FAIL => Self::AuthFailed,
+      wifi_err_reason_t::WIFI_REASON_AUTH_EXPIRE => Self::AuthTimeout,
+      wifi_err_reason_t::WIFI_REASON_ASSOC_FAIL |
+      wifi_err_reason_t::WIFI_REASON_ASSOC_EXPIRE |
+      wifi_err_reason_t::WIFI_REASON_ASSOC_TOOMANY => Self::AssocFailed,
+      _ => Self::Other,
+    }
+  }
 }
 
 /// The error type returned when a [`ConnectFuture`](struct.ConnectFuture.html) fails.
@@ -547,9 +809,37 @@ pub struct ConnectionError {
   reason: wifi_err_reason_t,
 }
 
+impl ConnectionError {
+  pub fn ssid(&self) -> &Ssid {
+    &self.ssid
+  }
+
+  pub fn bssid(&self) -> &MacAddr6 {
+    &self.bssid
+  }
+
+  /// The raw disconnect reason reported by the WiFi driver.
+  pub fn reason(&self) -> wifi_err_reason_t {
+    self.reason
+  }
+
+  pub fn kind(&self) -> ConnectionErrorKind {
+    self.reason.into()
+  }
+}
+
 impl fmt::Display for ConnectionError {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    write!(f, "Error connecting to {} ({}): {:?}", self.ssid, self.bssid, self.reason)
+    let description = match self.kind() {
+      ConnectionErrorKind::ApNotFound => "access point not found",
+      ConnectionErrorKind::WrongPassword => "wrong password",
+      ConnectionErrorKind::AuthFailed => "authentication failed",
+      ConnectionErrorKind::AuthTimeout => "authentication timed out",
+      ConnectionErrorKind::AssocFailed => "association failed",
+      ConnectionErrorKind::Other => return write!(f, "Error connecting to {} ({}): {:?}", self.ssid, self.bssid, self.reason),
+    };
+
+    write!(f, "Error connecting to {} ({}): {}", self.ssid, self.bssid, description)
   }
 }
 
@@ -560,12 +850,14 @@ pub enum WifiError {
   Internal(EspError),
   /// A connection error returned when a [`ConnectFuture`](struct.ConnectFuture.html) fails.
   ConnectionError(ConnectionError),
+  /// A [`ConnectFuture`](struct.ConnectFuture.html) did not complete within its timeout.
+  Timeout,
 }
 
 impl WifiError {
   /// Create a new uninitialized [`Wifi`](struct.Wifi.html) instance.
   pub fn wifi(self) -> Wifi {
-    Wifi { config: (), deinit_on_drop: true, ip_info: None }
+    Wifi { config: (), deinit_on_drop: true, ip_info: None, reconnector: None }
   }
 }
 
@@ -580,6 +872,7 @@ impl fmt::Display for WifiError {
     match self {
       Self::Internal(esp_error) => esp_error.fmt(f),
       Self::ConnectionError(error) => error.fmt(f),
+      Self::Timeout => write!(f, "Timed out connecting to access point"),
     }
   }
 }
@@ -594,74 +887,95 @@ impl core::future::Future for ConnectFuture {
 
   #[cfg(target_device = "esp32")]
   fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
-    fn register_sta_handlers(b: *mut (Pin<&mut ConnectFuture>, &Waker)) -> Result<(), EspError> {
-      esp_ok!(esp_event_handler_register(
-        WIFI_EVENT, wifi_event_t::WIFI_EVENT_STA_START as _, Some(wifi_sta_handler), b as *mut _,
-      ))?;
-      esp_ok!(esp_event_handler_register(
-        WIFI_EVENT, wifi_event_t::WIFI_EVENT_STA_CONNECTED as _, Some(wifi_sta_handler), b as *mut _,
-      ))?;
-      esp_ok!(esp_event_handler_register(
-        WIFI_EVENT, wifi_event_t::WIFI_EVENT_STA_DISCONNECTED as _, Some(wifi_sta_handler), b as *mut _,
-      ))?;
-      esp_ok!(esp_event_handler_register(
-        IP_EVENT, ip_event_t::IP_EVENT_STA_GOT_IP as _, Some(wifi_sta_handler), b as *mut _,
-      ))
-    }
-
-    fn unregister_sta_handlers() -> Result<(), EspError> {
-      esp_ok!(esp_event_handler_unregister(
-        WIFI_EVENT, wifi_event_t::WIFI_EVENT_STA_START as _, Some(wifi_sta_handler),
-      )).and(esp_ok!(esp_event_handler_unregister(
-        WIFI_EVENT, wifi_event_t::WIFI_EVENT_STA_CONNECTED as _, Some(wifi_sta_handler),
-      ))).and(esp_ok!(esp_event_handler_unregister(
-        WIFI_EVENT, wifi_event_t::WIFI_EVENT_STA_DISCONNECTED as _, Some(wifi_sta_handler),
-      ))).and(esp_ok!(esp_event_handler_unregister(
-        IP_EVENT, ip_event_t::IP_EVENT_STA_GOT_IP as _, Some(wifi_sta_handler),
-      )))
-    }
-
-    match &self.state {
-      ConnectFutureState::Starting => {
-        let b: Box<(Pin<&mut ConnectFuture>, &Waker)> = Box::new((self.as_mut(), cx.waker()));
-        let b = Box::into_raw(b);
-
-        if let Err(err) = register_sta_handlers(b) {
-          let _ = unregister_sta_handlers();
-          drop(unsafe { Box::from_raw(b) });
-          return Poll::Ready(Err(err.into()));
+    let this = &mut *self;
+
+    match this.state {
+      ConnectFutureState::Finished => panic!("`ConnectFuture` polled after completion"),
+      ConnectFutureState::Starting if this.handlers.is_none() => {
+        match this.start(cx.waker()) {
+          Ok(()) => return Poll::Pending,
+          Err(err) => this.state = ConnectFutureState::Failed(err.into()),
         }
-
-        esp_ok!(esp_wifi_start())?;
-        Poll::Pending
       },
-      ConnectFutureState::ConnectedWithoutIp { .. } => {
-        Poll::Pending
+      _ => (),
+    }
+
+    if let Some(handlers) = &this.handlers {
+      let mut state = handlers.context().state.lock().unwrap();
+
+      match state.0 {
+        ConnectFutureState::Starting | ConnectFutureState::ConnectedWithoutIp { .. } => {
+          state.1 = Some(cx.waker().clone());
+          return Poll::Pending
+        },
+        _ => this.state = mem::replace(&mut state.0, ConnectFutureState::Finished),
       }
-      _ => {
-        if let Err(err) = unregister_sta_handlers() {
-          if !matches!(self.state, ConnectFutureState::Failed(..)) {
-            self.state = ConnectFutureState::Failed(err.into())
-          }
-        }
+    }
+
+    this.timer = None;
+    this.handlers = None;
 
-        match self.state {
-          ConnectFutureState::Starting | ConnectFutureState::ConnectedWithoutIp { .. } => unreachable!(),
-          ConnectFutureState::Failed(ref err) => {
+    match mem::replace(&mut this.state, ConnectFutureState::Finished) {
+      ConnectFutureState::Starting | ConnectFutureState::ConnectedWithoutIp { .. } | ConnectFutureState::Finished => unreachable!(),
+      ConnectFutureState::Failed(err) => {
+        leave_sta_mode();
+        Poll::Ready(Err(err))
+      },
+      ConnectFutureState::Connected { ip_info, .. } => {
+        let config = this.config.take().unwrap();
+        let reconnector = match config.reconnect_policy().cloned().map(Reconnector::spawn).transpose() {
+          Ok(reconnector) => reconnector,
+          Err(err) => {
             leave_sta_mode();
-            Poll::Ready(Err(err.clone().into()))
+            return Poll::Ready(Err(err.into()))
           },
-          ConnectFutureState::Connected { ref mut ip_info, .. } => {
-            let ip_info = mem::replace(ip_info, unsafe { MaybeUninit::uninit().assume_init() });
-            let config = mem::replace(&mut self.as_mut().config, unsafe { MaybeUninit::uninit().assume_init() });
-            Poll::Ready(Ok(WifiRunning::Sta(Wifi { config, deinit_on_drop: true, ip_info: Some(ip_info) })))
-          }
-        }
+        };
+        Poll::Ready(Ok(WifiRunning::Sta(Wifi { config, deinit_on_drop: true, ip_info: Some(ip_info), reconnector })))
       }
     }
   }
 }
 
+impl Drop for ConnectFuture {
+  /// Aborts a connection attempt which is still in progress.
+  fn drop(&mut self) {
+    if let ConnectFutureState::Finished = self.state {
+      return
+    }
+
+    #[cfg(target_device = "esp32")]
+    {
+      self.timer = None;
+      self.handlers = None;
+    }
+
+    leave_sta_mode();
+  }
+}
+
+/// Update the state of a pending connection and wake its future.
+///
+/// Only the first terminal state is kept, later events must not overwrite it.
+#[cfg(target_device = "esp32")]
+fn set_state(context: &HandlerContext, f: impl FnOnce(ConnectFutureState) -> ConnectFutureState) {
+  let waker = if let Ok(mut state) = context.state.lock() {
+    match state.0 {
+      ConnectFutureState::Starting | ConnectFutureState::ConnectedWithoutIp { .. } => {
+        let previous_state = mem::replace(&mut state.0, ConnectFutureState::Finished);
+        state.0 = f(previous_state);
+        state.1.take()
+      },
+      _ => None,
+    }
+  } else {
+    None
+  };
+
+  if let Some(waker) = waker {
+    waker.wake();
+  }
+}
+
 #[cfg(target_device = "esp32")]
 extern "C" fn wifi_sta_handler(
   event_handler_arg: *mut libc::c_void,
@@ -669,80 +983,57 @@ extern "C" fn wifi_sta_handler(
   event_id: i32,
   event_data: *mut libc::c_void,
 ) {
+  let context = unsafe { &*(event_handler_arg as *const HandlerContext) };
+
   if event_base == unsafe { WIFI_EVENT } {
     let event_id: wifi_event_t = unsafe { transmute(event_id) };
 
-    eprintln!("WIFI_EVENT: {:?}", event_id);
-
     match event_id {
       wifi_event_t::WIFI_EVENT_STA_START => {
         if let Err(err) = esp_ok!(esp_wifi_connect()) {
-          let (mut f, waker) = unsafe { *Box::from_raw(event_handler_arg as *mut (Pin<&mut ConnectFuture>, &Waker)) };
-          f.state = ConnectFutureState::Failed(err.into());
-          waker.wake_by_ref();
+          set_state(context, |_| ConnectFutureState::Failed(err.into()));
         }
       },
       wifi_event_t::WIFI_EVENT_STA_CONNECTED => {
         let event = unsafe { &*(event_data as *const wifi_event_sta_connected_t) };
 
-        eprintln!("EVENT_DATA: {:?}", event);
-
         let ssid = Ssid { ssid: event.ssid, ssid_len: event.ssid_len as usize };
         let bssid = MacAddr6::from(event.bssid);
         let channel = event.channel;
         let auth_mode = AuthMode::from(event.authmode);
 
-        let (ref mut f, _) = unsafe { &mut *(event_handler_arg as *mut (Pin<&mut ConnectFuture>, &Waker)) };
-        f.state = ConnectFutureState::ConnectedWithoutIp { ssid, bssid, channel, auth_mode };
-
-        eprintln!("EVENT_STATE: {:?}", f.state);
+        set_state(context, |_| ConnectFutureState::ConnectedWithoutIp { ssid, bssid, channel, auth_mode });
       },
       wifi_event_t::WIFI_EVENT_STA_DISCONNECTED => {
         let event = unsafe { &*(event_data as *const wifi_event_sta_disconnected_t) };
 
-        eprintln!("EVENT_DATA: {:?}", event);
-
         let ssid = Ssid { ssid: event.ssid, ssid_len: event.ssid_len as usize };
         let bssid = MacAddr6::from(event.bssid);
         let reason: wifi_err_reason_t = unsafe { transmute(event.reason as u32) };
 
-        let error = ConnectionError {
-          ssid, bssid, reason
-        };
-
-        let (mut f, waker) = unsafe { *Box::from_raw(event_handler_arg as *mut (Pin<&mut ConnectFuture>, &Waker)) };
-        f.state = ConnectFutureState::Failed(WifiError::ConnectionError(error));
-
-        eprintln!("EVENT_STATE: {:?}", f.state);
-
-        waker.wake_by_ref();
+        set_state(context, |_| if context.timed_out.load(SeqCst) {
+          ConnectFutureState::Failed(WifiError::Timeout)
+        } else {
+          ConnectFutureState::Failed(WifiError::ConnectionError(ConnectionError { ssid, bssid, reason }))
+        });
       },
       _ => (),
     }
   } else if event_base == unsafe { IP_EVENT } {
     let event_id: ip_event_t = unsafe { transmute(event_id) };
 
-    eprintln!("IP_EVENT: {:?}", event_id);
-
     match event_id {
       ip_event_t::IP_EVENT_STA_GOT_IP => {
         let event = unsafe { &*(event_data as *const ip_event_got_ip_t) };
 
         let ip_info = unsafe { IpInfo::from_native_unchecked(event.ip_info) };
 
-        eprintln!("EVENT_DATA: {:?}", event);
-
-        let (mut f, waker) = unsafe { *Box::from_raw(event_handler_arg as *mut (Pin<&mut ConnectFuture>, &Waker)) };
-
-        if let ConnectFutureState::ConnectedWithoutIp { ssid, bssid, channel, auth_mode } = mem::replace(&mut f.state, ConnectFutureState::Starting) {
-          f.state = ConnectFutureState::Connected { ip_info, ssid, bssid, channel, auth_mode };
-        } else {
-          unreachable!();
-        }
-
-        eprintln!("EVENT_STATE: {:?}", f.state);
-
-        waker.wake_by_ref();
+        set_state(context, |state| match state {
+          ConnectFutureState::ConnectedWithoutIp { ssid, bssid, channel, auth_mode } => {
+            ConnectFutureState::Connected { ip_info, ssid, bssid, channel, auth_mode }
+          },
+          state => state,
+        });
       },
       _ => (),
     }
@@ -0,0 +1,49 @@
+use esp_idf_bindgen::{esp_wifi_get_ps, esp_wifi_set_ps, wifi_ps_type_t};
+
+use super::*;
+
+/// WiFi modem power-save mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerSave {
+  /// Keep the modem awake at all times for the lowest latency.
+  None,
+  /// Wake up for every DTIM beacon.
+  MinModem,
+  /// Wake up every listen interval, as set with
+  /// [`StaConfigBuilder::listen_interval`](struct.StaConfigBuilder.html#method.listen_interval).
+  MaxModem,
+}
+
+impl From<PowerSave> for wifi_ps_type_t {
+  fn from(power_save: PowerSave) -> Self {
+    match power_save {
+      PowerSave::None => wifi_ps_type_t::WIFI_PS_NONE,
+      PowerSave::MinModem => wifi_ps_type_t::WIFI_PS_MIN_MODEM,
+      PowerSave::MaxModem => wifi_ps_type_t::WIFI_PS_MAX_MODEM,
+    }
+  }
+}
+
+impl From<wifi_ps_type_t> for PowerSave {
+  fn from(power_save: wifi_ps_type_t) -> Self {
+    match power_save {
+      wifi_ps_type_t::WIFI_PS_NONE => PowerSave::None,
+      wifi_ps_type_t::WIFI_PS_MIN_MODEM => PowerSave::MinModem,
+      wifi_ps_type_t::WIFI_PS_MAX_MODEM => PowerSave::MaxModem,
+    }
+  }
+}
+
+impl<T> Wifi<T> {
+  /// Set the modem power-save mode.
+  pub fn set_power_save(&mut self, power_save: PowerSave) -> Result<(), EspError> {
+    esp_ok!(esp_wifi_set_ps(power_save.into()))
+  }
+
+  /// Get the current modem power-save mode.
+  pub fn power_save(&self) -> Result<PowerSave, EspError> {
+    let mut power_save = wifi_ps_type_t::WIFI_PS_NONE;
+    esp_ok!(esp_wifi_get_ps(&mut power_save))?;
+    Ok(power_save.into())
+  }
+}
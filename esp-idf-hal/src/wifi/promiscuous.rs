@@ -0,0 +1,166 @@
+use core::marker::PhantomData;
+use core::slice;
+use std::sync::mpsc::{self, Receiver, RecvError, RecvTimeoutError, SyncSender, TryRecvError};
+use std::time::Duration;
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  esp_wifi_set_channel,
+  esp_wifi_set_promiscuous,
+  esp_wifi_set_promiscuous_filter,
+  esp_wifi_set_promiscuous_rx_cb,
+  wifi_promiscuous_filter_t,
+  wifi_promiscuous_pkt_t,
+  wifi_promiscuous_pkt_type_t,
+  wifi_second_chan_t,
+  ESP_ERR_INVALID_STATE,
+  WIFI_PROMIS_FILTER_MASK_MGMT,
+  WIFI_PROMIS_FILTER_MASK_CTRL,
+  WIFI_PROMIS_FILTER_MASK_DATA,
+  WIFI_PROMIS_FILTER_MASK_MISC,
+};
+
+use crate::callback_slot::CallbackSlot;
+
+use super::*;
+
+bitflags! {
+  /// Types of packets received in promiscuous mode.
+  pub struct PacketFilter: u32 {
+    const MGMT = WIFI_PROMIS_FILTER_MASK_MGMT;
+    const CTRL = WIFI_PROMIS_FILTER_MASK_CTRL;
+    const DATA = WIFI_PROMIS_FILTER_MASK_DATA;
+    const MISC = WIFI_PROMIS_FILTER_MASK_MISC;
+  }
+}
+
+/// The type of a packet received in promiscuous mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketType {
+  Mgmt,
+  Ctrl,
+  Data,
+  Misc,
+}
+
+impl From<wifi_promiscuous_pkt_type_t> for PacketType {
+  fn from(packet_type: wifi_promiscuous_pkt_type_t) -> Self {
+    match packet_type {
+      wifi_promiscuous_pkt_type_t::WIFI_PKT_MGMT => PacketType::Mgmt,
+      wifi_promiscuous_pkt_type_t::WIFI_PKT_CTRL => PacketType::Ctrl,
+      wifi_promiscuous_pkt_type_t::WIFI_PKT_DATA => PacketType::Data,
+      wifi_promiscuous_pkt_type_t::WIFI_PKT_MISC => PacketType::Misc,
+    }
+  }
+}
+
+/// A frame received in promiscuous mode.
+#[derive(Debug, Clone)]
+pub struct Frame {
+  packet_type: PacketType,
+  rssi: i8,
+  channel: u8,
+  payload: Vec<u8>,
+}
+
+impl Frame {
+  pub fn packet_type(&self) -> PacketType {
+    self.packet_type
+  }
+
+  /// The signal strength of the frame in dBm.
+  pub fn rssi(&self) -> i8 {
+    self.rssi
+  }
+
+  pub fn channel(&self) -> u8 {
+    self.channel
+  }
+
+  /// The raw 802.11 frame, including the frame check sequence.
+  pub fn payload(&self) -> &[u8] {
+    &self.payload
+  }
+}
+
+static SENDER: CallbackSlot<SyncSender<Frame>> = CallbackSlot::new();
+
+/// A WiFi packet sniffer, returned by [`Wifi::promiscuous`](struct.Wifi.html#method.promiscuous).
+///
+/// Promiscuous mode is disabled when the sniffer is dropped.
+#[derive(Debug)]
+pub struct Sniffer<'w> {
+  receiver: Receiver<Frame>,
+  _wifi: PhantomData<&'w mut ()>,
+}
+
+impl<T> Wifi<T> {
+  /// Enable promiscuous mode and receive all packets matching `filter`.
+  ///
+  /// Received frames are buffered in a channel holding up to `capacity` frames,
+  /// further frames are dropped until the channel has room again.
+  pub fn promiscuous(&mut self, filter: PacketFilter, capacity: usize) -> Result<Sniffer<'_>, EspError> {
+    let (sender, receiver) = mpsc::sync_channel(capacity);
+
+    if SENDER.set(sender).is_err() {
+      return Err(EspError { code: ESP_ERR_INVALID_STATE as esp_err_t })
+    }
+
+    let sniffer = Sniffer { receiver, _wifi: PhantomData };
+
+    let filter = wifi_promiscuous_filter_t { filter_mask: filter.bits() };
+    esp_ok!(esp_wifi_set_promiscuous_filter(&filter))?;
+    esp_ok!(esp_wifi_set_promiscuous_rx_cb(Some(promiscuous_rx_cb)))?;
+    esp_ok!(esp_wifi_set_promiscuous(true))?;
+
+    Ok(sniffer)
+  }
+}
+
+impl Sniffer<'_> {
+  /// Switch to the given primary channel.
+  pub fn set_channel(&mut self, channel: u8) -> Result<(), EspError> {
+    esp_ok!(esp_wifi_set_channel(channel, wifi_second_chan_t::WIFI_SECOND_CHAN_NONE))
+  }
+
+  /// Block until the next frame is received.
+  pub fn recv(&self) -> Result<Frame, RecvError> {
+    self.receiver.recv()
+  }
+
+  /// Return the next frame if one has already been received.
+  pub fn try_recv(&self) -> Result<Frame, TryRecvError> {
+    self.receiver.try_recv()
+  }
+
+  /// Block until the next frame is received or `timeout` has elapsed.
+  pub fn recv_timeout(&self, timeout: Duration) -> Result<Frame, RecvTimeoutError> {
+    self.receiver.recv_timeout(timeout)
+  }
+}
+
+impl Drop for Sniffer<'_> {
+  fn drop(&mut self) {
+    let _ = esp_ok!(esp_wifi_set_promiscuous(false));
+    let _ = esp_ok!(esp_wifi_set_promiscuous_rx_cb(None));
+
+    SENDER.clear();
+  }
+}
+
+extern "C" fn promiscuous_rx_cb(buf: *mut libc::c_void, packet_type: wifi_promiscuous_pkt_type_t) {
+  let packet = unsafe { &*(buf as *const wifi_promiscuous_pkt_t) };
+  let len = packet.rx_ctrl.sig_len() as usize;
+  let payload = unsafe { slice::from_raw_parts(packet.payload.as_ptr(), len) }.to_vec();
+
+  let frame = Frame {
+    packet_type: packet_type.into(),
+    rssi: packet.rx_ctrl.rssi() as i8,
+    channel: packet.rx_ctrl.channel() as u8,
+    payload,
+  };
+
+  SENDER.with(|sender| {
+    let _ = sender.try_send(frame);
+  });
+}
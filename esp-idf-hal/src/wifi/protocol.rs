@@ -0,0 +1,68 @@
+use esp_idf_bindgen::{
+  esp_err_t,
+  esp_wifi_get_protocol,
+  esp_wifi_set_protocol,
+  wifi_ap_record_t,
+  ESP_ERR_INVALID_ARG,
+  WIFI_PROTOCOL_11B,
+  WIFI_PROTOCOL_11G,
+  WIFI_PROTOCOL_11N,
+};
+#[cfg(target_device = "esp32")]
+use esp_idf_bindgen::WIFI_PROTOCOL_LR;
+
+use super::*;
+
+bitflags! {
+  /// A set of 802.11 PHY protocols.
+  pub struct Protocol: u8 {
+    const B = WIFI_PROTOCOL_11B as u8;
+    const G = WIFI_PROTOCOL_11G as u8;
+    const N = WIFI_PROTOCOL_11N as u8;
+    /// Espressif's proprietary Long Range mode.
+    #[cfg(target_device = "esp32")]
+    const LR = WIFI_PROTOCOL_LR as u8;
+  }
+}
+
+impl Protocol {
+  pub(crate) fn from_ap_record(ap: &wifi_ap_record_t) -> Self {
+    let mut protocol = Self::empty();
+    protocol.set(Self::B, ap.phy_11b() != 0);
+    protocol.set(Self::G, ap.phy_11g() != 0);
+    protocol.set(Self::N, ap.phy_11n() != 0);
+    #[cfg(target_device = "esp32")]
+    protocol.set(Self::LR, ap.phy_lr() != 0);
+    protocol
+  }
+}
+
+impl<T> Wifi<T> {
+  /// Restrict the given WiFi `interface` to a set of PHY protocols.
+  ///
+  /// Valid combinations are `B`, `B | G` and `B | G | N`, each optionally combined with
+  /// `LR`, or `LR` on its own. Long Range mode only works between two ESP32s.
+  pub fn set_protocol(&mut self, interface: Interface, protocol: Protocol) -> Result<(), EspError> {
+    let bgn = protocol & (Protocol::B | Protocol::G | Protocol::N);
+
+    #[cfg(target_device = "esp32")]
+    let lr_only = protocol == Protocol::LR;
+    #[cfg(target_device = "esp8266")]
+    let lr_only = false;
+
+    let valid = lr_only || bgn == Protocol::B || bgn == Protocol::B | Protocol::G || bgn == Protocol::B | Protocol::G | Protocol::N;
+
+    if !valid {
+      return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+    }
+
+    esp_ok!(esp_wifi_set_protocol(wifi_interface(interface)?, protocol.bits()))
+  }
+
+  /// Get the PHY protocols enabled for the given WiFi `interface`.
+  pub fn protocol(&self, interface: Interface) -> Result<Protocol, EspError> {
+    let mut protocol = 0;
+    esp_ok!(esp_wifi_get_protocol(wifi_interface(interface)?, &mut protocol))?;
+    Ok(Protocol::from_bits_truncate(protocol))
+  }
+}
@@ -0,0 +1,134 @@
+use esp_idf_bindgen::esp_wifi_80211_tx;
+use macaddr::MacAddr6;
+
+use super::*;
+
+/// The length of an 802.11 MAC header without QoS control.
+pub const RAW_FRAME_HEADER_LEN: usize = 24;
+/// The maximum length of a raw frame accepted by [`Wifi::transmit`](struct.Wifi.html#method.transmit).
+pub const RAW_FRAME_MAX_LEN: usize = 1500;
+
+/// Kinds of frames which can be transmitted with [`Wifi::transmit`](struct.Wifi.html#method.transmit).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawFrameKind {
+  ProbeRequest,
+  ProbeResponse,
+  Beacon,
+  Action,
+  /// A data frame, optionally sent to or from the distribution system.
+  Data { to_ds: bool, from_ds: bool },
+}
+
+impl RawFrameKind {
+  fn frame_control(self) -> [u8; 2] {
+    const MGMT: u8 = 0b00;
+    const DATA: u8 = 0b10;
+
+    let (frame_type, subtype, flags) = match self {
+      Self::ProbeRequest => (MGMT, 4, 0),
+      Self::ProbeResponse => (MGMT, 5, 0),
+      Self::Beacon => (MGMT, 8, 0),
+      Self::Action => (MGMT, 13, 0),
+      Self::Data { to_ds, from_ds } => (DATA, 0, (to_ds as u8) | ((from_ds as u8) << 1)),
+    };
+
+    [(subtype << 4) | (frame_type << 2), flags]
+  }
+}
+
+/// A raw 802.11 frame, created with a [`RawFrameBuilder`](struct.RawFrameBuilder.html).
+#[derive(Debug, Clone)]
+pub struct RawFrame {
+  bytes: Vec<u8>,
+}
+
+impl RawFrame {
+  pub fn builder(kind: RawFrameKind) -> RawFrameBuilder {
+    RawFrameBuilder {
+      kind,
+      duration: 0,
+      addr1: MacAddr6::broadcast(),
+      addr2: MacAddr6::nil(),
+      addr3: MacAddr6::nil(),
+      payload: Vec::new(),
+    }
+  }
+
+  /// The frame including the MAC header, excluding the frame check sequence.
+  pub fn as_bytes(&self) -> &[u8] {
+    &self.bytes
+  }
+}
+
+/// Builder for [`RawFrame`](struct.RawFrame.html).
+#[derive(Debug, Clone)]
+pub struct RawFrameBuilder {
+  kind: RawFrameKind,
+  duration: u16,
+  addr1: MacAddr6,
+  addr2: MacAddr6,
+  addr3: MacAddr6,
+  payload: Vec<u8>,
+}
+
+impl RawFrameBuilder {
+  /// Set the duration field in microseconds.
+  pub fn duration(&mut self, duration: u16) -> &mut Self {
+    self.duration = duration;
+    self
+  }
+
+  /// Set the receiver address. Defaults to the broadcast address.
+  pub fn destination(&mut self, destination: MacAddr6) -> &mut Self {
+    self.addr1 = destination;
+    self
+  }
+
+  /// Set the transmitter address.
+  pub fn source(&mut self, source: MacAddr6) -> &mut Self {
+    self.addr2 = source;
+    self
+  }
+
+  /// Set the BSSID.
+  pub fn bssid(&mut self, bssid: MacAddr6) -> &mut Self {
+    self.addr3 = bssid;
+    self
+  }
+
+  /// Set the frame body following the MAC header.
+  pub fn payload(&mut self, payload: impl Into<Vec<u8>>) -> &mut Self {
+    let payload = payload.into();
+    assert!(
+      RAW_FRAME_HEADER_LEN + payload.len() <= RAW_FRAME_MAX_LEN,
+      "payload must be at most {} bytes long", RAW_FRAME_MAX_LEN - RAW_FRAME_HEADER_LEN,
+    );
+    self.payload = payload;
+    self
+  }
+
+  pub fn build(&self) -> RawFrame {
+    let mut bytes = Vec::with_capacity(RAW_FRAME_HEADER_LEN + self.payload.len());
+    bytes.extend_from_slice(&self.kind.frame_control());
+    bytes.extend_from_slice(&self.duration.to_le_bytes());
+    bytes.extend_from_slice(self.addr1.as_bytes());
+    bytes.extend_from_slice(self.addr2.as_bytes());
+    bytes.extend_from_slice(self.addr3.as_bytes());
+    // The sequence control field is filled in by the driver if requested.
+    bytes.extend_from_slice(&[0, 0]);
+    bytes.extend_from_slice(&self.payload);
+
+    RawFrame { bytes }
+  }
+}
+
+impl<T> Wifi<T> {
+  /// Transmit a raw 802.11 frame on the given interface.
+  ///
+  /// If `sys_seq` is `true`, the driver fills in the sequence number, otherwise it is
+  /// always `0`. The interface must be started.
+  pub fn transmit(&mut self, interface: Interface, frame: &RawFrame, sys_seq: bool) -> Result<(), EspError> {
+    let bytes = frame.as_bytes();
+    esp_ok!(esp_wifi_80211_tx(wifi_interface(interface)?, bytes.as_ptr() as *const _, bytes.len() as _, sys_seq))
+  }
+}
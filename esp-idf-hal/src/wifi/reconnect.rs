@@ -0,0 +1,234 @@
+use core::fmt;
+use std::cmp;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering::SeqCst};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(target_device = "esp32")]
+use esp_idf_bindgen::{
+  esp_err_t,
+  esp_random,
+  esp_wifi_connect,
+  ESP_FAIL,
+};
+
+use crate::EspError;
+
+#[cfg(target_device = "esp32")]
+use super::{events, WifiEvent};
+
+type LostHook = Arc<dyn Fn(u8) + Send + Sync>;
+#[cfg(target_device = "esp32")]
+type ErrorHook = Arc<dyn Fn(EspError) + Send + Sync>;
+
+/// Policy for automatically reconnecting a station after it was disconnected.
+///
+/// The delay before attempt `n` is `initial_backoff * 2^n`, capped at `max_backoff`.
+/// With jitter enabled, a random delay between half and the full backoff is used.
+#[derive(Clone)]
+pub struct ReconnectPolicy {
+  max_retries: Option<u32>,
+  initial_backoff: Duration,
+  max_backoff: Duration,
+  jitter: bool,
+  on_lost: Option<LostHook>,
+  #[cfg(target_device = "esp32")]
+  on_error: Option<ErrorHook>,
+}
+
+impl fmt::Debug for ReconnectPolicy {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut f = f.debug_struct("ReconnectPolicy");
+    f
+      .field("max_retries", &self.max_retries)
+      .field("initial_backoff", &self.initial_backoff)
+      .field("max_backoff", &self.max_backoff)
+      .field("jitter", &self.jitter)
+      .field("on_lost", &self.on_lost.is_some());
+
+    #[cfg(target_device = "esp32")]
+    f.field("on_error", &self.on_error.is_some());
+
+    f.finish()
+  }
+}
+
+impl ReconnectPolicy {
+  pub fn builder() -> ReconnectPolicyBuilder {
+    ReconnectPolicyBuilder::default()
+  }
+
+  fn backoff(&self, attempt: u32) -> Duration {
+    let factor = 1u32.checked_shl(attempt).unwrap_or(u32::max_value());
+    let backoff = self.initial_backoff.checked_mul(factor).unwrap_or(self.max_backoff);
+    let backoff = cmp::min(backoff, self.max_backoff);
+
+    #[cfg(target_device = "esp32")]
+    if self.jitter {
+      let millis = backoff.as_millis() as u32;
+      let half = millis / 2;
+      let random = unsafe { esp_random() };
+      return Duration::from_millis((half + random % (millis - half + 1)) as u64)
+    }
+
+    backoff
+  }
+}
+
+/// Builder for [`ReconnectPolicy`](struct.ReconnectPolicy.html).
+pub struct ReconnectPolicyBuilder {
+  max_retries: Option<u32>,
+  initial_backoff: Duration,
+  max_backoff: Duration,
+  jitter: bool,
+  on_lost: Option<LostHook>,
+  #[cfg(target_device = "esp32")]
+  on_error: Option<ErrorHook>,
+}
+
+impl fmt::Debug for ReconnectPolicyBuilder {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut f = f.debug_struct("ReconnectPolicyBuilder");
+    f
+      .field("max_retries", &self.max_retries)
+      .field("initial_backoff", &self.initial_backoff)
+      .field("max_backoff", &self.max_backoff)
+      .field("jitter", &self.jitter)
+      .field("on_lost", &self.on_lost.is_some());
+
+    #[cfg(target_device = "esp32")]
+    f.field("on_error", &self.on_error.is_some());
+
+    f.finish()
+  }
+}
+
+impl Default for ReconnectPolicyBuilder {
+  fn default() -> Self {
+    Self {
+      max_retries: None,
+      initial_backoff: Duration::from_millis(500),
+      max_backoff: Duration::from_secs(60),
+      jitter: true,
+      on_lost: None,
+      #[cfg(target_device = "esp32")]
+      on_error: None,
+    }
+  }
+}
+
+impl ReconnectPolicyBuilder {
+  /// Give up after `max_retries` failed attempts. Retries forever if `None`.
+  pub fn max_retries(&mut self, max_retries: impl Into<Option<u32>>) -> &mut Self {
+    self.max_retries = max_retries.into();
+    self
+  }
+
+  pub fn initial_backoff(&mut self, initial_backoff: Duration) -> &mut Self {
+    self.initial_backoff = initial_backoff;
+    self
+  }
+
+  pub fn max_backoff(&mut self, max_backoff: Duration) -> &mut Self {
+    self.max_backoff = max_backoff;
+    self
+  }
+
+  pub fn jitter(&mut self, jitter: bool) -> &mut Self {
+    self.jitter = jitter;
+    self
+  }
+
+  /// Call `on_lost` with the last raw disconnect reason once all retries have failed.
+  pub fn on_lost(&mut self, on_lost: impl Fn(u8) + Send + Sync + 'static) -> &mut Self {
+    self.on_lost = Some(Arc::new(on_lost));
+    self
+  }
+
+  /// Call `on_error` whenever a reconnection attempt cannot be started.
+  #[cfg(target_device = "esp32")]
+  pub fn on_error(&mut self, on_error: impl Fn(EspError) + Send + Sync + 'static) -> &mut Self {
+    self.on_error = Some(Arc::new(on_error));
+    self
+  }
+
+  pub fn build(&self) -> ReconnectPolicy {
+    assert!(self.initial_backoff <= self.max_backoff, "initial backoff must not exceed maximum backoff");
+
+    ReconnectPolicy {
+      max_retries: self.max_retries,
+      initial_backoff: self.initial_backoff,
+      max_backoff: self.max_backoff,
+      jitter: self.jitter,
+      on_lost: self.on_lost.clone(),
+      #[cfg(target_device = "esp32")]
+      on_error: self.on_error.clone(),
+    }
+  }
+}
+
+/// A background task applying a [`ReconnectPolicy`](struct.ReconnectPolicy.html)
+/// for as long as it is alive.
+#[derive(Debug)]
+pub(crate) struct Reconnector {
+  stopped: Arc<AtomicBool>,
+}
+
+impl Reconnector {
+  /// Start applying `policy`. Errors of later reconnection attempts are reported to its `on_error` hook.
+  #[cfg(target_device = "esp32")]
+  pub(crate) fn spawn(policy: ReconnectPolicy) -> Result<Self, EspError> {
+    let events = events()?;
+
+    let stopped = Arc::new(AtomicBool::new(false));
+    let thread_stopped = Arc::clone(&stopped);
+
+    thread::Builder::new()
+      .name("wifi_reconnect".into())
+      .stack_size(3072)
+      .spawn(move || {
+        let mut attempt = 0;
+
+        while !thread_stopped.load(SeqCst) {
+          let reason = match events.recv_timeout(Duration::from_secs(1)) {
+            Ok(WifiEvent::StaDisconnected { reason, .. }) => reason,
+            Ok(WifiEvent::StaGotIp { .. }) => {
+              attempt = 0;
+              continue
+            },
+            _ => continue,
+          };
+
+          if policy.max_retries.map_or(false, |max_retries| attempt >= max_retries) {
+            if let Some(on_lost) = &policy.on_lost {
+              on_lost(reason);
+            }
+            return
+          }
+
+          thread::sleep(policy.backoff(attempt));
+          attempt += 1;
+
+          if thread_stopped.load(SeqCst) {
+            return
+          }
+
+          if let Err(err) = esp_ok!(esp_wifi_connect()) {
+            if let Some(on_error) = &policy.on_error {
+              on_error(err);
+            }
+          }
+        }
+      })
+      .map_err(|_| EspError { code: ESP_FAIL as esp_err_t })?;
+
+    Ok(Self { stopped })
+  }
+}
+
+impl Drop for Reconnector {
+  fn drop(&mut self) {
+    self.stopped.store(true, SeqCst);
+  }
+}
@@ -8,6 +8,7 @@ use std::time::Duration;
 
 use esp_idf_bindgen::{
   esp_wifi_scan_start,
+  esp_wifi_scan_stop,
   esp_wifi_scan_get_ap_num,
   esp_wifi_scan_get_ap_records,
   wifi_ap_record_t,
@@ -115,6 +116,10 @@ impl ScanConfigBuilder {
 pub struct ApRecord {
   ssid: Ssid,
   bssid: MacAddr6,
+  channel: u8,
+  rssi: i8,
+  auth_mode: AuthMode,
+  protocol: Protocol,
 }
 
 impl ApRecord {
@@ -125,16 +130,55 @@ impl ApRecord {
   pub fn bssid(&self) -> &MacAddr6 {
     &self.bssid
   }
+
+  /// The primary channel of the access point.
+  pub fn channel(&self) -> u8 {
+    self.channel
+  }
+
+  /// The signal strength of the access point in dBm.
+  pub fn rssi(&self) -> i8 {
+    self.rssi
+  }
+
+  pub fn auth_mode(&self) -> AuthMode {
+    self.auth_mode
+  }
+
+  /// The PHY protocols supported by the access point.
+  pub fn protocol(&self) -> Protocol {
+    self.protocol
+  }
+}
+
+impl ApRecord {
+  pub(crate) fn from_native(ap: &wifi_ap_record_t) -> Self {
+    let ssid_len = memchr::memchr(0, &ap.ssid).unwrap_or(ap.ssid.len());
+    let ssid = unsafe { Ssid::from_bytes_unchecked(&ap.ssid[..ssid_len]) };
+
+    ApRecord {
+      ssid,
+      bssid: MacAddr6::from(ap.bssid),
+      channel: ap.primary,
+      rssi: ap.rssi,
+      auth_mode: AuthMode::from(ap.authmode),
+      protocol: Protocol::from_ap_record(ap),
+    }
+  }
 }
 
 #[derive(Debug)]
 enum ScanFutureState {
-  Starting(*const Waker),
+  Starting(Option<Waker>),
   Failed(WifiError),
   Done,
+  Finished,
 }
 
 /// A future representing a scan of nearby WiFi networks.
+///
+/// The future resolves once the `WIFI_EVENT_SCAN_DONE` event is received,
+/// so polling it does not block the current task while the scan is running.
 #[must_use = "futures do nothing unless polled"]
 #[derive(Debug)]
 pub struct ScanFuture {
@@ -144,7 +188,7 @@ pub struct ScanFuture {
 impl ScanFuture {
   #[inline]
   pub(crate) fn new(config: &ScanConfig) -> Self {
-    let mut state = Box::pin(ScanFutureState::Starting(ptr::null()));
+    let mut state = Box::pin(ScanFutureState::Starting(None));
 
     enter_sta_mode();
 
@@ -218,14 +262,20 @@ impl Future for ScanFuture {
   fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
     match &mut *self.state {
       ScanFutureState::Starting(ref mut waker) => {
-        *waker = cx.waker() as *const _;
+        *waker = Some(cx.waker().clone());
         Poll::Pending
       },
-      ScanFutureState::Failed(ref mut err) => {
+      ScanFutureState::Failed(..) => {
         leave_sta_mode();
-        Poll::Ready(Err(mem::replace(err, unsafe { MaybeUninit::uninit().assume_init() })))
+
+        match mem::replace(&mut *self.state, ScanFutureState::Finished) {
+          ScanFutureState::Failed(err) => Poll::Ready(Err(err)),
+          _ => unreachable!(),
+        }
       },
       ScanFutureState::Done => {
+        *self.state = ScanFutureState::Finished;
+
         let unregister = unregister_scan_done_handler();
         let aps = get_ap_records();
         leave_sta_mode();
@@ -234,7 +284,27 @@ impl Future for ScanFuture {
         let aps = aps?;
 
         Poll::Ready(Ok(aps))
-      }
+      },
+      ScanFutureState::Finished => panic!("`ScanFuture` polled after completion"),
+    }
+  }
+}
+
+impl Drop for ScanFuture {
+  /// Aborts a scan which is still in progress.
+  fn drop(&mut self) {
+    match *self.state {
+      ScanFutureState::Starting(..) => {
+        let _ = unregister_scan_done_handler();
+        let _ = esp_ok!(esp_wifi_scan_stop());
+        leave_sta_mode();
+      },
+      ScanFutureState::Done => {
+        let _ = unregister_scan_done_handler();
+        leave_sta_mode();
+      },
+      ScanFutureState::Failed(..) => leave_sta_mode(),
+      ScanFutureState::Finished => (),
     }
   }
 }
@@ -247,15 +317,9 @@ fn get_ap_records() -> Result<Vec<ApRecord>, EspError> {
   let mut aps: Vec<MaybeUninit<wifi_ap_record_t>> = vec![MaybeUninit::uninit(); ap_num as usize];
   esp_ok!(esp_wifi_scan_get_ap_records(&mut ap_num as _, aps.as_mut_ptr() as *mut wifi_ap_record_t))?;
 
-  Ok(aps.into_iter().map(|ap| {
+  Ok(aps.into_iter().take(ap_num as usize).map(|ap| {
     let ap = unsafe { ap.assume_init() };
-
-    let ssid_len = memchr::memchr(0, &ap.ssid).unwrap_or(ap.ssid.len());
-    let ssid = unsafe { Ssid::from_bytes_unchecked(&ap.ssid[..ssid_len]) };
-
-    let bssid = MacAddr6::from(ap.bssid);
-
-    ApRecord { ssid, bssid }
+    ApRecord::from_native(&ap)
   }).collect())
 }
 
@@ -298,10 +362,10 @@ extern "C" fn wifi_scan_done_handler(
   _event_id: i32,
   _event_data: *mut libc::c_void,
 ) {
-  let state =  unsafe { &mut *(event_handler_arg as *mut ScanFutureState) };
+  let state = unsafe { &mut *(event_handler_arg as *mut ScanFutureState) };
   if let ScanFutureState::Starting(waker) = mem::replace(state, ScanFutureState::Done) {
-    if let Some(waker) = unsafe { waker.as_ref() } {
-      waker.wake_by_ref();
+    if let Some(waker) = waker {
+      waker.wake();
     }
   }
 }
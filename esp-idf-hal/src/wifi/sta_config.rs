@@ -1,4 +1,6 @@
 use core::fmt;
+#[cfg(target_device = "esp32")]
+use std::time::Duration;
 
 use esp_idf_bindgen::{
   wifi_config_t,
@@ -8,12 +10,20 @@ use esp_idf_bindgen::{
   wifi_scan_threshold_t,
 };
 
-use super::{AuthMode, Ssid, Password};
+use macaddr::MacAddr6;
+
+use super::{AuthMode, Ssid, Password, ReconnectPolicy};
+#[cfg(target_device = "esp32")]
+use super::EnterpriseConfig;
+#[cfg(target_device = "esp32")]
+use crate::interface::IpConfig;
 
 /// Scan method used when connecting to an access point.
 #[derive(Debug, Clone, Copy)]
 pub enum ScanMethod {
+  /// Connect to the first matching access point found.
   Fast,
+  /// Scan all channels and connect to the best matching access point according to the [`SortMethod`](enum.SortMethod.html).
   Full,
 }
 
@@ -70,6 +80,14 @@ impl Default for ScanThreshold {
   }
 }
 
+impl ScanThreshold {
+  /// Only consider access points with a signal strength of at least `rssi` dBm
+  /// and an authentication mode at least as secure as `auth_mode`.
+  pub fn new(rssi: i8, auth_mode: AuthMode) -> Self {
+    Self { rssi, auth_mode }
+  }
+}
+
 impl From<ScanThreshold> for wifi_scan_threshold_t {
   fn from(scan_threshold: ScanThreshold) -> Self {
     Self {
@@ -79,17 +97,60 @@ impl From<ScanThreshold> for wifi_scan_threshold_t {
   }
 }
 
+/// Protected Management Frames (802.11w) setting for a station.
+///
+/// WPA3-SAE requires protected management frames, so connecting to a WPA3-only
+/// network needs at least `Capable`, while `Required` rejects WPA2 access points
+/// in WPA2/WPA3 transition mode.
+///
+/// The SAE password element is always derived using hunting-and-pecking, since hash-to-element
+/// is not supported by ESP-IDF `release/v4.2`, so H2E-only access points cannot be joined.
+#[cfg(target_device = "esp32")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pmf {
+  Disabled,
+  Capable,
+  Required,
+}
+
+#[cfg(target_device = "esp32")]
+impl Default for Pmf {
+  fn default() -> Self {
+    Self::Capable
+  }
+}
+
+#[cfg(target_device = "esp32")]
+impl From<Pmf> for esp_idf_bindgen::wifi_pmf_config_t {
+  fn from(pmf: Pmf) -> Self {
+    Self {
+      capable: pmf != Pmf::Disabled,
+      required: pmf == Pmf::Required,
+    }
+  }
+}
+
 /// Configuration for a station.
 #[derive(Debug, Clone)]
 pub struct StaConfig {
   ssid: Ssid,
   password: Password,
   scan_method: ScanMethod,
-  bssid: Option<[u8; 6]>,
+  bssid: Option<MacAddr6>,
   channel: Option<u8>,
   listen_interval: Option<u16>,
   sort_method: SortMethod,
   threshold: Option<ScanThreshold>,
+  #[cfg(target_device = "esp32")]
+  pmf: Pmf,
+  #[cfg(target_device = "esp32")]
+  enterprise: Option<EnterpriseConfig>,
+  #[cfg(target_device = "esp32")]
+  reconnect_policy: Option<ReconnectPolicy>,
+  #[cfg(target_device = "esp32")]
+  ip_config: IpConfig,
+  #[cfg(target_device = "esp32")]
+  connect_timeout: Option<Duration>,
 }
 
 impl StaConfig {
@@ -101,6 +162,42 @@ impl StaConfig {
     &self.password
   }
 
+  pub fn scan_method(&self) -> ScanMethod {
+    self.scan_method
+  }
+
+  pub fn bssid(&self) -> Option<&MacAddr6> {
+    self.bssid.as_ref()
+  }
+
+  pub fn channel(&self) -> Option<u8> {
+    self.channel
+  }
+
+  pub fn sort_method(&self) -> SortMethod {
+    self.sort_method
+  }
+
+  #[cfg(target_device = "esp32")]
+  pub fn enterprise(&self) -> Option<&EnterpriseConfig> {
+    self.enterprise.as_ref()
+  }
+
+  #[cfg(target_device = "esp32")]
+  pub fn reconnect_policy(&self) -> Option<&ReconnectPolicy> {
+    self.reconnect_policy.as_ref()
+  }
+
+  #[cfg(target_device = "esp32")]
+  pub fn ip_config(&self) -> &IpConfig {
+    &self.ip_config
+  }
+
+  #[cfg(target_device = "esp32")]
+  pub fn connect_timeout(&self) -> Option<Duration> {
+    self.connect_timeout
+  }
+
   pub fn builder() -> StaConfigBuilder {
     StaConfigBuilder::default()
   }
@@ -114,16 +211,13 @@ impl From<&StaConfig> for wifi_config_t {
         password: sta_config.password.password,
         scan_method: sta_config.scan_method.into(),
         bssid_set: sta_config.bssid.is_some(),
-        bssid: sta_config.bssid.unwrap_or([0, 0, 0, 0, 0, 0]),
+        bssid: sta_config.bssid.map_or([0; 6], MacAddr6::into_array),
         channel: sta_config.channel.unwrap_or(0),
         listen_interval: sta_config.listen_interval.unwrap_or(0),
         sort_method: sta_config.sort_method.into(),
         threshold: sta_config.threshold.unwrap_or_default().into(),
         #[cfg(target_device = "esp32")]
-        pmf_cfg: esp_idf_bindgen::wifi_pmf_config_t {
-          capable: false,
-          required: false,
-        },
+        pmf_cfg: sta_config.pmf.into(),
       }
     }
   }
@@ -134,16 +228,27 @@ pub struct StaConfigBuilder {
   ssid: Option<Ssid>,
   password: Password,
   scan_method: ScanMethod,
-  bssid: Option<[u8; 6]>,
+  bssid: Option<MacAddr6>,
   channel: Option<u8>,
   listen_interval: Option<u16>,
   sort_method: SortMethod,
   threshold: Option<ScanThreshold>,
+  #[cfg(target_device = "esp32")]
+  pmf: Pmf,
+  #[cfg(target_device = "esp32")]
+  enterprise: Option<EnterpriseConfig>,
+  #[cfg(target_device = "esp32")]
+  reconnect_policy: Option<ReconnectPolicy>,
+  #[cfg(target_device = "esp32")]
+  ip_config: IpConfig,
+  #[cfg(target_device = "esp32")]
+  connect_timeout: Option<Duration>,
 }
 
 impl fmt::Debug for StaConfigBuilder {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    f.debug_struct("StaConfigBuilder")
+    let mut f = f.debug_struct("StaConfigBuilder");
+    f
       .field("ssid", &self.ssid)
       .field("password", &"********")
       .field("scan_method", &self.scan_method)
@@ -151,8 +256,20 @@ impl fmt::Debug for StaConfigBuilder {
       .field("channel", &self.channel)
       .field("listen_interval", &self.listen_interval)
       .field("sort_method", &self.sort_method)
-      .field("threshold", &self.threshold)
-      .finish()
+      .field("threshold", &self.threshold);
+
+    #[cfg(target_device = "esp32")]
+    f.field("pmf", &self.pmf);
+    #[cfg(target_device = "esp32")]
+    f.field("enterprise", &self.enterprise);
+    #[cfg(target_device = "esp32")]
+    f.field("reconnect_policy", &self.reconnect_policy);
+    #[cfg(target_device = "esp32")]
+    f.field("ip_config", &self.ip_config);
+    #[cfg(target_device = "esp32")]
+    f.field("connect_timeout", &self.connect_timeout);
+
+    f.finish()
   }
 }
 
@@ -167,6 +284,16 @@ impl Default for StaConfigBuilder {
       listen_interval: Default::default(),
       sort_method: Default::default(),
       threshold: Default::default(),
+      #[cfg(target_device = "esp32")]
+      pmf: Default::default(),
+      #[cfg(target_device = "esp32")]
+      enterprise: None,
+      #[cfg(target_device = "esp32")]
+      reconnect_policy: None,
+      #[cfg(target_device = "esp32")]
+      ip_config: IpConfig::Dhcp,
+      #[cfg(target_device = "esp32")]
+      connect_timeout: None,
     }
   }
 }
@@ -182,6 +309,88 @@ impl StaConfigBuilder {
     self
   }
 
+  /// Set the [`ScanMethod`](enum.ScanMethod.html) used to find the access point. Defaults to `ScanMethod::Fast`.
+  pub fn scan_method(&mut self, scan_method: ScanMethod) -> &mut Self {
+    self.scan_method = scan_method;
+    self
+  }
+
+  /// Only connect to the access point with the given BSSID.
+  ///
+  /// Useful for choosing between multiple access points with the same SSID.
+  pub fn bssid(&mut self, bssid: impl Into<Option<MacAddr6>>) -> &mut Self {
+    self.bssid = bssid.into();
+    self
+  }
+
+  /// Set the channel of the access point, if known. This speeds up connecting,
+  /// since other channels are only scanned if the access point is not found.
+  pub fn channel(&mut self, channel: impl Into<Option<u8>>) -> &mut Self {
+    let channel = channel.into();
+    if let Some(channel) = channel {
+      assert!(channel >= 1 && channel <= 14, "invalid channel {}", channel);
+    }
+    self.channel = channel;
+    self
+  }
+
+  /// Set the [`SortMethod`](enum.SortMethod.html) used with `ScanMethod::Full`. Defaults to `SortMethod::BySignal`.
+  pub fn sort_method(&mut self, sort_method: SortMethod) -> &mut Self {
+    self.sort_method = sort_method;
+    self
+  }
+
+  /// Set the number of beacon intervals between wake-ups in `PowerSave::MaxModem` mode.
+  pub fn listen_interval(&mut self, listen_interval: u16) -> &mut Self {
+    self.listen_interval = Some(listen_interval);
+    self
+  }
+
+  /// Only connect to access points matching the given [`ScanThreshold`](struct.ScanThreshold.html).
+  ///
+  /// Use an `auth_mode` of `AuthMode::Wpa3Psk` to only connect to WPA3 networks.
+  pub fn threshold(&mut self, threshold: ScanThreshold) -> &mut Self {
+    self.threshold = Some(threshold);
+    self
+  }
+
+  /// Set the [`Pmf`](enum.Pmf.html) (Protected Management Frames) setting.
+  #[cfg(target_device = "esp32")]
+  pub fn pmf(&mut self, pmf: Pmf) -> &mut Self {
+    self.pmf = pmf;
+    self
+  }
+
+  /// Use WPA2-Enterprise authentication with the given [`EnterpriseConfig`](struct.EnterpriseConfig.html).
+  #[cfg(target_device = "esp32")]
+  pub fn enterprise(&mut self, enterprise: EnterpriseConfig) -> &mut Self {
+    self.enterprise = Some(enterprise);
+    self
+  }
+
+  /// Automatically reconnect according to the given [`ReconnectPolicy`](struct.ReconnectPolicy.html)
+  /// when the connection is lost after it has been established.
+  #[cfg(target_device = "esp32")]
+  pub fn reconnect_policy(&mut self, reconnect_policy: ReconnectPolicy) -> &mut Self {
+    self.reconnect_policy = Some(reconnect_policy);
+    self
+  }
+
+  /// Use the given [`IpConfig`](../interface/enum.IpConfig.html), e.g. a static IP address instead of DHCP.
+  #[cfg(target_device = "esp32")]
+  pub fn ip_config(&mut self, ip_config: IpConfig) -> &mut Self {
+    self.ip_config = ip_config;
+    self
+  }
+
+  /// Fail with `WifiError::Timeout` if the connection, including obtaining an IP address,
+  /// is not established within the given duration. By default, there is no timeout.
+  #[cfg(target_device = "esp32")]
+  pub fn connect_timeout(&mut self, connect_timeout: Duration) -> &mut Self {
+    self.connect_timeout = Some(connect_timeout);
+    self
+  }
+
   pub fn build(&self) -> StaConfig {
     StaConfig {
       ssid: self.ssid.clone().expect("missing SSID"),
@@ -192,6 +401,16 @@ impl StaConfigBuilder {
       listen_interval: self.listen_interval,
       sort_method: self.sort_method,
       threshold: self.threshold,
+      #[cfg(target_device = "esp32")]
+      pmf: self.pmf,
+      #[cfg(target_device = "esp32")]
+      enterprise: self.enterprise.clone(),
+      #[cfg(target_device = "esp32")]
+      reconnect_policy: self.reconnect_policy.clone(),
+      #[cfg(target_device = "esp32")]
+      ip_config: self.ip_config.clone(),
+      #[cfg(target_device = "esp32")]
+      connect_timeout: self.connect_timeout,
     }
   }
 }
@@ -0,0 +1,36 @@
+use esp_idf_bindgen::{esp_wifi_set_storage, wifi_storage_t};
+
+use super::*;
+
+/// Where the WiFi driver stores its configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Storage {
+  /// Store the configuration in RAM and in the default NVS partition, so it persists across restarts.
+  Flash,
+  /// Store the configuration in RAM only.
+  Ram,
+}
+
+impl Default for Storage {
+  fn default() -> Self {
+    Self::Flash
+  }
+}
+
+impl From<Storage> for wifi_storage_t {
+  fn from(storage: Storage) -> Self {
+    match storage {
+      Storage::Flash => wifi_storage_t::WIFI_STORAGE_FLASH,
+      Storage::Ram => wifi_storage_t::WIFI_STORAGE_RAM,
+    }
+  }
+}
+
+impl<T> Wifi<T> {
+  /// Set where the WiFi driver stores configurations, including credentials, set afterwards.
+  ///
+  /// Use `Storage::Ram` to keep credentials out of flash and manage persistence yourself.
+  pub fn set_storage(&mut self, storage: Storage) -> Result<(), EspError> {
+    esp_ok!(esp_wifi_set_storage(storage.into()))
+  }
+}
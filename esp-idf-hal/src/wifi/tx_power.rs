@@ -0,0 +1,30 @@
+use esp_idf_bindgen::{esp_err_t, esp_wifi_get_max_tx_power, esp_wifi_set_max_tx_power, ESP_ERR_INVALID_ARG};
+
+use super::*;
+
+/// Minimum transmit power in dBm.
+pub const MIN_TX_POWER: f32 = 2.0;
+/// Maximum transmit power in dBm.
+pub const MAX_TX_POWER: f32 = 20.0;
+
+impl<T> Wifi<T> {
+  /// Set the maximum transmit power in dBm, rounded to the nearest 0.25 dBm.
+  ///
+  /// The power must be between [`MIN_TX_POWER`](constant.MIN_TX_POWER.html) and
+  /// [`MAX_TX_POWER`](constant.MAX_TX_POWER.html). WiFi must be started.
+  pub fn set_max_tx_power(&mut self, dbm: f32) -> Result<(), EspError> {
+    if !(MIN_TX_POWER..=MAX_TX_POWER).contains(&dbm) {
+      return Err(EspError { code: ESP_ERR_INVALID_ARG as esp_err_t })
+    }
+
+    let quarter_dbm = (dbm * 4.0 + 0.5) as i8;
+    esp_ok!(esp_wifi_set_max_tx_power(quarter_dbm))
+  }
+
+  /// Get the maximum transmit power in dBm.
+  pub fn max_tx_power(&self) -> Result<f32, EspError> {
+    let mut quarter_dbm = 0;
+    esp_ok!(esp_wifi_get_max_tx_power(&mut quarter_dbm))?;
+    Ok(quarter_dbm as f32 / 4.0)
+  }
+}
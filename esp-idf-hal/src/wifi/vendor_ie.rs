@@ -0,0 +1,257 @@
+use core::marker::PhantomData;
+use core::slice;
+use std::sync::mpsc::{self, Receiver, RecvError, RecvTimeoutError, SyncSender, TryRecvError};
+use std::time::Duration;
+
+use esp_idf_bindgen::{
+  esp_err_t,
+  esp_wifi_set_vendor_ie,
+  esp_wifi_set_vendor_ie_cb,
+  vendor_ie_data_t,
+  wifi_vendor_ie_id_t,
+  wifi_vendor_ie_type_t,
+  ESP_ERR_INVALID_STATE,
+  WIFI_VENDOR_IE_ELEMENT_ID,
+};
+use macaddr::MacAddr6;
+
+use crate::callback_slot::CallbackSlot;
+
+use super::*;
+
+/// The maximum payload length of a vendor-specific information element.
+pub const VENDOR_IE_MAX_PAYLOAD_LEN: usize = 255 - 4;
+
+/// The type of frame a vendor-specific information element is part of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VendorIeFrame {
+  Beacon,
+  ProbeRequest,
+  ProbeResponse,
+  AssocRequest,
+  AssocResponse,
+}
+
+impl From<VendorIeFrame> for wifi_vendor_ie_type_t {
+  fn from(frame: VendorIeFrame) -> Self {
+    match frame {
+      VendorIeFrame::Beacon => wifi_vendor_ie_type_t::WIFI_VND_IE_TYPE_BEACON,
+      VendorIeFrame::ProbeRequest => wifi_vendor_ie_type_t::WIFI_VND_IE_TYPE_PROBE_REQ,
+      VendorIeFrame::ProbeResponse => wifi_vendor_ie_type_t::WIFI_VND_IE_TYPE_PROBE_RESP,
+      VendorIeFrame::AssocRequest => wifi_vendor_ie_type_t::WIFI_VND_IE_TYPE_ASSOC_REQ,
+      VendorIeFrame::AssocResponse => wifi_vendor_ie_type_t::WIFI_VND_IE_TYPE_ASSOC_RESP,
+    }
+  }
+}
+
+impl From<wifi_vendor_ie_type_t> for VendorIeFrame {
+  fn from(frame: wifi_vendor_ie_type_t) -> Self {
+    match frame {
+      wifi_vendor_ie_type_t::WIFI_VND_IE_TYPE_BEACON => VendorIeFrame::Beacon,
+      wifi_vendor_ie_type_t::WIFI_VND_IE_TYPE_PROBE_REQ => VendorIeFrame::ProbeRequest,
+      wifi_vendor_ie_type_t::WIFI_VND_IE_TYPE_PROBE_RESP => VendorIeFrame::ProbeResponse,
+      wifi_vendor_ie_type_t::WIFI_VND_IE_TYPE_ASSOC_REQ => VendorIeFrame::AssocRequest,
+      wifi_vendor_ie_type_t::WIFI_VND_IE_TYPE_ASSOC_RESP => VendorIeFrame::AssocResponse,
+    }
+  }
+}
+
+/// One of the two slots for vendor-specific information elements per frame type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VendorIeSlot {
+  First,
+  Second,
+}
+
+impl From<VendorIeSlot> for wifi_vendor_ie_id_t {
+  fn from(slot: VendorIeSlot) -> Self {
+    match slot {
+      VendorIeSlot::First => wifi_vendor_ie_id_t::WIFI_VND_IE_ID_0,
+      VendorIeSlot::Second => wifi_vendor_ie_id_t::WIFI_VND_IE_ID_1,
+    }
+  }
+}
+
+/// A vendor-specific information element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VendorIe {
+  oui: [u8; 3],
+  oui_type: u8,
+  payload: Vec<u8>,
+}
+
+impl VendorIe {
+  /// Create a new vendor-specific information element with the given
+  /// organizationally unique identifier, type and payload.
+  pub fn new(oui: [u8; 3], oui_type: u8, payload: impl Into<Vec<u8>>) -> Self {
+    let payload = payload.into();
+    assert!(payload.len() <= VENDOR_IE_MAX_PAYLOAD_LEN, "payload must be at most {} bytes long", VENDOR_IE_MAX_PAYLOAD_LEN);
+    Self { oui, oui_type, payload }
+  }
+
+  /// Parse a vendor-specific information element including its element ID and length.
+  pub fn parse(bytes: &[u8]) -> Option<Self> {
+    match bytes {
+      [id, len, oui0, oui1, oui2, oui_type, rest @ ..] if *id as u32 == WIFI_VENDOR_IE_ELEMENT_ID && *len >= 4 => {
+        let payload = rest.get(..(*len as usize - 4))?;
+        Some(Self { oui: [*oui0, *oui1, *oui2], oui_type: *oui_type, payload: payload.to_vec() })
+      },
+      _ => None,
+    }
+  }
+
+  pub fn oui(&self) -> [u8; 3] {
+    self.oui
+  }
+
+  pub fn oui_type(&self) -> u8 {
+    self.oui_type
+  }
+
+  pub fn payload(&self) -> &[u8] {
+    &self.payload
+  }
+
+  /// Serialize this element including its element ID and length.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(6 + self.payload.len());
+    bytes.push(WIFI_VENDOR_IE_ELEMENT_ID as u8);
+    bytes.push((4 + self.payload.len()) as u8);
+    bytes.extend_from_slice(&self.oui);
+    bytes.push(self.oui_type);
+    bytes.extend_from_slice(&self.payload);
+    bytes
+  }
+}
+
+/// A vendor-specific information element received from another device.
+#[derive(Debug, Clone)]
+pub struct ReceivedVendorIe {
+  frame: VendorIeFrame,
+  src: MacAddr6,
+  rssi: i32,
+  ie: VendorIe,
+}
+
+impl ReceivedVendorIe {
+  pub fn frame(&self) -> VendorIeFrame {
+    self.frame
+  }
+
+  /// The source MAC address of the frame.
+  pub fn src(&self) -> &MacAddr6 {
+    &self.src
+  }
+
+  /// The signal strength of the frame in dBm.
+  pub fn rssi(&self) -> i32 {
+    self.rssi
+  }
+
+  pub fn ie(&self) -> &VendorIe {
+    &self.ie
+  }
+}
+
+static SENDER: CallbackSlot<SyncSender<ReceivedVendorIe>> = CallbackSlot::new();
+
+/// A stream of received vendor-specific information elements,
+/// returned by [`Wifi::vendor_ies`](struct.Wifi.html#method.vendor_ies).
+///
+/// The callback is unregistered when the receiver is dropped.
+#[derive(Debug)]
+pub struct VendorIeReceiver<'w> {
+  receiver: Receiver<ReceivedVendorIe>,
+  _wifi: PhantomData<&'w mut ()>,
+}
+
+impl<T> Wifi<T> {
+  /// Add a vendor-specific information element to all frames of the given type, replacing
+  /// the element previously set in the given slot.
+  pub fn set_vendor_ie(&mut self, frame: VendorIeFrame, slot: VendorIeSlot, ie: &VendorIe) -> Result<(), EspError> {
+    let bytes = ie.to_bytes();
+    esp_ok!(esp_wifi_set_vendor_ie(true, frame.into(), slot.into(), bytes.as_ptr() as *const _))
+  }
+
+  /// Remove the vendor-specific information element in the given slot from frames of the given type.
+  pub fn remove_vendor_ie(&mut self, frame: VendorIeFrame, slot: VendorIeSlot) -> Result<(), EspError> {
+    esp_ok!(esp_wifi_set_vendor_ie(false, frame.into(), slot.into(), ptr::null()))
+  }
+
+  /// Receive vendor-specific information elements contained in frames from other devices.
+  ///
+  /// Elements are buffered in a channel holding up to `capacity` elements,
+  /// further elements are dropped until the channel has room again.
+  pub fn vendor_ies(&mut self, capacity: usize) -> Result<VendorIeReceiver<'_>, EspError> {
+    let (sender, receiver) = mpsc::sync_channel(capacity);
+
+    if SENDER.set(sender).is_err() {
+      return Err(EspError { code: ESP_ERR_INVALID_STATE as esp_err_t })
+    }
+
+    let vendor_ie_receiver = VendorIeReceiver { receiver, _wifi: PhantomData };
+
+    esp_ok!(esp_wifi_set_vendor_ie_cb(Some(vendor_ie_cb), ptr::null_mut()))?;
+
+    Ok(vendor_ie_receiver)
+  }
+}
+
+impl VendorIeReceiver<'_> {
+  /// Block until the next element is received.
+  pub fn recv(&self) -> Result<ReceivedVendorIe, RecvError> {
+    self.receiver.recv()
+  }
+
+  /// Return the next element if one has already been received.
+  pub fn try_recv(&self) -> Result<ReceivedVendorIe, TryRecvError> {
+    self.receiver.try_recv()
+  }
+
+  /// Block until the next element is received or `timeout` has elapsed.
+  pub fn recv_timeout(&self, timeout: Duration) -> Result<ReceivedVendorIe, RecvTimeoutError> {
+    self.receiver.recv_timeout(timeout)
+  }
+}
+
+impl Drop for VendorIeReceiver<'_> {
+  fn drop(&mut self) {
+    let _ = esp_ok!(esp_wifi_set_vendor_ie_cb(None, ptr::null_mut()));
+
+    SENDER.clear();
+  }
+}
+
+extern "C" fn vendor_ie_cb(
+  _ctx: *mut libc::c_void,
+  frame: wifi_vendor_ie_type_t,
+  sa: *const u8,
+  vnd_ie: *const vendor_ie_data_t,
+  rssi: libc::c_int,
+) {
+  if vnd_ie.is_null() {
+    return
+  }
+
+  let len = unsafe { (*vnd_ie).length } as usize;
+  let bytes = unsafe { slice::from_raw_parts(vnd_ie as *const u8, 2 + len) };
+
+  let ie = match VendorIe::parse(bytes) {
+    Some(ie) => ie,
+    None => return,
+  };
+
+  let mut src = [0; 6];
+  src.copy_from_slice(unsafe { slice::from_raw_parts(sa, 6) });
+
+  let received = ReceivedVendorIe {
+    frame: frame.into(),
+    src: MacAddr6::from(src),
+    rssi: rssi as i32,
+    ie,
+  };
+
+  SENDER.with(|sender| {
+    let _ = sender.try_send(received);
+  });
+}